@@ -0,0 +1,29 @@
+#version 450
+
+// Blurs the bright pass along one direction, the bloom runs it across and then down. Nine
+// texels of a gaussian are read in five samples, the ones off the center falling between two
+// texels for the linear filtering to mix them.
+
+const float WEIGHT_CENTER = 0.2270270;
+const float WEIGHT_NEAR = 0.3162162;
+const float WEIGHT_FAR = 0.0702703;
+const float OFFSET_NEAR = 1.3846154;
+const float OFFSET_FAR = 3.2307692;
+
+layout(set = 0, binding = 0) uniform sampler2D bright;
+// How far apart the texels are along the direction of the blur.
+layout(push_constant) uniform Blur {
+    vec2 texel_step;
+};
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    vec2 near = texel_step * OFFSET_NEAR;
+    vec2 far = texel_step * OFFSET_FAR;
+    vec3 sum = texture(bright, uv).rgb * WEIGHT_CENTER;
+    sum += (texture(bright, uv + near).rgb + texture(bright, uv - near).rgb) * WEIGHT_NEAR;
+    sum += (texture(bright, uv + far).rgb + texture(bright, uv - far).rgb) * WEIGHT_FAR;
+    color = vec4(sum, 1.0);
+}
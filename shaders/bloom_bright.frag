@@ -0,0 +1,17 @@
+#version 450
+
+// The bright pass of the bloom, keeping only what's bright enough to glow. Everything dimmer
+// than the threshold goes black, brighter colors fade in up to their full strength.
+
+const float THRESHOLD = 0.8;
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    vec3 texel = texture(scene, uv).rgb;
+    float brightness = max(texel.r, max(texel.g, texel.b));
+    color = vec4(texel * smoothstep(THRESHOLD, 1.0, brightness), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 450
+
+// Puts the blurred glow back on top of the scene.
+
+const float STRENGTH = 1.5;
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+layout(set = 0, binding = 1) uniform sampler2D glow;
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    color = vec4(texture(scene, uv).rgb + texture(glow, uv).rgb * STRENGTH, 1.0);
+}
@@ -0,0 +1,32 @@
+#version 450
+
+// Draws the rendered scene as if on an old CRT: bent like the glass of the tube, striped with
+// scanlines and darkening towards the corners.
+
+const float PI = 3.14159265;
+// How strongly the picture bends away from the center.
+const float CURVATURE = 0.1;
+// How many scanlines there are from the top of the screen to the bottom, and how dark they get.
+const float SCANLINES = 112.0;
+const float SCANLINE_DEPTH = 0.15;
+// How much darker the corners get than the center.
+const float VIGNETTE = 0.3;
+
+layout(set = 0, binding = 0) uniform sampler2D scene;
+
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+    vec2 centered = uv * 2.0 - 1.0;
+    centered += centered * (centered.yx * centered.yx * CURVATURE);
+    vec2 curved = centered * 0.5 + 0.5;
+
+    // The bent picture leaves black borders around it.
+    vec2 within = step(vec2(0.0), curved) * step(curved, vec2(1.0));
+    float inside = within.x * within.y;
+    float scanline = sin(curved.y * SCANLINES * 2.0 * PI) * SCANLINE_DEPTH + (1.0 - SCANLINE_DEPTH);
+    float shade = clamp(1.0 - dot(centered, centered) * VIGNETTE, 0.0, 1.0);
+
+    color = vec4(texture(scene, curved).rgb * (inside * scanline * shade), 1.0);
+}
@@ -0,0 +1,17 @@
+#version 450
+
+// Draws the texels of a sprite, the same as the sprite shader of amethyst_rendy. The
+// multisampled sprites pass builds its own pipelines with it, as amethyst's are single sampled.
+
+layout(set = 1, binding = 0) uniform sampler2D albedo;
+
+layout(location = 0) in vec2 tex_uv;
+layout(location = 0) out vec4 out_color;
+
+void main() {
+    vec4 color = texture(albedo, tex_uv);
+    if (color.a == 0.0) {
+        discard;
+    }
+    out_color = color;
+}
@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{
+    settings_dir, write_to_settings_dir, ACHIEVEMENTS_PATH, ACHIEVEMENT_PROGRESS_FILE,
+};
+use crate::pong::Side;
+
+/// What has to happen for an achievement to unlock.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum Condition {
+    /// A rally of this many paddle hits.
+    Rally(u32),
+    /// A game won without the winner conceding a point in it.
+    FlawlessGame,
+    /// This many matches won, over every match played.
+    MatchWins(u32),
+    /// This many points scored, over every match played.
+    Points(u32),
+    /// Lasting this many seconds in a survival run.
+    Survive(f32),
+}
+
+/// Something to do in the game, unlocked once its `condition` is met.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Achievement {
+    /// What it's saved as unlocked under, so it can be renamed.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// The achievements there are, defined in `resources/achievements.ron`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct AchievementList {
+    achievements: Vec<Achievement>,
+}
+
+/// Which achievements are unlocked on this machine and the running totals counted towards
+/// them, kept in `achievement_progress.ron` next to the settings.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AchievementProgress {
+    /// The ids of the achievements unlocked.
+    pub unlocked: Vec<String>,
+    pub match_wins: u32,
+    pub points: u32,
+}
+
+/// The achievements and how far the players here have got with them. The `AchievementSystem`
+/// counts what happens in a match towards them, and the `AchievementToastSystem` shows the
+/// ones unlocked.
+#[derive(Default)]
+pub struct Achievements {
+    pub achievements: Vec<Achievement>,
+    pub progress: AchievementProgress,
+    /// The sides whose play counts in the match being played. Nobody's does in matches
+    /// watched or played back, or for the computer, the Twitch chat and online opponents.
+    pub players: Vec<Side>,
+    /// The sides that conceded a point in the game being played.
+    conceded: Vec<Side>,
+    /// Achievements unlocked that haven't been shown yet.
+    pub unlocked: VecDeque<Achievement>,
+}
+
+impl Achievements {
+    /// The achievements defined and the progress saved the last time, none the first time.
+    pub fn load_saved() -> Achievements {
+        let path = settings_dir().join(ACHIEVEMENT_PROGRESS_FILE);
+        let progress = if path.exists() {
+            AchievementProgress::load(path)
+        } else {
+            AchievementProgress::default()
+        };
+        Achievements {
+            achievements: AchievementList::load(ACHIEVEMENTS_PATH).achievements,
+            progress,
+            ..Achievements::default()
+        }
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.progress.unlocked.contains(&achievement.id)
+    }
+
+    /// Starts counting a new match, played by the `players`.
+    pub fn start_match(&mut self, players: Vec<Side>) {
+        self.players = players;
+        self.conceded.clear();
+    }
+
+    pub fn rally(&mut self, hits: u32) {
+        if !self.players.is_empty() {
+            self.unlock(|condition| {
+                matches!(condition, Condition::Rally(length) if hits >= length)
+            });
+        }
+    }
+
+    pub fn scored(&mut self, side: Side) {
+        if self.players.contains(&side) {
+            self.progress.points += 1;
+            let points = self.progress.points;
+            self.unlock(|condition| matches!(condition, Condition::Points(goal) if points >= goal));
+        }
+    }
+
+    pub fn conceded(&mut self, side: Side) {
+        self.conceded.push(side);
+    }
+
+    /// Counts the game `winner` won, which was the last of the match if `match_won`, and saves
+    /// the progress.
+    pub fn game_won(&mut self, winner: Side, match_won: bool) {
+        let flawless = !self.conceded.contains(&winner);
+        self.conceded.clear();
+        if !self.players.contains(&winner) {
+            self.save();
+            return;
+        }
+        if match_won {
+            self.progress.match_wins += 1;
+        }
+        let wins = self.progress.match_wins;
+        self.unlock(|condition| match condition {
+            Condition::FlawlessGame => flawless,
+            Condition::MatchWins(goal) => wins >= goal,
+            _ => false,
+        });
+        self.save();
+    }
+
+    pub fn survived(&mut self, seconds: f32) {
+        if !self.players.is_empty() {
+            self.unlock(|condition| {
+                matches!(condition, Condition::Survive(goal) if seconds >= goal)
+            });
+        }
+    }
+
+    /// Unlocks every achievement not unlocked yet whose condition is met, saving the progress
+    /// if any were.
+    fn unlock(&mut self, met: impl Fn(Condition) -> bool) {
+        let newly: Vec<Achievement> = self
+            .achievements
+            .iter()
+            .filter(|achievement| !self.is_unlocked(achievement) && met(achievement.condition))
+            .cloned()
+            .collect();
+        if newly.is_empty() {
+            return;
+        }
+        for achievement in newly {
+            self.progress.unlocked.push(achievement.id.clone());
+            self.unlocked.push_back(achievement);
+        }
+        self.save();
+    }
+
+    fn save(&self) {
+        if self.players.is_empty() {
+            return;
+        }
+        if let Err(err) = write_to_settings_dir(&self.progress, ACHIEVEMENT_PROGRESS_FILE) {
+            log::error!("Failed to save the achievements: {}", err);
+        }
+    }
+}
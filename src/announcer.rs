@@ -0,0 +1,105 @@
+use amethyst::{
+    assets::{AssetStorage, Loader, ProgressCounter},
+    audio::{output::Output, AudioSink, Source, SourceHandle},
+    prelude::*,
+};
+use std::collections::VecDeque;
+
+use crate::{
+    audio::{find_audio, load_audio},
+    pong::Side,
+};
+
+/// The rally lengths the announcer calls out.
+pub const RALLY_MILESTONES: [u32; 3] = [5, 10, 20];
+/// How many lines can wait for the announcer to finish the one they're saying, more are
+/// dropped rather than called out late.
+const MAX_QUEUED_LINES: usize = 2;
+
+/// Something the announcer says.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Line {
+    MatchPoint,
+    Scores(Side),
+    /// One of the `RALLY_MILESTONES` was reached.
+    Rally(u32),
+}
+
+impl Line {
+    /// Where the clip for the line is, under `assets/audio/announcer/` without its extension.
+    fn clip(self) -> String {
+        let name = match self {
+            Line::MatchPoint => "match_point".to_string(),
+            Line::Scores(side) => format!("{}_scores", side.name().to_lowercase()),
+            Line::Rally(length) => format!("rally_{}", length),
+        };
+        format!("audio/announcer/{}", name)
+    }
+
+    fn all() -> Vec<Line> {
+        let mut lines = vec![Line::MatchPoint];
+        lines.extend(
+            [Side::Left, Side::Right, Side::Top, Side::Bottom]
+                .iter()
+                .map(|side| Line::Scores(*side)),
+        );
+        lines.extend(RALLY_MILESTONES.iter().map(|length| Line::Rally(*length)));
+        lines
+    }
+}
+
+/// Calls out the points and long rallies of a match. Lines are said one after the other on a
+/// sink of their own, so they never talk over each other, by the `AnnouncerSystem`.
+///
+/// The clips in `assets/audio/announcer/` are named after their line, like `left_scores`, and
+/// can be swapped for other recordings in any of the formats `find_audio` looks for.
+pub struct Announcer {
+    clips: Vec<(Line, SourceHandle)>,
+    queue: VecDeque<Line>,
+    /// `None` without an audio output.
+    pub sink: Option<AudioSink>,
+}
+
+impl Announcer {
+    /// Has the announcer say the line once they're done with the ones before it.
+    pub fn announce(&mut self, line: Line) {
+        if self.queue.len() < MAX_QUEUED_LINES {
+            self.queue.push_back(line);
+        }
+    }
+
+    /// The clip for the next line that's waiting to be said.
+    pub fn next_clip(&mut self) -> Option<SourceHandle> {
+        let line = self.queue.pop_front()?;
+        self.clips
+            .iter()
+            .find(|(clip_line, _)| *clip_line == line)
+            .map(|(_, clip)| clip.clone())
+    }
+
+    /// Forgets the lines that weren't said yet, once the match is over.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+/// Loads the announcer's clips, counting them on `progress`.
+pub fn initialise_announcer(world: &mut World, progress: &mut ProgressCounter) {
+    let clips = {
+        let loader = world.read_resource::<Loader>();
+        let storage = world.read_resource::<AssetStorage<Source>>();
+        Line::all()
+            .into_iter()
+            .map(|line| {
+                let clip = load_audio(&find_audio(&line.clip()), &loader, progress, &storage);
+                (line, clip)
+            })
+            .collect()
+    };
+    let sink = world.res.try_fetch::<Output>().map(|output| AudioSink::new(&output));
+    world.add_resource(Announcer {
+        clips,
+        queue: VecDeque::new(),
+        sink,
+    });
+}
@@ -0,0 +1,368 @@
+use amethyst::{
+    assets::{AssetStorage, Loader, ProgressCounter},
+    audio::{
+        output::Output, AudioEmitter, AudioListener, AudioSink, FlacFormat, OggFormat, Source,
+        SourceHandle, WavFormat,
+    },
+    core::{math::Point3, Float, Transform},
+    ecs::prelude::{Builder, Component, DenseVecStorage, Entities, LazyUpdate},
+    prelude::*,
+};
+
+use std::{ffi::OsStr, path::Path};
+
+
+/// Where the game's assets are, relative to where it's started from.
+const ASSETS_DIR: &str = "assets";
+/// The kinds of audio files the sounds can be shipped as, in the order they're looked for.
+const AUDIO_EXTENSIONS: [&str; 3] = ["ogg", "flac", "wav"];
+
+/// How long it takes one music track to fade out and the next one to fade in, in seconds.
+pub const MUSIC_CROSSFADE: f32 = 1.5;
+/// How long it takes a stem of the match music to come in or drop out, in seconds.
+pub const MUSIC_STEM_FADE: f32 = 2.0;
+/// How far the music is turned down while a loud sound plays over it, and how long it takes to
+/// get there and back, in seconds.
+pub const MUSIC_DUCK_LEVEL: f32 = 0.3;
+pub const MUSIC_DUCK_FADE: f32 = 0.25;
+/// How long the music is turned down for the score sound and the fanfare.
+pub const SCORE_DUCK: f32 = 0.5;
+pub const FANFARE_DUCK: f32 = 1.2;
+
+/// The music tracks, the menus have one and the matches another.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Track {
+    Menu,
+    Game,
+}
+
+/// A looping music track. It plays on a sink of its own, so it can fade out while the next
+/// track fades in on another.
+pub struct MusicTrack {
+    pub track: Track,
+    pub source: SourceHandle,
+    /// `None` without an audio output.
+    pub sink: Option<AudioSink>,
+    /// How far the track has faded in, from 0.0 for silent to 1.0 for full volume.
+    pub fade: f32,
+}
+
+/// A layer of the match music, played in time with it on a sink of its own once a rally has
+/// gone on long enough. The music gets busier the longer the ball stays in play and drops back
+/// once someone scores.
+pub struct MusicStem {
+    pub source: SourceHandle,
+    pub sink: Option<AudioSink>,
+    /// The number of returns in the rally the stem comes in at.
+    pub rally: u32,
+    /// How far the stem has come in, from 0.0 for silent to 1.0 for full volume.
+    pub level: f32,
+}
+
+/// The background music. The screens pick the track to play, the `MusicSystem` loops it and
+/// crossfades from the one that played before.
+pub struct Music {
+    /// The track fading in or playing, every other one fades out.
+    pub playing: Option<Track>,
+    pub tracks: Vec<MusicTrack>,
+    /// Layered on top of the `Track::Game` music as the rally grows.
+    pub stems: Vec<MusicStem>,
+    /// Time left the music stays turned down for a loud sound.
+    pub duck: f32,
+    /// How loud the music plays while it's turned down and coming back up, 1.0 once it's back.
+    pub duck_level: f32,
+}
+
+impl Music {
+    /// Crossfades to `track`, a track that's already playing carries on.
+    pub fn play(&mut self, track: Track) {
+        self.playing = Some(track);
+    }
+
+    /// Turns the music down for a while, so a loud sound can be heard over it.
+    pub fn duck(&mut self, seconds: f32) {
+        self.duck = self.duck.max(seconds);
+    }
+}
+
+/// How loud the crowd murmurs while nothing's going on, and how long it takes them to calm
+/// down again, from their loudest, after a score.
+pub const CROWD_CALM: f32 = 0.3;
+pub const CROWD_CALM_DOWN: f32 = 3.0;
+/// The rally length the crowd is at their most excited by.
+pub const CROWD_RALLY_PEAK: u32 = 15;
+
+/// How far to the sides the sounds played from the edges of the arena are panned, 1.0 would
+/// play them in one ear only.
+const PAN_WIDTH: f32 = 0.8;
+/// How long a sound played from somewhere in the arena is kept around, it must be longer than
+/// any of those sounds.
+pub const SOUND_EMITTER_LIFETIME: f32 = 1.0;
+
+/// A sound effect playing from somewhere in the arena. The `SoundEmitterSystem` deletes it once
+/// it has played.
+pub struct SoundEmitter {
+    pub remaining: f32,
+}
+
+impl Component for SoundEmitter {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The crowd watching a match. They murmur on a loop, louder the longer the rally goes, and
+/// cheer when someone scores. The `CrowdSystem` keeps them quiet outside of matches.
+pub struct Crowd {
+    pub murmur: SourceHandle,
+    pub cheer: SourceHandle,
+    /// `None` without an audio output.
+    pub sink: Option<AudioSink>,
+    /// From 0.0 for calm to 1.0 for a crowd on their feet.
+    pub excitement: f32,
+    /// How far the murmur has faded in, it fades out between matches.
+    pub fade: f32,
+}
+
+/// Handles to the sound effects played during a match.
+pub struct Sounds {
+    /// Played when the ball comes off a paddle.
+    pub bounce_sfx: SourceHandle,
+    /// Played when the ball comes off a wall or an obstacle.
+    pub wall_sfx: SourceHandle,
+    /// Played when a player scores.
+    pub score_sfx: SourceHandle,
+    pub shield_sfx: SourceHandle,
+    /// Played once a match is won.
+    pub fanfare_sfx: SourceHandle,
+}
+
+/// Loads the sound effects and music, counting them on `progress`, and hooks an `AudioSink` up
+/// to the default audio output if there is one. The `AudioBundle` only opens that output when
+/// the machine has an audio device, without one the sounds are loaded but never played.
+///
+/// Each sound is looked for as OGG and FLAC before WAV, see `find_audio`.
+pub fn initialise_audio(world: &mut World, progress: &mut ProgressCounter) {
+    amethyst::audio::output::init_output(&mut world.res);
+    let output = world.res.try_fetch::<Output>().map(|output| output.clone());
+
+    let (sounds, music, crowd) = {
+        let loader = world.read_resource::<Loader>();
+        let storage = world.read_resource::<AssetStorage<Source>>();
+        let mut load = |name: &str| load_audio(&find_audio(name), &loader, progress, &storage);
+
+        let sounds = Sounds {
+            bounce_sfx: load("audio/bounce"),
+            wall_sfx: load("audio/wall"),
+            score_sfx: load("audio/score"),
+            shield_sfx: load("audio/shield"),
+            fanfare_sfx: load("audio/fanfare"),
+        };
+
+        let mut track = |track: Track, name: &str| MusicTrack {
+            track,
+            source: load(name),
+            sink: output.as_ref().map(AudioSink::new),
+            fade: 0.0,
+        };
+        let tracks = vec![
+            track(Track::Menu, "audio/menu_music"),
+            track(Track::Game, "audio/game_music"),
+        ];
+        let mut stem = |name: &str, rally: u32| MusicStem {
+            source: load(name),
+            sink: output.as_ref().map(AudioSink::new),
+            rally,
+            level: 0.0,
+        };
+        let stems = vec![
+            stem("audio/game_music_drums", 3),
+            stem("audio/game_music_lead", 6),
+        ];
+        let music = Music {
+            playing: None,
+            tracks,
+            stems,
+            duck: 0.0,
+            duck_level: 1.0,
+        };
+        let crowd = Crowd {
+            murmur: load("audio/crowd_murmur"),
+            cheer: load("audio/crowd_cheer"),
+            sink: output.as_ref().map(AudioSink::new),
+            excitement: 0.0,
+            fade: 0.0,
+        };
+        (sounds, music, crowd)
+    };
+    world.add_resource(sounds);
+    world.add_resource(music);
+    world.add_resource(crowd);
+}
+
+/// Drops whatever the sink has queued. A stopped sink stays stopped, so it's swapped for a new
+/// one instead.
+pub fn silence(sink: &mut AudioSink, output: &Output) {
+    if !sink.empty() {
+        *sink = AudioSink::new(output);
+    }
+}
+
+/// Picks the file for a sound given by its path under the assets directory without the
+/// extension, like `audio/menu_music`. An OGG or FLAC file is picked over the WAV, so the
+/// bigger files like the music can be shipped compressed. Without any of them it's the WAV,
+/// which fails to load and logs the error.
+pub fn find_audio(name: &str) -> String {
+    AUDIO_EXTENSIONS
+        .iter()
+        .map(|extension| format!("{}.{}", name, extension))
+        .find(|path| Path::new(ASSETS_DIR).join(path).is_file())
+        .unwrap_or_else(|| format!("{}.wav", name))
+}
+
+/// Loads a sound from the assets directory, decoding it by the extension of its file.
+pub fn load_audio(
+    path: &str,
+    loader: &Loader,
+    progress: &mut ProgressCounter,
+    storage: &AssetStorage<Source>,
+) -> SourceHandle {
+    match Path::new(path).extension().and_then(OsStr::to_str) {
+        Some("ogg") => loader.load(path, OggFormat, progress, storage),
+        Some("flac") => loader.load(path, FlacFormat, progress, storage),
+        _ => loader.load(path, WavFormat, progress, storage),
+    }
+}
+
+/// Adds the listener the sounds played from the arena are panned for, standing in front of the
+/// arena with the left side in their left ear. The match deletes it along with everything else.
+pub fn initialise_audio_listener(world: &mut World) {
+    world.register::<AudioListener>();
+    world.register::<AudioEmitter>();
+    world
+        .create_entity()
+        .with(AudioListener {
+            left_ear: Point3::new(Float::from(-1.0), Float::from(0.0), Float::from(0.0)),
+            right_ear: Point3::new(Float::from(1.0), Float::from(0.0), Float::from(0.0)),
+        })
+        .with(Transform::default())
+        .build();
+}
+
+/// Plays the sound of the ball coming off a paddle at the given volume, panned towards the side
+/// of the arena the ball is at, `across` being how far across it is from the left edge to the
+/// right one, from 0 to 1. Without an audio output this does nothing, like all the sounds
+/// below.
+pub fn play_bounce_sound(
+    sounds: &Sounds,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+    across: f32,
+    entities: &Entities,
+    lazy: &LazyUpdate,
+) {
+    if output.is_some() {
+        play_at(&sounds.bounce_sfx, storage, volume, across, entities, lazy);
+    }
+}
+
+/// Plays the sound of the ball coming off a wall or an obstacle, panned like the bounce sound.
+pub fn play_wall_sound(
+    sounds: &Sounds,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+    across: f32,
+    entities: &Entities,
+    lazy: &LazyUpdate,
+) {
+    if output.is_some() {
+        play_at(&sounds.wall_sfx, storage, volume, across, entities, lazy);
+    }
+}
+
+/// Plays the sound of a player scoring.
+pub fn play_score_sound(
+    sounds: &Sounds,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+) {
+    play_once(&sounds.score_sfx, storage, output, volume);
+}
+
+/// Plays the sound of a shield stopping a goal.
+pub fn play_shield_sound(
+    sounds: &Sounds,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+) {
+    play_once(&sounds.shield_sfx, storage, output, volume);
+}
+
+/// Plays the crowd cheering a point.
+pub fn play_cheer(
+    crowd: &Crowd,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+) {
+    play_once(&crowd.cheer, storage, output, volume);
+}
+
+/// Plays the fanfare for the winner of a match at the given volume.
+pub fn play_fanfare(
+    sounds: &Sounds,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+) {
+    play_once(&sounds.fanfare_sfx, storage, output, volume);
+}
+
+fn play_once(
+    handle: &SourceHandle,
+    storage: &AssetStorage<Source>,
+    output: Option<&Output>,
+    volume: f32,
+) {
+    if let Some(output) = output {
+        if let Some(sound) = storage.get(handle) {
+            output.play_once(sound, volume);
+        }
+    }
+}
+
+// Plays the sound from an emitter in front of the listener, to the left or right by how far
+// `across` the arena it is. The `AudioSystem` pans it between the listener's ears. It also turns
+// sounds down by the square of their distance to the ears, so the emitter is moved back from
+// them to play the sound at `volume`.
+fn play_at(
+    handle: &SourceHandle,
+    storage: &AssetStorage<Source>,
+    volume: f32,
+    across: f32,
+    entities: &Entities,
+    lazy: &LazyUpdate,
+) {
+    if volume <= 0.0 {
+        return;
+    }
+    let mut emitter = AudioEmitter::new();
+    match storage.get(handle) {
+        Some(sound) if emitter.play(sound).is_ok() => {}
+        _ => return,
+    }
+    let pan = (across * 2.0 - 1.0).clamp(-1.0, 1.0) * PAN_WIDTH;
+    let distance = (1.0 / volume - 1.0).max(0.0).sqrt();
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(pan, 0.0, distance);
+
+    lazy.create_entity(entities)
+        .with(emitter)
+        .with(transform)
+        .with(SoundEmitter {
+            remaining: SOUND_EMITTER_LIFETIME,
+        })
+        .build();
+}
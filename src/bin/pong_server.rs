@@ -0,0 +1,18 @@
+//! The dedicated server, a lobby that plays the online matches itself instead of relaying the
+//! players' inputs to each other. It needs no window or graphics, e.g. for running on a VPS:
+//!
+//! `pong_server 0.0.0.0:7777`
+
+use std::io;
+
+use pong::lobby;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:7777";
+
+fn main() -> io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    amethyst::start_logger(Default::default());
+    lobby::serve(&address, true)
+}
@@ -0,0 +1,296 @@
+use amethyst::{
+    ecs::Resources,
+    renderer::{
+        rendy::{
+            command::{QueueId, RenderPassEncoder},
+            factory::Factory,
+            graph::{
+                render::{
+                    Layout, PrepareResult, SetLayout, SimpleGraphicsPipeline,
+                    SimpleGraphicsPipelineDesc,
+                },
+                GraphBuilder, GraphContext, ImageAccess, ImageId, NodeBuffer, NodeId, NodeImage,
+            },
+            hal::{
+                self,
+                format::{Format, Swizzle},
+                image::{Filter, Kind, SamplerInfo, ViewKind, WrapMode},
+                pso::{
+                    BlendState, ColorBlendDesc, ColorMask, DepthStencilDesc, Descriptor,
+                    DescriptorType, ShaderStageFlags,
+                },
+                Device,
+            },
+            resource::{
+                DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo,
+                Sampler,
+            },
+            shader::{ShaderSet, ShaderSetBuilder, SpirvShader},
+        },
+        types::Backend,
+        util::{desc_write, set_layout_bindings},
+        SubpassBuilder,
+    },
+};
+
+/// How many texels apart the blur reads its samples, which spreads the glow further out than
+/// the nine texels of its gaussian would.
+const BLUR_SPREAD: f32 = 2.0;
+
+/// Adds the passes of the bloom to the graph, after the pass `after` that draws the `scene`.
+/// They draw the scene with its glow onto `target`, the returned node is the last of them.
+/// Their images are the `kind` and `format` of the scene.
+pub fn add_bloom<B: Backend>(
+    graph_builder: &mut GraphBuilder<B, Resources>,
+    kind: Kind,
+    format: Format,
+    scene: ImageId,
+    target: ImageId,
+    after: NodeId,
+) -> NodeId {
+    // Every pass draws over the whole of its image, so none of them are cleared.
+    let bright = graph_builder.create_image(kind, 1, format, None);
+    let blurred_across = graph_builder.create_image(kind, 1, format, None);
+    let blurred = graph_builder.create_image(kind, 1, format, None);
+
+    let passes = [
+        (DrawBloomDesc::BrightPass, vec![scene], bright),
+        (DrawBloomDesc::HorizontalBlur, vec![bright], blurred_across),
+        (DrawBloomDesc::VerticalBlur, vec![blurred_across], blurred),
+        (DrawBloomDesc::Composite, vec![scene, blurred], target),
+    ];
+    let mut last = after;
+    for (stage, images, color) in passes.iter() {
+        let group = images
+            .iter()
+            .fold(stage.builder(), |group, image| group.with_image(*image))
+            .with_dependency(last);
+        last = graph_builder.add_node(
+            SubpassBuilder::new()
+                .with_group(group)
+                .with_color(*color)
+                .into_pass(),
+        );
+    }
+    last
+}
+
+/// One pass of the bloom, which makes the ball, the particles and anything else bright enough
+/// glow. The bright pass keeps what's bright in the scene, the two blurs spread it across and
+/// down, and the composite draws the scene with the blurred glow on top of it.
+///
+/// Every pass draws a triangle over the whole screen and reads the images it's built with, the
+/// composite the scene and then the glow. The GLSL of the shaders is in `shaders/` and their
+/// SPIR-V in `shaders/compiled/`.
+#[derive(Clone, Copy, Debug)]
+pub enum DrawBloomDesc {
+    BrightPass,
+    HorizontalBlur,
+    VerticalBlur,
+    Composite,
+}
+
+impl DrawBloomDesc {
+    fn image_count(self) -> usize {
+        match self {
+            DrawBloomDesc::Composite => 2,
+            _ => 1,
+        }
+    }
+
+    fn is_blur(self) -> bool {
+        matches!(
+            self,
+            DrawBloomDesc::HorizontalBlur | DrawBloomDesc::VerticalBlur
+        )
+    }
+
+    fn fragment_shader(self) -> &'static [u8] {
+        match self {
+            DrawBloomDesc::BrightPass => {
+                include_bytes!("../shaders/compiled/bloom_bright.frag.spv")
+            }
+            DrawBloomDesc::HorizontalBlur | DrawBloomDesc::VerticalBlur => {
+                include_bytes!("../shaders/compiled/bloom_blur.frag.spv")
+            }
+            DrawBloomDesc::Composite => {
+                include_bytes!("../shaders/compiled/bloom_composite.frag.spv")
+            }
+        }
+    }
+}
+
+impl<B: Backend> SimpleGraphicsPipelineDesc<B, Resources> for DrawBloomDesc {
+    type Pipeline = DrawBloom<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        let access = ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: hal::pso::PipelineStage::FRAGMENT_SHADER,
+        };
+        vec![access; self.image_count()]
+    }
+
+    fn colors(&self) -> Vec<ColorBlendDesc> {
+        vec![ColorBlendDesc(ColorMask::ALL, BlendState::Off)]
+    }
+
+    fn depth_stencil(&self) -> Option<DepthStencilDesc> {
+        None
+    }
+
+    fn layout(&self) -> Layout {
+        // The blurs are told how far apart the texels are along their direction.
+        let push_constants = if self.is_blur() {
+            vec![(ShaderStageFlags::FRAGMENT, 0..8)]
+        } else {
+            Vec::new()
+        };
+        Layout {
+            sets: vec![SetLayout {
+                bindings: set_layout_bindings(Some((
+                    self.image_count() as u32,
+                    DescriptorType::CombinedImageSampler,
+                    ShaderStageFlags::FRAGMENT,
+                ))),
+            }],
+            push_constants,
+        }
+    }
+
+    fn load_shader_set(&self, factory: &mut Factory<B>, _aux: &Resources) -> ShaderSet<B> {
+        let vertex = SpirvShader::new(
+            include_bytes!("../shaders/compiled/fullscreen.vert.spv").to_vec(),
+            ShaderStageFlags::VERTEX,
+            "main",
+        );
+        let fragment = SpirvShader::new(
+            self.fragment_shader().to_vec(),
+            ShaderStageFlags::FRAGMENT,
+            "main",
+        );
+        ShaderSetBuilder::default()
+            .with_vertex(&vertex)
+            .expect("bloom vertex shader should load")
+            .with_fragment(&fragment)
+            .expect("bloom fragment shader should load")
+            .build(factory, Default::default())
+            .expect("bloom shaders should build")
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &Resources,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+        set_layouts: &[Handle<DescriptorSetLayout<B>>],
+    ) -> Result<DrawBloom<B>, failure::Error> {
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))?;
+        let set = factory.create_descriptor_set(set_layouts[0].clone())?;
+
+        let mut views = Vec::with_capacity(images.len());
+        let mut texel_step = None;
+        for (binding, node_image) in images.iter().enumerate() {
+            let image = ctx
+                .get_image(node_image.id)
+                .ok_or_else(|| failure::format_err!("The bloom pass has no image to read"))?;
+            // The blurs read a single image, the step is a texel of it or a few.
+            let extent = image.kind().extent();
+            texel_step = match self {
+                DrawBloomDesc::HorizontalBlur => Some([BLUR_SPREAD / extent.width as f32, 0.0]),
+                DrawBloomDesc::VerticalBlur => Some([0.0, BLUR_SPREAD / extent.height as f32]),
+                _ => None,
+            };
+
+            let view = factory.create_image_view(
+                image.clone(),
+                ImageViewInfo {
+                    view_kind: ViewKind::D2,
+                    format: image.format(),
+                    swizzle: Swizzle::NO,
+                    range: node_image.range.clone(),
+                },
+            )?;
+            unsafe {
+                factory.write_descriptor_sets(vec![desc_write(
+                    set.raw(),
+                    binding as u32,
+                    Descriptor::CombinedImageSampler(view.raw(), node_image.layout, sampler.raw()),
+                )]);
+            }
+            views.push(view);
+        }
+
+        Ok(DrawBloom {
+            set,
+            views,
+            sampler,
+            texel_step,
+        })
+    }
+}
+
+/// The pipeline of a `DrawBloomDesc`, it keeps the images of its pass bound for its shaders to
+/// sample.
+#[derive(Debug)]
+pub struct DrawBloom<B: Backend> {
+    set: Escape<DescriptorSet<B>>,
+    views: Vec<Escape<ImageView<B>>>,
+    sampler: Handle<Sampler<B>>,
+    /// How far apart the texels the blurs read are, in texture coordinates.
+    texel_step: Option<[f32; 2]>,
+}
+
+impl<B: Backend> SimpleGraphicsPipeline<B, Resources> for DrawBloom<B> {
+    type Desc = DrawBloomDesc;
+
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _set_layouts: &[Handle<DescriptorSetLayout<B>>],
+        _index: usize,
+        _aux: &Resources,
+    ) -> PrepareResult {
+        // Like the CRT pass the same triangle is drawn every frame, only the images change.
+        PrepareResult::DrawReuse
+    }
+
+    fn draw(
+        &mut self,
+        layout: &B::PipelineLayout,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _aux: &Resources,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            if let Some([x, y]) = self.texel_step {
+                encoder.push_constants(
+                    layout,
+                    ShaderStageFlags::FRAGMENT,
+                    0,
+                    &[x.to_bits(), y.to_bits()],
+                );
+            }
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self, _factory: &mut Factory<B>, _aux: &Resources) {
+        // The descriptor set goes first, the views and sampler are still bound to it.
+        drop(self.set);
+        drop(self.views);
+        drop(self.sampler);
+    }
+}
@@ -0,0 +1,163 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{Entity, Join},
+    input::is_close_requested,
+    prelude::*,
+    ui::UiText,
+};
+
+use crate::{
+    audio::{play_fanfare, Music, Sounds, FANFARE_DUCK},
+    config::AudioSettings,
+    confirm::ConfirmQuit,
+    menu::{menu_input, MenuInput},
+    pong::{Paddle, RunningState, ScoreText, Side},
+    theme::Theme,
+};
+
+/// How long the celebration lasts, in seconds.
+const CELEBRATION_DURATION: f32 = 2.5;
+/// How many times the winning paddle hops during the celebration, and how far out into the
+/// arena it gets.
+const HOPS: f32 = 4.0;
+const HOP_HEIGHT: f32 = 4.0;
+/// How many times a second the winner's score flashes.
+const FLASH_RATE: f32 = 4.0;
+
+/// Pushed on top of the `Pong` state once a match is won, before the results are shown. The
+/// winning paddles hop, the winner's score flashes and a fanfare plays while the match stays
+/// paused underneath.
+///
+/// Enter or escape skips straight to the results.
+pub struct Celebration {
+    winner: Side,
+    timer: f32,
+    /// The winning paddles and where they stood when the match was won.
+    paddles: Vec<(Entity, Float, Float)>,
+    previous: RunningState,
+}
+
+impl Celebration {
+    pub fn new(winner: Side) -> Celebration {
+        Celebration {
+            winner,
+            timer: CELEBRATION_DURATION,
+            paddles: Vec::new(),
+            previous: RunningState::Running,
+        }
+    }
+
+    /// Colors the score of the winner.
+    fn color_score(&self, world: &World, color: [f32; 4]) {
+        let score = match world.read_resource::<ScoreText>().for_side(self.winner) {
+            Some(score) => score,
+            None => return,
+        };
+        if let Some(text) = world.write_storage::<UiText>().get_mut(score) {
+            text.color = color;
+        }
+    }
+
+    /// Moves the winning paddles and flashes the score for the time the celebration has run.
+    fn animate(&self, world: &World) {
+        let elapsed = CELEBRATION_DURATION - self.timer;
+        let progress = elapsed / CELEBRATION_DURATION;
+        let hop = (progress * HOPS * std::f32::consts::PI).sin().abs() * HOP_HEIGHT;
+        let offset = self.winner.normal() * hop;
+
+        let mut transforms = world.write_storage::<Transform>();
+        for (entity, x, y) in &self.paddles {
+            if let Some(transform) = transforms.get_mut(*entity) {
+                transform.set_translation_x(*x + Float::from(offset.x));
+                transform.set_translation_y(*y + Float::from(offset.y));
+            }
+        }
+
+        let lit = (elapsed * FLASH_RATE).fract() < 0.5;
+        let color = {
+            let theme = world.read_resource::<Theme>();
+            if lit {
+                theme.score_flash_color
+            } else {
+                theme.score_color
+            }
+        };
+        self.color_score(world, color);
+    }
+}
+
+impl SimpleState for Celebration {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        {
+            let mut running = world.write_resource::<RunningState>();
+            self.previous = *running;
+            *running = RunningState::Paused;
+        }
+
+        self.paddles = {
+            let entities = world.entities();
+            let paddles = world.read_storage::<Paddle>();
+            let transforms = world.read_storage::<Transform>();
+            (&entities, &paddles, &transforms)
+                .join()
+                .filter(|(_, paddle, _)| paddle.side == self.winner)
+                .map(|(entity, _, transform)| {
+                    (entity, transform.translation().x, transform.translation().y)
+                })
+                .collect()
+        };
+
+        let volume = world.read_resource::<AudioSettings>().sfx_volume();
+        play_fanfare(
+            &world.read_resource::<Sounds>(),
+            &world.read_resource::<AssetStorage<Source>>(),
+            world.res.try_fetch::<Output>().as_deref(),
+            volume,
+        );
+        world.write_resource::<Music>().duck(FANFARE_DUCK);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        *world.write_resource::<RunningState>() = self.previous;
+
+        let mut transforms = world.write_storage::<Transform>();
+        for (entity, x, y) in self.paddles.drain(..) {
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.set_translation_x(x);
+                transform.set_translation_y(y);
+            }
+        }
+        let color = world.read_resource::<Theme>().score_color;
+        self.color_score(world, color);
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+        match menu_input(&event) {
+            Some(MenuInput::Select) | Some(MenuInput::Back) => Trans::Pop,
+            _ => Trans::None,
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.timer -= data.world.res.fetch::<Time>().delta_seconds();
+        if self.timer <= 0.0 {
+            // Back to the match, which goes on to the results.
+            return Trans::Pop;
+        }
+        self.animate(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,481 @@
+use std::{fs, path::PathBuf};
+
+use amethyst::{
+    config::{Config, ConfigError},
+    input::{Bindings, InputHandler, StringBindings},
+    prelude::World,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    palette::PaletteName,
+    pong::{
+        Side, ARENA_HEIGHT, ARENA_WIDTH, BALL_RADIUS, BALL_VELOCITY_X, BALL_VELOCITY_Y,
+        PADDLE_HEIGHT, PADDLE_SPEED, PADDLE_WIDTH, WIN_SCORE,
+    },
+    skin::DEFAULT_SKIN,
+    theme::DEFAULT_THEME,
+};
+
+/// The `GameConfig` the game ships with, used until the player's `UserSettings` are saved.
+pub const GAME_CONFIG_PATH: &str = "resources/game_config.ron";
+/// The key bindings the game ships with.
+pub const BINDINGS_CONFIG_PATH: &str = "resources/bindings_config.ron";
+/// The player names the game ships with, none.
+pub const PLAYER_NAMES_PATH: &str = "resources/player_names.ron";
+/// The `AudioSettings` the game ships with.
+pub const AUDIO_SETTINGS_PATH: &str = "resources/audio_settings.ron";
+/// The name of the file the `UserSettings` are kept in, in `settings_dir()`.
+pub const SETTINGS_FILE: &str = "settings.ron";
+/// The name of the file the `HighScores` are kept in, next to the settings.
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+/// The name of the file the `Profiles` are kept in, next to the settings.
+pub const PROFILES_FILE: &str = "profiles.ron";
+/// The name of the file the `MatchHistory` is kept in, next to the settings.
+pub const HISTORY_FILE: &str = "history.ron";
+/// The name of the file the `AchievementProgress` is kept in, next to the settings.
+pub const ACHIEVEMENT_PROGRESS_FILE: &str = "achievement_progress.ron";
+/// The name of the file with where the cloud sync syncs the saves with and which of them it last
+/// saw, next to the settings. It's never synced itself.
+pub const CLOUD_SYNC_FILE: &str = "cloud_sync.ron";
+/// Where the achievements there are to unlock are defined.
+pub const ACHIEVEMENTS_PATH: &str = "resources/achievements.ron";
+/// The name of the file the leaderboard is kept in as it was last fetched, for showing it
+/// offline, next to the settings.
+pub const LEADERBOARD_CACHE_FILE: &str = "leaderboard_cache.ron";
+/// The name of the file the online match results wait in until they reach the leaderboard, next
+/// to the settings.
+pub const LEADERBOARD_QUEUE_FILE: &str = "leaderboard_queue.ron";
+/// The lobby server online matches are found through when the config doesn't say, one run
+/// with `cargo run -- lobby_server=0.0.0.0:7777` on the same machine.
+pub const DEFAULT_LOBBY_ADDRESS: &str = "127.0.0.1:7777";
+
+/// How finished frames are put on the screen, picked in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PresentMode {
+    /// Frames wait for the display to refresh, so they never tear.
+    #[default]
+    Vsync,
+    /// Frames wait for the refresh like with vsync, but a newer frame replaces one that's
+    /// still waiting.
+    Mailbox,
+    /// Frames are shown as soon as they're drawn, with the least latency and some tearing.
+    Immediate,
+}
+
+impl PresentMode {
+    pub const ALL: [PresentMode; 3] =
+        [PresentMode::Vsync, PresentMode::Mailbox, PresentMode::Immediate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PresentMode::Vsync => "vsync",
+            PresentMode::Mailbox => "mailbox",
+            PresentMode::Immediate => "immediate",
+        }
+    }
+}
+
+/// How many samples the sprites are drawn with per pixel, which smooths the edges of the
+/// round and turned ones. Picked in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Msaa {
+    #[default]
+    Off,
+    X2,
+    X4,
+}
+
+impl Msaa {
+    pub const ALL: [Msaa; 3] = [Msaa::Off, Msaa::X2, Msaa::X4];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Msaa::Off => "off",
+            Msaa::X2 => "2x",
+            Msaa::X4 => "4x",
+        }
+    }
+
+    pub fn samples(self) -> u8 {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+        }
+    }
+}
+
+/// How new key bindings are kept, picked in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum KeyMode {
+    /// By what the key is called in the keyboard layout, so W is W on any layout.
+    #[default]
+    Names,
+    /// By where the key is on the keyboard, so the keys under W, A, S and D on a QWERTY
+    /// keyboard move the paddle on an AZERTY or Dvorak one too.
+    Positions,
+}
+
+impl KeyMode {
+    pub const ALL: [KeyMode; 2] = [KeyMode::Names, KeyMode::Positions];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyMode::Names => "name",
+            KeyMode::Positions => "position",
+        }
+    }
+}
+
+/// How the paddles answer the sticks of the gamepads, set in the settings.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StickResponse {
+    /// How far the stick has to be pushed before the paddle moves, from 0.0 to 1.0. Worn or
+    /// cheap sticks that drift stay inside it.
+    pub dead_zone: f32,
+    /// The push past the dead zone is raised to this power, above 1.0 gives finer control near
+    /// the middle and 1.0 keeps it linear.
+    pub curve: f32,
+    /// How much of its last movement the paddle keeps every sixtieth of a second, from 0.0 for
+    /// following the stick right away to just under 1.0. Smooths out a jittery stick.
+    pub smoothing: f32,
+}
+
+impl Default for StickResponse {
+    fn default() -> Self {
+        StickResponse {
+            dead_zone: 0.15,
+            curve: 1.0,
+            smoothing: 0.0,
+        }
+    }
+}
+
+impl StickResponse {
+    /// How far a stick pushed this far, from -1.0 to 1.0, moves the paddle, before smoothing.
+    pub fn shape(&self, push: f32) -> f32 {
+        let past_dead_zone = (push.abs() - self.dead_zone).max(0.0) / (1.0 - self.dead_zone);
+        past_dead_zone.min(1.0).powf(self.curve) * push.signum()
+    }
+
+    /// Moves the `smoothed` stick on towards `shaped` for a frame `delta` seconds long.
+    pub fn smooth(&self, smoothed: f32, shaped: f32, delta: f32) -> f32 {
+        let kept = self.smoothing.powf(delta * 60.0);
+        smoothed * kept + shaped * (1.0 - kept)
+    }
+}
+
+/// The player's preferences, kept in the `UserSettings`. `resources/game_config.ron` has the
+/// ones the game starts out with, and the `Gameplay` it's played at.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// Whether the window covers the whole screen.
+    pub fullscreen: bool,
+    /// Width and height of the window while it isn't fullscreen.
+    pub resolution: (u32, u32),
+    /// How frames are presented, vsync is used when the one picked isn't supported.
+    pub present_mode: PresentMode,
+    /// The multisampling of the sprites, fewer samples are used when the GPU can't do as many.
+    pub msaa: Msaa,
+    /// Name of the UI theme, one of the files in `assets/themes/`.
+    pub theme: String,
+    /// Whether the announcer calls out the points and long rallies.
+    pub announcer: bool,
+    /// Whether spin curves the ball's path in flight (the Magnus effect).
+    pub magnus_effect: bool,
+    /// Scales how strongly spin curves the ball, 1.0 turns the velocity by the spin's full
+    /// radians per second.
+    pub curve_strength: f32,
+    /// Seconds the trail behind the ball takes to fade out, 0.0 leaves no trail.
+    pub trail_length: f32,
+    /// Scales how hard the screen shakes on goals and power shots, 0.0 keeps it still.
+    pub screen_shake: f32,
+    /// Draws the game as if on an old CRT, with scanlines and a curved screen.
+    pub crt: bool,
+    /// Makes the ball, the particles and everything else bright glow.
+    pub bloom: bool,
+    /// The colors of the paddles, balls, background and UI.
+    pub palette: PaletteName,
+    /// Name of the sprite sheet the arena is drawn with, one of the directories in
+    /// `assets/skins/` or the default one.
+    pub skin: String,
+    /// The player whose paddle follows the mouse instead of their keys, if any.
+    pub mouse_paddle: Option<Side>,
+    /// How far the paddle goes for how far the mouse does, at 1.0 the paddle is right where the
+    /// mouse points.
+    pub mouse_sensitivity: f32,
+    /// How the paddles answer the gamepad sticks.
+    pub stick: StickResponse,
+    /// Whether keys are bound by their name or their position, the bindings are converted when
+    /// it's changed.
+    pub key_mode: KeyMode,
+    /// Whether the gamepads rumble when their player hits the ball or concedes a goal.
+    pub rumble: bool,
+    /// Where the lobby server online matches are found through is, as a host and port.
+    pub lobby_address: String,
+    /// The HTTPS leaderboard the results of online matches are submitted to, if any. They're
+    /// posted as JSON to `<url>/results`, and the board is read from `<url>/top?count=100`.
+    pub leaderboard_url: Option<String>,
+    /// Whether what's being played shows on the player's Discord profile, in builds with the
+    /// `discord` feature.
+    pub discord_presence: bool,
+    /// The Discord application the status is shown as, registered on Discord's developer
+    /// portal. Nothing is shown without one.
+    pub discord_application_id: Option<u64>,
+    /// The Twitch channel whose chat plays the right paddle in a `chat_plays` match, by typing
+    /// up or down.
+    pub twitch_channel: Option<String>,
+    /// Whether this file, the theme's and `resources/bindings_config.ron` are read again
+    /// whenever they change while the game runs, for tuning it without restarting. Only the
+    /// `Gameplay` and this are taken from this file, and the controls changed in the bindings
+    /// file. Always read from `resources/game_config.ron`, like the `Gameplay`.
+    #[serde(skip_serializing)]
+    pub hot_reload: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            fullscreen: false,
+            resolution: (500, 500),
+            present_mode: PresentMode::Vsync,
+            msaa: Msaa::Off,
+            theme: DEFAULT_THEME.to_string(),
+            announcer: true,
+            magnus_effect: true,
+            curve_strength: 1.0,
+            trail_length: 0.15,
+            screen_shake: 1.0,
+            crt: false,
+            bloom: false,
+            palette: PaletteName::Classic,
+            skin: DEFAULT_SKIN.to_string(),
+            mouse_paddle: None,
+            mouse_sensitivity: 1.0,
+            stick: StickResponse::default(),
+            key_mode: KeyMode::Names,
+            rumble: true,
+            lobby_address: DEFAULT_LOBBY_ADDRESS.to_string(),
+            leaderboard_url: None,
+            discord_presence: true,
+            discord_application_id: None,
+            twitch_channel: None,
+            hot_reload: false,
+        }
+    }
+}
+
+/// The sizes and speeds of the arena, paddles and ball, tuned in `resources/game_config.ron`
+/// without recompiling. They're read from there, rather than kept with the player's settings,
+/// as every match starts and put in as a resource, for the entities to be made and moved by.
+/// That resource is the only copy. Online matches and their replays are always played at the
+/// defaults, which the other player and the server play them at too.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Gameplay {
+    /// The size of the arena, in arena units.
+    pub arena_width: f32,
+    pub arena_height: f32,
+    pub paddle_width: f32,
+    pub paddle_height: f32,
+    /// How far a paddle moves in a frame at full push, in arena units.
+    pub paddle_speed: f32,
+    pub ball_radius: f32,
+    /// How fast the ball is served, in arena units per second.
+    pub ball_velocity_x: f32,
+    pub ball_velocity_y: f32,
+    /// Points games are played to, unless the match is set up otherwise.
+    pub win_score: i32,
+}
+
+impl Gameplay {
+    /// The ones in `resources/game_config.ron`, the defaults if it can't be read.
+    pub fn load_shipped() -> Gameplay {
+        ShippedConfig::load(GAME_CONFIG_PATH).gameplay
+    }
+}
+
+impl Default for Gameplay {
+    fn default() -> Self {
+        Gameplay {
+            arena_width: ARENA_WIDTH,
+            arena_height: ARENA_HEIGHT,
+            paddle_width: PADDLE_WIDTH,
+            paddle_height: PADDLE_HEIGHT,
+            paddle_speed: PADDLE_SPEED,
+            ball_radius: BALL_RADIUS,
+            ball_velocity_x: BALL_VELOCITY_X,
+            ball_velocity_y: BALL_VELOCITY_Y,
+            win_score: WIN_SCORE,
+        }
+    }
+}
+
+/// What's always read from `resources/game_config.ron` rather than the player's settings, the
+/// rest of the file is skipped over.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShippedConfig {
+    pub hot_reload: bool,
+    pub gameplay: Gameplay,
+}
+
+/// How loud the game plays, kept in the `UserSettings`. Every volume goes from 0.0 for silent
+/// to 1.0, changes in the settings are heard right away.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// Scales the music and the sound effects alike.
+    pub master: f32,
+    pub music: f32,
+    /// Volume of the sound effects.
+    pub sfx: f32,
+    /// Silences everything, toggled with the `mute` action. The game always starts with sound.
+    #[serde(skip)]
+    pub muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.0,
+            music: 0.7,
+            sfx: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// The volume the music plays at, with the master volume applied.
+    pub fn music_volume(&self) -> f32 {
+        self.master_volume() * self.music
+    }
+
+    /// The volume the sound effects play at, with the master volume applied.
+    pub fn sfx_volume(&self) -> f32 {
+        self.master_volume() * self.sfx
+    }
+
+    fn master_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master
+        }
+    }
+}
+
+/// The names the players typed in before their last match, by the side they played on.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PlayerNames {
+    pub left: String,
+    pub right: String,
+    pub top: String,
+    pub bottom: String,
+}
+
+impl PlayerNames {
+    /// The name of the player on the side, the side's own name if they didn't give one.
+    pub fn name(&self, side: Side) -> &str {
+        let name = match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+            Side::Top => &self.top,
+            Side::Bottom => &self.bottom,
+        };
+        if name.is_empty() {
+            side.name()
+        } else {
+            name
+        }
+    }
+
+    pub fn name_mut(&mut self, side: Side) -> &mut String {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+            Side::Top => &mut self.top,
+            Side::Bottom => &mut self.bottom,
+        }
+    }
+}
+
+/// Everything the player can change about the game, kept together in `settings.ron` between
+/// sessions. Whatever isn't in there yet is what the game ships with in `resources/`.
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct UserSettings {
+    pub game: GameConfig,
+    pub audio: AudioSettings,
+    /// The key and gamepad bindings, as changed on the controls screen.
+    pub controls: Bindings<StringBindings>,
+    pub names: PlayerNames,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            game: GameConfig::load(GAME_CONFIG_PATH),
+            audio: AudioSettings::load(AUDIO_SETTINGS_PATH),
+            controls: Bindings::load(BINDINGS_CONFIG_PATH),
+            names: PlayerNames::load(PLAYER_NAMES_PATH),
+        }
+    }
+}
+
+impl UserSettings {
+    /// The settings saved the last time the game was played, or the defaults the first time.
+    pub fn load_saved() -> UserSettings {
+        let path = settings_dir().join(SETTINGS_FILE);
+        let mut settings = if path.exists() {
+            UserSettings::load(path)
+        } else {
+            UserSettings::default()
+        };
+        settings.game.hot_reload = ShippedConfig::load(GAME_CONFIG_PATH).hot_reload;
+        settings
+    }
+
+    /// The settings as they are now, out of the world's resources.
+    pub fn of(world: &World) -> UserSettings {
+        UserSettings {
+            game: world.read_resource::<GameConfig>().clone(),
+            audio: world.read_resource::<AudioSettings>().clone(),
+            controls: world
+                .read_resource::<InputHandler<StringBindings>>()
+                .bindings
+                .clone(),
+            names: world.read_resource::<PlayerNames>().clone(),
+        }
+    }
+
+    /// Writes the settings to `settings.ron`, so they're kept for the next time the game is
+    /// started.
+    pub fn save(&self) {
+        if let Err(err) = write_to_settings_dir(self, SETTINGS_FILE) {
+            log::error!("Failed to save the settings: {}", err);
+        }
+    }
+}
+
+/// Where the `UserSettings` are kept: the `pong` directory in the platform's config directory,
+/// `~/.config` on Linux, `~/Library/Application Support` on macOS and `%APPDATA%` on Windows.
+/// `resources/` on platforms without one.
+pub fn settings_dir() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("pong"))
+        .unwrap_or_else(|| PathBuf::from("resources"))
+}
+
+/// Writes `file` to the file called `name` in the `settings_dir()`, which is made the first
+/// time.
+pub fn write_to_settings_dir<T: Config>(file: &T, name: &str) -> Result<(), ConfigError> {
+    let dir = settings_dir();
+    fs::create_dir_all(&dir)?;
+    file.write(dir.join(name))
+}
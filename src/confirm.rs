@@ -0,0 +1,140 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::{
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::RunningState,
+    theme::Theme,
+};
+
+/// Answers in the quit dialog, staying is picked to begin with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Answer {
+    Stay,
+    Quit,
+}
+
+impl Answer {
+    const ALL: [Answer; 2] = [Answer::Stay, Answer::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            Answer::Stay => "No",
+            Answer::Quit => "Yes, quit",
+        }
+    }
+}
+
+/// Asks "Are you sure?" before the game quits, pushed on top of whatever screen the player
+/// tried to quit from. A match underneath is paused while the dialog is up.
+///
+/// Up and down pick an answer, enter confirms it and escape goes back. Closing the window
+/// again while the dialog is up quits right away.
+#[derive(Default)]
+pub struct ConfirmQuit {
+    selected: usize,
+    /// What the match was doing before the dialog paused it.
+    previous: RunningState,
+    answer_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl ConfirmQuit {
+    /// Updates the text of every answer, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let labels = Answer::ALL.iter().map(|answer| answer.label());
+        refresh_rows(world, &self.answer_texts, labels, Some(self.selected));
+    }
+}
+
+impl SimpleState for ConfirmQuit {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        {
+            let mut running = world.write_resource::<RunningState>();
+            self.previous = *running;
+            *running = RunningState::Paused;
+        }
+
+        let theme = world.read_resource::<Theme>().clone();
+
+        let backdrop = world
+            .create_entity()
+            .with(UiTransform::new(
+                "confirm_backdrop".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 0., 0.8, 280., 140.,
+            ))
+            .with(theme.backdrop.clone())
+            .build();
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "confirm_title".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 35., 1., 270., 40.,
+            ))
+            .with(theme.text("Are you sure?".to_string(), 25.))
+            .build();
+        self.texts.push(backdrop);
+        self.texts.push(title);
+
+        for index in 0..Answer::ALL.len() {
+            let transform = UiTransform::new(
+                format!("confirm_{}", index), Anchor::Middle, Anchor::Middle,
+                0., -10. - 30. * index as f32, 1., 250., 30.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 20.))
+                .with(theme.button.clone())
+                .build();
+            self.answer_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        *data.world.write_resource::<RunningState>() = self.previous;
+        data.world
+            .delete_entities(&self.texts)
+            .expect("dialog text should be alive");
+        data.world
+            .delete_entities(&self.answer_texts)
+            .expect("dialog text should be alive");
+        self.texts.clear();
+        self.answer_texts.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        match menu_input(&event) {
+            Some(MenuInput::Up) => {
+                self.selected = (self.selected + Answer::ALL.len() - 1) % Answer::ALL.len();
+            }
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % Answer::ALL.len(),
+            Some(MenuInput::Select) => {
+                return match Answer::ALL[self.selected] {
+                    Answer::Stay => Trans::Pop,
+                    Answer::Quit => Trans::Quit,
+                };
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,296 @@
+use amethyst::input::{
+    Axis, BindingError, Bindings, Button, ControllerButton, InputHandler, StringBindings,
+    VirtualKeyCode,
+};
+
+use crate::{
+    config::KeyMode,
+    pong::{Lane, Side},
+};
+
+/// How many gamepads the players can be given in the settings.
+pub const GAMEPAD_COUNT: u32 = 8;
+
+/// The names the controls of one paddle go by in `bindings_config.ron`.
+#[derive(Clone, Copy, Debug)]
+pub struct Controls {
+    /// What the player is called on the settings screen.
+    pub player: &'static str,
+    /// The keys moving the paddle, an emulated axis.
+    pub paddle: &'static str,
+    /// Whether the paddle slides left and right, along the top or bottom of the arena.
+    pub horizontal: bool,
+    /// The stick of the player's gamepad, the d-pad of that gamepad moves the paddle as well.
+    pub stick: &'static str,
+    /// Serves the ball, in the lower lane of doubles it only charges power shots.
+    pub serve: &'static str,
+    pub dash: &'static str,
+}
+
+/// The controls of every paddle, the lower lane ones are only played in doubles.
+pub const CONTROLS: [Controls; 6] = [
+    Controls {
+        player: "left",
+        paddle: "left_paddle",
+        horizontal: false,
+        stick: "left_paddle_stick",
+        serve: "left_serve",
+        dash: "left_dash",
+    },
+    Controls {
+        player: "right",
+        paddle: "right_paddle",
+        horizontal: false,
+        stick: "right_paddle_stick",
+        serve: "right_serve",
+        dash: "right_dash",
+    },
+    Controls {
+        player: "left lower",
+        paddle: "left_lower_paddle",
+        horizontal: false,
+        stick: "left_lower_paddle_stick",
+        serve: "left_lower_hit",
+        dash: "left_lower_dash",
+    },
+    Controls {
+        player: "right lower",
+        paddle: "right_lower_paddle",
+        horizontal: false,
+        stick: "right_lower_paddle_stick",
+        serve: "right_lower_hit",
+        dash: "right_lower_dash",
+    },
+    Controls {
+        player: "top",
+        paddle: "top_paddle",
+        horizontal: true,
+        stick: "top_paddle_stick",
+        serve: "top_serve",
+        dash: "top_dash",
+    },
+    Controls {
+        player: "bottom",
+        paddle: "bottom_paddle",
+        horizontal: true,
+        stick: "bottom_paddle_stick",
+        serve: "bottom_serve",
+        dash: "bottom_dash",
+    },
+];
+
+/// Where the keys that move around between keyboard layouts are on a QWERTY one, by the scan
+/// codes winit reports for them on Windows and Linux. The other keys, like the arrows and the
+/// numpad, are in the same place on every layout and stay bound by their names.
+const KEY_POSITIONS: [(VirtualKeyCode, u32); 47] = [
+    (VirtualKeyCode::Key1, 2),
+    (VirtualKeyCode::Key2, 3),
+    (VirtualKeyCode::Key3, 4),
+    (VirtualKeyCode::Key4, 5),
+    (VirtualKeyCode::Key5, 6),
+    (VirtualKeyCode::Key6, 7),
+    (VirtualKeyCode::Key7, 8),
+    (VirtualKeyCode::Key8, 9),
+    (VirtualKeyCode::Key9, 10),
+    (VirtualKeyCode::Key0, 11),
+    (VirtualKeyCode::Minus, 12),
+    (VirtualKeyCode::Equals, 13),
+    (VirtualKeyCode::Q, 16),
+    (VirtualKeyCode::W, 17),
+    (VirtualKeyCode::E, 18),
+    (VirtualKeyCode::R, 19),
+    (VirtualKeyCode::T, 20),
+    (VirtualKeyCode::Y, 21),
+    (VirtualKeyCode::U, 22),
+    (VirtualKeyCode::I, 23),
+    (VirtualKeyCode::O, 24),
+    (VirtualKeyCode::P, 25),
+    (VirtualKeyCode::LBracket, 26),
+    (VirtualKeyCode::RBracket, 27),
+    (VirtualKeyCode::A, 30),
+    (VirtualKeyCode::S, 31),
+    (VirtualKeyCode::D, 32),
+    (VirtualKeyCode::F, 33),
+    (VirtualKeyCode::G, 34),
+    (VirtualKeyCode::H, 35),
+    (VirtualKeyCode::J, 36),
+    (VirtualKeyCode::K, 37),
+    (VirtualKeyCode::L, 38),
+    (VirtualKeyCode::Semicolon, 39),
+    (VirtualKeyCode::Apostrophe, 40),
+    (VirtualKeyCode::Grave, 41),
+    (VirtualKeyCode::Backslash, 43),
+    (VirtualKeyCode::Z, 44),
+    (VirtualKeyCode::X, 45),
+    (VirtualKeyCode::C, 46),
+    (VirtualKeyCode::V, 47),
+    (VirtualKeyCode::B, 48),
+    (VirtualKeyCode::N, 49),
+    (VirtualKeyCode::M, 50),
+    (VirtualKeyCode::Comma, 51),
+    (VirtualKeyCode::Period, 52),
+    (VirtualKeyCode::Slash, 53),
+];
+
+/// The button for the key in the same place as `button` on a QWERTY keyboard, by its position
+/// for `KeyMode::Positions` and by its name for `KeyMode::Names`. Keys not in `KEY_POSITIONS`
+/// and other buttons are kept as they are.
+pub fn convert_button(button: Button, mode: KeyMode) -> Button {
+    let converted = KEY_POSITIONS
+        .iter()
+        .find_map(|(key, scan_code)| match (button, mode) {
+            (Button::Key(pressed), KeyMode::Positions) if pressed == *key => {
+                Some(Button::ScanCode(*scan_code))
+            }
+            (Button::ScanCode(pressed), KeyMode::Names) if pressed == *scan_code => {
+                Some(Button::Key(*key))
+            }
+            _ => None,
+        });
+    converted.unwrap_or(button)
+}
+
+/// The bindings with every key converted by `convert_button`, which fails if two controls end
+/// up on the same key.
+pub fn convert_keys(
+    bindings: &Bindings<StringBindings>,
+    mode: KeyMode,
+) -> Result<Bindings<StringBindings>, BindingError<StringBindings>> {
+    let mut converted = Bindings::new();
+    for id in bindings.axes() {
+        let axis = match bindings.axis(id) {
+            Some(Axis::Emulated { pos, neg }) => Axis::Emulated {
+                pos: convert_button(*pos, mode),
+                neg: convert_button(*neg, mode),
+            },
+            Some(axis) => axis.clone(),
+            None => continue,
+        };
+        converted.insert_axis(id.clone(), axis)?;
+    }
+    for id in bindings.actions() {
+        for combo in bindings.action_bindings(id) {
+            let combo = combo.iter().map(|button| convert_button(*button, mode));
+            converted.insert_action_binding(id.clone(), combo)?;
+        }
+    }
+    Ok(converted)
+}
+
+/// Whether the button is on a gamepad.
+pub fn is_gamepad_button(button: &Button) -> bool {
+    matches!(button, Button::Controller(..))
+}
+
+/// The button bound to the action that's shown on the screen, the first key bound to it or its
+/// first gamepad button if it has no keys.
+pub fn action_button(bindings: &Bindings<StringBindings>, action: &str) -> Option<Button> {
+    let buttons: Vec<Button> = bindings
+        .action_bindings(action)
+        .filter_map(|combo| combo.first().cloned())
+        .collect();
+    buttons
+        .iter()
+        .find(|button| !is_gamepad_button(button))
+        .or_else(|| buttons.first())
+        .cloned()
+}
+
+impl Controls {
+    /// The controls of the paddle on the side, in its lane.
+    pub fn of(side: Side, lane: Lane) -> &'static Controls {
+        match (side, lane) {
+            (Side::Left, Lane::Lower) => &CONTROLS[2],
+            (Side::Right, Lane::Lower) => &CONTROLS[3],
+            (Side::Left, _) => &CONTROLS[0],
+            (Side::Right, _) => &CONTROLS[1],
+            (Side::Top, _) => &CONTROLS[4],
+            (Side::Bottom, _) => &CONTROLS[5],
+        }
+    }
+
+    /// The gamepad the player is given, the one their stick is bound to.
+    pub fn gamepad(&self, bindings: &Bindings<StringBindings>) -> Option<u32> {
+        match bindings.axis(self.stick) {
+            Some(Axis::Controller { controller_id, .. }) => Some(*controller_id),
+            _ => None,
+        }
+    }
+
+    /// Hands the player the gamepad, moving their stick and the gamepad buttons of their
+    /// actions over to it. Fails without changing anything if another player has it.
+    pub fn assign_gamepad(
+        &self,
+        bindings: &mut Bindings<StringBindings>,
+        gamepad: u32,
+    ) -> Result<(), BindingError<StringBindings>> {
+        let mut assigned = bindings.clone();
+        if let Some(Axis::Controller {
+            axis,
+            invert,
+            dead_zone,
+            ..
+        }) = assigned.remove_axis(self.stick)
+        {
+            let stick = Axis::Controller {
+                controller_id: gamepad,
+                axis,
+                invert,
+                dead_zone,
+            };
+            assigned.insert_axis(self.stick, stick)?;
+        }
+        for action in &[self.serve, self.dash] {
+            let combos: Vec<Vec<Button>> = assigned
+                .action_bindings(*action)
+                .map(|combo| combo.to_vec())
+                .collect();
+            for combo in combos {
+                if let [Button::Controller(_, button)] = combo.as_slice() {
+                    let button = *button;
+                    assigned
+                        .remove_action_binding(*action, &combo)
+                        .expect("binding was just read");
+                    assigned.insert_action_binding(
+                        action.to_string(),
+                        vec![Button::Controller(gamepad, button)],
+                    )?;
+                }
+            }
+        }
+        *bindings = assigned;
+        Ok(())
+    }
+
+    /// How far the player is moving their paddle, with the keys, the d-pad of their gamepad or
+    /// its `stick`, shaped and smoothed from the value `InputHandler` gives. Top and bottom
+    /// paddles take the d-pad's right and left.
+    pub fn movement(
+        &self,
+        input: &InputHandler<StringBindings>,
+        stick: Option<f32>,
+    ) -> Option<f64> {
+        let keys = input.axis_value(self.paddle);
+        let stick = stick.map(f64::from);
+        let dpad = self.gamepad(&input.bindings).map(|gamepad| {
+            let (pos, neg) = if self.horizontal {
+                (ControllerButton::DPadRight, ControllerButton::DPadLeft)
+            } else {
+                (ControllerButton::DPadUp, ControllerButton::DPadDown)
+            };
+            match (
+                input.controller_button_is_down(gamepad, pos),
+                input.controller_button_is_down(gamepad, neg),
+            ) {
+                (true, false) => 1.0,
+                (false, true) => -1.0,
+                _ => 0.0,
+            }
+        });
+        if keys.is_none() && stick.is_none() {
+            return None;
+        }
+        let total = keys.unwrap_or(0.0) + stick.unwrap_or(0.0) + dpad.unwrap_or(0.0);
+        Some(total.clamp(-1.0, 1.0))
+    }
+}
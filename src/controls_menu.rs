@@ -0,0 +1,443 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::{
+        is_close_requested, Axis, BindingError, Bindings, Button, ElementState, InputHandler,
+        StringBindings,
+    },
+    prelude::*,
+    ui::{Anchor, Interactable, UiEvent, UiEventType, UiText, UiTransform},
+    winit::{Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+};
+
+use crate::{
+    config::{GameConfig, KeyMode, UserSettings},
+    controls::{action_button, convert_button, is_gamepad_button, CONTROLS, GAMEPAD_COUNT},
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::button_name,
+    theme::Theme,
+};
+
+/// How many controls fit on the screen, the list scrolls to show the rest.
+const VISIBLE_ROWS: usize = 22;
+
+const HINT: &str = "Enter or a click rebinds a key, left and right change a gamepad";
+
+/// A key the players can rebind, named by its axis or action in `bindings_config.ron`.
+#[derive(Clone, Copy)]
+enum Control {
+    /// The key moving a paddle up, the positive side of an emulated axis.
+    AxisUp(&'static str),
+    /// The key moving a paddle down.
+    AxisDown(&'static str),
+    Action(&'static str),
+}
+
+impl Control {
+    /// The button the control is bound to, the key if an action has a gamepad button too.
+    fn button(self, bindings: &Bindings<StringBindings>) -> Option<Button> {
+        match self {
+            Control::AxisUp(axis) => match bindings.axis(axis) {
+                Some(Axis::Emulated { pos, .. }) => Some(*pos),
+                _ => None,
+            },
+            Control::AxisDown(axis) => match bindings.axis(axis) {
+                Some(Axis::Emulated { neg, .. }) => Some(*neg),
+                _ => None,
+            },
+            Control::Action(action) => action_button(bindings, action),
+        }
+    }
+
+    /// Whether the control is the axis or action `id`, `up` being the side of an axis.
+    fn is(self, id: &str, up: Option<bool>) -> bool {
+        match self {
+            Control::AxisUp(axis) => axis == id && up == Some(true),
+            Control::AxisDown(axis) => axis == id && up == Some(false),
+            Control::Action(action) => action == id && up.is_none(),
+        }
+    }
+
+    /// Binds the control to the key instead of what it was bound to, an action keeps its
+    /// gamepad buttons. Fails without changing anything if the key can't be bound.
+    fn bind(
+        self,
+        bindings: &mut Bindings<StringBindings>,
+        key: Button,
+    ) -> Result<(), BindingError<StringBindings>> {
+        let (axis, up) = match self {
+            Control::AxisUp(axis) => (axis, true),
+            Control::AxisDown(axis) => (axis, false),
+            Control::Action(action) => {
+                let old: Vec<Vec<Button>> = bindings
+                    .action_bindings(action)
+                    .filter(|combo| !combo.iter().any(is_gamepad_button))
+                    .map(|combo| combo.to_vec())
+                    .collect();
+                for combo in &old {
+                    bindings
+                        .remove_action_binding(action, combo)
+                        .expect("binding was just read");
+                }
+                if let Err(err) =
+                    bindings.insert_action_binding(action.to_string(), vec![key])
+                {
+                    // Put the old keys back so the action keeps working.
+                    for combo in old {
+                        bindings
+                            .insert_action_binding(action.to_string(), combo)
+                            .expect("binding was bound before");
+                    }
+                    return Err(err);
+                }
+                return Ok(());
+            }
+        };
+
+        let (pos, neg) = match bindings.axis(axis) {
+            Some(Axis::Emulated { pos, neg }) => (*pos, *neg),
+            _ => return Ok(()),
+        };
+        let new = if up {
+            Axis::Emulated { pos: key, neg }
+        } else {
+            Axis::Emulated { pos, neg: key }
+        };
+        bindings.insert_axis(axis, new).map(|_| ())
+    }
+}
+
+/// One of the rows of the controls screen.
+enum Row {
+    /// A rebindable key, with what it's called on the screen.
+    Key(String, Control),
+    /// The gamepad of a player, the index of their `Controls`.
+    Gamepad(usize),
+}
+
+impl Row {
+    /// Every player's keys and gamepad, followed by the keys of the whole game.
+    fn all() -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (index, controls) in CONTROLS.iter().enumerate() {
+            let (up, down) = if controls.horizontal {
+                ("right", "left")
+            } else {
+                ("up", "down")
+            };
+            // The lower lane in doubles doesn't serve, its hit action only charges power shots.
+            let serve = if controls.serve.ends_with("_hit") { "hit" } else { "serve" };
+            let name = |what| format!("{} {}", controls.player, what);
+            rows.push(Row::Key(name(up), Control::AxisUp(controls.paddle)));
+            rows.push(Row::Key(name(down), Control::AxisDown(controls.paddle)));
+            rows.push(Row::Key(name(serve), Control::Action(controls.serve)));
+            rows.push(Row::Key(name("dash"), Control::Action(controls.dash)));
+            rows.push(Row::Gamepad(index));
+        }
+        rows.push(Row::Key("mute".to_string(), Control::Action("mute")));
+        rows.push(Row::Key("FPS counter".to_string(), Control::Action("toggle_fps")));
+        rows.push(Row::Key("network stats".to_string(), Control::Action("toggle_net_stats")));
+        rows
+    }
+
+    fn describe(&self, bindings: &Bindings<StringBindings>) -> String {
+        match self {
+            Row::Key(name, control) => match control.button(bindings) {
+                Some(button) => format!("{} {}", name, button_name(&button)),
+                None => format!("{} unbound", name),
+            },
+            Row::Gamepad(index) => {
+                let controls = &CONTROLS[*index];
+                match controls.gamepad(bindings) {
+                    Some(gamepad) => format!("{} gamepad {}", controls.player, gamepad),
+                    None => format!("{} gamepad unbound", controls.player),
+                }
+            }
+        }
+    }
+}
+
+/// Rebinds the players' keys and hands out their gamepads, pushed from the settings. Changes
+/// apply right away, and are saved with the `UserSettings` when leaving the screen.
+///
+/// Up and down pick a control, Enter or clicking it waits for its new key and left and right
+/// give a player another gamepad. A key used by another control isn't bound, the screen says
+/// which control has it instead. Escape goes back.
+#[derive(Default)]
+pub struct ControlsMenu {
+    rows: Vec<Row>,
+    selected: usize,
+    /// The row shown first, the list scrolls to keep the selected one in view.
+    scroll: usize,
+    /// Whether the next key pressed is bound to the selected control.
+    rebinding: bool,
+    /// Shown in place of the hint, after a key could not be bound for example.
+    status: Option<String>,
+    hint: Option<Entity>,
+    row_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl ControlsMenu {
+    fn show(&mut self, world: &mut World) {
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "controls_title".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -30., 1., 500., 40.,
+            ))
+            .with(theme.text("Controls".to_string(), 30.))
+            .build();
+        let hint = world
+            .create_entity()
+            .with(UiTransform::new(
+                "controls_hint".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+                0., 30., 1., 600., 30.,
+            ))
+            .with(theme.text(String::new(), 15.))
+            .build();
+        self.texts.push(title);
+        self.texts.push(hint);
+        self.hint = Some(hint);
+
+        // The rows can be clicked, whichever control is shown in them.
+        for row in 0..self.rows.len().min(VISIBLE_ROWS) {
+            let transform = UiTransform::new(
+                format!("controls_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -55. - 16. * row as f32, 1., 400., 16.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 15.))
+                .with(Interactable)
+                .build();
+            self.row_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.texts)
+            .expect("controls text should be alive");
+        world
+            .delete_entities(&self.row_texts)
+            .expect("controls text should be alive");
+        self.texts.clear();
+        self.row_texts.clear();
+        self.hint = None;
+    }
+
+    /// Updates the text of every row, marking the selected one.
+    fn refresh(&mut self, world: &mut World) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let labels = self.rows.iter().skip(self.scroll).map(|row| row.describe(&input.bindings));
+        refresh_rows(world, &self.row_texts, labels, Some(self.selected - self.scroll));
+        let mut ui_text = world.write_storage::<UiText>();
+        if let Some(text) = self.hint.and_then(|hint| ui_text.get_mut(hint)) {
+            text.text = self.status.clone().unwrap_or_else(|| HINT.to_string());
+        }
+    }
+
+    /// What the control the key is bound to is called, if it's another one than `control`. A
+    /// key bound by its name clashes with the same key bound by its position on a QWERTY
+    /// keyboard. Controls not on the screen go by their name in `bindings_config.ron`.
+    fn clash(
+        &self,
+        bindings: &Bindings<StringBindings>,
+        control: Control,
+        key: Button,
+    ) -> Option<String> {
+        let position = convert_button(key, KeyMode::Positions);
+        let same = |button: &Button| convert_button(*button, KeyMode::Positions) == position;
+        let mut users = Vec::new();
+        for axis in bindings.axes() {
+            if let Some(Axis::Emulated { pos, neg }) = bindings.axis(axis) {
+                if same(pos) {
+                    users.push((axis.clone(), Some(true)));
+                }
+                if same(neg) {
+                    users.push((axis.clone(), Some(false)));
+                }
+            }
+        }
+        for action in bindings.actions() {
+            if bindings
+                .action_bindings(action)
+                .any(|combo| combo.len() == 1 && same(&combo[0]))
+            {
+                users.push((action.clone(), None));
+            }
+        }
+
+        let (id, up) = users.into_iter().find(|(id, up)| !control.is(id, *up))?;
+        let name = self.rows.iter().find_map(|row| match row {
+            Row::Key(name, other) if other.is(&id, up) => Some(name.clone()),
+            _ => None,
+        });
+        Some(name.unwrap_or_else(|| id.replace('_', " ")))
+    }
+
+    /// Binds the selected control to the key, Escape keeps the old one. When keys are bound by
+    /// their position the ones that move around between layouts are bound by their scan code.
+    fn rebind(&mut self, world: &mut World, key: VirtualKeyCode, scan_code: u32) {
+        self.rebinding = false;
+        self.status = None;
+        if key == VirtualKeyCode::Escape {
+            return;
+        }
+        let button = match world.read_resource::<GameConfig>().key_mode {
+            KeyMode::Positions => match convert_button(Button::Key(key), KeyMode::Positions) {
+                Button::ScanCode(_) => Button::ScanCode(scan_code),
+                button => button,
+            },
+            KeyMode::Names => Button::Key(key),
+        };
+        if let Some(Row::Key(_, control)) = self.rows.get(self.selected) {
+            let control = *control;
+            let mut input = world.write_resource::<InputHandler<StringBindings>>();
+            if let Some(other) = self.clash(&input.bindings, control, button) {
+                let key = button_name(&button);
+                self.status = Some(format!("{} is already used by {}", key, other));
+            } else if control.bind(&mut input.bindings, button).is_err() {
+                self.status = Some(format!("{} can't be bound", button_name(&button)));
+            }
+        }
+    }
+
+    /// Gives the player the next gamepad or the one before it, skipping the ones other players
+    /// have.
+    fn step_gamepad(&mut self, world: &mut World, index: usize, up: bool) {
+        let controls = &CONTROLS[index];
+        let mut input = world.write_resource::<InputHandler<StringBindings>>();
+        let current = match controls.gamepad(&input.bindings) {
+            Some(gamepad) => gamepad,
+            None => return,
+        };
+        let mut gamepad = current;
+        loop {
+            gamepad = if up {
+                (gamepad + 1) % GAMEPAD_COUNT
+            } else {
+                (gamepad + GAMEPAD_COUNT - 1) % GAMEPAD_COUNT
+            };
+            if gamepad == current {
+                self.status = Some("Every other gamepad is used by another player".to_string());
+                return;
+            }
+            if controls.assign_gamepad(&mut input.bindings, gamepad).is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// Waits for the new key of the selected control, or gives a player the next gamepad.
+    fn activate(&mut self, world: &mut World) {
+        self.status = None;
+        match self.rows.get(self.selected) {
+            Some(Row::Key(..)) => {
+                self.rebinding = true;
+                self.status = Some("Press the new key, Escape to keep the old one".to_string());
+            }
+            Some(Row::Gamepad(index)) => {
+                let index = *index;
+                self.step_gamepad(world, index, true);
+            }
+            None => {}
+        }
+    }
+}
+
+/// The key pressed in the event and its scan code.
+fn pressed_key(event: &Event) -> Option<(VirtualKeyCode, u32)> {
+    match event {
+        Event::WindowEvent {
+            event:
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(key),
+                            scancode,
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                },
+            ..
+        } => Some((*key, *scancode)),
+        _ => None,
+    }
+}
+
+impl SimpleState for ControlsMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.rows = Row::all();
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        UserSettings::of(data.world).save();
+        self.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        // Waiting for a new key, nothing else on the screen reacts until one is pressed.
+        if self.rebinding {
+            if let StateEvent::Window(event) = &event {
+                if let Some((key, scan_code)) = pressed_key(event) {
+                    self.rebind(data.world, key, scan_code);
+                    self.refresh(data.world);
+                }
+            }
+            return Trans::None;
+        }
+
+        if let StateEvent::Ui(UiEvent {
+            event_type: UiEventType::Click,
+            target,
+        }) = &event
+        {
+            if let Some(row) = self.row_texts.iter().position(|text| text == target) {
+                self.selected = self.scroll + row;
+                self.activate(data.world);
+                self.refresh(data.world);
+            }
+            return Trans::None;
+        }
+
+        let rows = self.rows.len();
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.selected = (self.selected + rows - 1) % rows,
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % rows,
+            Some(input @ MenuInput::Left) | Some(input @ MenuInput::Right) => {
+                self.status = None;
+                if let Some(Row::Gamepad(index)) = self.rows.get(self.selected) {
+                    let index = *index;
+                    self.step_gamepad(data.world, index, input == MenuInput::Right);
+                }
+            }
+            Some(MenuInput::Select) => self.activate(data.world),
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,179 @@
+use amethyst::{
+    ecs::Resources,
+    renderer::{
+        rendy::{
+            command::{QueueId, RenderPassEncoder},
+            factory::Factory,
+            graph::{
+                render::{
+                    Layout, PrepareResult, SetLayout, SimpleGraphicsPipeline,
+                    SimpleGraphicsPipelineDesc,
+                },
+                GraphContext, ImageAccess, NodeBuffer, NodeImage,
+            },
+            hal::{
+                self,
+                format::Swizzle,
+                image::{Filter, SamplerInfo, ViewKind, WrapMode},
+                pso::{
+                    BlendState, ColorBlendDesc, ColorMask, DepthStencilDesc, Descriptor,
+                    DescriptorType, ShaderStageFlags,
+                },
+                Device,
+            },
+            resource::{
+                DescriptorSet, DescriptorSetLayout, Escape, Handle, ImageView, ImageViewInfo,
+                Sampler,
+            },
+            shader::{ShaderSet, ShaderSetBuilder, SpirvShader},
+        },
+        types::Backend,
+        util::{desc_write, set_layout_bindings},
+    },
+};
+
+/// Draws the rendered scene over the whole screen as if on an old CRT, with the glass bent,
+/// scanlines across it and the corners in shadow. The scene is the one image the pass is built
+/// with, the GLSL of its shaders is in `shaders/` and their SPIR-V in `shaders/compiled/`.
+#[derive(Clone, Debug, Default)]
+pub struct DrawCrtDesc;
+
+impl<B: Backend> SimpleGraphicsPipelineDesc<B, Resources> for DrawCrtDesc {
+    type Pipeline = DrawCrt<B>;
+
+    fn images(&self) -> Vec<ImageAccess> {
+        vec![ImageAccess {
+            access: hal::image::Access::SHADER_READ,
+            usage: hal::image::Usage::SAMPLED,
+            layout: hal::image::Layout::ShaderReadOnlyOptimal,
+            stages: hal::pso::PipelineStage::FRAGMENT_SHADER,
+        }]
+    }
+
+    fn colors(&self) -> Vec<ColorBlendDesc> {
+        vec![ColorBlendDesc(ColorMask::ALL, BlendState::Off)]
+    }
+
+    fn depth_stencil(&self) -> Option<DepthStencilDesc> {
+        None
+    }
+
+    fn layout(&self) -> Layout {
+        Layout {
+            sets: vec![SetLayout {
+                bindings: set_layout_bindings(Some((
+                    1,
+                    DescriptorType::CombinedImageSampler,
+                    ShaderStageFlags::FRAGMENT,
+                ))),
+            }],
+            push_constants: Vec::new(),
+        }
+    }
+
+    fn load_shader_set(&self, factory: &mut Factory<B>, _aux: &Resources) -> ShaderSet<B> {
+        let vertex = SpirvShader::new(
+            include_bytes!("../shaders/compiled/fullscreen.vert.spv").to_vec(),
+            ShaderStageFlags::VERTEX,
+            "main",
+        );
+        let fragment = SpirvShader::new(
+            include_bytes!("../shaders/compiled/crt.frag.spv").to_vec(),
+            ShaderStageFlags::FRAGMENT,
+            "main",
+        );
+        ShaderSetBuilder::default()
+            .with_vertex(&vertex)
+            .expect("CRT vertex shader should load")
+            .with_fragment(&fragment)
+            .expect("CRT fragment shader should load")
+            .build(factory, Default::default())
+            .expect("CRT shaders should build")
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &Resources,
+        _buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+        set_layouts: &[Handle<DescriptorSetLayout<B>>],
+    ) -> Result<DrawCrt<B>, failure::Error> {
+        let scene = &images[0];
+        let image = ctx
+            .get_image(scene.id)
+            .ok_or_else(|| failure::format_err!("The CRT pass has no scene to draw"))?;
+        let view = factory.create_image_view(
+            image.clone(),
+            ImageViewInfo {
+                view_kind: ViewKind::D2,
+                format: image.format(),
+                swizzle: Swizzle::NO,
+                range: scene.range.clone(),
+            },
+        )?;
+        let sampler = factory.get_sampler(SamplerInfo::new(Filter::Linear, WrapMode::Clamp))?;
+
+        let set = factory.create_descriptor_set(set_layouts[0].clone())?;
+        unsafe {
+            factory.write_descriptor_sets(vec![desc_write(
+                set.raw(),
+                0,
+                Descriptor::CombinedImageSampler(view.raw(), scene.layout, sampler.raw()),
+            )]);
+        }
+
+        Ok(DrawCrt { set, view, sampler })
+    }
+}
+
+/// The pipeline of the `DrawCrtDesc`, it keeps the scene bound for its shaders to sample.
+#[derive(Debug)]
+pub struct DrawCrt<B: Backend> {
+    set: Escape<DescriptorSet<B>>,
+    view: Escape<ImageView<B>>,
+    sampler: Handle<Sampler<B>>,
+}
+
+impl<B: Backend> SimpleGraphicsPipeline<B, Resources> for DrawCrt<B> {
+    type Desc = DrawCrtDesc;
+
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _set_layouts: &[Handle<DescriptorSetLayout<B>>],
+        _index: usize,
+        _aux: &Resources,
+    ) -> PrepareResult {
+        // The same triangle is drawn every frame, only the scene under it changes.
+        PrepareResult::DrawReuse
+    }
+
+    fn draw(
+        &mut self,
+        layout: &B::PipelineLayout,
+        mut encoder: RenderPassEncoder<'_, B>,
+        _index: usize,
+        _aux: &Resources,
+    ) {
+        unsafe {
+            encoder.bind_graphics_descriptor_sets(
+                layout,
+                0,
+                Some(self.set.raw()),
+                std::iter::empty(),
+            );
+            encoder.draw(0..3, 0..1);
+        }
+    }
+
+    fn dispose(self, _factory: &mut Factory<B>, _aux: &Resources) {
+        // The descriptor set goes first, the view and sampler are still bound to it.
+        drop(self.set);
+        drop(self.view);
+        drop(self.sampler);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    mem,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::settings_dir;
+use crate::net::into_io_error;
+
+/// The file in the `settings_dir()` the best practice session is kept in.
+pub const GHOST_FILE: &str = "ghost.bin";
+/// Seconds between the positions of the player's paddle kept for the ghost.
+pub const GHOST_INTERVAL: f32 = 0.05;
+
+/// Where the player's paddle was over a practice session, and how well they did in it.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GhostRun {
+    pub returned: u32,
+    pub missed: u32,
+    /// How high the paddle was every `GHOST_INTERVAL` from the start of the session.
+    pub positions: Vec<f32>,
+}
+
+impl GhostRun {
+    /// Whether the run went better than `other`: more shots returned, or as many with fewer
+    /// missed.
+    fn beats(&self, other: &GhostRun) -> bool {
+        (self.returned, other.missed) > (other.returned, self.missed)
+    }
+
+    /// How high the paddle was `elapsed` seconds into the session, between the positions kept
+    /// around then. `None` once the session was over by then.
+    pub fn position(&self, elapsed: f32) -> Option<f32> {
+        let at = elapsed / GHOST_INTERVAL;
+        let index = at as usize;
+        let before = *self.positions.get(index)?;
+        let after = self.positions.get(index + 1).copied().unwrap_or(before);
+        Some(before + (after - before) * at.fract())
+    }
+}
+
+/// The best practice session played on this machine, which the ghost paddle plays back
+/// alongside the player in practice, and the one being played now. A session that goes
+/// better than the best one takes its place once it's over.
+#[derive(Default)]
+pub struct Ghost {
+    pub best: Option<GhostRun>,
+    /// Whether a session is being played, the tutorial's practice isn't one.
+    pub active: bool,
+    pub current: GhostRun,
+    /// Seconds played of the session.
+    pub elapsed: f32,
+}
+
+impl Ghost {
+    /// The best session saved, no ghost the first time practice is played.
+    pub fn load_saved() -> Ghost {
+        let path = settings_dir().join(GHOST_FILE);
+        let best = match File::open(&path) {
+            Ok(file) => match bincode::deserialize_from(BufReader::new(file)) {
+                Ok(best) => Some(best),
+                Err(err) => {
+                    log::warn!("Failed to read the practice ghost: {}", err);
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        Ghost {
+            best,
+            ..Ghost::default()
+        }
+    }
+
+    /// Starts recording a new session.
+    pub fn start(&mut self) {
+        self.active = true;
+        self.current = GhostRun::default();
+        self.elapsed = 0.0;
+    }
+
+    /// Ends the session with its results, which is saved as the best one if it beat it.
+    pub fn finish(&mut self, returned: u32, missed: u32) {
+        self.active = false;
+        let mut run = mem::take(&mut self.current);
+        run.returned = returned;
+        run.missed = missed;
+        if run.returned == 0 || self.best.as_ref().is_some_and(|best| !run.beats(best)) {
+            return;
+        }
+        if let Err(err) = save(&run) {
+            log::error!("Failed to save the practice ghost: {}", err);
+        }
+        self.best = Some(run);
+    }
+}
+
+fn save(run: &GhostRun) -> io::Result<()> {
+    let dir = settings_dir();
+    fs::create_dir_all(&dir)?;
+    let writer = BufWriter::new(File::create(dir.join(GHOST_FILE))?);
+    bincode::serialize_into(writer, run).map_err(|err| into_io_error(*err))
+}
@@ -0,0 +1,229 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::{
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::{Handicap, Handicaps, Mutator, Mutators, Pong, Side},
+    theme::Theme,
+};
+
+/// Steps paddle size and speed handicaps are changed by.
+const HANDICAP_STEP: f32 = 0.25;
+const MIN_HANDICAP: f32 = 0.5;
+const MAX_HANDICAP: f32 = 2.0;
+
+/// One of the handicaps a player can change.
+#[derive(Clone, Copy)]
+enum Setting {
+    HeadStart,
+    PaddleSize,
+    PaddleSpeed,
+}
+
+impl Setting {
+    const ALL: [Setting; 3] = [Setting::HeadStart, Setting::PaddleSize, Setting::PaddleSpeed];
+
+    fn describe(self, handicap: &Handicap) -> String {
+        match self {
+            Setting::HeadStart => format!("head start {}", handicap.head_start),
+            Setting::PaddleSize => format!("paddle size {:.0}%", handicap.paddle_size * 100.),
+            Setting::PaddleSpeed => format!("paddle speed {:.0}%", handicap.paddle_speed * 100.),
+        }
+    }
+
+    /// Moves the setting up or down by a step, for `win_score` point games.
+    fn change(self, handicap: &mut Handicap, up: bool, win_score: i32) {
+        let step = if up { HANDICAP_STEP } else { -HANDICAP_STEP };
+        match self {
+            // A head start of the whole game wouldn't leave anything to play for.
+            Setting::HeadStart => {
+                let step = if up { 1 } else { -1 };
+                handicap.head_start = (handicap.head_start + step).clamp(0, win_score - 1);
+            }
+            Setting::PaddleSize => {
+                handicap.paddle_size =
+                    (handicap.paddle_size + step).clamp(MIN_HANDICAP, MAX_HANDICAP);
+            }
+            Setting::PaddleSpeed => {
+                handicap.paddle_speed =
+                    (handicap.paddle_speed + step).clamp(MIN_HANDICAP, MAX_HANDICAP);
+            }
+        }
+    }
+}
+
+/// A line on the setup screen.
+#[derive(Clone, Copy)]
+enum Row {
+    Handicap(Side, Setting),
+    Mutator(Mutator),
+}
+
+/// Shown before a match to give players handicaps, so mismatched players can still have a
+/// close game, and to turn mutators on and off.
+///
+/// Up and down pick a handicap or mutator, left and right change it and enter starts the
+/// match. Handicaps are listed on the left and mutators on the right.
+pub struct HandicapSetup {
+    /// The match to play once the handicaps are set.
+    pong: Option<Pong>,
+    handicaps: Handicaps,
+    /// Starts out with the mutators picked on the command line.
+    mutators: Mutators,
+    /// Every handicap and mutator that can be changed, one line each.
+    rows: Vec<Row>,
+    selected: usize,
+    row_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl HandicapSetup {
+    pub fn new(pong: Pong) -> HandicapSetup {
+        let rows = pong
+            .mode()
+            .sides()
+            .iter()
+            .flat_map(|side| Setting::ALL.iter().map(move |setting| Row::Handicap(*side, *setting)))
+            .chain(Mutator::ALL.iter().map(|mutator| Row::Mutator(*mutator)))
+            .collect();
+        HandicapSetup {
+            mutators: pong.mutators().clone(),
+            pong: Some(pong),
+            handicaps: Handicaps::default(),
+            rows,
+            selected: 0,
+            row_texts: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Updates the text of every row, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let labels = self.rows.iter().map(|row| match row {
+            Row::Handicap(side, setting) => {
+                format!("{} {}", side.name(), setting.describe(&self.handicaps.get(*side)))
+            }
+            Row::Mutator(mutator) => format!(
+                "[{}] {}",
+                if self.mutators.is_on(*mutator) { "x" } else { " " },
+                mutator.name().replace('_', " ")
+            ),
+        });
+        refresh_rows(world, &self.row_texts, labels, Some(self.selected));
+    }
+}
+
+impl SimpleState for HandicapSetup {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "handicap_title".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -30., 1., 500., 40.,
+            ))
+            .with(theme.text("Handicaps and mutators".to_string(), 30.))
+            .build();
+        let hint = world
+            .create_entity()
+            .with(UiTransform::new(
+                "handicap_hint".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+                0., 30., 1., 600., 30.,
+            ))
+            .with(theme.text(
+                "Up and down to pick, left and right to change, Enter to play".to_string(),
+                15.,
+            ))
+            .build();
+        self.texts.push(title);
+        self.texts.push(hint);
+
+        // Each column counts its own lines.
+        let (mut handicaps, mut mutators) = (0., 0.);
+        for (index, row) in self.rows.iter().enumerate() {
+            let (x, line) = match row {
+                Row::Handicap(..) => {
+                    handicaps += 1.;
+                    (-125., handicaps)
+                }
+                Row::Mutator(_) => {
+                    mutators += 1.;
+                    (125., mutators)
+                }
+            };
+            let transform = UiTransform::new(
+                format!("handicap_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                x, -58. - 22. * line, 1., 240., 22.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 18.))
+                .build();
+            self.row_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("handicap text should be alive");
+        data.world
+            .delete_entities(&self.row_texts)
+            .expect("handicap text should be alive");
+        self.texts.clear();
+        self.row_texts.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let rows = self.rows.len();
+        match menu_input(&event) {
+            // Back to the main menu.
+            Some(MenuInput::Back) => return Trans::Pop,
+            Some(MenuInput::Select) => {
+                if let Some(pong) = self.pong.take() {
+                    let pong = pong
+                        .with_handicaps(self.handicaps)
+                        .with_mutators(self.mutators.clone());
+                    return Trans::Switch(Box::new(pong));
+                }
+            }
+            Some(MenuInput::Up) => self.selected = (self.selected + rows - 1) % rows,
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % rows,
+            Some(input @ MenuInput::Left) | Some(input @ MenuInput::Right) => {
+                match self.rows[self.selected] {
+                    Row::Handicap(side, setting) => {
+                        let win_score =
+                            self.pong.as_ref().map_or(1, |pong| pong.rules().win_score);
+                        setting.change(
+                            self.handicaps.get_mut(side),
+                            input == MenuInput::Right,
+                            win_score,
+                        );
+                    }
+                    Row::Mutator(mutator) => self.mutators.toggle(mutator),
+                }
+            }
+            None => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,162 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{settings_dir, write_to_settings_dir, HIGH_SCORES_FILE};
+
+/// How many scores every table keeps, from the top.
+pub const HIGH_SCORES_SIZE: usize = 10;
+
+/// The modes played for a high score, each with a table of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HighScoreTable {
+    /// The seconds a survival run lasted.
+    Survival,
+    /// The points the winner of a time attack game scored before the time ran out.
+    TimeAttack,
+}
+
+impl HighScoreTable {
+    pub const ALL: [HighScoreTable; 2] = [HighScoreTable::Survival, HighScoreTable::TimeAttack];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HighScoreTable::Survival => "Survival",
+            HighScoreTable::TimeAttack => "Time attack",
+        }
+    }
+
+    /// The score as it's shown in the table.
+    pub fn format(self, score: f32) -> String {
+        match self {
+            HighScoreTable::Survival => format!("{:.1}s", score),
+            HighScoreTable::TimeAttack => format!("{}", score as i32),
+        }
+    }
+}
+
+/// A score on one of the tables.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HighScore {
+    pub name: String,
+    pub score: f32,
+    /// The day it was set, as year-month-day.
+    pub date: String,
+}
+
+/// The best scores set on this machine, kept in `high_scores.ron` next to the settings.
+/// Every table is sorted best first.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub survival: Vec<HighScore>,
+    pub time_attack: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// The tables saved the last time a score made one, empty the first time.
+    pub fn load_saved() -> HighScores {
+        let path = settings_dir().join(HIGH_SCORES_FILE);
+        if path.exists() {
+            HighScores::load(path)
+        } else {
+            HighScores::default()
+        }
+    }
+
+    pub fn table(&self, table: HighScoreTable) -> &[HighScore] {
+        match table {
+            HighScoreTable::Survival => &self.survival,
+            HighScoreTable::TimeAttack => &self.time_attack,
+        }
+    }
+
+    fn table_mut(&mut self, table: HighScoreTable) -> &mut Vec<HighScore> {
+        match table {
+            HighScoreTable::Survival => &mut self.survival,
+            HighScoreTable::TimeAttack => &mut self.time_attack,
+        }
+    }
+
+    /// Where `score` would go on the table, `None` if it doesn't make it. A score as good as
+    /// one already there goes below it.
+    pub fn rank(&self, table: HighScoreTable, score: f32) -> Option<usize> {
+        if score <= 0.0 {
+            return None;
+        }
+        let scores = self.table(table);
+        let rank = scores
+            .iter()
+            .position(|high_score| score > high_score.score)
+            .unwrap_or(scores.len());
+        Some(rank).filter(|rank| *rank < HIGH_SCORES_SIZE)
+    }
+
+    /// Puts the score on the table under `name`, dated today, and saves the tables. Returns
+    /// where it went.
+    pub fn add(&mut self, table: HighScoreTable, name: String, score: f32) -> Option<usize> {
+        let rank = self.rank(table, score)?;
+        let date = today();
+        let scores = self.table_mut(table);
+        scores.insert(rank, HighScore { name, score, date });
+        scores.truncate(HIGH_SCORES_SIZE);
+
+        if let Err(err) = write_to_settings_dir(self, HIGH_SCORES_FILE) {
+            log::error!("Failed to save the high scores: {}", err);
+        }
+        Some(rank)
+    }
+}
+
+/// Today's date in UTC, as year-month-day.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_date(seconds);
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+/// The year, month and day in UTC `seconds` after the Unix epoch.
+pub fn civil_date(seconds: u64) -> (i64, i64, i64) {
+    // Days since the epoch to a civil date, shifted so the year starts in March and the leap
+    // day falls at its end.
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_at_the_epoch() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(86_399), (1970, 1, 1));
+    }
+
+    #[test]
+    fn counts_the_leap_days() {
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_date(1_582_934_400), (2020, 2, 29));
+        assert_eq!(civil_date(1_582_934_400 + 86_400), (2020, 3, 1));
+        assert_eq!(civil_date(1_614_556_800), (2021, 3, 1));
+    }
+
+    #[test]
+    fn ends_the_year_in_december() {
+        assert_eq!(civil_date(946_598_400), (1999, 12, 31));
+    }
+}
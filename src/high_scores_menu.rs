@@ -0,0 +1,276 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, Selectable, Selected, TextEditing, UiText, UiTransform},
+};
+
+use crate::{
+    confirm::ConfirmQuit,
+    high_scores::{HighScoreTable, HighScores, HIGH_SCORES_SIZE},
+    menu::{menu_input, MenuInput},
+    theme::Theme,
+};
+
+/// Longest name that can be put on a table.
+const MAX_NAME_LENGTH: usize = 10;
+
+/// The screen shown after the high scores of a new record, the results of the match it was set
+/// in.
+pub type NextScreen = Box<dyn State<GameData<'static, 'static>, StateEvent>>;
+
+/// Shows the survival and time attack tables side by side, picked on the main menu or shown
+/// once a new record is put on one, which stands out from the rest.
+///
+/// Escape or Enter goes back, or on to the results after a new record.
+#[derive(Default)]
+pub struct HighScoresMenu {
+    /// The table and rank of the new record.
+    record: Option<(HighScoreTable, usize)>,
+    next: Option<NextScreen>,
+    texts: Vec<Entity>,
+}
+
+impl HighScoresMenu {
+    /// The tables with the record just set at `rank` standing out, going on to `next`.
+    pub fn with_record(table: HighScoreTable, rank: usize, next: NextScreen) -> HighScoresMenu {
+        HighScoresMenu {
+            record: Some((table, rank)),
+            next: Some(next),
+            texts: Vec::new(),
+        }
+    }
+}
+
+impl SimpleState for HighScoresMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+        let tables: Vec<Vec<String>> = {
+            let high_scores = world.read_resource::<HighScores>();
+            HighScoreTable::ALL
+                .iter()
+                .map(|table| {
+                    let scores = high_scores.table(*table);
+                    (0..HIGH_SCORES_SIZE)
+                        .map(|rank| match scores.get(rank) {
+                            Some(score) => format!(
+                                "{:>2}. {:<10} {:>6}  {}",
+                                rank + 1,
+                                score.name,
+                                table.format(score.score),
+                                score.date
+                            ),
+                            None => format!("{:>2}. -", rank + 1),
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+
+        let hint = if self.next.is_some() {
+            "Enter to continue"
+        } else {
+            "Escape to go back"
+        };
+        let lines = [
+            ("high_scores_title", Anchor::TopMiddle, -20., "High scores", 30.),
+            ("high_scores_hint", Anchor::BottomMiddle, 30., hint, 15.),
+        ];
+        for (id, anchor, y, line, font_size) in lines.iter() {
+            let text = world
+                .create_entity()
+                .with(UiTransform::new(
+                    id.to_string(), anchor.clone(), anchor.clone(),
+                    0., *y, 1., 500., 40.,
+                ))
+                .with(theme.text(line.to_string(), *font_size))
+                .build();
+            self.texts.push(text);
+        }
+
+        for (column, (table, lines)) in HighScoreTable::ALL.iter().zip(tables).enumerate() {
+            let x = -125. + 250. * column as f32;
+            let heading = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("high_scores_{}", column), Anchor::TopMiddle, Anchor::TopMiddle,
+                    x, -70., 1., 240., 25.,
+                ))
+                .with(theme.text(table.name().to_string(), 20.))
+                .build();
+            self.texts.push(heading);
+
+            for (rank, line) in lines.into_iter().enumerate() {
+                let mut text = theme.text(line, 13.);
+                text.align = Anchor::MiddleLeft;
+                let record = self.record == Some((*table, rank));
+                text.color = theme.item_color(record);
+                let transform = UiTransform::new(
+                    format!("high_scores_{}_{}", column, rank), Anchor::TopMiddle,
+                    Anchor::TopMiddle, x, -100. - 20. * rank as f32, 1., 240., 20.,
+                );
+                let text = world.create_entity().with(transform).with(text).build();
+                self.texts.push(text);
+            }
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("high score text should be alive");
+        self.texts.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+        match menu_input(&event) {
+            Some(MenuInput::Select) | Some(MenuInput::Back) => match self.next.take() {
+                Some(next) => Trans::Switch(next),
+                None => Trans::Pop,
+            },
+            _ => Trans::None,
+        }
+    }
+}
+
+/// Shown when a score makes one of the high score tables, for the player to put their name on
+/// it. The tables are shown afterwards with the new record standing out.
+///
+/// Enter puts the name typed in on the table, Escape leaves the score off it.
+pub struct NewHighScore {
+    table: HighScoreTable,
+    score: f32,
+    /// The name the field starts out with, the player's from the name entry.
+    name: String,
+    next: Option<NextScreen>,
+    field: Option<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl NewHighScore {
+    pub fn new(table: HighScoreTable, score: f32, name: String, next: NextScreen) -> NewHighScore {
+        NewHighScore {
+            table,
+            score,
+            name,
+            next: Some(next),
+            field: None,
+            texts: Vec::new(),
+        }
+    }
+}
+
+impl SimpleState for NewHighScore {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+        let rank = world
+            .read_resource::<HighScores>()
+            .rank(self.table, self.score)
+            .unwrap_or(0);
+
+        let lines = [
+            ("New high score!".to_string(), 80., 30.),
+            (
+                format!(
+                    "{} in {}, number {} on the table",
+                    self.table.format(self.score),
+                    self.table.name().to_lowercase(),
+                    rank + 1
+                ),
+                40.,
+                18.,
+            ),
+            ("Type your name, Enter to save it or Escape to skip".to_string(), -50., 15.),
+        ];
+        for (index, (line, y, font_size)) in lines.iter().enumerate() {
+            let text = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("new_high_score_{}", index), Anchor::Middle, Anchor::Middle,
+                    0., *y, 1., 500., 40.,
+                ))
+                .with(theme.text(line.clone(), *font_size))
+                .build();
+            self.texts.push(text);
+        }
+
+        let field = world
+            .create_entity()
+            .with(UiTransform::new(
+                "new_high_score_name".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 0., 1., 200., 30.,
+            ))
+            .with(UiText::new(theme.font.clone(), self.name.clone(), theme.input_color, 20.))
+            .with(TextEditing::new(
+                MAX_NAME_LENGTH,
+                theme.input_selected_color,
+                theme.input_color,
+                false,
+            ))
+            .with(Selectable::<()>::new(0))
+            .with(Selected)
+            .build();
+        self.field = Some(field);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let entities: Vec<Entity> = self.texts.drain(..).chain(self.field.take()).collect();
+        data.world
+            .delete_entities(&entities)
+            .expect("high score text should be alive");
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+        // The arrow keys move through the name being typed, so only saving and skipping are
+        // read here.
+        let save = match menu_input(&event) {
+            Some(MenuInput::Select) => true,
+            Some(MenuInput::Back) => false,
+            _ => return Trans::None,
+        };
+        let next = match self.next.take() {
+            Some(next) => next,
+            None => return Trans::None,
+        };
+        if !save {
+            return Trans::Switch(next);
+        }
+
+        let name = {
+            let ui_text = data.world.read_storage::<UiText>();
+            let typed = self.field.and_then(|field| ui_text.get(field));
+            typed.map(|text| text.text.trim().to_string()).unwrap_or_default()
+        };
+        let name = if name.is_empty() { "Player".to_string() } else { name };
+        let rank = data
+            .world
+            .write_resource::<HighScores>()
+            .add(self.table, name, self.score);
+        match rank {
+            Some(rank) => {
+                Trans::Switch(Box::new(HighScoresMenu::with_record(self.table, rank, next)))
+            }
+            None => Trans::Switch(next),
+        }
+    }
+}
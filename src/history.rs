@@ -0,0 +1,174 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{settings_dir, write_to_settings_dir, HISTORY_FILE};
+
+/// A match finished on this machine, as it's kept in the history and exported.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MatchEntry {
+    /// When the match ended, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The kind of match, e.g. "Classic against the Hard AI" or "Online".
+    pub mode: String,
+    /// Who played each side, in the order of the sides.
+    pub players: Vec<String>,
+    /// The games each side won, and the points they had in the last game.
+    pub games: Vec<u32>,
+    pub points: Vec<i32>,
+    /// Who won, nobody for a survival run.
+    pub winner: String,
+    /// Seconds played with a ball in the arena.
+    pub duration: f32,
+}
+
+impl MatchEntry {
+    /// The time now, for a match ending now.
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// Every match finished on this machine, the oldest first, kept in `history.ron` next to the
+/// settings. `cargo run -- export_history=matches.csv` writes it out as CSV, or as JSON for a
+/// file ending in `.json`, to be looked into elsewhere.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MatchHistory {
+    pub matches: Vec<MatchEntry>,
+}
+
+impl MatchHistory {
+    /// The history saved the last time a match was finished, empty the first time.
+    pub fn load_saved() -> MatchHistory {
+        let path = settings_dir().join(HISTORY_FILE);
+        if path.exists() {
+            MatchHistory::load(path)
+        } else {
+            MatchHistory::default()
+        }
+    }
+
+    /// Adds the match to the history and saves it.
+    pub fn add(&mut self, entry: MatchEntry) {
+        self.matches.push(entry);
+        if let Err(err) = write_to_settings_dir(self, HISTORY_FILE) {
+            log::error!("Failed to save the match history: {}", err);
+        }
+    }
+
+    /// Writes the history to `path`, as JSON if it ends in `.json` and as CSV otherwise.
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_writer_pretty(&mut writer, &self.matches)?;
+        } else {
+            self.write_csv(&mut writer)?;
+        }
+        writer.flush()
+    }
+
+    /// One row a match, the players, games and points of every side put together in a column.
+    fn write_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "timestamp,mode,players,games,points,winner,duration")?;
+        for entry in &self.matches {
+            let games: Vec<String> = entry.games.iter().map(u32::to_string).collect();
+            let points: Vec<String> = entry.points.iter().map(i32::to_string).collect();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{:.1}",
+                entry.timestamp,
+                csv_field(&entry.mode),
+                csv_field(&entry.players.join(" vs ")),
+                games.join("-"),
+                points.join("-"),
+                csv_field(&entry.winner),
+                entry.duration
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes the field if it has anything in it that would break the row up, like names with
+/// commas in them.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, path::PathBuf, process};
+
+    fn history() -> MatchHistory {
+        MatchHistory {
+            matches: vec![MatchEntry {
+                timestamp: 1_582_934_400,
+                mode: "Classic".to_string(),
+                players: vec!["Ann, the first".to_string(), "Bo".to_string()],
+                games: vec![2, 1],
+                points: vec![5, 3],
+                winner: "Ann, the first".to_string(),
+                duration: 93.5,
+            }],
+        }
+    }
+
+    /// A file in the temp directory only this test run writes to.
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("pong-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn leaves_plain_fields_as_they_are() {
+        assert_eq!(csv_field("Classic against the Hard AI"), "Classic against the Hard AI");
+    }
+
+    #[test]
+    fn quotes_fields_that_would_break_the_row_up() {
+        assert_eq!(csv_field("Ann, the first"), "\"Ann, the first\"");
+        assert_eq!(csv_field("Bo \"the wall\""), "\"Bo \"\"the wall\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn exports_json_for_a_json_file() {
+        let path = temp_path("history.json");
+        history().export(&path).unwrap();
+        let exported = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let matches: Vec<MatchEntry> = serde_json::from_str(&exported).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].players, history().matches[0].players);
+    }
+
+    #[test]
+    fn exports_csv_for_any_other_file() {
+        let path = temp_path("history.csv");
+        history().export(&path).unwrap();
+        let exported = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            exported,
+            "timestamp,mode,players,games,points,winner,duration\n\
+             1582934400,Classic,\"Ann, the first vs Bo\",2-1,5-3,\"Ann, the first\",93.5\n"
+        );
+    }
+}
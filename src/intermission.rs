@@ -0,0 +1,65 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::Entity,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::theme::Theme;
+
+/// How long the intermission screen stays up, in seconds.
+const INTERMISSION_DURATION: f32 = 3.0;
+
+/// Shown between the games of a match, pushed on top of the `Pong` state.
+pub struct Intermission {
+    message: String,
+    timer: f32,
+    text: Option<Entity>,
+}
+
+impl Intermission {
+    pub fn new(message: String) -> Intermission {
+        Intermission {
+            message,
+            timer: INTERMISSION_DURATION,
+            text: None,
+        }
+    }
+}
+
+impl SimpleState for Intermission {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+        let transform = UiTransform::new(
+            "intermission".to_string(), Anchor::Middle, Anchor::Middle,
+            0., 0., 1., 500., 50.,
+        );
+
+        self.text = Some(
+            world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(self.message.clone(), 30.))
+                .build(),
+        );
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        if let Some(text) = self.text.take() {
+            data.world
+                .delete_entity(text)
+                .expect("intermission text should be alive");
+        }
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.timer -= data.world.res.fetch::<Time>().delta_seconds();
+        if self.timer <= 0.0 {
+            // Back to the match for the next game.
+            Trans::Pop
+        } else {
+            Trans::None
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{
+    settings_dir, write_to_settings_dir, LEADERBOARD_CACHE_FILE, LEADERBOARD_QUEUE_FILE,
+};
+
+/// How many players the leaderboard shows, from the top.
+pub const LEADERBOARD_SIZE: usize = 100;
+
+/// How an online match ended for the local player, submitted to the leaderboard. Online
+/// matches are the ranked ones, played against someone else through the lobby.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchResult {
+    pub player: String,
+    pub opponent: String,
+    pub won: bool,
+    /// The games the player and their opponent won.
+    pub games: u32,
+    pub opponent_games: u32,
+}
+
+/// A player on the leaderboard, as the leaderboard ranks them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub rating: i32,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// The board as it was last fetched, kept in `LEADERBOARD_CACHE_FILE` to be shown while it's
+/// fetched again or when the leaderboard can't be reached.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Cache {
+    entries: Vec<LeaderboardEntry>,
+}
+
+/// The results that couldn't be submitted yet, kept in `LEADERBOARD_QUEUE_FILE` until they
+/// are, so matches played offline still count.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Queue {
+    results: Vec<MatchResult>,
+}
+
+enum Command {
+    Submit(MatchResult),
+    Refresh,
+}
+
+enum Update {
+    /// How many results are left to submit.
+    Queued(usize),
+    Board(Vec<LeaderboardEntry>),
+    Failed(String),
+}
+
+/// The leaderboard at `GameConfig::leaderboard_url`, talked to on a thread of its own so the
+/// game never waits on it. Results are queued and submitted in the order the matches were
+/// played, whenever the leaderboard can be reached.
+pub struct Leaderboard {
+    commands: Mutex<Sender<Command>>,
+    updates: Mutex<Receiver<Update>>,
+    /// The board as last fetched, or as cached from the last session.
+    pub entries: Vec<LeaderboardEntry>,
+    /// How many results are waiting to be submitted.
+    pub queued: usize,
+    /// Why the leaderboard couldn't be reached the last time it was tried.
+    pub error: Option<String>,
+    /// Whether the board is being fetched.
+    pub fetching: bool,
+}
+
+impl Leaderboard {
+    /// Starts talking to the leaderboard at `url`, which has to be served over HTTPS. Results
+    /// left over from the last session are submitted right away.
+    pub fn new(url: Option<String>) -> Leaderboard {
+        let cache: Cache = load(LEADERBOARD_CACHE_FILE);
+        let queue: Queue = load(LEADERBOARD_QUEUE_FILE);
+        let queued = queue.results.len();
+        let (commands, to_run) = mpsc::channel();
+        let (updated, updates) = mpsc::channel();
+        thread::spawn(move || run(url, queue, to_run, updated));
+        let mut leaderboard = Leaderboard {
+            commands: Mutex::new(commands),
+            updates: Mutex::new(updates),
+            entries: cache.entries,
+            queued,
+            error: None,
+            fetching: false,
+        };
+        if queued > 0 {
+            leaderboard.refresh();
+        }
+        leaderboard
+    }
+
+    fn send(&self, command: Command) {
+        // The thread only stops once the game does.
+        let _ = self.commands.lock().unwrap().send(command);
+    }
+
+    pub fn submit(&mut self, result: MatchResult) {
+        self.queued += 1;
+        self.send(Command::Submit(result));
+    }
+
+    /// Fetches the board again, after submitting whatever is queued.
+    pub fn refresh(&mut self) {
+        self.fetching = true;
+        self.send(Command::Refresh);
+    }
+
+    /// Takes in what the thread found out since the last call, returns whether anything
+    /// changed.
+    pub fn poll(&mut self) -> bool {
+        let updates: Vec<Update> = self.updates.lock().unwrap().try_iter().collect();
+        for update in &updates {
+            match update {
+                Update::Queued(queued) => self.queued = *queued,
+                Update::Board(entries) => {
+                    self.entries = entries.clone();
+                    self.error = None;
+                    self.fetching = false;
+                }
+                Update::Failed(err) => {
+                    self.error = Some(err.clone());
+                    self.fetching = false;
+                }
+            }
+        }
+        !updates.is_empty()
+    }
+}
+
+/// Runs the commands until the game stops, submitting the queued results before each.
+fn run(
+    url: Option<String>,
+    mut queue: Queue,
+    commands: Receiver<Command>,
+    updates: Sender<Update>,
+) {
+    let client = reqwest::Client::new();
+    for command in commands {
+        if let Command::Submit(result) = &command {
+            queue.results.push(result.clone());
+            save(&queue, LEADERBOARD_QUEUE_FILE);
+        }
+        let submitted = submit_queued(&client, url.as_deref(), &mut queue);
+        save(&queue, LEADERBOARD_QUEUE_FILE);
+        let _ = updates.send(Update::Queued(queue.results.len()));
+
+        let update = match (command, submitted) {
+            (Command::Refresh, Ok(())) => match fetch(&client, url.as_deref()) {
+                Ok(entries) => {
+                    save(&Cache { entries: entries.clone() }, LEADERBOARD_CACHE_FILE);
+                    Update::Board(entries)
+                }
+                Err(err) => Update::Failed(err),
+            },
+            (_, Err(err)) => Update::Failed(err),
+            (Command::Submit(_), Ok(())) => continue,
+        };
+        let _ = updates.send(update);
+    }
+}
+
+/// The leaderboard's address, which is only ever talked to over HTTPS.
+fn endpoint(url: Option<&str>) -> Result<&str, String> {
+    match url {
+        Some(url) if url.starts_with("https://") => Ok(url.trim_end_matches('/')),
+        Some(_) => Err("the leaderboard has to be an https:// address".to_string()),
+        None => Err("no leaderboard_url is set in the game config".to_string()),
+    }
+}
+
+/// Submits the queued results in order, stopping at the first that can't be.
+fn submit_queued(
+    client: &reqwest::Client,
+    url: Option<&str>,
+    queue: &mut Queue,
+) -> Result<(), String> {
+    if queue.results.is_empty() {
+        return Ok(());
+    }
+    let url = format!("{}/results", endpoint(url)?);
+    while let Some(result) = queue.results.first() {
+        client
+            .post(&url)
+            .json(result)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?;
+        queue.results.remove(0);
+    }
+    Ok(())
+}
+
+fn fetch(client: &reqwest::Client, url: Option<&str>) -> Result<Vec<LeaderboardEntry>, String> {
+    let url = format!("{}/top?count={}", endpoint(url)?, LEADERBOARD_SIZE);
+    let mut entries: Vec<LeaderboardEntry> = client
+        .get(&url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|mut response| response.json())
+        .map_err(|err| err.to_string())?;
+    entries.truncate(LEADERBOARD_SIZE);
+    Ok(entries)
+}
+
+fn load<T: Config + Default>(name: &str) -> T {
+    let path = settings_dir().join(name);
+    if path.exists() {
+        T::load(path)
+    } else {
+        T::default()
+    }
+}
+
+fn save<T: Config>(file: &T, name: &str) {
+    if let Err(err) = write_to_settings_dir(file, name) {
+        log::error!("Failed to save {}: {}", name, err);
+    }
+}
@@ -0,0 +1,153 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::{
+    confirm::ConfirmQuit,
+    leaderboard::Leaderboard,
+    menu::{menu_input, MenuInput},
+    theme::Theme,
+};
+
+/// How many players fit on the screen, the board scrolls to show the rest.
+const VISIBLE_ROWS: usize = 20;
+
+/// Shows the top of the leaderboard, picked on the main menu. It's fetched again when the
+/// screen opens, the board from the last time is shown meanwhile or when it can't be reached.
+///
+/// Up and down scroll, escape goes back to the main menu.
+#[derive(Default)]
+pub struct LeaderboardMenu {
+    scroll: usize,
+    status: Option<Entity>,
+    row_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl LeaderboardMenu {
+    /// Updates the rows shown and the status line.
+    fn refresh(&self, world: &mut World) {
+        let leaderboard = world.read_resource::<Leaderboard>();
+        let mut ui_text = world.write_storage::<UiText>();
+        for (row, entity) in self.row_texts.iter().enumerate() {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                let rank = self.scroll + row;
+                text.text = match leaderboard.entries.get(rank) {
+                    Some(entry) => format!(
+                        "{:>3}. {:<20} {:>5}  {}-{}",
+                        rank + 1,
+                        entry.name,
+                        entry.rating,
+                        entry.wins,
+                        entry.losses
+                    ),
+                    None => String::new(),
+                };
+            }
+        }
+
+        let mut status = if leaderboard.fetching {
+            "Updating the leaderboard".to_string()
+        } else if let Some(err) = &leaderboard.error {
+            format!("Offline, couldn't reach the leaderboard: {}", err)
+        } else if leaderboard.entries.is_empty() {
+            "Nobody is on the leaderboard yet".to_string()
+        } else {
+            String::new()
+        };
+        if leaderboard.queued > 0 {
+            if !status.is_empty() {
+                status += ", ";
+            }
+            status += &format!("{} results still to be sent", leaderboard.queued);
+        }
+        if let Some(text) = self.status.and_then(|status| ui_text.get_mut(status)) {
+            text.text = status;
+        }
+    }
+
+    fn rows(world: &World) -> usize {
+        world.read_resource::<Leaderboard>().entries.len()
+    }
+}
+
+impl SimpleState for LeaderboardMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        world.write_resource::<Leaderboard>().refresh();
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "leaderboard_title".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -20., 1., 500., 40.,
+            ))
+            .with(theme.text("Leaderboard".to_string(), 30.))
+            .build();
+        let status = world
+            .create_entity()
+            .with(UiTransform::new(
+                "leaderboard_status".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+                0., 30., 1., 600., 30.,
+            ))
+            .with(theme.text(String::new(), 15.))
+            .build();
+        self.texts = vec![title, status];
+        self.status = Some(status);
+
+        for row in 0..VISIBLE_ROWS {
+            let transform = UiTransform::new(
+                format!("leaderboard_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -65. - 18. * row as f32, 1., 420., 18.,
+            );
+            let mut line = theme.text(String::new(), 15.);
+            line.align = Anchor::MiddleLeft;
+            let text = world.create_entity().with(transform).with(line).build();
+            self.row_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let entities: Vec<Entity> = self.texts.drain(..).chain(self.row_texts.drain(..)).collect();
+        data.world
+            .delete_entities(&entities)
+            .expect("leaderboard text should be alive");
+        self.status = None;
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if data.world.write_resource::<Leaderboard>().poll() {
+            // The board may have gotten shorter.
+            self.scroll = self.scroll.min(Self::rows(data.world).saturating_sub(VISIBLE_ROWS));
+            self.refresh(data.world);
+        }
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+
+        let last = Self::rows(data.world).saturating_sub(VISIBLE_ROWS);
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.scroll = self.scroll.saturating_sub(1),
+            Some(MenuInput::Down) => self.scroll = (self.scroll + 1).min(last),
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,51 @@
+//! Pong, and everything it's played with. The game itself is the `pong` binary, the
+//! `pong_server` binary runs matches on a server without a window.
+
+pub mod achievements;
+pub mod announcer;
+pub mod audio;
+pub mod bloom;
+pub mod celebration;
+#[cfg(feature = "cloud_sync")]
+pub mod cloud_sync;
+pub mod config;
+pub mod confirm;
+pub mod controls;
+pub mod controls_menu;
+pub mod crt;
+pub mod ghost;
+pub mod handicap;
+pub mod high_scores;
+pub mod high_scores_menu;
+pub mod history;
+pub mod intermission;
+pub mod leaderboard;
+pub mod leaderboard_menu;
+pub mod loading;
+pub mod lobby;
+pub mod matchmaking;
+pub mod menu;
+pub mod mode_select;
+pub mod msaa;
+pub mod names;
+pub mod net;
+pub mod palette;
+pub mod pause;
+pub mod pong;
+pub mod pre_match;
+pub mod presence;
+pub mod profiles;
+pub mod profiles_menu;
+pub mod reconnect;
+pub mod replay;
+pub mod replays_menu;
+pub mod results;
+pub mod rollback;
+pub mod settings;
+pub mod skin;
+#[cfg(feature = "steam")]
+pub mod steam;
+pub mod systems;
+pub mod theme;
+pub mod tutorial;
+pub mod twitch;
@@ -0,0 +1,172 @@
+use amethyst::{
+    assets::{AssetStorage, Completion, Handle, Loader, ProgressCounter},
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    renderer::{
+        formats::texture::ImageFormat,
+        sprite::{SpriteSheet, SpriteSheetFormat},
+        Texture,
+    },
+    ui::{Anchor, UiImage, UiTransform},
+};
+
+use crate::{
+    announcer::initialise_announcer, audio::initialise_audio, config::GameConfig, menu::MainMenu,
+    pong::{BackgroundSheetHandle, SpriteSheetHandle},
+    skin::load_skin,
+    theme::Theme,
+};
+
+/// Size of the progress bar once it's full.
+const BAR_WIDTH: f32 = 300.;
+const BAR_HEIGHT: f32 = 20.;
+
+/// The first state of the game. It loads the sprite sheet, the theme and the sounds while a
+/// progress bar fills up, and goes on to the main menu once they're all in so nothing is
+/// spawned with a handle that isn't loaded yet.
+///
+/// Assets that fail to load are reported and the game goes on without them.
+pub struct Loading {
+    /// The screen to show once loading is done.
+    menu: Option<MainMenu>,
+    progress: ProgressCounter,
+    bar: Option<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl Loading {
+    pub fn new(menu: MainMenu) -> Loading {
+        Loading {
+            menu: Some(menu),
+            progress: ProgressCounter::new(),
+            bar: None,
+            texts: Vec::new(),
+        }
+    }
+}
+
+impl SimpleState for Loading {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        let theme_name = world.read_resource::<GameConfig>().theme.clone();
+        let theme = Theme::load(world, &theme_name, &mut self.progress);
+        let skin = world.read_resource::<GameConfig>().skin.clone();
+        let sprite_sheet = load_skin(world, &skin, &mut self.progress);
+        world.add_resource(SpriteSheetHandle(sprite_sheet));
+        let background =
+            load_sprite_sheet(world, "texture/background_spritesheet", &mut self.progress);
+        world.add_resource(BackgroundSheetHandle(background));
+        initialise_audio(world, &mut self.progress);
+        initialise_announcer(world, &mut self.progress);
+
+        // The text only shows up once its font is in, the bar is there from the start.
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_title".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 40., 1., 300., 40.,
+            ))
+            .with(theme.text("Loading".to_string(), 30.))
+            .build();
+        let frame = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_frame".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 0., 0.5, BAR_WIDTH + 4., BAR_HEIGHT + 4.,
+            ))
+            .with(UiImage::SolidColor(theme.bar_frame_color))
+            .build();
+        // Grows to the right from the left end of the frame.
+        let bar = world
+            .create_entity()
+            .with(UiTransform::new(
+                "loading_bar".to_string(), Anchor::Middle, Anchor::MiddleLeft,
+                -BAR_WIDTH / 2., 0., 1., 0., BAR_HEIGHT,
+            ))
+            .with(UiImage::SolidColor(theme.bar_color))
+            .build();
+        self.texts.push(title);
+        self.texts.push(frame);
+        self.texts.push(bar);
+        self.bar = Some(bar);
+
+        world.add_resource(theme);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("loading screen should be alive");
+        self.texts.clear();
+        self.bar = None;
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let total = self.progress.num_assets().max(1);
+        let done = self.progress.num_assets() - self.progress.num_loading();
+        if let Some(bar) = self.bar {
+            if let Some(transform) = data.world.write_storage::<UiTransform>().get_mut(bar) {
+                transform.width = BAR_WIDTH * done as f32 / total as f32;
+            }
+        }
+
+        if self.progress.num_loading() > 0 {
+            return Trans::None;
+        }
+        if let Completion::Failed = self.progress.complete() {
+            for error in self.progress.errors() {
+                log::error!("Failed to load {}: {}", error.asset_name, error.error);
+            }
+        }
+        match self.menu.take() {
+            Some(menu) => Trans::Switch(Box::new(menu)),
+            None => Trans::None,
+        }
+    }
+}
+
+/// Loads the sprite sheet at `path` in the assets directory, without its extension. It's the
+/// one shared by the paddles, balls and everything else in the arena, or the one of the
+/// background.
+pub fn load_sprite_sheet(
+    world: &mut World,
+    path: &str,
+    progress: &mut ProgressCounter,
+) -> Handle<SpriteSheet> {
+    // The texture is the pixel data
+    // `texture_handle` is a cloneable reference to the texture
+    let texture_handle = {
+        let loader = world.read_resource::<Loader>();
+        let texture_storage = world.read_resource::<AssetStorage<Texture>>();
+        loader.load(
+            format!("{}.png", path),
+            ImageFormat::default(),
+            &mut *progress,
+            &texture_storage,
+        )
+    };
+
+    let loader = world.read_resource::<Loader>();
+    let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
+    loader.load(
+        format!("{}.ron", path), // Here we load the associated ron file
+        SpriteSheetFormat(texture_handle),
+        progress,
+        &sprite_sheet_store,
+    )
+}
@@ -0,0 +1,536 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufReader},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    net::{
+        read_message, write_message, FromLobby, PeerMessage, SpectatorMessage, ToLobby,
+        RECONNECT_TIME,
+    },
+    pong::{MatchRules, Side},
+    rollback::{MatchEvent, MatchState, PlayerInput, ROLLBACK_FRAME},
+};
+
+/// Most inputs kept for a player in the match the server plays, the oldest are dropped after
+/// that so their paddle isn't played further and further behind them.
+const MAX_QUEUED_INPUTS: usize = 8;
+/// Most messages waiting to be written to a player, two seconds of frames. A player who stops
+/// reading them is dropped once they pile up, rather than holding up everyone else.
+const MAX_OUTGOING_MESSAGES: usize = 120;
+
+/// A player connected to the lobby.
+struct Client {
+    name: String,
+    /// The messages for the player, written to their connection on a thread of its own.
+    outgoing: SyncSender<FromLobby>,
+    /// The player's connection, shut down to drop them.
+    stream: TcpStream,
+    /// Who the player is playing against, and relaying their messages to.
+    opponent: Option<usize>,
+    /// Whether the player hosts the match they're in.
+    host: bool,
+    /// The host of the match the player is watching.
+    watching: Option<usize>,
+    /// What the player rejoins their match with after losing their connection.
+    token: u64,
+    /// When the player lost their connection in the middle of a match, their opponent waits
+    /// `RECONNECT_TIME` for them to rejoin it.
+    dropped: Option<Instant>,
+    /// The player's inputs still to be played in the match the server plays, and their frames.
+    inputs: VecDeque<(u32, PlayerInput)>,
+    /// The input the player was last played with, played again until the next one comes in,
+    /// and its frame.
+    played: (Option<u32>, PlayerInput),
+    /// The rules of the match the player was paired for, both players were sent them.
+    rules: MatchRules,
+}
+
+/// A match an authoritative lobby plays, by the host playing it.
+struct ServerMatch {
+    guest: usize,
+    /// The rules the players were paired with.
+    rules: MatchRules,
+    frame: u32,
+    state: MatchState,
+    /// The games the left and right players won in the match so far.
+    games: [u32; 2],
+}
+
+/// Everyone connected to the lobby, shared between the threads reading their connections.
+#[derive(Default)]
+struct Lobby {
+    next_id: usize,
+    clients: HashMap<usize, Client>,
+    /// The player waiting for an opponent, if anyone is.
+    waiting: Option<usize>,
+    /// Whether the lobby plays the matches itself, rather than relaying the players' inputs.
+    authoritative: bool,
+    matches: HashMap<usize, ServerMatch>,
+}
+
+/// Runs the lobby server on `address`, e.g. `0.0.0.0:7777`, until the program is stopped.
+/// Players are paired in the order they queue up, and everything they send each other in a
+/// match goes through the lobby, so nobody has to open a port but the lobby itself. Anyone can
+/// watch a match too, the lobby passes them what its host broadcasts.
+///
+/// An `authoritative` lobby plays the matches itself, from the inputs the players send, and
+/// sends both players and everyone watching every frame of it. Nobody can cheat by sending
+/// something the other end would play differently then.
+pub fn serve(address: &str, authoritative: bool) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    log::info!("Lobby listening on {}", listener.local_addr()?);
+    let lobby = Arc::new(Mutex::new(Lobby {
+        authoritative,
+        ..Lobby::default()
+    }));
+    if authoritative {
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || play_matches(&lobby));
+    }
+    {
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(1));
+            lobby.lock().unwrap().expire_dropped();
+        });
+    }
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("Failed to accept a player: {}", err);
+                continue;
+            }
+        };
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || {
+            if let Err(err) = serve_client(&lobby, stream) {
+                log::warn!("Lost a player: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Plays a frame of every match going on every `ROLLBACK_FRAME`, for as long as the lobby runs.
+fn play_matches(lobby: &Mutex<Lobby>) {
+    let frame = Duration::from_secs_f32(ROLLBACK_FRAME);
+    let mut next = Instant::now();
+    loop {
+        next += frame;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        } else {
+            // Frames that are too late to play on time are skipped, rather than rushed.
+            next = now;
+        }
+        lobby.lock().unwrap().play_frame();
+    }
+}
+
+/// Reads the messages of one player until they disconnect.
+fn serve_client(lobby: &Mutex<Lobby>, stream: TcpStream) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let (outgoing, messages) = mpsc::sync_channel(MAX_OUTGOING_MESSAGES);
+    let writer = stream.try_clone()?;
+    thread::spawn(move || write_messages(writer, messages));
+    let id = lobby.lock().unwrap().join(stream.try_clone()?, outgoing);
+    let mut reader = BufReader::new(stream);
+    let err = loop {
+        match read_message(&mut reader) {
+            Ok(message) => lobby.lock().unwrap().handle(id, message),
+            Err(err) => break err,
+        }
+    };
+    lobby.lock().unwrap().drop_client(id);
+    // Closing the connection is how players leave.
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+/// Writes the messages for one player to their connection, until they leave the lobby.
+fn write_messages(mut stream: TcpStream, messages: Receiver<FromLobby>) {
+    for message in messages {
+        if write_message(&mut stream, &message).is_err() {
+            // The thread reading the connection drops the player.
+            let _ = stream.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+}
+
+impl Lobby {
+    fn join(&mut self, stream: TcpStream, outgoing: SyncSender<FromLobby>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        let client = Client {
+            name: format!("Player {}", id + 1),
+            outgoing,
+            stream,
+            opponent: None,
+            host: false,
+            watching: None,
+            token: rand::random(),
+            dropped: None,
+            inputs: VecDeque::new(),
+            played: (None, PlayerInput::default()),
+            rules: MatchRules::default(),
+        };
+        self.clients.insert(id, client);
+        id
+    }
+
+    fn handle(&mut self, id: usize, message: ToLobby) {
+        match message {
+            ToLobby::Register { name } => {
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.name = name;
+                }
+            }
+            ToLobby::Queue => self.queue(id),
+            ToLobby::Leave => {
+                self.unpair(id);
+                if self.waiting == Some(id) {
+                    self.waiting = None;
+                }
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.watching = None;
+                }
+            }
+            ToLobby::Relay(message) => {
+                if let Some(opponent) = self.clients.get(&id).and_then(|client| client.opponent) {
+                    // The host starting the match starts it on an authoritative lobby too.
+                    if let PeerMessage::Start(mutators) = &message {
+                        if self.authoritative && self.clients[&id].host {
+                            let rules = self.clients[&id].rules;
+                            let state = MatchState::new(rules.win_score, mutators.clone());
+                            let started = ServerMatch {
+                                guest: opponent,
+                                rules,
+                                frame: 0,
+                                state,
+                                games: [0; 2],
+                            };
+                            self.matches.insert(id, started);
+                            for player in &[id, opponent] {
+                                if let Some(client) = self.clients.get_mut(player) {
+                                    client.inputs.clear();
+                                    client.played = (None, PlayerInput::default());
+                                }
+                            }
+                        }
+                    }
+                    self.send(opponent, FromLobby::Relay(message));
+                }
+            }
+            ToLobby::Spectate => self.spectate(id),
+            ToLobby::Broadcast(message) => {
+                if self.clients.get(&id).is_some_and(|client| client.host) {
+                    for spectator in self.spectators(id) {
+                        self.send(spectator, FromLobby::Broadcast(message.clone()));
+                    }
+                }
+            }
+            ToLobby::Rejoin { token } => self.rejoin(id, token),
+            ToLobby::Ping(number) => self.send(id, FromLobby::Pong(number)),
+            ToLobby::Input { frame, input } => {
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.inputs.push_back((frame, input));
+                    if client.inputs.len() > MAX_QUEUED_INPUTS {
+                        client.inputs.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Plays a frame of every match, and sends it to its players and everyone watching it. A
+    /// match won starts over, the players play the next one if they go for a rematch.
+    fn play_frame(&mut self) {
+        let hosts: Vec<usize> = self.matches.keys().copied().collect();
+        for host in hosts {
+            let guest = self.matches[&host].guest;
+            // The match waits for a player who lost their connection.
+            let dropped = |id| self.clients.get(id).is_some_and(|client| client.dropped.is_some());
+            if dropped(&host) || dropped(&guest) {
+                continue;
+            }
+            let inputs = [self.next_input(host), self.next_input(guest)];
+            let server_match = match self.matches.get_mut(&host) {
+                Some(server_match) => server_match,
+                None => continue,
+            };
+            let mut events = Vec::new();
+            server_match.state.step(inputs, &mut events);
+            for event in &events {
+                if let MatchEvent::GameWon { winner } = event {
+                    let games = &mut server_match.games[if *winner == Side::Left { 0 } else { 1 }];
+                    *games += 1;
+                    if *games >= server_match.rules.games_to_win() {
+                        server_match.games = [0; 2];
+                    }
+                }
+            }
+            let frame = server_match.frame;
+            server_match.frame += 1;
+            let state = server_match.state.clone();
+            let games = server_match.games;
+
+            for spectator in self.spectators(host) {
+                let state = state.clone();
+                let broadcast = SpectatorMessage::State { state, games };
+                self.send(spectator, FromLobby::Broadcast(broadcast));
+            }
+            for player in &[host, guest] {
+                let played = self.clients.get(player).and_then(|client| client.played.0);
+                let frame = FromLobby::Frame {
+                    frame,
+                    state: state.clone(),
+                    events: events.clone(),
+                    inputs,
+                    played,
+                };
+                self.send(*player, frame);
+            }
+        }
+    }
+
+    /// The player's next input to play, the one they were last played with until it comes in.
+    fn next_input(&mut self, id: usize) -> PlayerInput {
+        match self.clients.get_mut(&id) {
+            Some(client) => {
+                if let Some((frame, input)) = client.inputs.pop_front() {
+                    client.played = (Some(frame), input);
+                }
+                client.played.1
+            }
+            None => PlayerInput::default(),
+        }
+    }
+
+    /// Pairs the player with whoever is waiting, who hosts the match, or has them wait. The
+    /// rules of the match are settled here, and both players are sent them.
+    fn queue(&mut self, id: usize) {
+        if self.clients.get(&id).is_some_and(|client| client.opponent.is_some()) {
+            return;
+        }
+        let host = match self.waiting.take() {
+            Some(host) if host != id => host,
+            _ => {
+                self.waiting = Some(id);
+                self.send(id, FromLobby::Queued);
+                return;
+            }
+        };
+        let rules = MatchRules::default();
+        for (player, opponent) in &[(host, id), (id, host)] {
+            let name = self.clients[opponent].name.clone();
+            let mut token = 0;
+            if let Some(client) = self.clients.get_mut(player) {
+                client.opponent = Some(*opponent);
+                client.host = *player == host;
+                client.rules = rules;
+                token = client.token;
+            }
+            let paired = FromLobby::Paired {
+                opponent: name,
+                host: *player == host,
+                authoritative: self.authoritative,
+                rules,
+                token,
+            };
+            self.send(*player, paired);
+        }
+    }
+
+    /// Has the player watch the match that has been going on the longest, hosts are let in in
+    /// the order they joined the lobby.
+    fn spectate(&mut self, id: usize) {
+        if self.clients.get(&id).is_some_and(|client| client.opponent.is_some()) {
+            return;
+        }
+        let players = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.host)
+            .filter_map(|(host, client)| Some((*host, client.opponent?)))
+            .min();
+        let (host, opponent) = match players {
+            Some(players) => players,
+            None => {
+                self.send(id, FromLobby::NoMatches);
+                return;
+            }
+        };
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.watching = Some(host);
+        }
+        // The host plays on the left.
+        let watching = FromLobby::Watching {
+            left: self.clients[&host].name.clone(),
+            right: self.clients[&opponent].name.clone(),
+        };
+        self.send(id, watching);
+    }
+
+    /// Everyone watching the match the host is playing.
+    fn spectators(&self, host: usize) -> Vec<usize> {
+        self.clients
+            .iter()
+            .filter(|(_, client)| client.watching == Some(host))
+            .map(|(spectator, _)| *spectator)
+            .collect()
+    }
+
+    /// Ends the match the player is in, telling their opponent and everyone watching.
+    fn unpair(&mut self, id: usize) {
+        let opponent = self.clients.get_mut(&id).and_then(|client| client.opponent.take());
+        let hosting = self.clients.get(&id).is_some_and(|client| client.host);
+        // A player left hosting the match after the host left has nobody to tell but the
+        // spectators.
+        let host = match opponent {
+            Some(_) if hosting => id,
+            Some(opponent) => opponent,
+            None if hosting => id,
+            None => return,
+        };
+        self.matches.remove(&host);
+        for player in [Some(id), opponent].iter().flatten() {
+            if let Some(client) = self.clients.get_mut(player) {
+                client.opponent = None;
+                client.host = false;
+            }
+        }
+        if let Some(opponent) = opponent {
+            self.send(opponent, FromLobby::OpponentLeft);
+        }
+        for spectator in self.spectators(host) {
+            if let Some(client) = self.clients.get_mut(&spectator) {
+                client.watching = None;
+            }
+            self.send(spectator, FromLobby::MatchEnded);
+        }
+    }
+
+    /// Holds the match of a player who lost their connection for them to rejoin, they leave
+    /// the lobby right away otherwise.
+    fn drop_client(&mut self, id: usize) {
+        let opponent = self.clients.get(&id).and_then(|client| client.opponent);
+        match (opponent, self.clients.get_mut(&id)) {
+            (Some(opponent), Some(client)) => {
+                client.dropped = Some(Instant::now());
+                self.send(opponent, FromLobby::OpponentDropped);
+            }
+            _ => self.leave(id),
+        }
+    }
+
+    /// Puts the player back in the match they lost their connection to with `token`, in place
+    /// of the connection they had.
+    fn rejoin(&mut self, id: usize, token: u64) {
+        let old = self
+            .clients
+            .iter()
+            .find(|(_, client)| {
+                client.dropped.is_some() && client.opponent.is_some() && client.token == token
+            })
+            .map(|(old, _)| *old);
+        let (old, mut client) = match old.and_then(|old| Some((old, self.clients.remove(&old)?))) {
+            Some(rejoined) => rejoined,
+            None => {
+                self.send(id, FromLobby::OpponentLeft);
+                return;
+            }
+        };
+        let new = match self.clients.remove(&id) {
+            Some(new) => new,
+            None => return,
+        };
+        client.outgoing = new.outgoing;
+        client.stream = new.stream;
+        client.dropped = None;
+        let opponent = client.opponent;
+        self.clients.insert(id, client);
+
+        if let Some(opponent) = opponent.and_then(|opponent| self.clients.get_mut(&opponent)) {
+            opponent.opponent = Some(id);
+        }
+        if let Some(server_match) = self.matches.remove(&old) {
+            self.matches.insert(id, server_match);
+        }
+        for server_match in self.matches.values_mut() {
+            if server_match.guest == old {
+                server_match.guest = id;
+            }
+        }
+        for spectator in self.spectators(old) {
+            if let Some(client) = self.clients.get_mut(&spectator) {
+                client.watching = Some(id);
+            }
+        }
+        self.send(id, FromLobby::Rejoined);
+        if let Some(opponent) = opponent {
+            self.send(opponent, FromLobby::OpponentRejoined);
+        }
+    }
+
+    /// Gives up on the players who didn't rejoin their match in `RECONNECT_TIME`. When it was
+    /// the host of a match the players play themselves, their opponent hosts it in their place
+    /// and the spectators go on watching it.
+    fn expire_dropped(&mut self) {
+        let wait = Duration::from_secs_f32(RECONNECT_TIME);
+        let expired: Vec<usize> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.dropped.is_some_and(|dropped| dropped.elapsed() >= wait))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            let opponent = self.clients[&id].opponent;
+            if let (Some(opponent), true, false) =
+                (opponent, self.clients[&id].host, self.authoritative)
+            {
+                for spectator in self.spectators(id) {
+                    if let Some(client) = self.clients.get_mut(&spectator) {
+                        client.watching = Some(opponent);
+                    }
+                }
+                for (player, host) in &[(id, false), (opponent, true)] {
+                    if let Some(client) = self.clients.get_mut(player) {
+                        client.opponent = None;
+                        client.host = *host;
+                    }
+                }
+                self.send(opponent, FromLobby::HostMigrated);
+            }
+            self.leave(id);
+        }
+    }
+
+    fn leave(&mut self, id: usize) {
+        self.handle(id, ToLobby::Leave);
+        self.clients.remove(&id);
+    }
+
+    fn send(&self, id: usize, message: FromLobby) {
+        if let Some(client) = self.clients.get(&id) {
+            // A player who stopped reading, or whose connection broke, has it shut down and is
+            // dropped by the thread reading it.
+            if client.outgoing.try_send(message).is_err() {
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
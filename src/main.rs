@@ -1,76 +1,528 @@
 use amethyst::{
     assets::Processor,
-    core::transform::TransformBundle,
+    audio::{output::default_output, AudioBundle, Source},
+    config::Config,
+    core::{math::Vector2, transform::TransformBundle, SystemExt},
     ecs::{ReadExpect, Resources, SystemData},
     input::{InputBundle, StringBindings},
     prelude::*,
     renderer::{
-        pass::DrawFlat2DDesc, types::DefaultBackend, Factory, Format, GraphBuilder, GraphCreator,
-        Kind, RenderGroupDesc, RenderingSystem, SpriteSheet, SubpassBuilder,
+        pass::{DrawFlat2DDesc, DrawFlat2DTransparentDesc},
+        sprite_visibility::SpriteVisibilitySortingSystem,
+        types::DefaultBackend,
+        rendy::graph::render::SimpleGraphicsPipelineDesc,
+        Factory, Format, GraphBuilder, GraphCreator, Kind, RenderGroupDesc, RenderingSystem,
+        SpriteSheet, SubpassBuilder,
     },
     ui::{DrawUiDesc, UiBundle},
-    window::{ScreenDimensions, Window, WindowBundle},
+    utils::fps_counter::FPSCounterBundle,
+    window::{DisplayConfig, ScreenDimensions, Window, WindowBundle},
 };
-mod pong;
-mod systems;
-use pong::Pong;
+use pong::{
+    achievements::Achievements,
+    bloom,
+    config::{GameConfig, Gameplay, Msaa, PresentMode, UserSettings},
+    crt,
+    ghost::Ghost,
+    high_scores::HighScores,
+    history::MatchHistory,
+    leaderboard::Leaderboard,
+    loading::Loading,
+    lobby,
+    menu::MainMenu,
+    msaa,
+    palette::Palette,
+    profiles::Profiles,
+    pong::{Difficulty, MatchRules, Mutator, Mutators, Pong, RunningState},
+    systems,
+    theme::Theme,
+};
+
+#[cfg(feature = "gamepad")]
+use amethyst::input::SdlEventsSystem;
 
 fn main() -> Result<(), amethyst::Error> {
+    // Started first, so the lobby server logs what goes wrong too.
     amethyst::start_logger(Default::default());
+
+    // `cargo run -- export_history=matches.csv` writes the match history out, as JSON for a
+    // `.json` file, instead of playing.
+    if let Some(path) = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.strip_prefix("export_history=").map(str::to_string))
+    {
+        return Ok(MatchHistory::load_saved().export(std::path::Path::new(&path))?);
+    }
+
+    // `cargo run -- lobby_server=0.0.0.0:7777` runs the lobby online matches are found through
+    // instead of the game. The `pong_server` binary runs one that plays the matches itself.
+    if let Some(address) = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.strip_prefix("lobby_server=").map(str::to_string))
+    {
+        return Ok(lobby::serve(&address, false)?);
+    }
+
+    // The saves from the other machines played on are brought in before any of them are read.
+    #[cfg(feature = "cloud_sync")]
+    pong::cloud_sync::start();
+    // The saves from Steam Cloud are brought in before any of them are read.
+    #[cfg(feature = "steam")]
+    let steam = pong::steam::Steam::init().inspect(pong::steam::Steam::download_saves);
     let app_root = std::path::PathBuf::from(".");
     let display_config_path = app_root.join("resources").join("display_config.ron");
-    let binding_path = app_root.join("resources").join("bindings_config.ron");
+    let UserSettings {
+        game: game_config,
+        audio: audio_settings,
+        controls,
+        names: player_names,
+    } = UserSettings::load_saved();
+    // The window opens in the size picked in the settings.
+    let mut display_config = DisplayConfig::load(display_config_path);
+    display_config.dimensions = Some(game_config.resolution);
 
-    let input_bundle =
-        InputBundle::<StringBindings>::new().with_bindings_from_file(binding_path)?;
+    let input_bundle = InputBundle::<StringBindings>::new().with_bindings(controls);
     let game_data = GameDataBuilder::default()
         .with_bundle(input_bundle)?
         // The WindowBundle provides all the scaffolding for opening a window
-        .with_bundle(WindowBundle::from_config_path(display_config_path))?
+        .with_bundle(WindowBundle::from_config(display_config))?
         .with_bundle(TransformBundle::new())?
         .with_bundle(UiBundle::<DefaultBackend, StringBindings>::new())?
+        // Keeps count of the frames drawn every second, for the FPS counter.
+        .with_bundle(FPSCounterBundle)?
         // A Processor system is added to handle loading spritesheets.
         .with(
             Processor::<SpriteSheet>::new(),
             "sprite_sheet_processor",
             &[],
+        );
+    // The AudioBundle opens the default audio output and brings a Processor for the sound
+    // effects. It can't do without an output, so on machines without an audio device only the
+    // Processor is added and the sounds stay quiet.
+    let game_data = if default_output().is_some() {
+        game_data.with_bundle(AudioBundle::default())?
+    } else {
+        game_data.with(Processor::<Source>::new(), "source_processor", &[])
+    };
+    let game_data = game_data
+        // Sorts out which sprites are see-through, so they're drawn after the others.
+        .with(
+            SpriteVisibilitySortingSystem::new(),
+            "sprite_visibility_system",
+            &["transform_system"],
         )
+        // The gameplay systems stand still while the game is paused. The `WinnerSystem` and
+        // `ScoreUiSystem` keep running since `Pausable` doesn't pass on their `setup`, they
+        // don't move anything anyway.
         .with(
-            systems::paddle::PaddleSystem,
+            systems::dash::DashSystem.pausable(RunningState::Running),
+            "dash_system",
+            &["input_system"],
+        )
+        .with(
+            systems::mirror::MirrorSystem.pausable(RunningState::Running),
+            "mirror_system",
+            &[],
+        )
+        // Reads the touches too, it leaves the drags out itself while the match is paused.
+        .with(systems::touch::TouchSystem::default(), "touch_system", &[])
+        .with(systems::chat_votes::ChatVoteSystem::default(), "chat_vote_system", &[])
+        .with(
+            systems::paddle::PaddleSystem::default().pausable(RunningState::Running),
             "paddle_system",
+            &["input_system", "dash_system", "mirror_system", "touch_system", "chat_vote_system"],
+        )
+        .with(
+            systems::ai::AiPaddleSystem.pausable(RunningState::Running),
+            "ai_paddle_system",
+            &[],
+        )
+        .with(
+            systems::move_balls::MoveBallsSystem.pausable(RunningState::Running),
+            "ball_system",
+            &[],
+        )
+        .with(
+            systems::power_shot::PowerShotSystem.pausable(RunningState::Running),
+            "power_shot_system",
             &["input_system"],
         )
-        .with(systems::move_balls::MoveBallsSystem, "ball_system", &[])
         .with(
-            systems::bounce::BounceSystem,
+            systems::goal_zones::GoalZoneSystem.pausable(RunningState::Running),
+            "goal_zone_system",
+            &[],
+        )
+        .with(
+            systems::bounce::BounceSystem.pausable(RunningState::Running),
             "collision_system",
-            &["paddle_system", "ball_system"],
+            &[
+                "paddle_system",
+                "ai_paddle_system",
+                "ball_system",
+                "power_shot_system",
+                "goal_zone_system",
+            ],
+        )
+        .with(
+            systems::portals::PortalSystem.pausable(RunningState::Running),
+            "portal_system",
+            &["collision_system"],
+        )
+        .with(systems::palette::PaletteSystem, "palette_system", &["portal_system"])
+        .with(
+            systems::blink::BlinkSystem,
+            "blink_system",
+            &["portal_system", "palette_system"],
+        )
+        .with(
+            systems::parallax::ParallaxSystem.pausable(RunningState::Running),
+            "parallax_system",
+            &[],
+        )
+        .with(
+            systems::power_shot::ScreenShakeSystem.pausable(RunningState::Running),
+            "screen_shake_system",
+            &["collision_system"],
         )
+        // Runs while paused too, so the arena keeps its shape when the window is resized then.
+        .with(systems::letterbox::LetterboxSystem, "letterbox_system", &[])
         .with(
-            systems::winner::WinnerSystem,
+            systems::winner::WinnerSystem::default(),
             "winner_system",
-            &["ball_system"],
+            &["ball_system", "goal_zone_system", "portal_system"],
+        )
+        .with(
+            systems::score_ui::ScoreUiSystem::default(),
+            "score_ui_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::rally::RallyUiSystem,
+            "rally_ui_system",
+            &["collision_system", "winner_system"],
+        )
+        .with(
+            systems::gamepad_menu::GamepadMenuSystem::default(),
+            "gamepad_menu_system",
+            &["input_system"],
+        )
+        .with(
+            systems::hotplug::GamepadHotplugSystem::default(),
+            "gamepad_hotplug_system",
+            &["input_system"],
+        )
+        .with(
+            systems::mute::MuteSystem::default(),
+            "mute_system",
+            &["input_system"],
+        )
+        .with(systems::music::MusicSystem, "music_system", &["mute_system"])
+        .with(
+            systems::window_mode::WindowModeSystem::default(),
+            "window_mode_system",
+            &["input_system"],
+        )
+        .with(
+            systems::fps::FpsSystem::default(),
+            "fps_system",
+            &["input_system", "fps_counter_system"],
+        )
+        .with(
+            systems::net_stats::NetStatsSystem::default(),
+            "net_stats_system",
+            &["input_system"],
+        )
+        .with(systems::sound::SoundEmitterSystem, "sound_emitter_system", &[])
+        .with(
+            systems::crowd::CrowdSystem::default(),
+            "crowd_system",
+            &["winner_system", "music_system"],
+        )
+        .with(
+            systems::announcer::AnnouncerSystem::default(),
+            "announcer_system",
+            &["winner_system", "music_system"],
+        )
+        .with(
+            systems::hints::ControlHintSystem.pausable(RunningState::Running),
+            "control_hint_system",
+            &[],
+        )
+        .with(
+            systems::countdown::CountdownSystem.pausable(RunningState::Running),
+            "countdown_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::serve::ServeSystem.pausable(RunningState::Running),
+            "serve_system",
+            &["paddle_system", "ai_paddle_system", "countdown_system"],
+        )
+        // Plays online matches in place of the paddle, serve, bounce and winner systems.
+        .with(
+            systems::online::OnlineSystem::default(),
+            "online_system",
+            &["winner_system", "serve_system", "paddle_system"],
+        )
+        // Shows the online match being watched, as its host broadcasts it.
+        .with(
+            systems::spectator::SpectatorSystem::default(),
+            "spectator_system",
+            &["winner_system", "serve_system", "paddle_system"],
+        )
+        // Plays saved online matches back, in place of the same systems.
+        .with(
+            systems::replay::ReplaySystem::default().pausable(RunningState::Running),
+            "replay_system",
+            &["winner_system", "serve_system", "paddle_system"],
+        )
+        .with(
+            systems::time_attack::TimeAttackSystem.pausable(RunningState::Running),
+            "time_attack_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::practice::LauncherSystem.pausable(RunningState::Running),
+            "launcher_system",
+            &["input_system", "winner_system"],
+        )
+        .with(
+            systems::ghost::GhostSystem.pausable(RunningState::Running),
+            "ghost_system",
+            &["paddle_system"],
+        )
+        .with(
+            systems::survival::SurvivalSystem.pausable(RunningState::Running),
+            "survival_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::achievements::AchievementSystem::default().pausable(RunningState::Running),
+            "achievement_system",
+            &[
+                "winner_system", "online_system", "time_attack_system", "collision_system",
+                "survival_system",
+            ],
+        )
+        // Runs on every screen, so achievements unlocked as a match ends stay up after it.
+        .with(
+            systems::achievements::AchievementToastSystem::default(),
+            "achievement_toast_system",
+            &["achievement_system"],
+        )
+        // Runs on every screen too, and while the match is paused, for tuning it as it's played.
+        .with(systems::hot_reload::HotReloadSystem::default(), "hot_reload_system", &[])
+        .with(
+            systems::stats::MatchStatsSystem::default().pausable(RunningState::Running),
+            "match_stats_system",
+            &[],
+        )
+        .with(
+            systems::overtime::OvertimeSystem.pausable(RunningState::Running),
+            "overtime_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::multi_ball::MultiBallSystem::default().pausable(RunningState::Running),
+            "multi_ball_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::powerups::PowerUpSpawnSystem.pausable(RunningState::Running),
+            "power_up_spawn_system",
+            &["winner_system"],
+        )
+        .with(
+            systems::powerups::PowerUpCollectSystem.pausable(RunningState::Running),
+            "power_up_collect_system",
+            &["ball_system", "collision_system"],
+        )
+        .with(
+            systems::powerups::PowerUpEffectSystem.pausable(RunningState::Running),
+            "power_up_effect_system",
+            &["power_up_collect_system"],
+        )
+        // Reads the hits the `BounceSystem` sends, so it can't be paused either.
+        .with(
+            systems::particles::ParticleEmitterSystem::default(),
+            "particle_emitter_system",
+            &["collision_system"],
+        )
+        .with(
+            systems::particles::TrailSystem.pausable(RunningState::Running),
+            "trail_system",
+            &["collision_system"],
+        )
+        .with(
+            systems::particles::ParticleSystem.pausable(RunningState::Running),
+            "particle_system",
+            &["particle_emitter_system", "trail_system"],
+        )
+        // Reads the hits too, it holds the animations still itself while the match is paused.
+        .with(
+            systems::animation::AnimationSystem::default(),
+            "animation_system",
+            &["collision_system"],
         )
         // The renderer must be executed on the same thread consecutively, so we initialize it as thread_local
         // which will always execute on the main thread.
         .with_thread_local(RenderingSystem::<DefaultBackend, _>::new(
             ExampleGraph::default(),
         ));
+    #[cfg(feature = "gamepad")]
+    let game_data = with_gamepads(game_data);
+    #[cfg(feature = "discord")]
+    let game_data = game_data.with(
+        systems::discord::DiscordPresenceSystem::default(),
+        "discord_presence_system",
+        &["winner_system", "music_system"],
+    );
+    #[cfg(feature = "steam")]
+    let game_data = match steam {
+        Some(steam) => game_data.with_thread_local(systems::steam::SteamSystem::new(steam)),
+        None => game_data,
+    };
 
     let assets_dir = app_root.join("assets");
-    let mut game = Application::new(assets_dir, Pong::default(), game_data)?;
+    let gameplay = Gameplay::load_shipped();
+    let menu = MainMenu::new(initial_state(&gameplay));
+    let mut game = Application::build(assets_dir, Loading::new(menu))?
+        .with_resource(Palette::new(game_config.palette))
+        .with_resource(Leaderboard::new(game_config.leaderboard_url.clone()))
+        .with_resource(HighScores::load_saved())
+        .with_resource(Ghost::load_saved())
+        .with_resource(Profiles::load_saved())
+        .with_resource(MatchHistory::load_saved())
+        .with_resource(Achievements::load_saved())
+        .with_resource(gameplay)
+        .with_resource(game_config)
+        .with_resource(player_names)
+        .with_resource(audio_settings)
+        .build(game_data)?;
     game.run();
     Ok(())
 }
 
+/// Reads the gamepads through SDL, the game goes on without them if SDL can't open them.
+#[cfg(feature = "gamepad")]
+fn with_gamepads<'a, 'b>(game_data: GameDataBuilder<'a, 'b>) -> GameDataBuilder<'a, 'b> {
+    match SdlEventsSystem::<StringBindings>::new(None) {
+        Ok(sdl_events) => game_data
+            .with_thread_local(sdl_events)
+            .with_thread_local(systems::rumble::RumbleSystem::default()),
+        Err(err) => {
+            log::warn!("Failed to open the gamepads: {}", err);
+            game_data
+        }
+    }
+}
+
+/// Picks between a 1 or 2 player match from the command line, e.g.
+/// `cargo run -- 1p hard` plays against the AI on hard.
+/// `4p` plays with a paddle on every edge of the arena and `2v2` with two teams of two.
+/// `survival` has one player hold out against more and more balls for as long as they can,
+/// and `practice` has them return balls from a launcher they can tune with 1 to 6.
+/// Adding `obstacles` puts a couple of blocks in the middle of the arena, `bo5` makes
+/// the match a best of 5 games instead of 3 and `win_score=11` plays games to 11 points.
+/// `time=90` plays time attack, the player who is ahead after 90 seconds wins.
+/// `portals` adds a pair of portals that balls can be shot through.
+/// Mutators are turned on by their name, e.g. `gravity` or `big_ball`, and can also be
+/// toggled on the screen before the match.
+/// `chat_plays` has the Twitch chat of the `twitch_channel` in the game config play the right
+/// paddle of a 2 player match, by typing up or down.
+fn initial_state(gameplay: &Gameplay) -> Pong {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let has_arg = |name: &str| args.iter().any(|arg| arg == name);
+
+    let pong = if has_arg("practice") {
+        Pong::practice()
+    } else if has_arg("survival") {
+        Pong::survival()
+    } else if has_arg("4p") {
+        Pong::four_player()
+    } else if has_arg("2v2") {
+        Pong::doubles()
+    } else if !has_arg("1p") {
+        Pong::two_player()
+    } else if has_arg("easy") {
+        Pong::single_player(Difficulty::Easy)
+    } else if has_arg("hard") {
+        Pong::single_player(Difficulty::Hard)
+    } else {
+        Pong::single_player(Difficulty::Medium)
+    };
+
+    let mut rules = MatchRules {
+        win_score: gameplay.win_score,
+        ..MatchRules::default()
+    };
+    if has_arg("bo5") {
+        rules.best_of = 5;
+    }
+    if let Some(win_score) = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("win_score="))
+        .find_map(|value| value.parse().ok())
+    {
+        rules.win_score = win_score;
+    }
+    // A time attack match is a single game against the clock, unless asked for more.
+    if let Some(time_limit) = args
+        .iter()
+        .filter_map(|arg| arg.strip_prefix("time="))
+        .find_map(|value| value.parse().ok())
+    {
+        rules.time_limit = Some(time_limit);
+        if !has_arg("bo5") {
+            rules.best_of = 1;
+        }
+    }
+    let mut mutators = Mutators::default();
+    for mutator in Mutator::ALL.iter().filter(|mutator| has_arg(mutator.name())) {
+        mutators.toggle(*mutator);
+    }
+    let pong = pong.with_rules(rules).with_mutators(mutators);
+    let pong = if has_arg("chat_plays") && !has_arg("1p") {
+        pong.with_chat_plays()
+    } else {
+        pong
+    };
+    let pong = if has_arg("portals") {
+        pong.with_portals(
+            Vector2::new(gameplay.arena_width * 0.3, gameplay.arena_height * 0.8),
+            Vector2::new(gameplay.arena_width * 0.7, gameplay.arena_height * 0.2),
+        )
+    } else {
+        pong
+    };
+
+    if has_arg("obstacles") {
+        let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+        pong.with_obstacle(width / 2.0, height * 0.25, 4.0, 16.0)
+            .with_obstacle(width / 2.0, height * 0.75, 4.0, 16.0)
+    } else {
+        pong
+    }
+}
+
 // This graph structure is used for creating a proper `RenderGraph` for rendering.
 // A renderGraph can be thought of as the stages during a render pass. In our case,
-// we are only executing one subpass (DrawFlat2D, or the sprite pass). This graph
-// also needs to be rebuilt whenever the window is resized, so the boilerplate code
-// for that operation is also here.
+// we are executing one subpass drawing the sprites and the UI, followed by the bloom and CRT
+// passes when they're turned on in the settings. With multisampling on, the sprites are drawn
+// in a multisampled pass of their own and the UI in one after it. This graph also needs to be
+// rebuilt whenever the window is resized or goes in or out of fullscreen, or those passes, the
+// multisampling or the present mode are changed, so the boilerplate code for that operation is
+// also here.
 #[derive(Default)]
 struct ExampleGraph {
     dimensions: Option<ScreenDimensions>,
+    fullscreen: bool,
+    crt: bool,
+    bloom: bool,
+    msaa: Msaa,
+    present_mode: PresentMode,
+    /// What the screen is cleared to, the background of the `Theme` or the `Palette`.
+    background: [f32; 4],
     dirty: bool,
 }
 
@@ -79,6 +531,38 @@ impl GraphCreator<DefaultBackend> for ExampleGraph {
     // the window has been resized. This implementation checks the screen size and returns true
     // if it has changed.
     fn rebuild(&mut self, res: &Resources) -> bool {
+        if let Some(config) = res.try_fetch::<GameConfig>() {
+            // The new window size may take a few frames to come in, the graph is built again
+            // once it does.
+            if config.fullscreen != self.fullscreen {
+                self.fullscreen = config.fullscreen;
+                self.dirty = true;
+            }
+            if config.crt != self.crt {
+                self.crt = config.crt;
+                self.dirty = true;
+            }
+            if config.bloom != self.bloom {
+                self.bloom = config.bloom;
+                self.dirty = true;
+            }
+            if config.msaa != self.msaa {
+                self.msaa = config.msaa;
+                self.dirty = true;
+            }
+            if config.present_mode != self.present_mode {
+                self.present_mode = config.present_mode;
+                self.dirty = true;
+            }
+        }
+        if let Some(palette) = res.try_fetch::<Palette>() {
+            let background = palette.clear_color(res.try_fetch::<Theme>().as_deref());
+            if background != self.background {
+                self.background = background;
+                self.dirty = true;
+            }
+        }
+
         // Rebuild when dimensions change, but wait until at least two frames have the same.
         let new_dimensions = res.try_fetch::<ScreenDimensions>();
         use std::ops::Deref;
@@ -99,7 +583,10 @@ impl GraphCreator<DefaultBackend> for ExampleGraph {
     ) -> GraphBuilder<DefaultBackend, Resources> {
         use amethyst::renderer::rendy::{
             graph::present::PresentNode,
-            hal::command::{ClearDepthStencil, ClearValue},
+            hal::{
+                self,
+                command::{ClearDepthStencil, ClearValue},
+            },
         };
 
         self.dirty = false;
@@ -119,8 +606,8 @@ impl GraphCreator<DefaultBackend> for ExampleGraph {
             window_kind,
             1,
             surface_format,
-            // clear screen to black
-            Some(ClearValue::Color([0.0, 0.0, 0.0, 1.0].into())),
+            // clear screen to the background of the theme or palette
+            Some(ClearValue::Color(self.background.into())),
         );
 
         let depth = graph_builder.create_image(
@@ -130,20 +617,97 @@ impl GraphCreator<DefaultBackend> for ExampleGraph {
             Some(ClearValue::DepthStencil(ClearDepthStencil(1.0, 0))),
         );
 
-        // Create our single `Subpass`, which is the DrawFlat2D pass.
+        // With the bloom or CRT pass on the sprites are drawn into an image of their own, which
+        // the passes after them read. The bloom draws the glowing scene into the image the CRT
+        // pass reads when they're both on, and the last of them draws onto the screen.
+        let clear = ClearValue::Color(self.background.into());
+        let crt_scene = if self.crt {
+            Some(graph_builder.create_image(window_kind, 1, surface_format, Some(clear)))
+        } else {
+            None
+        };
+        let scene = if self.bloom {
+            graph_builder.create_image(window_kind, 1, surface_format, Some(clear))
+        } else {
+            crt_scene.unwrap_or(color)
+        };
+
+        // Create the `Subpass` drawing the game, which is the DrawFlat2D pass.
         // We pass the subpass builder a description of our pass for construction
-        let pass = graph_builder.add_node(
-            SubpassBuilder::new()
-                .with_group(DrawFlat2DDesc::default().builder()) // Draws sprites
-                .with_group(DrawUiDesc::default().builder()) // Draws UI components
-                .with_color(color)
-                .with_depth_stencil(depth)
-                .into_pass(),
-        );
+        let samples = msaa::supported_samples(factory, self.msaa.samples());
+        let mut pass = if samples > 1 {
+            // The sprites are resolved onto the scene, and the UI drawn over them with the
+            // single sampled depth.
+            let sprites = msaa::add_multisampled_sprites(
+                &mut graph_builder,
+                window_kind,
+                surface_format,
+                samples,
+                clear,
+                scene,
+            );
+            graph_builder.add_node(
+                SubpassBuilder::new()
+                    .with_group(DrawUiDesc::new().builder())
+                    .with_color(scene)
+                    .with_depth_stencil(depth)
+                    .with_dependency(sprites)
+                    .into_pass(),
+            )
+        } else {
+            graph_builder.add_node(
+                SubpassBuilder::new()
+                    .with_group(DrawFlat2DDesc::default().builder()) // Draws sprites
+                    .with_group(DrawFlat2DTransparentDesc::new().builder()) // Draws faded sprites
+                    .with_group(DrawUiDesc::default().builder()) // Draws UI components
+                    .with_color(scene)
+                    .with_depth_stencil(depth)
+                    .into_pass(),
+            )
+        };
+
+        if self.bloom {
+            let target = crt_scene.unwrap_or(color);
+            pass = bloom::add_bloom(
+                &mut graph_builder,
+                window_kind,
+                surface_format,
+                scene,
+                target,
+                pass,
+            );
+        }
 
-        // Finally, add the pass to the graph
-        let _present = graph_builder
-            .add_node(PresentNode::builder(factory, surface, color).with_dependency(pass));
+        if let Some(crt_scene) = crt_scene {
+            pass = graph_builder.add_node(
+                SubpassBuilder::new()
+                    .with_group(
+                        crt::DrawCrtDesc
+                            .builder()
+                            .with_image(crt_scene)
+                            .with_dependency(pass),
+                    )
+                    .with_color(color)
+                    .into_pass(),
+            );
+        }
+
+        // Finally, add the pass to the graph. Fifo is vsync and always supported, so it's what
+        // the present mode falls back to.
+        let wanted = match self.present_mode {
+            PresentMode::Vsync => hal::PresentMode::Fifo,
+            PresentMode::Mailbox => hal::PresentMode::Mailbox,
+            PresentMode::Immediate => hal::PresentMode::Immediate,
+        };
+        let _present = graph_builder.add_node(
+            PresentNode::builder(factory, surface, color)
+                .with_present_modes_priority(|mode| match mode {
+                    mode if mode == wanted => Some(1),
+                    hal::PresentMode::Fifo => Some(0),
+                    _ => None,
+                })
+                .with_dependency(pass),
+        );
 
         graph_builder
     }
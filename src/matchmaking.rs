@@ -0,0 +1,162 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::{
+    config::{GameConfig, PlayerNames},
+    confirm::ConfirmQuit,
+    menu::{menu_input, MenuInput},
+    net::{FromLobby, LobbyClient, Online, Peer, ToLobby, Watching},
+    pong::{Pong, Side},
+    pre_match::PreMatch,
+    theme::Theme,
+};
+
+/// Looks for an opponent through the lobby server in `GameConfig::lobby_address`, shown when
+/// Online is picked on the main menu. The player goes by the name last typed in for the left
+/// side, and goes on to the `PreMatch` with their opponent as soon as the lobby pairs them with
+/// someone. Watch online looks for a match to watch instead.
+///
+/// Back stops looking and goes back to the main menu.
+#[derive(Default)]
+pub struct Matchmaking {
+    /// Whether the player is looking for a match to watch rather than to play.
+    spectate: bool,
+    status: Option<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl Matchmaking {
+    pub fn spectate() -> Matchmaking {
+        Matchmaking {
+            spectate: true,
+            ..Default::default()
+        }
+    }
+
+    fn set_status(&self, world: &mut World, status: String) {
+        let mut ui_text = world.write_storage::<UiText>();
+        if let Some(text) = self.status.and_then(|status| ui_text.get_mut(status)) {
+            text.text = status;
+        }
+    }
+}
+
+impl SimpleState for Matchmaking {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let address = world.read_resource::<GameConfig>().lobby_address.clone();
+        let name = world.read_resource::<PlayerNames>().name(Side::Left).to_string();
+        let lobby = LobbyClient::connect(address.clone());
+        lobby.send(ToLobby::Register { name });
+        lobby.send(if self.spectate {
+            ToLobby::Spectate
+        } else {
+            ToLobby::Queue
+        });
+        *world.write_resource::<Online>() = Online {
+            lobby: Some(lobby),
+            ..Default::default()
+        };
+
+        let theme = world.read_resource::<Theme>().clone();
+        let title = if self.spectate { "Watch online" } else { "Online" };
+        let lines = [
+            (title.to_string(), 60., 40.),
+            (format!("Connecting to {}", address), 0., 20.),
+            ("Escape to cancel".to_string(), -60., 15.),
+        ];
+        self.texts = lines
+            .iter()
+            .enumerate()
+            .map(|(index, (line, y, font_size))| {
+                let transform = UiTransform::new(
+                    format!("matchmaking_{}", index), Anchor::Middle, Anchor::Middle,
+                    0., *y, 1., 450., 50.,
+                );
+                world
+                    .create_entity()
+                    .with(transform)
+                    .with(theme.text(line.clone(), *font_size))
+                    .build()
+            })
+            .collect();
+        self.status = Some(self.texts[1]);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("matchmaking text should be alive");
+        self.texts.clear();
+        self.status = None;
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        // One at a time, so what the opponent sends right away is left for the `PreMatch`.
+        loop {
+            let message = match data.world.read_resource::<Online>().next() {
+                Some(message) => message,
+                None => break,
+            };
+            match message {
+                Ok(FromLobby::Queued) => {
+                    self.set_status(data.world, "Looking for an opponent".to_string());
+                }
+                Ok(FromLobby::Paired {
+                    opponent,
+                    host,
+                    authoritative,
+                    rules,
+                    token,
+                }) => {
+                    data.world.write_resource::<Online>().peer = Some(Peer {
+                        name: opponent,
+                        host,
+                        authoritative,
+                        rules,
+                        token,
+                        migrated: false,
+                    });
+                    return Trans::Switch(Box::new(PreMatch::default()));
+                }
+                Ok(FromLobby::Watching { left, right }) => {
+                    data.world.write_resource::<Online>().watching = Some(Watching { left, right });
+                    return Trans::Switch(Box::new(Pong::online()));
+                }
+                Ok(FromLobby::NoMatches) => {
+                    data.world.write_resource::<Online>().disconnect();
+                    self.set_status(data.world, "Nobody is playing right now".to_string());
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    data.world.write_resource::<Online>().disconnect();
+                    self.set_status(data.world, format!("Couldn't reach the lobby: {}", err));
+                }
+            }
+        }
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+        match menu_input(&event) {
+            Some(MenuInput::Back) => {
+                data.world.write_resource::<Online>().disconnect();
+                Trans::Pop
+            }
+            _ => Trans::None,
+        }
+    }
+}
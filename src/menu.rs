@@ -0,0 +1,283 @@
+use std::fmt::Display;
+
+use amethyst::{
+    ecs::prelude::Entity,
+    input::{is_close_requested, is_key_down, InputEvent},
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+    winit::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+};
+
+use crate::{
+    audio::{Music, Track},
+    confirm::ConfirmQuit,
+    high_scores_menu::HighScoresMenu,
+    leaderboard_menu::LeaderboardMenu,
+    matchmaking::Matchmaking,
+    mode_select::ModeSelect,
+    net::Online,
+    pong::Pong,
+    profiles_menu::ProfilesMenu,
+    replays_menu::ReplaysMenu,
+    settings::{apply_window_mode, Settings},
+    theme::Theme,
+    tutorial::Tutorial,
+};
+
+/// What a key press or gamepad button means on a menu screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+/// Whether the event is Alt+Enter being pressed, which switches between windowed and fullscreen
+/// on every screen rather than picking anything on a menu.
+pub fn is_alt_enter(event: &Event) -> bool {
+    match event {
+        Event::WindowEvent {
+            event:
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Return),
+                            modifiers,
+                            ..
+                        },
+                    ..
+                },
+            ..
+        } => modifiers.alt,
+        _ => false,
+    }
+}
+
+/// Reads a menu input from the keyboard, or from the `menu_*` actions the `GamepadMenuSystem`
+/// sends for every gamepad. Start picks like Enter does.
+pub fn menu_input(event: &StateEvent) -> Option<MenuInput> {
+    match event {
+        StateEvent::Window(event) if !is_alt_enter(event) => [
+            (VirtualKeyCode::Up, MenuInput::Up),
+            (VirtualKeyCode::Down, MenuInput::Down),
+            (VirtualKeyCode::Left, MenuInput::Left),
+            (VirtualKeyCode::Right, MenuInput::Right),
+            (VirtualKeyCode::Return, MenuInput::Select),
+            (VirtualKeyCode::Escape, MenuInput::Back),
+        ]
+        .iter()
+        .find(|(key, _)| is_key_down(event, *key))
+        .map(|(_, input)| *input),
+        StateEvent::Input(InputEvent::ActionPressed(action)) => match action.as_str() {
+            "menu_up" => Some(MenuInput::Up),
+            "menu_down" => Some(MenuInput::Down),
+            "menu_left" => Some(MenuInput::Left),
+            "menu_right" => Some(MenuInput::Right),
+            "menu_select" | "menu_start" => Some(MenuInput::Select),
+            "menu_back" => Some(MenuInput::Back),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Shows the `labels` on the `rows` of a menu, one each, marking the `selected` row with a ">"
+/// and the theme's color for it. Rows past the last label are left blank.
+pub fn refresh_rows<I>(world: &World, rows: &[Entity], labels: I, selected: Option<usize>)
+where
+    I: IntoIterator,
+    I::Item: Display,
+{
+    let theme = world.read_resource::<Theme>();
+    let mut ui_text = world.write_storage::<UiText>();
+    let mut labels = labels.into_iter();
+    for (index, entity) in rows.iter().enumerate() {
+        let label = labels.next();
+        if let Some(text) = ui_text.get_mut(*entity) {
+            let picked = selected == Some(index);
+            let marker = if picked { ">" } else { " " };
+            text.text = label.map_or_else(String::new, |label| format!("{} {}", marker, label));
+            text.color = theme.item_color(picked);
+        }
+    }
+}
+
+/// Entries of the main menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Play,
+    Online,
+    Watch,
+    Leaderboard,
+    HighScores,
+    Replays,
+    Profiles,
+    Tutorial,
+    Settings,
+    Quit,
+}
+
+impl Item {
+    const ALL: [Item; 10] = [
+        Item::Play,
+        Item::Online,
+        Item::Watch,
+        Item::Leaderboard,
+        Item::HighScores,
+        Item::Replays,
+        Item::Profiles,
+        Item::Tutorial,
+        Item::Settings,
+        Item::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Item::Play => "Play",
+            Item::Online => "Play online",
+            Item::Watch => "Watch online",
+            Item::Leaderboard => "Leaderboard",
+            Item::HighScores => "High scores",
+            Item::Replays => "Replays",
+            Item::Profiles => "Profiles",
+            Item::Tutorial => "Tutorial",
+            Item::Settings => "Settings",
+            Item::Quit => "Quit",
+        }
+    }
+}
+
+/// The first screen of the game, at the bottom of the state stack. Playing pushes the match
+/// setup on top of it, and the game comes back here once the match is left.
+pub struct MainMenu {
+    /// The match picked on the command line, every match played from the menu is a fresh
+    /// copy of it.
+    pong: Pong,
+    selected: usize,
+    item_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl MainMenu {
+    pub fn new(pong: Pong) -> MainMenu {
+        MainMenu {
+            pong,
+            selected: 0,
+            item_texts: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "menu_title".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 100., 1., 500., 60.,
+            ))
+            .with(theme.text("Pong!".to_string(), 50.))
+            .build();
+        self.texts.push(title);
+
+        for index in 0..Item::ALL.len() {
+            let transform = UiTransform::new(
+                format!("menu_{}", index), Anchor::Middle, Anchor::Middle,
+                0., 40. - 27. * index as f32, 1., 300., 27.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 25.))
+                .with(theme.button.clone())
+                .build();
+            self.item_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.texts)
+            .expect("menu text should be alive");
+        world
+            .delete_entities(&self.item_texts)
+            .expect("menu text should be alive");
+        self.texts.clear();
+        self.item_texts.clear();
+    }
+
+    /// Updates the text of every item, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let labels = Item::ALL.iter().map(|item| item.label());
+        refresh_rows(world, &self.item_texts, labels, Some(self.selected));
+    }
+}
+
+impl SimpleState for MainMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // The window opens in the mode from the display config, switch to the saved one.
+        apply_window_mode(data.world);
+        data.world.write_resource::<Music>().play(Track::Menu);
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    // Hide the menu while the screens pushed on top of it are showing.
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    // Back from a match, the menu music fades in again. An online match is over by now, which
+    // the lobby tells the opponent once the connection is closed.
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world.write_resource::<Online>().disconnect();
+        data.world.write_resource::<Music>().play(Track::Menu);
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+
+        match menu_input(&event) {
+            Some(MenuInput::Up) => {
+                self.selected = (self.selected + Item::ALL.len() - 1) % Item::ALL.len();
+            }
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % Item::ALL.len(),
+            Some(MenuInput::Select) => {
+                return match Item::ALL[self.selected] {
+                    Item::Play => Trans::Push(Box::new(ModeSelect::new(self.pong.rematch()))),
+                    Item::Online => Trans::Push(Box::new(Matchmaking::default())),
+                    Item::Watch => Trans::Push(Box::new(Matchmaking::spectate())),
+                    Item::Leaderboard => Trans::Push(Box::new(LeaderboardMenu::default())),
+                    Item::HighScores => Trans::Push(Box::new(HighScoresMenu::default())),
+                    Item::Replays => Trans::Push(Box::new(ReplaysMenu::default())),
+                    Item::Profiles => Trans::Push(Box::new(ProfilesMenu::default())),
+                    Item::Tutorial => Trans::Push(Box::new(Tutorial::default())),
+                    Item::Settings => Trans::Push(Box::new(Settings::default())),
+                    Item::Quit => Trans::Push(Box::new(ConfirmQuit::default())),
+                };
+            }
+            Some(MenuInput::Back) => return Trans::Push(Box::new(ConfirmQuit::default())),
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,216 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    menu::{menu_input, refresh_rows, MenuInput},
+    names::NameEntry,
+    pong::{Difficulty, GameMode, MatchConfig, Mutator, Mutators, Pong},
+    theme::Theme,
+};
+
+/// How many mutators a mutators match starts out with.
+const RANDOM_MUTATORS: usize = 2;
+
+/// Opponents the classic match cycles through, nobody means two players.
+const OPPONENTS: [Option<Difficulty>; 4] = [
+    None,
+    Some(Difficulty::Easy),
+    Some(Difficulty::Medium),
+    Some(Difficulty::Hard),
+];
+
+/// Entries of the mode select screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Classic,
+    FourPlayer,
+    Survival,
+    Practice,
+    /// A classic two player match with a few random mutators.
+    Mutators,
+}
+
+impl Choice {
+    const ALL: [Choice; 5] = [
+        Choice::Classic,
+        Choice::FourPlayer,
+        Choice::Survival,
+        Choice::Practice,
+        Choice::Mutators,
+    ];
+
+    fn describe(self, opponent: Option<Difficulty>) -> String {
+        match self {
+            Choice::Classic => match opponent {
+                None => "Classic, two players".to_string(),
+                Some(difficulty) => format!("Classic, against the {:?} AI", difficulty),
+            },
+            Choice::FourPlayer => "Four players".to_string(),
+            Choice::Survival => "Survival".to_string(),
+            Choice::Practice => "Practice".to_string(),
+            Choice::Mutators => "Mutators".to_string(),
+        }
+    }
+
+    /// The choice that plays the kind of match the config describes.
+    fn for_config(config: &MatchConfig) -> Choice {
+        match config.mode {
+            GameMode::FourPlayer => Choice::FourPlayer,
+            GameMode::Survival => Choice::Survival,
+            GameMode::Practice => Choice::Practice,
+            GameMode::Classic | GameMode::Doubles => Choice::Classic,
+        }
+    }
+}
+
+/// Picks the kind of match to play, shown when Play is picked on the main menu. The pick is
+/// stored in the `MatchConfig` resource, and the match then goes on to the name entry.
+///
+/// Up and down pick a mode, left and right pick the opponent of a classic match, enter goes on
+/// and escape goes back.
+pub struct ModeSelect {
+    /// The match picked on the command line, its rules and arena are kept whatever the mode.
+    pong: Option<Pong>,
+    selected: usize,
+    /// Index into `OPPONENTS` for classic matches.
+    opponent: usize,
+    item_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl ModeSelect {
+    pub fn new(pong: Pong) -> ModeSelect {
+        ModeSelect {
+            pong: Some(pong),
+            selected: 0,
+            opponent: 0,
+            item_texts: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Updates the text of every item, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let opponent = OPPONENTS[self.opponent];
+        let labels = Choice::ALL.iter().map(|choice| choice.describe(opponent));
+        refresh_rows(world, &self.item_texts, labels, Some(self.selected));
+    }
+
+    /// The match config for the selected mode, on top of the mutators from the command line.
+    fn config(&self) -> MatchConfig {
+        let base = self.pong.as_ref().map(Pong::match_config).unwrap_or_default();
+        let (mode, opponent, mutators) = match Choice::ALL[self.selected] {
+            Choice::Classic => (GameMode::Classic, OPPONENTS[self.opponent], base.mutators),
+            Choice::FourPlayer => (GameMode::FourPlayer, None, base.mutators),
+            Choice::Survival => (GameMode::Survival, None, base.mutators),
+            Choice::Practice => (GameMode::Practice, None, base.mutators),
+            Choice::Mutators => {
+                let mut mutators = Mutators::default();
+                let picks = Mutator::ALL.choose_multiple(&mut rand::thread_rng(), RANDOM_MUTATORS);
+                for mutator in picks {
+                    mutators.toggle(*mutator);
+                }
+                (GameMode::Classic, None, mutators)
+            }
+        };
+        MatchConfig {
+            mode,
+            opponent,
+            mutators,
+        }
+    }
+}
+
+impl SimpleState for ModeSelect {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        // Start out on the last pick, or the mode from the command line the first time.
+        let last = match world.res.try_fetch::<MatchConfig>() {
+            Some(config) => config.clone(),
+            None => self.pong.as_ref().map(Pong::match_config).unwrap_or_default(),
+        };
+        let choice = Choice::for_config(&last);
+        self.selected = Choice::ALL.iter().position(|c| *c == choice).unwrap_or(0);
+        self.opponent = OPPONENTS.iter().position(|o| *o == last.opponent).unwrap_or(0);
+
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "mode_title".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 100., 1., 500., 40.,
+            ))
+            .with(theme.text("Pick a mode".to_string(), 30.))
+            .build();
+        self.texts.push(title);
+
+        for index in 0..Choice::ALL.len() {
+            let transform = UiTransform::new(
+                format!("mode_{}", index), Anchor::Middle, Anchor::Middle,
+                0., 40. - 30. * index as f32, 1., 400., 30.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 20.))
+                .with(theme.button.clone())
+                .build();
+            self.item_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("mode select text should be alive");
+        data.world
+            .delete_entities(&self.item_texts)
+            .expect("mode select text should be alive");
+        self.texts.clear();
+        self.item_texts.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let items = Choice::ALL.len();
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.selected = (self.selected + items - 1) % items,
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % items,
+            Some(input @ MenuInput::Left) | Some(input @ MenuInput::Right) => {
+                if Choice::ALL[self.selected] == Choice::Classic {
+                    let step = if input == MenuInput::Right { 1 } else { OPPONENTS.len() - 1 };
+                    self.opponent = (self.opponent + step) % OPPONENTS.len();
+                }
+            }
+            Some(MenuInput::Select) => {
+                let config = self.config();
+                if let Some(pong) = self.pong.take() {
+                    let pong = pong.with_match_config(&config);
+                    data.world.add_resource(config);
+                    return Trans::Switch(Box::new(NameEntry::new(pong)));
+                }
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,620 @@
+use amethyst::{
+    assets::AssetStorage,
+    core::Transform,
+    ecs::{Join, Read, ReadExpect, ReadStorage, Resources, SystemData},
+    renderer::{
+        batch::{GroupIterator, OneLevelBatch, OrderedOneLevelBatch},
+        pipeline::{PipelineDescBuilder, PipelinesBuilder},
+        pod::SpriteArgs,
+        rendy::{
+            command::{
+                CommandPool, Family, FamilyId, Fence, Graphics, IndividualReset, MultiShot,
+                NoSimultaneousUse, Queue, QueueId, RenderPassEncoder, Submission, Supports,
+            },
+            factory::Factory,
+            frame::{
+                cirque::{CirqueRef, CommandCirque},
+                Frames,
+            },
+            graph::{
+                gfx_acquire_barriers, gfx_release_barriers, is_metal,
+                render::{PrepareResult, RenderGroup, RenderGroupDesc},
+                BufferAccess, BufferId, DynNode, GraphBuilder, GraphContext, ImageAccess, ImageId,
+                NodeBuffer, NodeBuilder, NodeId, NodeImage,
+            },
+            hal::{
+                self,
+                command::{ClearDepthStencil, ClearValue, ClearValueRaw},
+                format::{Format, Swizzle},
+                image::{Access, Extent, Kind, Layout, Usage, ViewKind},
+                memory::Dependencies,
+                pass::{
+                    Attachment, AttachmentLoadOp, AttachmentOps, AttachmentStoreOp, Subpass,
+                    SubpassDependency, SubpassDesc,
+                },
+                pso::{self, PipelineStage, ShaderStageFlags},
+                Device, PhysicalDevice,
+            },
+            mesh::AsVertex,
+            shader::{Shader, SpirvShader},
+        },
+        sprite::{SpriteRender, SpriteSheet},
+        sprite_visibility::SpriteVisibility,
+        submodules::{DynamicVertexBuffer, FlatEnvironmentSub, TextureId, TextureSub},
+        types::{Backend, Texture},
+        util,
+    },
+};
+
+/// The most samples up to `wanted` the GPU can draw the sprites with, 1 if it can't multisample
+/// at all.
+pub fn supported_samples<B: Backend>(factory: &Factory<B>, wanted: u8) -> u8 {
+    let limits = factory.physical().limits();
+    // Both are masks of the sample counts supported, with a bit set for each.
+    let supported = limits.framebuffer_color_samples_count & limits.framebuffer_depth_samples_count;
+    let mut samples = wanted.max(1);
+    while samples > 1 && supported & samples == 0 {
+        samples /= 2;
+    }
+    samples
+}
+
+/// Adds the pass drawing the sprites with `samples` samples per pixel to the graph. They're
+/// drawn onto images of their own cleared to `clear`, which are resolved onto the `scene` at
+/// the end of the pass. The scene is the `kind` and `format` of the window, the UI is drawn
+/// onto it after the returned node.
+pub fn add_multisampled_sprites<B: Backend>(
+    graph_builder: &mut GraphBuilder<B, Resources>,
+    kind: Kind,
+    format: Format,
+    samples: u8,
+    clear: ClearValue,
+    scene: ImageId,
+) -> NodeId {
+    let extent = kind.extent();
+    let multisampled = Kind::D2(extent.width, extent.height, 1, samples);
+    // The pass clears them itself, what's in them isn't kept past the resolve.
+    let color = graph_builder.create_image(multisampled, 1, format, None);
+    let depth = graph_builder.create_image(multisampled, 1, Format::D32Sfloat, None);
+    graph_builder.add_node(MultisampledPassBuilder {
+        sprites: DrawMultisampledSpritesDesc { samples },
+        color,
+        depth,
+        scene,
+        clear,
+    })
+}
+
+/// Draws the sprites like amethyst's `DrawFlat2DDesc` and `DrawFlat2DTransparentDesc` do, the
+/// opaque ones first and the faded ones over them, in a subpass with `samples` samples per
+/// pixel. amethyst's pipelines are all single sampled, so they can't draw in one.
+#[derive(Clone, Debug)]
+struct DrawMultisampledSpritesDesc {
+    samples: u8,
+}
+
+impl<B: Backend> RenderGroupDesc<B, Resources> for DrawMultisampledSpritesDesc {
+    fn build(
+        self,
+        _ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        _queue: QueueId,
+        _aux: &Resources,
+        framebuffer_width: u32,
+        framebuffer_height: u32,
+        subpass: Subpass<'_, B>,
+        _buffers: Vec<NodeBuffer>,
+        _images: Vec<NodeImage>,
+    ) -> Result<Box<dyn RenderGroup<B, Resources>>, failure::Error> {
+        let env = FlatEnvironmentSub::new(factory)?;
+        let textures = TextureSub::new(factory)?;
+        let pipeline_layout = unsafe {
+            factory.device().create_pipeline_layout(
+                vec![env.raw_layout(), textures.raw_layout()],
+                None as Option<(_, _)>,
+            )
+        }?;
+
+        // The sprite shaders of amethyst, the GLSL is in `shaders/`.
+        let vertex = SpirvShader::new(
+            include_bytes!("../shaders/compiled/sprite.vert.spv").to_vec(),
+            ShaderStageFlags::VERTEX,
+            "main",
+        );
+        let fragment = SpirvShader::new(
+            include_bytes!("../shaders/compiled/sprite.frag.spv").to_vec(),
+            ShaderStageFlags::FRAGMENT,
+            "main",
+        );
+        let shader_vertex = unsafe { vertex.module(factory) }?;
+        let shader_fragment = unsafe { fragment.module(factory) }?;
+
+        let multisampling = pso::Multisampling {
+            rasterization_samples: self.samples,
+            sample_shading: None,
+            sample_mask: !0,
+            alpha_coverage: false,
+            alpha_to_one: false,
+        };
+        let pipeline = |transparent: bool| {
+            PipelineDescBuilder::new()
+                .with_vertex_desc(&[(SpriteArgs::vertex(), pso::VertexInputRate::Instance(1))])
+                .with_input_assembler(pso::InputAssemblerDesc::new(hal::Primitive::TriangleStrip))
+                .with_shaders(util::simple_shader_set(&shader_vertex, Some(&shader_fragment)))
+                .with_layout(&pipeline_layout)
+                .with_subpass(Subpass {
+                    index: subpass.index,
+                    main_pass: subpass.main_pass,
+                })
+                .with_framebuffer_size(framebuffer_width, framebuffer_height)
+                .with_blend_targets(vec![pso::ColorBlendDesc(
+                    pso::ColorMask::ALL,
+                    if transparent {
+                        pso::BlendState::PREMULTIPLIED_ALPHA
+                    } else {
+                        pso::BlendState::Off
+                    },
+                )])
+                .with_depth_test(pso::DepthTest::On {
+                    fun: pso::Comparison::Less,
+                    write: !transparent,
+                })
+                .with_multisampling(Some(multisampling.clone()))
+        };
+        let pipelines = PipelinesBuilder::new()
+            .with_pipeline(pipeline(false))
+            .with_pipeline(pipeline(true))
+            .build(factory, None);
+
+        unsafe {
+            factory.destroy_shader_module(shader_vertex);
+            factory.destroy_shader_module(shader_fragment);
+        }
+
+        let mut pipelines = match pipelines {
+            Ok(pipelines) => pipelines,
+            Err(err) => {
+                unsafe { factory.device().destroy_pipeline_layout(pipeline_layout) };
+                return Err(err);
+            }
+        };
+        let transparent = pipelines.remove(1);
+        let opaque = pipelines.remove(0);
+
+        Ok(Box::new(DrawMultisampledSprites {
+            opaque,
+            transparent,
+            pipeline_layout,
+            env,
+            textures,
+            opaque_vertex: DynamicVertexBuffer::new(),
+            transparent_vertex: DynamicVertexBuffer::new(),
+            opaque_sprites: Default::default(),
+            transparent_sprites: Default::default(),
+        }))
+    }
+}
+
+/// The group of the `DrawMultisampledSpritesDesc`. The opaque sprites are batched by their
+/// texture, the faded ones are kept in the order they're drawn back to front in.
+#[derive(Debug)]
+struct DrawMultisampledSprites<B: Backend> {
+    opaque: B::GraphicsPipeline,
+    transparent: B::GraphicsPipeline,
+    pipeline_layout: B::PipelineLayout,
+    env: FlatEnvironmentSub<B>,
+    textures: TextureSub<B>,
+    opaque_vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    transparent_vertex: DynamicVertexBuffer<B, SpriteArgs>,
+    opaque_sprites: OneLevelBatch<TextureId, SpriteArgs>,
+    transparent_sprites: OrderedOneLevelBatch<TextureId, SpriteArgs>,
+}
+
+impl<B: Backend> RenderGroup<B, Resources> for DrawMultisampledSprites<B> {
+    fn prepare(
+        &mut self,
+        factory: &Factory<B>,
+        _queue: QueueId,
+        index: usize,
+        _subpass: Subpass<'_, B>,
+        resources: &Resources,
+    ) -> PrepareResult {
+        let (sprite_sheets, texture_storage, visibility, sprite_renders, transforms) = <(
+            Read<'_, AssetStorage<SpriteSheet>>,
+            Read<'_, AssetStorage<Texture>>,
+            ReadExpect<'_, SpriteVisibility>,
+            ReadStorage<'_, SpriteRender>,
+            ReadStorage<'_, Transform>,
+        )>::fetch(resources);
+
+        self.env.process(factory, index, resources);
+        let textures = &mut self.textures;
+        let mut sprite = |sprite_render: &SpriteRender, transform: &Transform| {
+            let (args, texture) =
+                SpriteArgs::from_data(&texture_storage, &sprite_sheets, sprite_render, transform)?;
+            let (texture_id, _) = textures.insert(
+                factory,
+                resources,
+                texture,
+                hal::image::Layout::ShaderReadOnlyOptimal,
+            )?;
+            Some((texture_id, args))
+        };
+
+        let opaque_sprites = &mut self.opaque_sprites;
+        opaque_sprites.clear_inner();
+        (&sprite_renders, &transforms, &visibility.visible_unordered)
+            .join()
+            .filter_map(|(sprite_render, transform, _)| sprite(sprite_render, transform))
+            .for_each_group(|texture_id, args| opaque_sprites.insert(texture_id, args.drain(..)));
+
+        let transparent_sprites = &mut self.transparent_sprites;
+        transparent_sprites.swap_clear();
+        let mut joined = (&sprite_renders, &transforms).join();
+        visibility
+            .visible_ordered
+            .iter()
+            .filter_map(|entity| joined.get_unchecked(entity.id()))
+            .filter_map(|(sprite_render, transform)| sprite(sprite_render, transform))
+            .for_each_group(|texture_id, args| {
+                transparent_sprites.insert(texture_id, args.drain(..))
+            });
+
+        self.textures.maintain(factory, resources);
+        self.opaque_sprites.prune();
+        self.opaque_vertex.write(
+            factory,
+            index,
+            self.opaque_sprites.count() as u64,
+            self.opaque_sprites.data(),
+        );
+        self.transparent_vertex.write(
+            factory,
+            index,
+            self.transparent_sprites.count() as u64,
+            Some(self.transparent_sprites.data()),
+        );
+
+        PrepareResult::DrawRecord
+    }
+
+    fn draw_inline(
+        &mut self,
+        mut encoder: RenderPassEncoder<'_, B>,
+        index: usize,
+        _subpass: Subpass<'_, B>,
+        _resources: &Resources,
+    ) {
+        let layout = &self.pipeline_layout;
+
+        encoder.bind_graphics_pipeline(&self.opaque);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.opaque_vertex.bind(index, 0, 0, &mut encoder);
+        for (&texture, range) in self.opaque_sprites.iter() {
+            if self.textures.loaded(texture) {
+                self.textures.bind(layout, 1, texture, &mut encoder);
+                unsafe { encoder.draw(0..4, range) };
+            }
+        }
+
+        encoder.bind_graphics_pipeline(&self.transparent);
+        self.env.bind(index, layout, 0, &mut encoder);
+        self.transparent_vertex.bind(index, 0, 0, &mut encoder);
+        for (&texture, range) in self.transparent_sprites.iter() {
+            if self.textures.loaded(texture) {
+                self.textures.bind(layout, 1, texture, &mut encoder);
+                unsafe { encoder.draw(0..4, range) };
+            }
+        }
+    }
+
+    fn dispose(self: Box<Self>, factory: &mut Factory<B>, _aux: &Resources) {
+        unsafe {
+            factory.device().destroy_graphics_pipeline(self.opaque);
+            factory.device().destroy_graphics_pipeline(self.transparent);
+            factory.device().destroy_pipeline_layout(self.pipeline_layout);
+        }
+    }
+}
+
+/// Builds the render pass of the multisampled sprites. rendy's own render passes give every
+/// attachment one sample and resolve none of them, so this one is built here: the sprites are
+/// drawn onto the multisampled `color` and `depth`, and the `color` is resolved onto the
+/// `scene`.
+#[derive(Debug)]
+struct MultisampledPassBuilder {
+    sprites: DrawMultisampledSpritesDesc,
+    color: ImageId,
+    depth: ImageId,
+    scene: ImageId,
+    /// What the `color` is cleared to.
+    clear: ClearValue,
+}
+
+impl<B: Backend> NodeBuilder<B, Resources> for MultisampledPassBuilder {
+    fn family(&self, _factory: &mut Factory<B>, families: &[Family<B>]) -> Option<FamilyId> {
+        families
+            .iter()
+            .find(|family| Supports::<Graphics>::supports(&family.capability()).is_some())
+            .map(|family| family.id())
+    }
+
+    fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
+        Vec::new()
+    }
+
+    fn images(&self) -> Vec<(ImageId, ImageAccess)> {
+        let color = ImageAccess {
+            access: Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE,
+            usage: Usage::COLOR_ATTACHMENT,
+            layout: Layout::ColorAttachmentOptimal,
+            stages: PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+        };
+        let depth = ImageAccess {
+            access: Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            usage: Usage::DEPTH_STENCIL_ATTACHMENT,
+            layout: Layout::DepthStencilAttachmentOptimal,
+            stages: PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS,
+        };
+        // The scene is only written, by the resolve.
+        let scene = ImageAccess {
+            access: Access::COLOR_ATTACHMENT_WRITE,
+            ..color
+        };
+        vec![(self.color, color), (self.depth, depth), (self.scene, scene)]
+    }
+
+    fn dependencies(&self) -> Vec<NodeId> {
+        Vec::new()
+    }
+
+    fn build<'a>(
+        self: Box<Self>,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        queue: usize,
+        aux: &Resources,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Box<dyn DynNode<B, Resources>>, failure::Error> {
+        let node_image = |id: ImageId| {
+            images
+                .iter()
+                .find(|image| image.id == id)
+                .expect("The graph should pass every image of the pass")
+        };
+        let attachments = [self.color, self.depth, self.scene];
+
+        let views = attachments
+            .iter()
+            .map(|&id| {
+                let image = ctx.get_image(id).expect("The images of the pass should exist");
+                unsafe {
+                    factory.device().create_image_view(
+                        image.raw(),
+                        ViewKind::D2,
+                        image.format(),
+                        Swizzle::NO,
+                        node_image(id).range.clone(),
+                    )
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The samples are thrown away once they're resolved, and the resolve draws over the
+        // whole of the scene, so none of the images are kept from before the pass.
+        let attachment = |id: ImageId, load, store| {
+            let image = ctx.get_image(id).expect("The images of the pass should exist");
+            Attachment {
+                format: Some(image.format()),
+                samples: image.kind().num_samples(),
+                ops: AttachmentOps::new(load, store),
+                stencil_ops: AttachmentOps::DONT_CARE,
+                layouts: Layout::Undefined..node_image(id).layout,
+            }
+        };
+        let pass_attachments = vec![
+            attachment(self.color, AttachmentLoadOp::Clear, AttachmentStoreOp::DontCare),
+            attachment(self.depth, AttachmentLoadOp::Clear, AttachmentStoreOp::DontCare),
+            attachment(self.scene, AttachmentLoadOp::DontCare, AttachmentStoreOp::Store),
+        ];
+        let subpass = SubpassDesc {
+            colors: &[(0, Layout::ColorAttachmentOptimal)],
+            depth_stencil: Some(&(1, Layout::DepthStencilAttachmentOptimal)),
+            inputs: &[],
+            resolves: &[(2, Layout::ColorAttachmentOptimal)],
+            preserves: &[],
+        };
+        let render_pass = unsafe {
+            factory.device().create_render_pass(
+                pass_attachments,
+                Some(subpass),
+                std::iter::empty::<SubpassDependency>(),
+            )
+        }?;
+
+        let extent = ctx
+            .get_image(self.scene)
+            .expect("The images of the pass should exist")
+            .kind()
+            .extent();
+        let framebuffer = unsafe {
+            factory.device().create_framebuffer(
+                &render_pass,
+                views.iter(),
+                Extent {
+                    depth: 1,
+                    ..extent
+                },
+            )
+        }?;
+
+        let group = RenderGroupDesc::<B, Resources>::build(
+            self.sprites,
+            ctx,
+            factory,
+            QueueId {
+                family: family.id(),
+                index: queue,
+            },
+            aux,
+            extent.width,
+            extent.height,
+            Subpass {
+                index: 0,
+                main_pass: &render_pass,
+            },
+            Vec::new(),
+            Vec::new(),
+        )?;
+
+        let clears = vec![
+            self.clear.into(),
+            ClearValue::DepthStencil(ClearDepthStencil(1.0, 0)).into(),
+            // The scene isn't cleared, but the clears are looked up by the attachment's index.
+            ClearValue::Color([0.0; 4].into()).into(),
+        ];
+        let command_pool = factory
+            .create_command_pool(family)?
+            .with_capability()
+            .expect("The pass should be run on a family that can draw");
+
+        Ok(Box::new(MultisampledPass {
+            group,
+            extent,
+            render_pass,
+            views,
+            framebuffer,
+            clears,
+            command_pool,
+            command_cirque: CommandCirque::new(),
+            buffers,
+            images,
+        }))
+    }
+}
+
+/// The render pass the `MultisampledPassBuilder` builds.
+#[derive(Debug)]
+struct MultisampledPass<B: Backend> {
+    group: Box<dyn RenderGroup<B, Resources>>,
+    extent: Extent,
+    render_pass: B::RenderPass,
+    views: Vec<B::ImageView>,
+    framebuffer: B::Framebuffer,
+    clears: Vec<ClearValueRaw>,
+    command_pool: CommandPool<B, Graphics, IndividualReset>,
+    command_cirque: CommandCirque<B, Graphics>,
+    /// What the graph passed the pass, with the barriers to put around it.
+    buffers: Vec<NodeBuffer>,
+    images: Vec<NodeImage>,
+}
+
+impl<B: Backend> DynNode<B, Resources> for MultisampledPass<B> {
+    unsafe fn run<'a>(
+        &mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        queue: &mut Queue<B>,
+        aux: &Resources,
+        frames: &Frames<B>,
+        waits: &[(&'a B::Semaphore, PipelineStage)],
+        signals: &[&'a B::Semaphore],
+        fence: Option<&mut Fence<B>>,
+    ) {
+        let MultisampledPass {
+            group,
+            extent,
+            render_pass,
+            framebuffer,
+            clears,
+            command_pool,
+            command_cirque,
+            buffers,
+            images,
+            ..
+        } = self;
+        let queue_id = queue.id();
+        let subpass = || Subpass {
+            index: 0,
+            main_pass: &*render_pass,
+        };
+
+        let submit = command_cirque.encode(frames, command_pool, |mut cbuf| {
+            let index = cbuf.index();
+            let prepared = group.prepare(factory, queue_id, index, subpass(), aux);
+            if let PrepareResult::DrawRecord = prepared {
+                cbuf = CirqueRef::Initial(cbuf.or_reset(|cbuf| cbuf.reset()));
+            }
+
+            cbuf.or_init(|cbuf| {
+                let mut cbuf = cbuf.begin(MultiShot(NoSimultaneousUse), ());
+                let mut encoder = cbuf.encoder();
+
+                // Like rendy's passes, the barriers are left to Metal to put in.
+                if !is_metal::<B>() {
+                    let (stages, barriers) = gfx_acquire_barriers(ctx, &*buffers, &*images);
+                    if !barriers.is_empty() {
+                        encoder.pipeline_barrier(stages, Dependencies::empty(), barriers);
+                    }
+                }
+
+                let area = pso::Rect {
+                    x: 0,
+                    y: 0,
+                    w: extent.width as _,
+                    h: extent.height as _,
+                };
+                let mut pass_encoder =
+                    encoder.begin_render_pass_inline(render_pass, framebuffer, area, clears);
+                group.draw_inline(pass_encoder.reborrow(), index, subpass(), aux);
+                drop(pass_encoder);
+
+                if !is_metal::<B>() {
+                    let (stages, barriers) = gfx_release_barriers(ctx, &*buffers, &*images);
+                    if !barriers.is_empty() {
+                        encoder.pipeline_barrier(stages, Dependencies::empty(), barriers);
+                    }
+                }
+                cbuf.finish()
+            })
+        });
+
+        queue.submit(
+            Some(
+                Submission::new()
+                    .submits(Some(submit))
+                    .wait(waits.iter().cloned())
+                    .signal(signals.iter().cloned()),
+            ),
+            fence,
+        );
+    }
+
+    unsafe fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &Resources) {
+        let MultisampledPass {
+            group,
+            render_pass,
+            views,
+            framebuffer,
+            mut command_pool,
+            command_cirque,
+            ..
+        } = *self;
+        group.dispose(factory, aux);
+        command_cirque.dispose(|buffer| {
+            buffer.either_with(
+                &mut command_pool,
+                |pool, executable| pool.free_buffers(Some(executable)),
+                |pool, pending| pool.free_buffers(Some(pending.mark_complete())),
+            );
+        });
+        factory.destroy_command_pool(command_pool.with_queue_type());
+        factory.device().destroy_framebuffer(framebuffer);
+        for view in views {
+            factory.device().destroy_image_view(view);
+        }
+        factory.device().destroy_render_pass(render_pass);
+    }
+}
@@ -0,0 +1,155 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, Selectable, Selected, TextEditing, UiText, UiTransform},
+};
+
+use crate::{
+    config::{PlayerNames, UserSettings},
+    handicap::HandicapSetup,
+    menu::{menu_input, MenuInput},
+    pong::{Pong, Side},
+    theme::Theme,
+};
+
+/// Longest name a player can type in, so it fits above their score.
+const MAX_NAME_LENGTH: usize = 10;
+
+/// Shown before the match setup so every player can type in their name. The names start out
+/// as the ones typed in last time.
+///
+/// Tab or a click moves to another name, enter goes on to the match setup and escape goes
+/// back.
+pub struct NameEntry {
+    /// The match to set up once the names are in.
+    pong: Option<Pong>,
+    /// The name field of every player.
+    fields: Vec<(Side, Entity)>,
+    texts: Vec<Entity>,
+}
+
+impl NameEntry {
+    pub fn new(pong: Pong) -> NameEntry {
+        NameEntry {
+            pong: Some(pong),
+            fields: Vec::new(),
+            texts: Vec::new(),
+        }
+    }
+
+    /// Copies the typed in names to the `PlayerNames` and saves them for the next session.
+    fn save(&self, world: &mut World) {
+        {
+            let ui_text = world.read_storage::<UiText>();
+            let mut names = world.write_resource::<PlayerNames>();
+            for (side, field) in &self.fields {
+                if let Some(text) = ui_text.get(*field) {
+                    *names.name_mut(*side) = text.text.trim().to_string();
+                }
+            }
+        }
+        UserSettings::of(world).save();
+    }
+}
+
+impl SimpleState for NameEntry {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "names_title".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -30., 1., 500., 40.,
+            ))
+            .with(theme.text("Player names".to_string(), 30.))
+            .build();
+        let hint = world
+            .create_entity()
+            .with(UiTransform::new(
+                "names_hint".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+                0., 30., 1., 600., 30.,
+            ))
+            .with(theme.text(
+                "Tab to pick a name, Enter to continue, Escape to go back".to_string(),
+                15.,
+            ))
+            .build();
+        self.texts.push(title);
+        self.texts.push(hint);
+
+        let sides = self.pong.as_ref().map(Pong::player_sides).unwrap_or_default();
+        for (index, side) in sides.into_iter().enumerate() {
+            let y = -100. - 40. * index as f32;
+            let label = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("names_label_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                    -100., y, 1., 150., 30.,
+                ))
+                .with(theme.text(side.name().to_string(), 20.))
+                .build();
+            self.texts.push(label);
+
+            let name = world.read_resource::<PlayerNames>().name(side).to_string();
+            let mut builder = world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("names_field_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                    60., y, 1., 200., 30.,
+                ))
+                .with(UiText::new(theme.font.clone(), name, theme.input_color, 20.))
+                .with(TextEditing::new(
+                    MAX_NAME_LENGTH,
+                    theme.input_selected_color,
+                    theme.input_color,
+                    false,
+                ))
+                .with(Selectable::<()>::new(index as u32));
+            // Start out typing the first name.
+            if index == 0 {
+                builder = builder.with(Selected);
+            }
+            self.fields.push((side, builder.build()));
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("name entry text should be alive");
+        let fields: Vec<Entity> = self.fields.iter().map(|(_, field)| *field).collect();
+        data.world
+            .delete_entities(&fields)
+            .expect("name fields should be alive");
+        self.texts.clear();
+        self.fields.clear();
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+        // The arrow keys move through the name being typed, so only picking and going back are
+        // read here. Without a keyboard the names are kept as they are.
+        match menu_input(&event) {
+            Some(MenuInput::Back) => Trans::Pop,
+            Some(MenuInput::Select) => {
+                self.save(data.world);
+                match self.pong.take() {
+                    Some(pong) => Trans::Switch(Box::new(HandicapSetup::new(pong))),
+                    None => Trans::None,
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, Read, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    pong::{MatchRules, Mutators, Side},
+    rollback::{MatchEvent, MatchState, PlayerInput},
+};
+
+/// How long connecting to the lobby server waits before giving up.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The largest message read off a connection, in bytes, so a broken or hostile one can't
+/// make the game allocate whatever it claims to send.
+const MAX_MESSAGE_SIZE: u64 = 64 * 1024;
+/// How long a match waits for a player who lost their connection to rejoin it, in seconds.
+pub const RECONNECT_TIME: f32 = 30.0;
+/// How often the lobby is pinged to measure the connection.
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Pings not answered in this long count as lost. Nothing is really lost over TCP, but a ping
+/// held up this long was as good as lost for the match.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// How many of the latest pings the loss is counted over.
+const PING_WINDOW: usize = 20;
+
+/// What a player sends the lobby server.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ToLobby {
+    /// The name the opponent is shown.
+    Register { name: String },
+    /// Waits for an opponent.
+    Queue,
+    /// Stops waiting for an opponent, or leaves the match being played.
+    Leave,
+    /// Passed on to the opponent as it is.
+    Relay(PeerMessage),
+    /// Watches the match that has been going on the longest, without playing in it.
+    Spectate,
+    /// Passed on to everyone watching the match, only the host's are.
+    Broadcast(SpectatorMessage),
+    /// Puts the player back in the match they lost their connection to, with the token they
+    /// were paired with.
+    Rejoin { token: u64 },
+    /// Answered right away with a `FromLobby::Pong` of the same number, to measure the
+    /// connection.
+    Ping(u32),
+    /// The player's input for a frame of the match the server plays, which plays them in order.
+    /// See the `Prediction`.
+    Input { frame: u32, input: PlayerInput },
+}
+
+/// What the lobby server sends a player.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum FromLobby {
+    /// The player is waiting for an opponent.
+    Queued,
+    /// The player was paired with an opponent. The host plays on the left. An `authoritative`
+    /// server plays the match itself and sends `Frame`s, otherwise the players do. Both
+    /// players play by the `rules` the lobby gave the match. The player rejoins the match with
+    /// `token` if they lose their connection.
+    Paired {
+        opponent: String,
+        host: bool,
+        authoritative: bool,
+        rules: MatchRules,
+        token: u64,
+    },
+    /// Sent on by the opponent.
+    Relay(PeerMessage),
+    /// The opponent left the match, or didn't rejoin it in `RECONNECT_TIME`.
+    OpponentLeft,
+    /// The opponent lost their connection, the match waits for them to rejoin.
+    OpponentDropped,
+    /// The opponent rejoined the match after losing their connection.
+    OpponentRejoined,
+    /// The player rejoined the match they lost their connection to.
+    Rejoined,
+    /// The host didn't rejoin the match, the player hosts it in their place. Nobody plays the
+    /// other side anymore.
+    HostMigrated,
+    /// The answer to a `ToLobby::Ping`.
+    Pong(u32),
+    /// The player is watching the match between the players on the left and the right.
+    Watching { left: String, right: String },
+    /// Nobody is playing a match to watch.
+    NoMatches,
+    /// Sent on by the host of the match being watched.
+    Broadcast(SpectatorMessage),
+    /// The match being watched is over, one of its players left.
+    MatchEnded,
+    /// A frame of the match as the server played it, what happened in it and the left and
+    /// right players' inputs it was played with. `played` is the frame of the player's own
+    /// input it was played with, once one came in.
+    Frame {
+        frame: u32,
+        state: MatchState,
+        events: Vec<MatchEvent>,
+        inputs: [PlayerInput; 2],
+        played: Option<u32>,
+    },
+}
+
+/// What the players of an online match tell each other, relayed by the lobby server.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PeerMessage {
+    /// The player's input for a frame of the match, both players play every frame with both
+    /// inputs. See the `Rollback`.
+    Input { frame: u32, input: PlayerInput },
+    /// A line typed in the chat before the match.
+    Chat(String),
+    /// Whether the player is ready to play.
+    Ready(bool),
+    /// The mutators the host picked for the match.
+    Mutators(Mutators),
+    /// The host started the match with the mutators.
+    Start(Mutators),
+    /// Sent to the player who rejoined the match, both players play on from the frame of the
+    /// match every input was known for.
+    Resync { frame: u32, state: MatchState },
+}
+
+/// What the host of an online match tells everyone watching it, relayed by the lobby server.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum SpectatorMessage {
+    /// The match as the host is playing it, sent every frame, and the games won by the left and
+    /// right players so far.
+    State { state: MatchState, games: [u32; 2] },
+}
+
+/// Writes a message the way `read_message` reads it back, in one go so it doesn't go out in
+/// pieces.
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let bytes = bincode::serialize(message).map_err(|err| into_io_error(*err))?;
+    writer.write_all(&bytes)
+}
+
+/// Reads the next message off a connection, failing if the connection is closed or sends
+/// something that isn't a message.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<T> {
+    bincode::config()
+        .limit(MAX_MESSAGE_SIZE)
+        .deserialize_from(reader)
+        .map_err(|err| into_io_error(*err))
+}
+
+pub fn into_io_error(err: bincode::ErrorKind) -> io::Error {
+    match err {
+        bincode::ErrorKind::Io(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+    }
+}
+
+/// How the connection to the lobby is doing, as measured by pinging it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NetStats {
+    /// How long the latest ping took to be answered, in milliseconds, once one was.
+    pub ping: Option<f32>,
+    /// How much the ping varies from one to the next, on average, in milliseconds.
+    pub jitter: f32,
+    /// The share of the latest pings that weren't answered in time, from 0.0 to 1.0.
+    pub loss: f32,
+}
+
+/// The pings sent to the lobby and what became of them, shared by the threads of a connection.
+#[derive(Default)]
+struct Pings {
+    next: u32,
+    /// The pings still to be answered, and when they were sent.
+    unanswered: VecDeque<(u32, Instant)>,
+    /// Whether each of the latest pings was answered in time, the oldest first.
+    answered: VecDeque<bool>,
+    stats: NetStats,
+}
+
+impl Pings {
+    /// The number of the next ping to send.
+    fn send(&mut self) -> u32 {
+        self.expire();
+        let number = self.next;
+        self.next = self.next.wrapping_add(1);
+        self.unanswered.push_back((number, Instant::now()));
+        number
+    }
+
+    fn answer(&mut self, number: u32) {
+        self.expire();
+        let sent = match self.unanswered.iter().position(|(sent, _)| *sent == number) {
+            Some(index) => self.unanswered.remove(index),
+            None => return,
+        };
+        if let Some((_, sent)) = sent {
+            let ping = sent.elapsed().as_secs_f32() * 1000.0;
+            if let Some(last) = self.stats.ping {
+                // Smoothed over the latest pings, the way RTP measures it.
+                self.stats.jitter += ((ping - last).abs() - self.stats.jitter) / 16.0;
+            }
+            self.stats.ping = Some(ping);
+            self.record(true);
+        }
+    }
+
+    /// Gives up on the pings that weren't answered in `PING_TIMEOUT`.
+    fn expire(&mut self) {
+        while self
+            .unanswered
+            .front()
+            .is_some_and(|(_, sent)| sent.elapsed() >= PING_TIMEOUT)
+        {
+            self.unanswered.pop_front();
+            self.record(false);
+        }
+    }
+
+    fn record(&mut self, answered: bool) {
+        self.answered.push_back(answered);
+        if self.answered.len() > PING_WINDOW {
+            self.answered.pop_front();
+        }
+        let lost = self.answered.iter().filter(|answered| !**answered).count();
+        self.stats.loss = lost as f32 / self.answered.len() as f32;
+    }
+}
+
+/// A connection to the lobby server. It's read and written on threads of its own, so the game
+/// never waits on the network, and closed when it's dropped.
+pub struct LobbyClient {
+    address: String,
+    outgoing: Mutex<Sender<ToLobby>>,
+    incoming: Mutex<Receiver<io::Result<FromLobby>>>,
+    pings: Arc<Mutex<Pings>>,
+}
+
+impl LobbyClient {
+    /// Starts connecting to the lobby at `address`. Messages sent meanwhile go out once it's
+    /// connected, and failing to connect comes in as an error from `receive`.
+    pub fn connect(address: String) -> LobbyClient {
+        let (outgoing, to_send) = mpsc::channel();
+        let (received, incoming) = mpsc::channel();
+        let connecting = address.clone();
+        let pings = Arc::new(Mutex::new(Pings::default()));
+        let measuring = Arc::clone(&pings);
+        thread::spawn(move || {
+            if let Err(err) = run_connection(&connecting, to_send, received.clone(), &measuring) {
+                // Nobody is listening anymore once the client was dropped.
+                let _ = received.send(Err(err));
+            }
+        });
+        LobbyClient {
+            address,
+            outgoing: Mutex::new(outgoing),
+            incoming: Mutex::new(incoming),
+            pings,
+        }
+    }
+
+    pub fn stats(&self) -> NetStats {
+        self.pings.lock().unwrap().stats
+    }
+
+    pub fn send(&self, message: ToLobby) {
+        // The connection's thread is only gone once it failed, which `receive` reports.
+        let _ = self.outgoing.lock().unwrap().send(message);
+    }
+
+    /// The messages that came in since the last call, an error once the connection is lost.
+    pub fn receive(&self) -> Vec<io::Result<FromLobby>> {
+        self.incoming.lock().unwrap().try_iter().collect()
+    }
+
+    /// The next message that came in, for states that move on before the rest are read.
+    pub fn next(&self) -> Option<io::Result<FromLobby>> {
+        self.incoming.lock().unwrap().try_recv().ok()
+    }
+}
+
+/// Connects to the lobby, passes everything read off the connection on to `received` from
+/// another thread and writes the messages from `to_send` until the client is dropped. The lobby
+/// is pinged every `PING_INTERVAL` meanwhile.
+fn run_connection(
+    address: &str,
+    to_send: Receiver<ToLobby>,
+    received: Sender<io::Result<FromLobby>>,
+    pings: &Arc<Mutex<Pings>>,
+) -> io::Result<()> {
+    let socket = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "the lobby address has no host")
+    })?;
+    let stream = TcpStream::connect_timeout(&socket, CONNECT_TIMEOUT)?;
+    // Inputs go out every frame, they shouldn't wait to be sent with the next ones.
+    stream.set_nodelay(true)?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let answered = Arc::clone(pings);
+    thread::spawn(move || loop {
+        let message = read_message(&mut reader);
+        if let Ok(FromLobby::Pong(number)) = message {
+            answered.lock().unwrap().answer(number);
+            continue;
+        }
+        let lost = message.is_err();
+        if received.send(message).is_err() || lost {
+            break;
+        }
+    });
+
+    let mut writer = &stream;
+    let mut next_ping = Instant::now();
+    loop {
+        let wait = next_ping.saturating_duration_since(Instant::now());
+        match to_send.recv_timeout(wait) {
+            Ok(message) => write_message(&mut writer, &message)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if Instant::now() >= next_ping {
+            let number = pings.lock().unwrap().send();
+            write_message(&mut writer, &ToLobby::Ping(number))?;
+            next_ping = Instant::now() + PING_INTERVAL;
+        }
+    }
+    stream.shutdown(Shutdown::Both)
+}
+
+/// Who the opponent of an online match is.
+pub struct Peer {
+    pub name: String,
+    /// Whether the local player hosts the match.
+    pub host: bool,
+    /// Whether the lobby server plays the match, rather than both players in the `Rollback`.
+    pub authoritative: bool,
+    /// The rules the lobby gave the match.
+    pub rules: MatchRules,
+    /// What the local player rejoins the match with if they lose their connection.
+    pub token: u64,
+    /// Whether the local player took over hosting the match from the host, who didn't come
+    /// back. The AI plays the other side then.
+    pub migrated: bool,
+}
+
+/// Who plays the online match being watched.
+pub struct Watching {
+    pub left: String,
+    pub right: String,
+}
+
+/// The connection to the lobby server, and the online match played or watched through it if
+/// there is one. The `Matchmaking` state connects, and going back to the main menu disconnects.
+#[derive(Default)]
+pub struct Online {
+    pub lobby: Option<LobbyClient>,
+    pub peer: Option<Peer>,
+    pub watching: Option<Watching>,
+    /// Why the match can't go on, set by the `OnlineSystem` when the opponent leaves, by the
+    /// `Reconnect` state when the match can't be rejoined and by the `SpectatorSystem` when the
+    /// match being watched ends or the connection is lost. The `Pong` state shows it.
+    pub lost: Option<String>,
+    /// Whether the connection was lost in the middle of the match, the `OnlineSystem` is
+    /// reconnecting to rejoin it.
+    pub reconnecting: bool,
+    /// Whether the opponent lost their connection, the match waits for them to rejoin.
+    pub opponent_dropped: bool,
+    /// Whether a saved online match is being played back, by the `ReplaySystem`.
+    pub replaying: bool,
+}
+
+impl Online {
+    /// Whether an online match is played, watched or played back, the `OnlineSystem`, the
+    /// `SpectatorSystem` or the `ReplaySystem` moves everything in the arena then.
+    pub fn in_match(&self) -> bool {
+        self.peer.is_some() || self.watching.is_some() || self.replaying
+    }
+
+    /// The side the local player plays, the host is on the left.
+    pub fn local_side(&self) -> Option<Side> {
+        self.peer
+            .as_ref()
+            .map(|peer| if peer.host { Side::Left } else { Side::Right })
+    }
+
+    pub fn send(&self, message: ToLobby) {
+        if let Some(lobby) = &self.lobby {
+            lobby.send(message);
+        }
+    }
+
+    pub fn receive(&self) -> Vec<io::Result<FromLobby>> {
+        self.lobby
+            .as_ref()
+            .map(LobbyClient::receive)
+            .unwrap_or_default()
+    }
+
+    pub fn next(&self) -> Option<io::Result<FromLobby>> {
+        self.lobby.as_ref().and_then(LobbyClient::next)
+    }
+
+    pub fn stats(&self) -> Option<NetStats> {
+        self.lobby.as_ref().map(LobbyClient::stats)
+    }
+
+    /// Connects to the lobby again to rejoin the match the connection was lost to.
+    pub fn reconnect(&mut self) {
+        let token = match &self.peer {
+            Some(peer) => peer.token,
+            None => return,
+        };
+        if let Some(lobby) = &self.lobby {
+            let lobby = LobbyClient::connect(lobby.address.clone());
+            lobby.send(ToLobby::Rejoin { token });
+            self.lobby = Some(lobby);
+        }
+    }
+
+    /// Leaves the match and closes the connection, the lobby tells the opponent.
+    pub fn disconnect(&mut self) {
+        // Closing the connection alone would have the opponent wait for the player to rejoin.
+        self.send(ToLobby::Leave);
+        *self = Online::default();
+    }
+}
@@ -0,0 +1,129 @@
+use amethyst::renderer::{palette::Srgba, resources::Tint};
+use serde::{Deserialize, Serialize};
+
+use crate::{pong::Side, theme::Theme};
+
+/// The color palettes the game can be played in, picked in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PaletteName {
+    /// White paddles and ball on black, like the arcade original.
+    #[default]
+    Classic,
+    Neon,
+    Pastel,
+}
+
+impl PaletteName {
+    pub const ALL: [PaletteName; 3] =
+        [PaletteName::Classic, PaletteName::Neon, PaletteName::Pastel];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteName::Classic => "classic",
+            PaletteName::Neon => "neon",
+            PaletteName::Pastel => "pastel",
+        }
+    }
+}
+
+/// Colors a palette gives the text of the UI, in place of the ones of the theme.
+#[derive(Clone, Copy, Debug)]
+pub struct PaletteUi {
+    pub text_color: [f32; 4],
+    pub highlight_color: [f32; 4],
+    pub score_color: [f32; 4],
+}
+
+/// The colors of the palette picked in the `GameConfig`. The `PaletteSystem` tints the paddles,
+/// balls and letterbox bars with it, the renderer clears the screen to its background and the
+/// `Theme` takes the colors of its UI.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    /// Paddles on the left and top, and on the right and bottom.
+    pub first_paddle: [f32; 4],
+    pub second_paddle: [f32; 4],
+    /// The balls, along with the particles and trails they leave.
+    pub ball: [f32; 4],
+    pub background: [f32; 4],
+    /// The bars around the arena when the window isn't shaped like it.
+    pub bars: [f32; 4],
+    /// Leaves the theme's colors alone when it's `None`.
+    pub ui: Option<PaletteUi>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::new(PaletteName::default())
+    }
+}
+
+impl Palette {
+    pub fn new(name: PaletteName) -> Palette {
+        match name {
+            PaletteName::Classic => Palette {
+                first_paddle: [1., 1., 1., 1.],
+                second_paddle: [1., 1., 1., 1.],
+                ball: [1., 1., 1., 1.],
+                background: [0., 0., 0., 1.],
+                bars: [0.12, 0.12, 0.12, 1.],
+                ui: None,
+            },
+            PaletteName::Neon => Palette {
+                first_paddle: [0., 1., 0.9, 1.],
+                second_paddle: [1., 0.1, 0.8, 1.],
+                ball: [1., 1., 0.2, 1.],
+                background: [0.02, 0., 0.05, 1.],
+                bars: [0.1, 0., 0.15, 1.],
+                ui: Some(PaletteUi {
+                    text_color: [0.6, 1., 0.95, 1.],
+                    highlight_color: [1., 0.3, 0.9, 1.],
+                    score_color: [0., 1., 0.9, 1.],
+                }),
+            },
+            PaletteName::Pastel => Palette {
+                first_paddle: [1., 0.7, 0.8, 1.],
+                second_paddle: [0.65, 0.9, 0.8, 1.],
+                ball: [1., 0.95, 0.7, 1.],
+                background: [0.1, 0.08, 0.14, 1.],
+                bars: [0.22, 0.18, 0.28, 1.],
+                ui: Some(PaletteUi {
+                    text_color: [0.95, 0.9, 1., 1.],
+                    highlight_color: [1., 0.7, 0.8, 1.],
+                    score_color: [0.8, 0.85, 1., 1.],
+                }),
+            },
+        }
+    }
+
+    pub fn paddle(&self, side: Side) -> [f32; 4] {
+        match side {
+            Side::Left | Side::Top => self.first_paddle,
+            Side::Right | Side::Bottom => self.second_paddle,
+        }
+    }
+
+    /// Gives the theme the colors of the palette's UI, if it has any, and its background along
+    /// with them.
+    pub fn recolor(&self, theme: &mut Theme) {
+        if let Some(ui) = self.ui {
+            theme.text_color = ui.text_color;
+            theme.highlight_color = ui.highlight_color;
+            theme.score_color = ui.score_color;
+            theme.background = Some(self.background);
+        }
+    }
+
+    /// What the screen is cleared to, the theme's background if it has one.
+    pub fn clear_color(&self, theme: Option<&Theme>) -> [f32; 4] {
+        theme
+            .and_then(|theme| theme.background)
+            .unwrap_or(self.background)
+    }
+}
+
+/// A tint of `color` faded out to `alpha`. Sprites are blended with premultiplied alpha, so the
+/// color fades along with it.
+pub fn faded_tint(color: [f32; 4], alpha: f32) -> Tint {
+    let alpha = color[3] * alpha;
+    Tint(Srgba::new(color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha))
+}
@@ -0,0 +1,177 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::{
+    confirm::ConfirmQuit,
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::RunningState,
+    settings::Settings,
+    theme::Theme,
+};
+
+/// Entries of the pause menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Resume,
+    Settings,
+    Quit,
+}
+
+impl Item {
+    const ALL: [Item; 3] = [Item::Resume, Item::Settings, Item::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            Item::Resume => "Resume",
+            Item::Settings => "Settings",
+            Item::Quit => "Quit",
+        }
+    }
+}
+
+/// Pushed on top of the `Pong` state when Escape or Start on a gamepad is pressed. It sets the
+/// `RunningState` to paused so the gameplay systems stand still, and puts an overlay over the
+/// arena.
+///
+/// Escape again resumes the match, as does picking Resume.
+#[derive(Default)]
+pub struct Paused {
+    /// Why the match was paused, when it wasn't the player's doing.
+    message: Option<String>,
+    selected: usize,
+    item_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl Paused {
+    /// Pauses the match with `message` shown over the menu.
+    pub fn with_message(message: String) -> Paused {
+        Paused {
+            message: Some(message),
+            ..Default::default()
+        }
+    }
+
+    fn show(&mut self, world: &mut World) {
+        let theme = world.read_resource::<Theme>().clone();
+
+        // A see-through backdrop dims the arena behind the menu.
+        let backdrop = world
+            .create_entity()
+            .with(UiTransform::new(
+                "pause_backdrop".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 0., 0.5, 260., 190.,
+            ))
+            .with(theme.backdrop.clone())
+            .build();
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "pause_title".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 50., 1., 250., 40.,
+            ))
+            .with(theme.text("Paused".to_string(), 30.))
+            .build();
+        self.texts.push(backdrop);
+        self.texts.push(title);
+        if let Some(message) = &self.message {
+            let message = world
+                .create_entity()
+                .with(UiTransform::new(
+                    "pause_message".to_string(), Anchor::Middle, Anchor::Middle,
+                    0., 115., 1., 450., 30.,
+                ))
+                .with(theme.text(message.clone(), 18.))
+                .build();
+            self.texts.push(message);
+        }
+
+        for index in 0..Item::ALL.len() {
+            let transform = UiTransform::new(
+                format!("pause_{}", index), Anchor::Middle, Anchor::Middle,
+                0., -30. * index as f32, 1., 250., 30.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 22.))
+                .with(theme.button.clone())
+                .build();
+            self.item_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.texts)
+            .expect("pause text should be alive");
+        world
+            .delete_entities(&self.item_texts)
+            .expect("pause text should be alive");
+        self.texts.clear();
+        self.item_texts.clear();
+    }
+
+    /// Updates the text of every item, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let labels = Item::ALL.iter().map(|item| item.label());
+        refresh_rows(world, &self.item_texts, labels, Some(self.selected));
+    }
+}
+
+impl SimpleState for Paused {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        *world.write_resource::<RunningState>() = RunningState::Paused;
+        self.show(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        *data.world.write_resource::<RunningState>() = RunningState::Running;
+        self.hide(data.world);
+    }
+
+    // The match stays paused while the settings are open, only the overlay goes away.
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+
+        match menu_input(&event) {
+            Some(MenuInput::Up) => {
+                self.selected = (self.selected + Item::ALL.len() - 1) % Item::ALL.len();
+            }
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % Item::ALL.len(),
+            Some(MenuInput::Select) => {
+                return match Item::ALL[self.selected] {
+                    Item::Resume => Trans::Pop,
+                    Item::Settings => Trans::Push(Box::new(Settings::default())),
+                    Item::Quit => Trans::Push(Box::new(ConfirmQuit::default())),
+                };
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
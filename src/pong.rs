@@ -1,41 +1,539 @@
 use amethyst::{
-    ui::{Anchor, TtfFormat, UiText, UiTransform},
-    assets::{AssetStorage, Handle, Loader},
-    core::{transform::Transform, timing::Time},
-    ecs::prelude::{Component, DenseVecStorage, Entity},
+    ui::{Anchor, UiImage, UiText, UiTransform},
+    assets::Handle,
+    core::{
+        math::{Vector2, Vector3},
+        transform::Transform,
+        timing::Time,
+        Float, Hidden,
+    },
+    ecs::prelude::{Component, DenseVecStorage, Entity, Join, NullStorage},
+    input::{
+        is_close_requested, is_key_down, Axis, Bindings, Button, InputEvent, InputHandler,
+        StringBindings,
+    },
     prelude::*,
+    shrev::{EventChannel, ReaderId},
     renderer::{
         camera::{Camera, Projection},
-        formats::texture::ImageFormat,
-        sprite::{SpriteRender, SpriteSheet, SpriteSheetFormat},
-        Texture,
+        sprite::{SpriteRender, SpriteSheet},
+        transparent::Transparent,
     },
+    winit::{Event, VirtualKeyCode},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Achievements,
+    announcer::Announcer,
+    audio::{initialise_audio_listener, Music, Track},
+    celebration::Celebration,
+    config::{GameConfig, Gameplay, KeyMode, PlayerNames},
+    confirm::ConfirmQuit,
+    controls::{action_button, convert_button, Controls},
+    ghost::Ghost,
+    high_scores::{HighScoreTable, HighScores},
+    high_scores_menu::{NewHighScore, NextScreen},
+    history::{MatchEntry, MatchHistory},
+    intermission::Intermission,
+    leaderboard::{Leaderboard, MatchResult},
+    net::Online,
+    palette::{faded_tint, Palette},
+    pause::Paused,
+    profiles::{MatchRecord, Profiles},
+    reconnect::Reconnect,
+    replay::{Playback, Replay},
+    results::{Results, SurvivalResults},
+    rollback::{Prediction, Rollback},
+    theme::Theme,
+    twitch::TwitchChat,
 };
 
+/// The standard sizes and speeds of the game, which online matches are played at and the
+/// `Gameplay` tuning starts out with. The ball and paddle sprites are drawn at these sizes.
 pub const BALL_VELOCITY_X: f32 = 50.0;
 pub const BALL_VELOCITY_Y: f32 = 25.0;
 pub const BALL_RADIUS: f32 = 2.0;
+/// Steepest angle, in radians, the ball leaves a paddle at when hit on its very edge.
+pub const BALL_MAX_BOUNCE_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+/// How much of the paddle's vertical speed turns into spin on contact.
+pub const BALL_SPIN_TRANSFER: f32 = 0.01;
+/// Spin can't exceed this many radians per second either way.
+pub const BALL_MAX_SPIN: f32 = 1.0;
+/// Fraction of the spin that is lost every second.
+pub const BALL_SPIN_DECAY: f32 = 0.8;
+/// How many radians a serve is angled by per arena unit per second the paddle moves.
+pub const SERVE_ANGLE_PER_SPEED: f32 = 0.01;
+/// Seconds the AI holds on to the ball before serving.
+pub const SERVE_AI_DELAY: f32 = 1.0;
+/// Seconds counted down after a point before the ball can be served again.
+pub const SERVE_COUNTDOWN: f32 = 3.0;
+/// Font sizes the countdown digits shrink from and to over their second on the screen.
+pub const COUNTDOWN_FONT_START: f32 = 90.0;
+pub const COUNTDOWN_FONT_END: f32 = 40.0;
+/// Seconds the control hints stay up at the start of a match, the last second of which they
+/// spend fading out.
+pub const CONTROL_HINTS_DURATION: f32 = 5.0;
+pub const CONTROL_HINTS_FADE: f32 = 1.0;
+/// Shows or hides the control hints during a match.
+pub const CONTROL_HINTS_KEY: VirtualKeyCode = VirtualKeyCode::F1;
+/// How much of the ghost paddle in practice shows.
+const GHOST_ALPHA: f32 = 0.3;
+/// The keys a replay is played back with, as shown under it.
+const REPLAY_CONTROLS: &str = "Space pause, . step, - and = speed, Left and Right jump a point";
+/// Most balls that can be in play at the same time.
+pub const MAX_BALLS: usize = 3;
+/// Seconds of play without a point before another ball joins in.
+pub const MULTI_BALL_INTERVAL: f32 = 15.0;
+/// Seconds between power-ups appearing in the arena.
+pub const POWER_UP_INTERVAL: f32 = 10.0;
+/// Seconds a collected power-up stays in effect.
+pub const POWER_UP_DURATION: f32 = 8.0;
+pub const POWER_UP_RADIUS: f32 = 2.0;
+/// The shield's sprite in the sprite sheet, its power-up has its own icon.
+pub const SHIELD_SPRITE: usize = 6;
+pub const BRICK_SPRITE: usize = 7;
+/// Bonus points for the player who breaks a brick.
+pub const BRICK_POINTS: i32 = 1;
+pub const BRICK_WIDTH: f32 = 4.0;
+pub const BRICK_HEIGHT: f32 = 8.0;
+pub const PORTAL_SPRITE: usize = 8;
+pub const PORTAL_RADIUS: f32 = 4.0;
+pub const STICKY_SPRITE: usize = 9;
+pub const MIRROR_SPRITE: usize = 10;
+/// With the blink mutator, how far from the left and right edges, as a fraction of the arena
+/// width, balls start fading out and are gone completely.
+pub const BLINK_FADE_START: f32 = 1.0 / 6.0;
+pub const BLINK_FADE_END: f32 = 1.0 / 3.0;
+/// Seconds between overtime ramping up the pressure.
+pub const OVERTIME_RAMP_INTERVAL: f32 = 10.0;
+/// Ball speed is multiplied by this every overtime ramp.
+pub const OVERTIME_SPEED_RAMP: f32 = 1.2;
+/// How far the top and bottom walls move in every overtime ramp.
+pub const OVERTIME_SHRINK: f32 = 6.0;
+pub const OVERTIME_MAX_INSET: f32 = 30.0;
+/// How much faster a paddle moves while dashing.
+pub const DASH_SPEED_MULTIPLIER: f32 = 3.0;
+/// How long a dash lasts, in seconds.
+pub const DASH_DURATION: f32 = 0.2;
+/// Seconds before a paddle can dash again.
+pub const DASH_COOLDOWN: f32 = 2.0;
+/// Width of a full dash cooldown bar, in pixels.
+pub const DASH_BAR_WIDTH: f32 = 60.0;
+/// Seconds of holding the hit action it takes to fully charge a power shot.
+pub const POWER_SHOT_CHARGE_TIME: f32 = 1.0;
+/// How much faster a fully charged power shot returns the ball.
+pub const POWER_SHOT_SPEED_MULTIPLIER: f32 = 2.0;
+/// How long the screen shakes after a power shot, in seconds, and how far the camera moves
+/// as it starts shaking, in arena units.
+pub const POWER_SHOT_SHAKE_DURATION: f32 = 0.3;
+pub const POWER_SHOT_SHAKE_AMOUNT: f32 = 1.5;
+/// How long and how hard the screen shakes after a goal.
+pub const GOAL_SHAKE_DURATION: f32 = 0.5;
+pub const GOAL_SHAKE_AMOUNT: f32 = 2.5;
+/// Width of a full charge meter, in pixels.
+pub const CHARGE_METER_WIDTH: f32 = 60.0;
+/// Balls are this much bigger with the big ball mutator.
+pub const BIG_BALL_MULTIPLIER: f32 = 2.0;
+/// Paddles are this much longer with the tiny paddles mutator.
+pub const TINY_PADDLE_MULTIPLIER: f32 = 0.5;
+/// Balls move this much faster with the double speed mutator.
+pub const DOUBLE_SPEED_MULTIPLIER: f32 = 2.0;
+/// Downward pull on the balls with the gravity mutator, in arena units per second squared.
+pub const GRAVITY_ACCELERATION: f32 = 40.0;
+/// Share of its vertical speed a ball keeps when bouncing off the floor with gravity on.
+pub const GRAVITY_RESTITUTION: f32 = 0.9;
+/// Length of the scoring window on the left and right edges with moving goal zones.
+pub const GOAL_ZONE_SIZE: f32 = 40.0;
+/// How fast the scoring windows slide along their edges, in radians per second.
+pub const GOAL_ZONE_SPEED: f32 = 0.8;
+/// With mirrored controls, the random range of seconds between a player's controls flipping.
+pub const MIRROR_MIN_INTERVAL: f32 = 8.0;
+pub const MIRROR_MAX_INTERVAL: f32 = 16.0;
+/// How long a player's controls stay flipped, in seconds.
+pub const MIRROR_DURATION: f32 = 3.0;
+/// Seconds the warning icon shows before the controls flip.
+pub const MIRROR_WARNING: f32 = 1.0;
+/// Seconds between balls at the start of a survival run.
+pub const SURVIVAL_FIRST_INTERVAL: f32 = 8.0;
+/// Every new ball in survival comes this much sooner than the one before it.
+pub const SURVIVAL_INTERVAL_DECAY: f32 = 0.85;
+pub const SURVIVAL_MIN_INTERVAL: f32 = 1.5;
+pub const SURVIVAL_MAX_BALLS: usize = 8;
+/// Range of the practice launcher's speed, in arena units per second.
+pub const LAUNCHER_MIN_SPEED: f32 = 20.0;
+pub const LAUNCHER_MAX_SPEED: f32 = 150.0;
+/// Range of the seconds between shots of the practice launcher.
+pub const LAUNCHER_MIN_INTERVAL: f32 = 0.5;
+pub const LAUNCHER_MAX_INTERVAL: f32 = 5.0;
 pub const ARENA_HEIGHT: f32 = 100.0;
 pub const ARENA_WIDTH: f32 = 100.0;
 pub const PADDLE_HEIGHT: f32 = 16.0;
 pub const PADDLE_WIDTH: f32 = 4.0;
+/// How far a paddle moves in a frame at full push.
+pub const PADDLE_SPEED: f32 = 1.2;
+pub const WIN_SCORE: i32 = 5;
+
+/// Whether the match is being played or paused, the gameplay systems only run while it's
+/// `Running`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RunningState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Which kind of match is being played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    /// One paddle on the left and one on the right, the top and bottom are walls.
+    #[default]
+    Classic,
+    /// A paddle guards each of the four edges and there are no walls.
+    FourPlayer,
+    /// Two teams of two, each teammate covers one half of their side.
+    Doubles,
+    /// A single player guards the left edge against more and more balls for as long as they
+    /// can, the right edge is a wall.
+    Survival,
+    /// A single player warms up returning balls fired from a launcher on the right edge.
+    Practice,
+}
+
+impl GameMode {
+    /// The sides that have players on them.
+    pub fn sides(self) -> &'static [Side] {
+        match self {
+            GameMode::FourPlayer => &[Side::Left, Side::Right, Side::Top, Side::Bottom],
+            GameMode::Classic | GameMode::Doubles => &[Side::Left, Side::Right],
+            GameMode::Survival | GameMode::Practice => &[Side::Left],
+        }
+    }
+
+    /// Whether a single player plays on their own, without anyone on the right and with the
+    /// balls brought in by the mode itself.
+    pub fn is_solo(self) -> bool {
+        self == GameMode::Survival || self == GameMode::Practice
+    }
+}
+
+/// The kind of match picked on the mode select screen. It's kept as a resource, so the screen
+/// starts out on the last pick when it's shown again.
+#[derive(Clone, Default)]
+pub struct MatchConfig {
+    pub mode: GameMode,
+    /// The AI difficulty when playing against the computer.
+    pub opponent: Option<Difficulty>,
+    pub mutators: Mutators,
+}
 
 /// ScoreBoard contains the actual score data
-#[derive(Default)]
+#[derive(Clone, Copy, Default)]
 pub struct ScoreBoard {
     pub score_left: i32,
     pub score_right: i32,
+    pub score_top: i32,
+    pub score_bottom: i32,
+}
+
+impl ScoreBoard {
+    /// A scoreboard for the start of a game, where every player has their head start.
+    pub fn with_head_starts(handicaps: &Handicaps) -> ScoreBoard {
+        ScoreBoard {
+            score_left: handicaps.left.head_start,
+            score_right: handicaps.right.head_start,
+            score_top: handicaps.top.head_start,
+            score_bottom: handicaps.bottom.head_start,
+        }
+    }
+
+    pub fn score(&self, side: Side) -> i32 {
+        match side {
+            Side::Left => self.score_left,
+            Side::Right => self.score_right,
+            Side::Top => self.score_top,
+            Side::Bottom => self.score_bottom,
+        }
+    }
+
+    pub fn score_mut(&mut self, side: Side) -> &mut i32 {
+        match side {
+            Side::Left => &mut self.score_left,
+            Side::Right => &mut self.score_right,
+            Side::Top => &mut self.score_top,
+            Side::Bottom => &mut self.score_bottom,
+        }
+    }
+}
+
+/// Evens out a match between players of different skill, set up before the match starts.
+#[derive(Clone, Copy, Debug)]
+pub struct Handicap {
+    /// Points the player starts every game with.
+    pub head_start: i32,
+    /// Scales the length of the player's paddle.
+    pub paddle_size: f32,
+    /// Scales how fast the player's paddle moves.
+    pub paddle_speed: f32,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Handicap {
+            head_start: 0,
+            paddle_size: 1.0,
+            paddle_speed: 1.0,
+        }
+    }
+}
+
+/// The handicap of every side, in doubles both teammates share their side's.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Handicaps {
+    pub left: Handicap,
+    pub right: Handicap,
+    pub top: Handicap,
+    pub bottom: Handicap,
+}
+
+impl Handicaps {
+    pub fn get(&self, side: Side) -> Handicap {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+            Side::Top => self.top,
+            Side::Bottom => self.bottom,
+        }
+    }
+
+    pub fn get_mut(&mut self, side: Side) -> &mut Handicap {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+            Side::Top => &mut self.top,
+            Side::Bottom => &mut self.bottom,
+        }
+    }
+}
+
+/// How a match is won: the first to `win_score` takes a game, and whoever wins the
+/// most out of `best_of` games takes the match.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct MatchRules {
+    pub win_score: i32,
+    pub best_of: u32,
+    /// Plays time attack games of this many seconds instead, whoever has the most points when
+    /// the time runs out wins the game.
+    pub time_limit: Option<f32>,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            win_score: WIN_SCORE,
+            best_of: 3,
+            time_limit: None,
+        }
+    }
+}
+
+impl MatchRules {
+    /// Games needed to win the match.
+    pub fn games_to_win(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+}
+
+/// Time left in a time attack game.
+#[derive(Default)]
+pub struct MatchTimer {
+    pub remaining: f32,
+    /// Set when the time ran out with the lead shared, the next point wins the game.
+    pub golden_goal: bool,
+    /// Set once the game has been decided, so it's only won once.
+    pub finished: bool,
+}
+
+impl MatchTimer {
+    pub fn new(rules: MatchRules) -> MatchTimer {
+        MatchTimer {
+            remaining: rules.time_limit.unwrap_or(0.0),
+            golden_goal: false,
+            finished: false,
+        }
+    }
+}
+
+/// Games won so far in the current match.
+#[derive(Default)]
+pub struct MatchScore {
+    pub games_left: u32,
+    pub games_right: u32,
+    pub games_top: u32,
+    pub games_bottom: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self, side: Side) -> u32 {
+        match side {
+            Side::Left => self.games_left,
+            Side::Right => self.games_right,
+            Side::Top => self.games_top,
+            Side::Bottom => self.games_bottom,
+        }
+    }
+
+    pub fn games_mut(&mut self, side: Side) -> &mut u32 {
+        match side {
+            Side::Left => &mut self.games_left,
+            Side::Right => &mut self.games_right,
+            Side::Top => &mut self.games_top,
+            Side::Bottom => &mut self.games_bottom,
+        }
+    }
+}
+
+/// Numbers kept over the whole match for the results screen and the players' profiles.
+#[derive(Clone, Copy, Default)]
+pub struct MatchStats {
+    /// Seconds played with a ball in the arena.
+    pub duration: f32,
+    /// Points every side scored and conceded over every game of the match.
+    pub points_scored: ScoreBoard,
+    pub points_conceded: ScoreBoard,
+}
+
+/// Paddle hits in a row, counted by the `BounceSystem` and shown on the HUD.
+#[derive(Clone, Copy, Default)]
+pub struct Rally {
+    /// Hits since the last point.
+    pub current: u32,
+    /// Longest rally of the match so far.
+    pub best: u32,
+}
+
+/// The HUD text showing the current and best rally.
+pub struct RallyText(pub Entity);
+
+/// Sudden-death overtime, entered when every player is one point away from winning the game.
+#[derive(Default)]
+pub struct Overtime {
+    pub active: bool,
+    /// Time until the next ramp up.
+    pub timer: f32,
+    /// How far the top and bottom walls have moved into the arena.
+    pub wall_inset: f32,
+}
+
+/// How far fingers on the left and right halves of the screen have dragged the paddles on that
+/// side, in arena units, since the `PaddleSystem` last moved them. Filled in by the
+/// `TouchSystem`.
+#[derive(Default)]
+pub struct TouchDrag {
+    pub left: f32,
+    pub right: f32,
+}
+
+/// Where the Twitch chat wants the chat paddle of a chat plays match to go, from -1.0 for down
+/// at full speed to 1.0 for up, as of the last count. Filled in by the `ChatVoteSystem`.
+#[derive(Default)]
+pub struct ChatVotes {
+    pub movement: f32,
+}
+
+/// The tally of the chat's last count, under the chat paddle's name.
+pub struct ChatVoteText(pub Entity);
+
+/// How fast a replay is played back and whether it's paused, along the bottom of the screen.
+pub struct ReplayText(pub Entity);
+
+/// Marks the walls that close in on the arena during overtime.
+#[derive(Default)]
+pub struct OvertimeWall;
+
+impl Component for OvertimeWall {
+    type Storage = NullStorage<Self>;
+}
+
+/// Sent by the `WinnerSystem` whenever a player scores, the `ScoreUiSystem` puts the new score
+/// on the screen.
+#[derive(Clone, Copy, Debug)]
+pub struct ScoreEvent {
+    pub side: Side,
+    /// The player's new score.
+    pub score: i32,
+}
+
+/// Sent by the `WinnerSystem` when a ball goes into a player's goal, whether or not anyone
+/// scores from it. The `MatchStatsSystem` counts it, and the `RumbleSystem` rumbles for it
+/// with the `gamepad` feature.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub struct GoalConceded {
+    pub side: Side,
+    /// How fast the ball was going, in arena units per second.
+    pub speed: f32,
+}
+
+/// Sent by the `WinnerSystem` when a player reaches `MatchRules::win_score`.
+#[derive(Clone, Copy, Debug)]
+pub struct GameWon {
+    pub winner: Side,
+}
+
+/// The sprite sheet shared by the paddles and balls, so systems can spawn entities too.
+pub struct SpriteSheetHandle(pub Handle<SpriteSheet>);
+
+/// The sprite sheet of the background, one sprite for each of its layers.
+pub struct BackgroundSheetHandle(pub Handle<SpriteSheet>);
+
+/// Counts down to the next extra ball being added to the match.
+pub struct MultiBall {
+    pub timer: f32,
+}
+
+impl Default for MultiBall {
+    fn default() -> Self {
+        MultiBall {
+            timer: MULTI_BALL_INTERVAL,
+        }
+    }
 }
 
 /// ScoreText contains the ui text components that display the score
 pub struct ScoreText {
     pub p1_score: Entity,
     pub p2_score: Entity,
+    /// Only shown in four player matches.
+    pub p3_score: Option<Entity>,
+    pub p4_score: Option<Entity>,
+    /// Banner that is only filled in during overtime.
+    pub overtime: Entity,
+    /// Time left in time attack games, empty otherwise.
+    pub timer: Entity,
+}
+
+impl ScoreText {
+    pub fn for_side(&self, side: Side) -> Option<Entity> {
+        match side {
+            Side::Left => Some(self.p1_score),
+            Side::Right => Some(self.p2_score),
+            Side::Top => self.p3_score,
+            Side::Bottom => self.p4_score,
+        }
+    }
 }
 
 pub struct Ball {
-    pub velocity: [f32; 2],
+    pub velocity: Vector2<f32>,
     pub radius: f32,
+    /// Rotation of the velocity in radians per second, which curves the ball's path.
+    pub spin: f32,
+    /// The paddle that touched the ball last, if any.
+    pub last_hit: Option<Side>,
+    /// Whether the ball is flying off a power shot, it slows back down on the next paddle.
+    pub power_shot: bool,
 }
 
 impl Component for Ball {
@@ -44,9 +542,14 @@ impl Component for Ball {
 
 /// Initialises one ball in the middle-ish of the arena.
 fn initialise_ball(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>) {
+    let gameplay = world.read_resource::<Gameplay>().clone();
+    let radius = world.read_resource::<Mutators>().ball_radius(gameplay.ball_radius);
+
     // Create the translation.
     let mut local_transform = Transform::default();
-    local_transform.set_translation_xyz(ARENA_WIDTH / 2.0, ARENA_HEIGHT / 2.0, 0.0);
+    let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+    local_transform.set_translation_xyz(width / 2.0, height / 2.0, 0.0);
+    local_transform.set_scale(ball_scale(radius));
 
     // Assign the sprite for the ball
     let sprite_render = SpriteRender {
@@ -57,182 +560,2616 @@ fn initialise_ball(world: &mut World, sprite_sheet_handle: Handle<SpriteSheet>)
     world
         .create_entity()
         .with(sprite_render)
+        .with(Animation::ball())
         .with(Ball {
-            radius: BALL_RADIUS,
-            velocity: [BALL_VELOCITY_X, BALL_VELOCITY_Y],
+            radius,
+            velocity: Vector2::new(gameplay.ball_velocity_x, gameplay.ball_velocity_y),
+            spin: 0.0,
+            last_hit: None,
+            power_shot: false,
         })
         .with(local_transform)
         .build();
 }
 
-#[derive(PartialEq, Eq)]
+/// Scales the ball sprite, which is drawn at `BALL_RADIUS`, to a ball of `radius`.
+pub fn ball_scale(radius: f32) -> Vector3<f32> {
+    let scale = radius / BALL_RADIUS;
+    Vector3::new(scale, scale, 1.0)
+}
+
+/// Scales the paddle sprite, which is drawn `PADDLE_WIDTH` by `PADDLE_HEIGHT`, to the size of
+/// `paddle`. Top and bottom paddles are the same sprite turned on its side, so it's always
+/// stretched along its own y axis.
+pub fn paddle_scale(paddle: &Paddle) -> Vector3<f32> {
+    let (thickness, length) = if paddle.side.is_horizontal() {
+        (paddle.height, paddle.width)
+    } else {
+        (paddle.width, paddle.height)
+    };
+    Vector3::new(thickness / PADDLE_WIDTH, length / PADDLE_HEIGHT, 1.0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Side {
     Left,
     Right,
+    Top,
+    Bottom,
+}
+
+impl Side {
+    pub fn name(self) -> &'static str {
+        match self {
+            Side::Left => "Left",
+            Side::Right => "Right",
+            Side::Top => "Top",
+            Side::Bottom => "Bottom",
+        }
+    }
+
+    /// The side across the arena.
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+        }
+    }
+
+    /// Top and bottom paddles lie flat and move left and right.
+    pub fn is_horizontal(self) -> bool {
+        self == Side::Top || self == Side::Bottom
+    }
+
+    /// Direction pointing away from this edge into the arena.
+    pub fn normal(self) -> Vector2<f32> {
+        match self {
+            Side::Left => Vector2::new(1.0, 0.0),
+            Side::Right => Vector2::new(-1.0, 0.0),
+            Side::Top => Vector2::new(0.0, -1.0),
+            Side::Bottom => Vector2::new(0.0, 1.0),
+        }
+    }
+
+    /// Direction along this edge that paddle velocities are measured in.
+    pub fn tangent(self) -> Vector2<f32> {
+        if self.is_horizontal() {
+            Vector2::new(1.0, 0.0)
+        } else {
+            Vector2::new(0.0, 1.0)
+        }
+    }
+}
+
+/// The part of its edge a paddle is allowed to move in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lane {
+    Full,
+    /// The upper half, used by one teammate in doubles.
+    Upper,
+    /// The lower half, used by the other teammate in doubles.
+    Lower,
+}
+
+impl Lane {
+    /// Start and end of the lane along an edge that is `edge_length` long.
+    pub fn range(self, edge_length: f32) -> (f32, f32) {
+        match self {
+            Lane::Full => (0.0, edge_length),
+            Lane::Upper => (edge_length / 2.0, edge_length),
+            Lane::Lower => (0.0, edge_length / 2.0),
+        }
+    }
 }
 
 pub struct Paddle {
+    /// The edge the paddle guards, which is also the team it plays for in doubles.
     pub side: Side,
+    pub lane: Lane,
     pub width: f32,
     pub height: f32,
+    /// Speed of the paddle along the edge it guards during the last frame, in arena units
+    /// per second. Positive is up for left and right paddles, and right for top and bottom ones.
+    pub velocity: f32,
+    /// Scales how fast the paddle moves, power-ups can change it.
+    pub speed_multiplier: f32,
+    /// Whether the player's up and down controls are swapped, by the mirror mutator.
+    pub inverted: bool,
 }
 
 impl Paddle {
-    fn new(side: Side) -> Paddle {
+    fn new(side: Side, gameplay: &Gameplay) -> Paddle {
+        let (width, height) = if side.is_horizontal() {
+            (gameplay.paddle_height, gameplay.paddle_width)
+        } else {
+            (gameplay.paddle_width, gameplay.paddle_height)
+        };
         Paddle {
             side,
-            width: PADDLE_WIDTH,
-            height: PADDLE_HEIGHT,
+            lane: Lane::Full,
+            width,
+            height,
+            velocity: 0.0,
+            speed_multiplier: 1.0,
+            inverted: false,
         }
     }
+
+    fn in_lane(mut self, lane: Lane) -> Paddle {
+        self.lane = lane;
+        self
+    }
 }
 
 impl Component for Paddle {
     type Storage = DenseVecStorage<Self>;
 }
 
-#[derive(Default)]
-pub struct Pong {
-    ball_spawn_timer: Option<f32>,
-    sprite_sheet_handle: Option<Handle<SpriteSheet>>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Grows the paddle of the player who hit the ball.
+    BiggerPaddle,
+    /// Speeds up the ball that collected it.
+    FasterBall,
+    /// Slows down the paddle of the player who didn't hit the ball.
+    SlowOpponent,
+    /// Puts a shield behind the paddle of the player who hit the ball, which stops one goal.
+    Shield,
+    /// Makes the paddle of the player who hit the ball catch it, they release it again with
+    /// their serve action.
+    Sticky,
 }
 
-impl SimpleState for Pong {
-    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
-        let world = data.world;
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::BiggerPaddle,
+        PowerUpKind::FasterBall,
+        PowerUpKind::SlowOpponent,
+        PowerUpKind::Shield,
+        PowerUpKind::Sticky,
+    ];
 
-        self.ball_spawn_timer.replace(1.0);
-        self.sprite_sheet_handle.replace(load_sprite_sheet(world));
-        //world.register::<Ball>(); // <- add this line temporarily
+    /// Factor the power-up scales paddle height, ball speed or paddle speed by. Shields and
+    /// sticky paddles don't scale anything.
+    pub fn multiplier(self) -> f32 {
+        match self {
+            PowerUpKind::BiggerPaddle => 1.5,
+            PowerUpKind::FasterBall => 1.5,
+            PowerUpKind::SlowOpponent => 0.5,
+            PowerUpKind::Shield | PowerUpKind::Sticky => 1.0,
+        }
+    }
 
-        initialise_scoreboard(world);
-        initialise_paddles(world, self.sprite_sheet_handle.clone().unwrap());
-        initialise_camera(world);
+    pub fn sprite_number(self) -> usize {
+        match self {
+            PowerUpKind::BiggerPaddle => 2,
+            PowerUpKind::FasterBall => 3,
+            PowerUpKind::SlowOpponent => 4,
+            PowerUpKind::Shield => 5,
+            PowerUpKind::Sticky => STICKY_SPRITE,
+        }
     }
+}
 
-    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
-            if let Some(timer) = self.ball_spawn_timer.take() {
-            // If the timer isn't expired yet, subtract the time that passed since the last update.
-            let time = timer - data.world.res.fetch::<Time>().delta_seconds();
+/// A shield along the edge behind a paddle, which stops the next goal and disappears.
+pub struct Shield {
+    pub side: Side,
+}
 
-            if time <= 0.0 {
-                // When timer expire, spawn the ball
-                initialise_ball(data.world, self.sprite_sheet_handle.clone().unwrap());
-            } else {
-                // If timer is not expired yet, put it back onto the state.
-                self.ball_spawn_timer.replace(time);
-            }
+impl Component for Shield {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A power-up waiting in the arena for a ball to pass through it.
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+    pub radius: f32,
+}
+
+impl Component for PowerUp {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// A collected power-up that is still in effect, the entity is also its HUD icon.
+pub struct ActivePowerUp {
+    pub kind: PowerUpKind,
+    /// The player that collected it, decides which side of the HUD the icon goes on.
+    pub side: Side,
+    /// The paddle or ball the effect was applied to.
+    pub target: Entity,
+    pub remaining: f32,
+}
+
+impl Component for ActivePowerUp {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Counts down to the next power-up being spawned.
+pub struct PowerUpTimer {
+    pub timer: f32,
+}
+
+impl Default for PowerUpTimer {
+    fn default() -> Self {
+        PowerUpTimer {
+            timer: POWER_UP_INTERVAL,
         }
-        Trans::None
     }
 }
 
-fn initialise_camera(world: &mut World) {
-    // Setup camera in a way that our screen covers whole arena and (0, 0) is in the bottom left.
-    let mut transform = Transform::default();
-    transform.set_translation_xyz(ARENA_WIDTH * 0.5, ARENA_HEIGHT * 0.5, 1.0);
+/// An axis aligned block the ball bounces off, centered on its transform.
+#[derive(Clone)]
+pub struct Obstacle {
+    pub width: f32,
+    pub height: f32,
+}
 
-    world
-        .create_entity()
-        .with(Camera::standard_2d(ARENA_WIDTH, ARENA_HEIGHT))
-        .with(transform)
-        .build();
+impl Component for Obstacle {
+    type Storage = DenseVecStorage<Self>;
 }
 
-/// Initialises one paddle on the left, and one paddle on the right.
-fn initialise_paddles(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
-    let mut left_transform = Transform::default();
-    let mut right_transform = Transform::default();
+/// A block in the middle of the arena that breaks when a ball hits it, giving bonus points to
+/// whoever hit the ball last. Centered on its transform like an `Obstacle`.
+pub struct Brick {
+    pub width: f32,
+    pub height: f32,
+    pub points: i32,
+}
 
-    // Correctly position the paddles.
-    let y = ARENA_HEIGHT / 2.0;
-    left_transform.set_translation_xyz(PADDLE_WIDTH * 0.5, y, 0.0);
-    right_transform.set_translation_xyz(ARENA_WIDTH - PADDLE_WIDTH * 0.5, y, 0.0);
+impl Component for Brick {
+    type Storage = DenseVecStorage<Self>;
+}
 
-    // Assign the sprites for the paddles
-    let sprite_render = SpriteRender {
-        sprite_sheet: sprite_sheet.clone(),
-        sprite_number: 0, // paddle is the first sprite in the sprite_sheet
-    };
+/// One end of a pair of portals, a ball going into it comes out of `exit` with the same
+/// velocity.
+pub struct Portal {
+    pub exit: Entity,
+    pub radius: f32,
+}
 
-    // Create a left plank entity.
-    world
-        .create_entity()
-        .with(Paddle::new(Side::Left))
-        .with(left_transform)
-        .with(sprite_render.clone())
-        .build();
+impl Component for Portal {
+    type Storage = DenseVecStorage<Self>;
+}
 
-    // Create right plank entity.
-    world
-        .create_entity()
-        .with(Paddle::new(Side::Right))
-        .with(right_transform)
-        .with(sprite_render.clone())
-        .build();
+/// Sent by the `BounceSystem` when a ball breaks a brick, for the `WinnerSystem` to hand out
+/// the points.
+#[derive(Clone, Debug)]
+pub struct BrickBroken {
+    /// The player who hit the ball last, nobody gets the points without one.
+    pub side: Option<Side>,
+    pub points: i32,
 }
 
-fn load_sprite_sheet(world: &mut World) -> Handle<SpriteSheet> {
-    // Load the sprite sheet necessary to render the graphics.
-    // The texture is the pixel data
-    // `texture_handle` is a cloneable reference to the texture
-    let texture_handle = {
-        let loader = world.read_resource::<Loader>();
-        let texture_storage = world.read_resource::<AssetStorage<Texture>>();
-        loader.load(
-            "texture/pong_spritesheet.png",
-            ImageFormat::default(),
-            (),
-            &texture_storage,
-        )
-    };
-
-    //...
-    let loader = world.read_resource::<Loader>();
-    let sprite_sheet_store = world.read_resource::<AssetStorage<SpriteSheet>>();
-    loader.load(
-        "texture/pong_spritesheet.ron", // Here we load the associated ron file
-        SpriteSheetFormat(texture_handle),
-        (),
-        &sprite_sheet_store,
-    )
+/// Sent by the `BounceSystem` when a ball bounces off a paddle, a wall or an obstacle, for the
+/// `ParticleEmitterSystem` to burst some particles where it hit and the `AnimationSystem` to
+/// light up the paddle.
+#[derive(Clone, Debug)]
+pub struct BallHit {
+    pub position: Vector2<f32>,
+    /// The ball's velocity coming out of the bounce, the particles spray along it.
+    pub velocity: Vector2<f32>,
+    /// The paddle the ball came off, if it was one.
+    pub paddle: Option<Entity>,
 }
 
-fn initialise_scoreboard(world: &mut World) {
-    let font = world.read_resource::<Loader>().load(
-        "font/square.ttf",
-        TtfFormat,
-        (),
-        &world.read_resource(),
-    );
-    let p1_transform = UiTransform::new(
-        "P1".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
-        -50., -50., 1., 200., 50.,
-    );
-    let p2_transform = UiTransform::new(
-        "P2".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
-        50., -50., 1., 200., 50.,
-    );
+/// How many particles a hit bursts into, how far they spread from the ball's path, in radians,
+/// and how long they last, in seconds.
+pub const PARTICLES_PER_HIT: usize = 8;
+pub const PARTICLE_SPREAD: f32 = 1.2;
+pub const PARTICLE_LIFETIME: f32 = 0.4;
+/// Size of a particle, drawn as a shrunk ball.
+pub const PARTICLE_RADIUS: f32 = 0.5;
 
-    let p1_score = world
-        .create_entity()
-        .with(p1_transform)
-        .with(UiText::new(
-            font.clone(),
-            "0".to_string(),
-            [1., 1., 1., 1.],
-            50.,
-        )).build();
+/// How far behind the ball its trail is drawn, it's drawn with the particles.
+pub const TRAIL_DEPTH: f32 = -0.1;
 
-    let p2_score = world
-        .create_entity()
-        .with(p2_transform)
-        .with(UiText::new(
-            font.clone(),
-            "0".to_string(),
-            [1., 1., 1., 1.],
-            50.,
-        )).build();
-
-    world.add_resource(ScoreText { p1_score, p2_score });
+/// A speck flying off a hit, or a ghost of the ball left behind in its trail. It fades out over
+/// its lifetime and is deleted by the `ParticleSystem` once it's gone.
+pub struct Particle {
+    pub velocity: Vector2<f32>,
+    /// Seconds the particle lasts in all, and how many of them are left.
+    pub lifetime: f32,
+    pub remaining: f32,
+}
+
+impl Component for Particle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// How fast each layer of the background scrolls to the left, in arena units per second. The
+/// far layer at the back comes first, each layer is the sprite of the background sprite sheet
+/// with its index.
+pub const BACKGROUND_SCROLL_SPEEDS: [f32; 3] = [1.0, 2.5, 5.0];
+/// How far behind the arena the far layer of the background is, the layers in front of it are
+/// a little closer each.
+const BACKGROUND_DEPTH: f32 = -0.5;
+/// Width and height of the background sprites, in pixels.
+const BACKGROUND_SPRITE_SIZE: f32 = 64.0;
+
+/// A layer of the background, the `ParallaxSystem` scrolls it by its speed. Every layer is
+/// drawn twice side by side so the second one fills in as the first scrolls away.
+pub struct Parallax {
+    pub speed: f32,
+}
+
+impl Component for Parallax {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The paddle lit up as a ball comes off it, and the ball with its corners glowing as it
+/// pulses, in the sprite sheet.
+pub const PADDLE_HIT_SPRITE: usize = 11;
+pub const BALL_PULSE_SPRITES: [usize; 2] = [12, 13];
+/// A plain white square in the sprite sheet, tinted to whatever color it's drawn in.
+pub const SOLID_SPRITE: usize = 14;
+/// Seconds a paddle stays lit up after a hit.
+pub const PADDLE_HIT_DURATION: f32 = 0.12;
+/// Seconds the ball rests between pulses, and each frame of a pulse lasts.
+pub const BALL_PULSE_REST: f32 = 0.6;
+pub const BALL_PULSE_FRAME: f32 = 0.08;
+
+/// One frame of an `Animation`, the sprite it shows and for how many seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub sprite: usize,
+    pub duration: f32,
+}
+
+/// Steps the `SpriteRender` of its entity through frames of the sprite sheet, the
+/// `AnimationSystem` advances it. The idle frames loop, frames started with `play` run once
+/// before it goes back to them.
+pub struct Animation {
+    idle: Vec<Frame>,
+    playing: Option<Vec<Frame>>,
+    frame: usize,
+    elapsed: f32,
+}
+
+impl Component for Animation {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Animation {
+    pub fn new(idle: Vec<Frame>) -> Animation {
+        Animation {
+            idle,
+            playing: None,
+            frame: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// A paddle standing still, which the `AnimationSystem` lights up when a ball hits it.
+    pub fn paddle() -> Animation {
+        Animation::new(vec![Frame {
+            sprite: 0,
+            duration: 1.0,
+        }])
+    }
+
+    /// The frames of a paddle lighting up.
+    pub fn paddle_hit() -> Vec<Frame> {
+        vec![Frame {
+            sprite: PADDLE_HIT_SPRITE,
+            duration: PADDLE_HIT_DURATION,
+        }]
+    }
+
+    /// A ball that pulses every so often, its corners glowing and fading again.
+    pub fn ball() -> Animation {
+        let [glow, bright] = BALL_PULSE_SPRITES;
+        let frame = |sprite, duration| Frame { sprite, duration };
+        Animation::new(vec![
+            frame(1, BALL_PULSE_REST),
+            frame(glow, BALL_PULSE_FRAME),
+            frame(bright, BALL_PULSE_FRAME),
+            frame(glow, BALL_PULSE_FRAME),
+        ])
+    }
+
+    /// Runs the frames once, starting over if they're already running.
+    pub fn play(&mut self, frames: Vec<Frame>) {
+        self.playing = Some(frames);
+        self.frame = 0;
+        self.elapsed = 0.0;
+    }
+
+    fn frames(&self) -> &[Frame] {
+        self.playing.as_deref().unwrap_or(&self.idle)
+    }
+
+    /// Moves the animation on by `delta` seconds, giving the sprite it shows now. A frame
+    /// without a duration is held.
+    pub fn advance(&mut self, delta: f32) -> Option<usize> {
+        self.elapsed += delta;
+        while let Some(frame) = self.frames().get(self.frame).copied() {
+            if frame.duration <= 0.0 || self.elapsed < frame.duration {
+                break;
+            }
+            self.elapsed -= frame.duration;
+            self.frame += 1;
+            if self.frame >= self.frames().len() {
+                self.frame = 0;
+                self.playing = None;
+            }
+        }
+        self.frames().get(self.frame).map(|frame| frame.sprite)
+    }
+}
+
+/// How far in front of the arena the letterbox bars are drawn, so nothing shows over them.
+const LETTERBOX_DEPTH: f32 = 0.5;
+/// How far out from the arena the letterbox bars reach, in arena widths, far enough to fill the
+/// widest window and to stay put on the screen while it shakes.
+const LETTERBOX_REACH: f32 = 4.0;
+/// How much closer the camera is for spectators, it follows the ball around the arena.
+pub const SPECTATOR_ZOOM: f32 = 1.25;
+/// How quickly the spectators' camera catches up with the ball, the share of the way it's
+/// left behind after a second.
+pub const SPECTATOR_CAMERA_LAG: f32 = 0.05;
+
+/// A bar around the arena, along one of its edges. The `LetterboxSystem` shows as much of the
+/// space around the arena as the window needs to keep it in proportion, the bars cover up that
+/// space in the `bars` color of the `Palette`.
+#[derive(Default)]
+pub struct LetterboxBar;
+
+impl Component for LetterboxBar {
+    type Storage = NullStorage<Self>;
+}
+
+/// Difficulty of the computer opponent in single player matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Seconds between the AI looking at where the ball is.
+    pub fn reaction_delay(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.35,
+            Difficulty::Medium => 0.2,
+            Difficulty::Hard => 0.08,
+        }
+    }
+
+    /// Fastest the AI paddle can move, in arena units per second.
+    pub fn max_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 35.0,
+            Difficulty::Medium => 55.0,
+            Difficulty::Hard => 80.0,
+        }
+    }
+}
+
+/// A ball waiting on the paddle of the player who conceded the last point, until they serve.
+/// Sticky paddles hold on to the balls they catch the same way.
+pub struct Serving {
+    pub side: Side,
+    /// The paddle holding the ball, the side's serving paddle when `None`.
+    pub paddle: Option<Entity>,
+    /// Time left before an AI player serves.
+    pub timer: f32,
+}
+
+impl Component for Serving {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Lets a player controlled paddle dash, briefly moving much faster.
+pub struct Dash {
+    /// Time left of the current dash.
+    pub remaining: f32,
+    /// Time left until the paddle can dash again.
+    pub cooldown: f32,
+    /// The UI bar showing the cooldown.
+    pub bar: Entity,
+}
+
+impl Component for Dash {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Flips a player controlled paddle's controls every now and then, for the mirror mutator.
+pub struct Mirror {
+    /// Time left until the controls flip, or flip back.
+    pub timer: f32,
+    /// The warning sign shown next to the paddle before and while its controls are flipped.
+    pub icon: Entity,
+}
+
+impl Component for Mirror {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Lets a player controlled paddle charge up a power shot by holding their hit action while
+/// the ball comes towards them.
+pub struct PowerShot {
+    /// How far the shot is charged, from 0.0 to 1.0. Only a full charge fires a power shot.
+    pub charge: f32,
+    /// The UI meter showing the charge.
+    pub meter: Entity,
+}
+
+impl Component for PowerShot {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Changes to the rules of pong that can be turned on for a match, in any combination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Mutator {
+    /// Balls are twice as big.
+    BigBall,
+    /// Paddles are half as long.
+    TinyPaddles,
+    /// Balls move twice as fast.
+    DoubleSpeed,
+    /// Balls going out of the top of the arena come back in at the bottom, and the other way
+    /// around.
+    NoWalls,
+    /// Balls fall towards the bottom of the arena and bounce off it like in volleyball.
+    Gravity,
+    /// Goals on the left and right edges only count inside a window sliding up and down each
+    /// of them.
+    GoalZones,
+    /// A wall of bricks in the middle of the arena, which give bonus points when broken.
+    Bricks,
+    /// Balls disappear in the middle third of the arena.
+    Blink,
+    /// Players' up and down controls flip for a few seconds every now and then.
+    Mirror,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 9] = [
+        Mutator::BigBall,
+        Mutator::TinyPaddles,
+        Mutator::DoubleSpeed,
+        Mutator::NoWalls,
+        Mutator::Gravity,
+        Mutator::GoalZones,
+        Mutator::Bricks,
+        Mutator::Blink,
+        Mutator::Mirror,
+    ];
+
+    /// Also the command line argument that turns the mutator on.
+    pub fn name(self) -> &'static str {
+        match self {
+            Mutator::BigBall => "big_ball",
+            Mutator::TinyPaddles => "tiny_paddles",
+            Mutator::DoubleSpeed => "double_speed",
+            Mutator::NoWalls => "no_walls",
+            Mutator::Gravity => "gravity",
+            Mutator::GoalZones => "goal_zones",
+            Mutator::Bricks => "bricks",
+            Mutator::Blink => "blink",
+            Mutator::Mirror => "mirror",
+        }
+    }
+}
+
+/// The mutators turned on for the match, systems check here whether theirs is on.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Mutators {
+    enabled: Vec<Mutator>,
+}
+
+impl Mutators {
+    pub fn is_on(&self, mutator: Mutator) -> bool {
+        self.enabled.contains(&mutator)
+    }
+
+    pub fn toggle(&mut self, mutator: Mutator) {
+        if self.is_on(mutator) {
+            self.enabled.retain(|m| *m != mutator);
+        } else {
+            self.enabled.push(mutator);
+        }
+    }
+
+    /// Radius new balls are served with, in a match where balls are `radius` big.
+    pub fn ball_radius(&self, radius: f32) -> f32 {
+        if self.is_on(Mutator::BigBall) {
+            radius * BIG_BALL_MULTIPLIER
+        } else {
+            radius
+        }
+    }
+}
+
+/// Where the scoring windows on the left and right edges currently are, when goals are
+/// limited to them. Balls that reach those edges outside the window bounce back.
+#[derive(Default)]
+pub struct GoalZones {
+    pub enabled: bool,
+    /// Center of each window along its edge.
+    pub left_center: f32,
+    pub right_center: f32,
+    /// How far along the windows are in sliding up and down.
+    pub phase: f32,
+}
+
+impl GoalZones {
+    /// Windows in the middle of edges `edge_length` long.
+    pub fn new(enabled: bool, edge_length: f32) -> GoalZones {
+        GoalZones {
+            enabled,
+            left_center: edge_length * 0.5,
+            right_center: edge_length * 0.5,
+            phase: 0.0,
+        }
+    }
+
+    /// Center of the scoring window on the `side` edge, if that edge has one.
+    pub fn center(&self, side: Side) -> Option<f32> {
+        match side {
+            _ if !self.enabled => None,
+            Side::Left => Some(self.left_center),
+            Side::Right => Some(self.right_center),
+            Side::Top | Side::Bottom => None,
+        }
+    }
+
+    /// Whether a ball reaching the `side` edge at `position` along it is a goal.
+    pub fn contains(&self, side: Side, position: f32) -> bool {
+        match self.center(side) {
+            Some(center) => (position - center).abs() <= GOAL_ZONE_SIZE * 0.5,
+            None => true,
+        }
+    }
+}
+
+/// Shows where the scoring window on an edge is.
+pub struct GoalZoneMarker {
+    pub side: Side,
+}
+
+impl Component for GoalZoneMarker {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Set by the `HotReloadSystem` when it changes the `Gameplay` of the match being played, to
+/// the one it was before, for the `Pong` state to fit the arena to the new one.
+#[derive(Default)]
+pub struct GameplayReload {
+    pub previous: Option<Gameplay>,
+}
+
+/// How a survival run is going.
+pub struct Survival {
+    /// Seconds the player has lasted so far, which is their score.
+    pub elapsed: f32,
+    /// Time left until the next ball comes in.
+    pub spawn_timer: f32,
+    /// Seconds between balls, which gets shorter with every ball.
+    pub spawn_interval: f32,
+    /// Set once a ball got past the player.
+    pub over: bool,
+}
+
+impl Default for Survival {
+    fn default() -> Self {
+        Survival {
+            elapsed: 0.0,
+            spawn_timer: SURVIVAL_FIRST_INTERVAL,
+            spawn_interval: SURVIVAL_FIRST_INTERVAL,
+            over: false,
+        }
+    }
+}
+
+/// The ball launcher of practice mode and how well the player has been returning its shots.
+pub struct Practice {
+    /// Angle the launcher fires at, in radians from straight at the left edge. Positive is
+    /// upwards.
+    pub angle: f32,
+    pub speed: f32,
+    /// Seconds between shots.
+    pub interval: f32,
+    /// Time left until the next shot.
+    pub timer: f32,
+    /// Whether the launcher fires on its own every `interval`, the tutorial fires it with
+    /// `fire` instead.
+    pub auto_fire: bool,
+    /// Shots the player got back to the right edge.
+    pub returned: u32,
+    /// Shots that got past the player.
+    pub missed: u32,
+}
+
+impl Default for Practice {
+    fn default() -> Self {
+        Practice {
+            angle: 0.0,
+            speed: BALL_VELOCITY_X,
+            interval: 2.0,
+            timer: 1.0,
+            auto_fire: true,
+            returned: 0,
+            missed: 0,
+        }
+    }
+}
+
+impl Practice {
+    /// Has the launcher fire on its next update.
+    pub fn fire(&mut self) {
+        self.timer = 0.0;
+    }
+}
+
+/// Time left of the countdown to the serve after a point. The ball stays on the serving paddle
+/// until it runs out.
+#[derive(Default)]
+pub struct ServeCountdown {
+    pub remaining: f32,
+}
+
+/// The text in the middle of the arena counting down to the serve.
+pub struct CountdownText(pub Entity);
+
+/// The keys of every player, shown next to their paddle at the start of a match.
+#[derive(Default)]
+pub struct ControlHints {
+    pub texts: Vec<Entity>,
+    /// Seconds until the hints have faded out.
+    pub remaining: f32,
+    /// Whether the hints were brought back with the `CONTROL_HINTS_KEY`, they stay up until
+    /// it's pressed again.
+    pub pinned: bool,
+}
+
+impl ControlHints {
+    pub fn visible(&self) -> bool {
+        self.pinned || self.remaining > 0.0
+    }
+
+    /// Shows the hints if they're hidden or fading, and hides them otherwise.
+    pub fn toggle(&mut self) {
+        if self.pinned {
+            self.pinned = false;
+            self.remaining = 0.0;
+        } else {
+            self.pinned = true;
+        }
+    }
+}
+
+/// The overlay showing practice stats and the launcher's settings.
+pub struct PracticeText {
+    pub stats: Entity,
+    pub launcher: Entity,
+}
+
+/// Shakes the camera for a moment, after a goal or a power shot. The shake dies down over its
+/// duration.
+#[derive(Default)]
+pub struct ScreenShake {
+    pub remaining: f32,
+    pub duration: f32,
+    /// How far the camera moves as the shake starts.
+    pub amount: f32,
+}
+
+impl ScreenShake {
+    /// Starts shaking for `duration` seconds, unless a harder shake is still going.
+    pub fn start(&mut self, duration: f32, amount: f32) {
+        if amount >= self.amount * self.decay() {
+            self.remaining = duration;
+            self.duration = duration;
+            self.amount = amount;
+        }
+    }
+
+    /// How much of the shake is left, from 1.0 as it starts to 0.0 once it's over.
+    pub fn decay(&self) -> f32 {
+        if self.duration > 0.0 {
+            self.remaining / self.duration
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Marks a paddle as driven by the AI instead of player input.
+#[derive(Default)]
+pub struct AiPaddle {
+    /// Position along its edge the paddle is currently heading for.
+    pub target: f32,
+    /// Time left until the AI looks at the ball again.
+    pub reaction_timer: f32,
+}
+
+impl Component for AiPaddle {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Marks the paddle of the opponent in an online match, which the `OnlineSystem` moves with
+/// the inputs they send, and both paddles of a match being watched.
+#[derive(Default)]
+pub struct RemotePaddle;
+
+impl Component for RemotePaddle {
+    type Storage = NullStorage<Self>;
+}
+
+/// Marks the faded paddle in practice that the `GhostSystem` moves the way the player moved
+/// theirs in their best session. Balls go through it.
+#[derive(Default)]
+pub struct GhostPaddle;
+
+impl Component for GhostPaddle {
+    type Storage = NullStorage<Self>;
+}
+
+/// Marks the paddle the Twitch chat plays in a chat plays match, which the `PaddleSystem` moves
+/// by the `ChatVotes` instead of any keys.
+#[derive(Default)]
+pub struct ChatPaddle;
+
+impl Component for ChatPaddle {
+    type Storage = NullStorage<Self>;
+}
+
+#[derive(Default)]
+pub struct Pong {
+    ball_spawn_timer: Option<f32>,
+    sprite_sheet_handle: Option<Handle<SpriteSheet>>,
+    /// When set the right paddle is played by the AI at this difficulty.
+    opponent: Option<Difficulty>,
+    /// Obstacles to place in the arena, by the position of their center.
+    obstacles: Vec<(Vector2<f32>, Obstacle)>,
+    /// Pairs of portals to place in the arena, by the positions of their centers.
+    portals: Vec<(Vector2<f32>, Vector2<f32>)>,
+    mode: GameMode,
+    rules: MatchRules,
+    mutators: Mutators,
+    handicaps: Handicaps,
+    /// Longest survival run played this session.
+    best_survival: f32,
+    /// Whether the match is played online, against the opponent in the `Online` resource.
+    online: bool,
+    /// Whether the Twitch chat of `GameConfig::twitch_channel` plays the right paddle.
+    chat_plays: bool,
+    /// Whether practice is played against the ghost of the best session, and recorded for it.
+    ghost: bool,
+    /// The saved online match played back in place of a match being played.
+    replay: Option<Replay>,
+    /// Shown once the celebration of a won match is over.
+    pending_results: Option<NextScreen>,
+    game_won_reader: Option<ReaderId<GameWon>>,
+}
+
+impl Pong {
+    /// A match against the computer, the player controls the left paddle.
+    pub fn single_player(difficulty: Difficulty) -> Pong {
+        Pong {
+            opponent: Some(difficulty),
+            ..Default::default()
+        }
+    }
+
+    /// A match between two players sharing the keyboard.
+    pub fn two_player() -> Pong {
+        Pong::default()
+    }
+
+    /// A classic match against the opponent the lobby paired the player with, who's in the
+    /// `Online` resource. Both players play it out in the `Rollback`, so there's only ever the
+    /// one ball in play, without multi-ball, power-ups, dashes or power shots. Also the match
+    /// being watched when the `Online` resource is watching one.
+    pub fn online() -> Pong {
+        Pong {
+            online: true,
+            ..Default::default()
+        }
+    }
+
+    /// Plays a saved online match back, the `ReplaySystem` plays it the way it was played and
+    /// nobody's input moves anything.
+    pub fn replay(replay: Replay) -> Pong {
+        Pong {
+            rules: replay.rules(),
+            mutators: replay.mutators.clone(),
+            replay: Some(replay),
+            ..Default::default()
+        }
+    }
+
+    /// A two versus two match, each teammate guarding half of their side.
+    pub fn doubles() -> Pong {
+        Pong {
+            mode: GameMode::Doubles,
+            ..Default::default()
+        }
+    }
+
+    /// A match between four players, each guarding one edge of the arena.
+    pub fn four_player() -> Pong {
+        Pong {
+            mode: GameMode::FourPlayer,
+            ..Default::default()
+        }
+    }
+
+    /// Practice against a ball launcher, without keeping score.
+    pub fn practice() -> Pong {
+        Pong {
+            mode: GameMode::Practice,
+            ghost: true,
+            ..Default::default()
+        }
+    }
+
+    /// Practices without the ghost of the best session, and without recording one.
+    pub fn without_ghost(mut self) -> Pong {
+        self.ghost = false;
+        self
+    }
+
+    /// A survival run, the player lasts as long as they can against more and more balls.
+    pub fn survival() -> Pong {
+        Pong {
+            mode: GameMode::Survival,
+            ..Default::default()
+        }
+    }
+
+    /// Has the Twitch chat play the right paddle of a 2 player match, by voting up or down.
+    pub fn with_chat_plays(mut self) -> Pong {
+        self.chat_plays = true;
+        self
+    }
+
+    pub fn with_rules(mut self, rules: MatchRules) -> Pong {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_handicaps(mut self, handicaps: Handicaps) -> Pong {
+        self.handicaps = handicaps;
+        self
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// The sides played by people rather than the AI.
+    pub fn player_sides(&self) -> Vec<Side> {
+        self.mode
+            .sides()
+            .iter()
+            .cloned()
+            .filter(|side| self.opponent.is_none() || *side != Side::Right)
+            .collect()
+    }
+
+    /// What to call whoever plays the side, the AI opponent doesn't get a name typed in. Online
+    /// the local player goes by the name they gave the lobby.
+    fn player_name(&self, world: &World, side: Side) -> String {
+        if let Some(replay) = &self.replay {
+            if side == Side::Left {
+                replay.left.clone()
+            } else {
+                replay.right.clone()
+            }
+        } else if self.online {
+            let online = world.read_resource::<Online>();
+            match (&online.peer, online.local_side(), &online.watching) {
+                (_, _, Some(watching)) if side == Side::Left => watching.left.clone(),
+                (_, _, Some(watching)) => watching.right.clone(),
+                (Some(peer), Some(local), _) if local != side => peer.name.clone(),
+                _ => world.read_resource::<PlayerNames>().name(Side::Left).to_string(),
+            }
+        } else if self.opponent.is_some() && side == Side::Right {
+            "Computer".to_string()
+        } else if self.chat_plays && side == Side::Right {
+            "Twitch chat".to_string()
+        } else {
+            world.read_resource::<PlayerNames>().name(side).to_string()
+        }
+    }
+
+    pub fn rules(&self) -> MatchRules {
+        self.rules
+    }
+
+    /// Adds a `width` by `height` obstacle centered on `x`, `y` to the arena.
+    pub fn with_obstacle(mut self, x: f32, y: f32, width: f32, height: f32) -> Pong {
+        self.obstacles.push((Vector2::new(x, y), Obstacle { width, height }));
+        self
+    }
+
+    /// Adds a pair of portals centered on `a` and `b`, balls going into one come out of the
+    /// other.
+    pub fn with_portals(mut self, a: Vector2<f32>, b: Vector2<f32>) -> Pong {
+        self.portals.push((a, b));
+        self
+    }
+
+    pub fn with_mutators(mut self, mutators: Mutators) -> Pong {
+        self.mutators = mutators;
+        self
+    }
+
+    pub fn mutators(&self) -> &Mutators {
+        &self.mutators
+    }
+
+    /// Sets the match up the way it was picked on the mode select screen, the rules and arena
+    /// stay as they are.
+    pub fn with_match_config(mut self, config: &MatchConfig) -> Pong {
+        self.mode = config.mode;
+        self.opponent = config.opponent;
+        self.mutators = config.mutators.clone();
+        self
+    }
+
+    pub fn match_config(&self) -> MatchConfig {
+        MatchConfig {
+            mode: self.mode,
+            opponent: self.opponent,
+            mutators: self.mutators.clone(),
+        }
+    }
+}
+
+impl SimpleState for Pong {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        // In practice the launcher does the serving.
+        if self.mode != GameMode::Practice {
+            self.ball_spawn_timer.replace(1.0);
+        }
+        // The `Loading` state loaded the sprite sheet before the game started.
+        let sprite_sheet = world.read_resource::<SpriteSheetHandle>().0.clone();
+        self.sprite_sheet_handle.replace(sprite_sheet);
+        // The menu music fades out into the match music.
+        world.write_resource::<Music>().play(Track::Game);
+        //world.register::<Ball>(); // <- add this line temporarily
+        // Online matches and replays are played the same on every machine, so they keep to
+        // the standard sizes and speeds.
+        let gameplay = if self.online || self.replay.is_some() {
+            Gameplay::default()
+        } else {
+            Gameplay::load_shipped()
+        };
+        let arena_height = gameplay.arena_height;
+        let ball_speed = gameplay.ball_velocity_x;
+        world.add_resource(gameplay);
+        world.add_resource(GameplayReload::default());
+        world.add_resource(MultiBall::default());
+        world.add_resource(PowerUpTimer::default());
+        world.add_resource(self.mode);
+        world.add_resource(self.rules);
+        world.add_resource(MatchScore::default());
+        world.add_resource(MatchStats::default());
+        world.add_resource(Rally::default());
+        world.add_resource(ScoreBoard::with_head_starts(&self.handicaps));
+        world.add_resource(Overtime::default());
+        world.add_resource(ScreenShake::default());
+        world.add_resource(self.mutators.clone());
+        let goal_zones = self.mutators.is_on(Mutator::GoalZones);
+        world.add_resource(GoalZones::new(goal_zones, arena_height));
+        world.add_resource(Survival::default());
+        world.add_resource(Practice {
+            speed: ball_speed,
+            ..Practice::default()
+        });
+        world.add_resource(MatchTimer::new(self.rules));
+        world.add_resource(ServeCountdown::default());
+        let players = self.local_players(world);
+        world.write_resource::<Achievements>().start_match(players);
+        self.game_won_reader = Some(
+            world
+                .write_resource::<EventChannel<GameWon>>()
+                .register_reader(),
+        );
+
+        if let Some(difficulty) = self.opponent {
+            world.add_resource(difficulty);
+        }
+
+        initialise_scoreboard(world, self.mode);
+        let names: Vec<(Side, String)> = self
+            .mode
+            .sides()
+            .iter()
+            .map(|side| (*side, self.player_name(world, *side)))
+            .collect();
+        initialise_player_names(world, self.mode, &names);
+        show_scores(world);
+        initialise_countdown_text(world);
+        initialise_rally_text(world);
+        initialise_paddles(
+            world,
+            self.sprite_sheet_handle.clone().unwrap(),
+            self.opponent.is_some(),
+            self.mode,
+        );
+        if self.chat_plays {
+            initialise_chat_paddle(world);
+        }
+        if self.online {
+            initialise_remote_paddle(world);
+            let (local, watching, authoritative) = {
+                let online = world.read_resource::<Online>();
+                let authoritative = online.peer.as_ref().is_some_and(|peer| peer.authoritative);
+                (online.local_side(), online.watching.is_some(), authoritative)
+            };
+            // An authoritative lobby plays the match itself.
+            match (local, authoritative) {
+                (Some(local), false) => {
+                    let mutators = self.mutators.clone();
+                    world.add_resource(Rollback::new(local, self.rules.win_score, mutators));
+                }
+                (Some(local), true) => world.add_resource(Prediction::new(local)),
+                (None, _) => {}
+            }
+            if watching {
+                initialise_spectator_text(world);
+            }
+        }
+        if let Some(replay) = &self.replay {
+            world.write_resource::<Online>().replaying = true;
+            world.add_resource(Playback::new(replay.clone()));
+            initialise_replay_text(world);
+        }
+        handicap_paddles(world, &self.handicaps, &self.mutators);
+        if !self.online && self.replay.is_none() {
+            initialise_player_meters(world);
+        }
+        initialise_control_hints(world);
+        initialise_audio_listener(world);
+        if self.mutators.is_on(Mutator::Mirror) {
+            initialise_mirrors(world, self.sprite_sheet_handle.clone().unwrap());
+        }
+        if self.mutators.is_on(Mutator::GoalZones) {
+            initialise_goal_zones(world, self.sprite_sheet_handle.clone().unwrap());
+        }
+        initialise_obstacles(
+            world,
+            self.sprite_sheet_handle.clone().unwrap(),
+            &self.obstacles,
+        );
+        initialise_portals(
+            world,
+            self.sprite_sheet_handle.clone().unwrap(),
+            &self.portals,
+        );
+        if self.mutators.is_on(Mutator::Bricks) {
+            initialise_bricks(world, self.sprite_sheet_handle.clone().unwrap());
+        }
+        if self.mode == GameMode::Practice {
+            initialise_practice_text(world);
+        }
+        if self.ghost {
+            world.write_resource::<Ghost>().start();
+            if world.read_resource::<Ghost>().best.is_some() {
+                initialise_ghost_paddle(world, self.sprite_sheet_handle.clone().unwrap());
+            }
+        }
+        initialise_background(world);
+        initialise_camera(world);
+        initialise_letterbox(world, self.sprite_sheet_handle.clone().unwrap());
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(results) = self.pending_results.take() {
+            return Trans::Switch(results);
+        }
+        // The match stands still once the opponent is gone, the player can only quit from
+        // there.
+        let lost = data.world.write_resource::<Online>().lost.take();
+        if let Some(message) = lost {
+            return Trans::Push(Box::new(Paused::with_message(message)));
+        }
+        let held = {
+            let online = data.world.read_resource::<Online>();
+            online.reconnecting || online.opponent_dropped
+        };
+        if held {
+            return Trans::Push(Box::new(Reconnect::default()));
+        }
+        let previous = data.world.write_resource::<GameplayReload>().previous.take();
+        if let Some(previous) = previous {
+            fit_arena(data.world, &previous, self.sprite_sheet_handle.clone().unwrap());
+        }
+
+            if let Some(timer) = self.ball_spawn_timer.take() {
+            // If the timer isn't expired yet, subtract the time that passed since the last update.
+            let time = timer - data.world.res.fetch::<Time>().delta_seconds();
+
+            if time <= 0.0 {
+                // When timer expire, spawn the ball
+                initialise_ball(data.world, self.sprite_sheet_handle.clone().unwrap());
+            } else {
+                // If timer is not expired yet, put it back onto the state.
+                self.ball_spawn_timer.replace(time);
+            }
+        }
+
+        let won: Vec<GameWon> = data
+            .world
+            .read_resource::<EventChannel<GameWon>>()
+            .read(self.game_won_reader.as_mut().unwrap())
+            .cloned()
+            .collect();
+        // A survival run is over as soon as one ball gets through.
+        if self.mode == GameMode::Survival {
+            let time = {
+                let survival = data.world.read_resource::<Survival>();
+                if !survival.over {
+                    return Trans::None;
+                }
+                survival.elapsed
+            };
+            self.best_survival = self.best_survival.max(time);
+            // Nobody wins a survival run, it's only lost.
+            self.record_profiles(data.world, None);
+            self.record_history(data.world, None);
+            let results = SurvivalResults::new(time, self.best_survival, self.rematch());
+            let table = HighScoreTable::Survival;
+            let results = self.with_high_score(data.world, table, time, Side::Left, results);
+            return Trans::Switch(results);
+        }
+
+        if let Some(game_won) = won.first() {
+            let (message, match_over) = self.finish_game(data.world, game_won.winner);
+            if match_over {
+                if self.online {
+                    self.submit_result(data.world, game_won.winner);
+                    self.save_replay(data.world);
+                }
+                self.record_profiles(data.world, Some(game_won.winner));
+                self.record_history(data.world, Some(game_won.winner));
+                let stats = *data.world.read_resource::<MatchStats>();
+                let rally = *data.world.read_resource::<Rally>();
+                let results = Results::new(message, stats, rally, self.rematch());
+                // The computer and online opponents don't go on the tables.
+                let scored = data.world.read_resource::<ScoreBoard>().score(game_won.winner)
+                    - self.handicaps.get(game_won.winner).head_start;
+                let results: NextScreen = if self.rules.time_limit.is_some()
+                    && !self.online
+                    && !(self.opponent.is_some() && game_won.winner == Side::Right)
+                {
+                    let table = HighScoreTable::TimeAttack;
+                    self.with_high_score(data.world, table, scored as f32, game_won.winner, results)
+                } else {
+                    Box::new(results)
+                };
+                self.pending_results = Some(results);
+                return Trans::Push(Box::new(Celebration::new(game_won.winner)));
+            }
+            return Trans::Push(Box::new(Intermission::new(message)));
+        }
+
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            // Closing the window mid-match asks first, the match stands still meanwhile.
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+            if is_key_down(event, VirtualKeyCode::Escape) {
+                return Trans::Push(Box::new(Paused::default()));
+            }
+            if is_key_down(event, CONTROL_HINTS_KEY) {
+                data.world.write_resource::<ControlHints>().toggle();
+            }
+            if self.replay.is_some() {
+                control_playback(data.world, event);
+            }
+        }
+        match &event {
+            // Start on any gamepad pauses too.
+            StateEvent::Input(InputEvent::ActionPressed(action)) if action == "menu_start" => {
+                Trans::Push(Box::new(Paused::default()))
+            }
+            // The match waits for a player whose gamepad came loose, until it's plugged back in.
+            StateEvent::Input(InputEvent::ControllerDisconnected { which }) => {
+                match gamepad_player(data.world, *which) {
+                    Some(player) => {
+                        let message = format!("The {} player's gamepad was unplugged", player);
+                        Trans::Push(Box::new(Paused::with_message(message)))
+                    }
+                    None => Trans::None,
+                }
+            }
+            _ => Trans::None,
+        }
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // The match is over, clean up every ball, paddle and piece of ui we created.
+        data.world.delete_all();
+        data.world.write_resource::<Announcer>().clear();
+        data.world.write_resource::<Online>().replaying = false;
+        if self.ghost {
+            let (returned, missed) = {
+                let practice = data.world.read_resource::<Practice>();
+                (practice.returned, practice.missed)
+            };
+            data.world.write_resource::<Ghost>().finish(returned, missed);
+        }
+        if self.chat_plays {
+            // Leaves the chat.
+            data.world.add_resource(TwitchChat::default());
+        }
+    }
+}
+
+impl Pong {
+    /// A new match with the same players and rules as this one.
+    pub fn rematch(&self) -> Pong {
+        Pong {
+            opponent: self.opponent,
+            obstacles: self.obstacles.clone(),
+            portals: self.portals.clone(),
+            mode: self.mode,
+            rules: self.rules,
+            mutators: self.mutators.clone(),
+            handicaps: self.handicaps,
+            best_survival: self.best_survival,
+            online: self.online,
+            chat_plays: self.chat_plays,
+            ghost: self.ghost,
+            replay: self.replay.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Shows `results`, after the player on `side` puts their name on the high score `table`
+    /// if `score` makes it.
+    fn with_high_score<T>(
+        &self,
+        world: &World,
+        table: HighScoreTable,
+        score: f32,
+        side: Side,
+        results: T,
+    ) -> NextScreen
+    where
+        T: SimpleState + 'static,
+    {
+        if world.read_resource::<HighScores>().rank(table, score).is_none() {
+            return Box::new(results);
+        }
+        // The field starts out empty for players who didn't give a name.
+        let name = self.player_name(world, side);
+        let name = if name == side.name() { String::new() } else { name };
+        Box::new(NewHighScore::new(table, score, name, Box::new(results)))
+    }
+
+    /// Submits the online match `winner` just won to the leaderboard, as the local player saw
+    /// it. Matches only watched aren't theirs to submit.
+    fn submit_result(&self, world: &mut World, winner: Side) {
+        let (local, opponent) = {
+            let online = world.read_resource::<Online>();
+            match (online.local_side(), &online.peer) {
+                (Some(local), Some(peer)) if online.watching.is_none() => {
+                    (local, peer.name.clone())
+                }
+                _ => return,
+            }
+        };
+        let other = if local == Side::Left { Side::Right } else { Side::Left };
+        let result = {
+            let match_score = world.read_resource::<MatchScore>();
+            MatchResult {
+                player: self.player_name(world, local),
+                opponent,
+                won: winner == local,
+                games: match_score.games(local),
+                opponent_games: match_score.games(other),
+            }
+        };
+        world.write_resource::<Leaderboard>().submit(result);
+    }
+
+    /// The sides played by people here. The computer, the Twitch chat and online opponents play
+    /// elsewhere, matches watched or played back aren't anyone's.
+    fn local_players(&self, world: &World) -> Vec<Side> {
+        if self.replay.is_some() {
+            Vec::new()
+        } else if self.online {
+            let online = world.read_resource::<Online>();
+            online.local_side().filter(|_| online.watching.is_none()).into_iter().collect()
+        } else if self.chat_plays {
+            vec![Side::Left]
+        } else {
+            self.player_sides()
+        }
+    }
+
+    /// Adds the match `winner` just won to the profiles of the `local_players()` who gave a
+    /// name, `None` for a survival run.
+    fn record_profiles(&self, world: &mut World, winner: Option<Side>) {
+        let sides = self.local_players(world);
+        let stats = *world.read_resource::<MatchStats>();
+        let longest_rally = world.read_resource::<Rally>().best;
+        let records = sides
+            .into_iter()
+            .map(|side| (side, self.player_name(world, side)))
+            .filter(|(side, name)| name != side.name())
+            .map(|(side, name)| {
+                let record = MatchRecord {
+                    won: Some(side) == winner,
+                    points_scored: stats.points_scored.score(side) as u32,
+                    points_conceded: stats.points_conceded.score(side) as u32,
+                    longest_rally,
+                    playtime: stats.duration,
+                };
+                (name, record)
+            })
+            .collect();
+        world.write_resource::<Profiles>().record(records);
+    }
+
+    /// Adds the match `winner` just won to the `MatchHistory`, unless it was only watched or
+    /// played back. A survival run has no winner.
+    fn record_history(&self, world: &mut World, winner: Option<Side>) {
+        let watched = world.read_resource::<Online>().watching.is_some();
+        if self.replay.is_some() || (self.online && watched) {
+            return;
+        }
+        let sides = self.mode.sides();
+        let entry = {
+            let scores = world.read_resource::<ScoreBoard>();
+            let match_score = world.read_resource::<MatchScore>();
+            MatchEntry {
+                timestamp: MatchEntry::now(),
+                mode: self.describe_mode(),
+                players: sides.iter().map(|side| self.player_name(world, *side)).collect(),
+                games: sides.iter().map(|side| match_score.games(*side)).collect(),
+                points: sides.iter().map(|side| scores.score(*side)).collect(),
+                winner: winner
+                    .map(|winner| self.player_name(world, winner))
+                    .unwrap_or_default(),
+                duration: world.read_resource::<MatchStats>().duration,
+            }
+        };
+        world.write_resource::<MatchHistory>().add(entry);
+    }
+
+    /// What kind of match it is, for the history.
+    fn describe_mode(&self) -> String {
+        let mode = match self.mode {
+            _ if self.online => "Online".to_string(),
+            GameMode::Classic => match self.opponent {
+                Some(difficulty) => format!("Classic against the {:?} AI", difficulty),
+                None if self.chat_plays => "Classic against the Twitch chat".to_string(),
+                None => "Classic".to_string(),
+            },
+            GameMode::Doubles => "Doubles".to_string(),
+            GameMode::FourPlayer => "Four players".to_string(),
+            GameMode::Survival => "Survival".to_string(),
+            GameMode::Practice => "Practice".to_string(),
+        };
+        if self.rules.time_limit.is_some() {
+            format!("{}, time attack", mode)
+        } else {
+            mode
+        }
+    }
+
+    /// Saves the online match that just ended as a `Replay`, as the local player played it.
+    /// Matches only watched aren't recorded.
+    fn save_replay(&self, world: &World) {
+        let authoritative = {
+            let online = world.read_resource::<Online>();
+            match &online.peer {
+                Some(peer) if online.watching.is_none() => peer.authoritative,
+                _ => return,
+            }
+        };
+        let left = self.player_name(world, Side::Left);
+        let right = self.player_name(world, Side::Right);
+        let mutators = self.mutators.clone();
+        let replay = if authoritative {
+            let prediction = world.res.try_fetch::<Prediction>();
+            prediction.and_then(|prediction| {
+                prediction.recording.replay(left, right, self.rules, mutators)
+            })
+        } else {
+            let rollback = world.res.try_fetch::<Rollback>();
+            rollback.and_then(|rollback| {
+                rollback.recording.replay(left, right, self.rules, mutators)
+            })
+        };
+        match replay {
+            Some(replay) => {
+                if let Err(err) = replay.save() {
+                    log::error!("Failed to save the replay: {}", err);
+                }
+            }
+            None => log::warn!("Not saving a replay, some of the match's frames never came in"),
+        }
+    }
+
+    /// Records the game for `winner`, clears the arena for the next one and describes the
+    /// result for the intermission or results screen. Also returns whether that decided the
+    /// match, the paddles and the final score are left up then for the celebration.
+    fn finish_game(&mut self, world: &mut World, winner: Side) -> (String, bool) {
+        let match_over = {
+            let mut match_score = world.write_resource::<MatchScore>();
+            let games = match_score.games_mut(winner);
+            *games += 1;
+            *games >= self.rules.games_to_win()
+        };
+
+        let message = {
+            let match_score = world.read_resource::<MatchScore>();
+            let games = if self.mode == GameMode::FourPlayer {
+                format!(
+                    "{} - {} - {} - {}",
+                    match_score.games_left,
+                    match_score.games_right,
+                    match_score.games_top,
+                    match_score.games_bottom
+                )
+            } else {
+                format!("{} - {}", match_score.games_left, match_score.games_right)
+            };
+            let name = self.player_name(world, winner);
+            if match_over {
+                format!("{} wins the match {}", name, games)
+            } else {
+                format!("{} wins the game {}", name, games)
+            }
+        };
+
+        // Take every ball, uncollected power-up, shield and brick out of play, a new ball is
+        // served once the intermission is over.
+        let leftovers: Vec<Entity> = {
+            let entities = world.entities();
+            let balls = world.read_storage::<Ball>();
+            let power_ups = world.read_storage::<PowerUp>();
+            let shields = world.read_storage::<Shield>();
+            let bricks = world.read_storage::<Brick>();
+            (&entities, &balls)
+                .join()
+                .map(|(entity, _)| entity)
+                .chain((&entities, &power_ups).join().map(|(entity, _)| entity))
+                .chain((&entities, &shields).join().map(|(entity, _)| entity))
+                .chain((&entities, &bricks).join().map(|(entity, _)| entity))
+                .collect()
+        };
+        world
+            .delete_entities(&leftovers)
+            .expect("balls, power-ups, shields and bricks should be alive");
+        if match_over {
+            return (message, match_over);
+        }
+        self.ball_spawn_timer.replace(1.0);
+        // The next game starts with a fresh ball, not a serve.
+        world.write_resource::<ServeCountdown>().remaining = 0.0;
+        // Every game starts with a full wall of bricks.
+        if self.mutators.is_on(Mutator::Bricks) {
+            initialise_bricks(world, self.sprite_sheet_handle.clone().unwrap());
+        }
+
+        *world.write_resource::<ScoreBoard>() = ScoreBoard::with_head_starts(&self.handicaps);
+        *world.write_resource::<MatchTimer>() = MatchTimer::new(self.rules);
+        show_scores(world);
+
+        (message, match_over)
+    }
+}
+
+/// Fits the arena to the `Gameplay` the `HotReloadSystem` changed it to from `previous`. The
+/// paddles and balls are resized, keeping what handicaps and power-ups did to them, and put
+/// back along their edges. Obstacles and portals keep their place relative to the arena, the
+/// bricks stay around its middle, and the goal zones start over in the middle of the new edges.
+/// The camera and everything laid out around the arena are made again.
+fn fit_arena(world: &mut World, previous: &Gameplay, sprite_sheet: Handle<SpriteSheet>) {
+    let gameplay = world.read_resource::<Gameplay>().clone();
+    {
+        let mut paddles = world.write_storage::<Paddle>();
+        let mut balls = world.write_storage::<Ball>();
+        let obstacles = world.read_storage::<Obstacle>();
+        let portals = world.read_storage::<Portal>();
+        let bricks = world.read_storage::<Brick>();
+        let mut transforms = world.write_storage::<Transform>();
+        let thickness = gameplay.paddle_width / previous.paddle_width;
+        let length = gameplay.paddle_height / previous.paddle_height;
+        let inset = gameplay.paddle_width * 0.5;
+        let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+        for (paddle, transform) in (&mut paddles, &mut transforms).join() {
+            if paddle.side.is_horizontal() {
+                paddle.width *= length;
+                paddle.height *= thickness;
+            } else {
+                paddle.width *= thickness;
+                paddle.height *= length;
+            }
+            transform.set_scale(paddle_scale(paddle));
+            match paddle.side {
+                Side::Left => transform.set_translation_x(Float::from(inset)),
+                Side::Right => transform.set_translation_x(Float::from(width - inset)),
+                Side::Bottom => transform.set_translation_y(Float::from(inset)),
+                Side::Top => transform.set_translation_y(Float::from(height - inset)),
+            };
+        }
+        let radius = gameplay.ball_radius / previous.ball_radius;
+        for (ball, transform) in (&mut balls, &mut transforms).join() {
+            ball.radius *= radius;
+            transform.set_scale(ball_scale(ball.radius));
+        }
+        let across = width / previous.arena_width;
+        let along = height / previous.arena_height;
+        for (_, transform) in (&obstacles, &mut transforms).join() {
+            stretch(transform, across, along);
+        }
+        for (_, transform) in (&portals, &mut transforms).join() {
+            stretch(transform, across, along);
+        }
+        for (_, transform) in (&bricks, &mut transforms).join() {
+            transform.prepend_translation_x((width - previous.arena_width) / 2.0);
+            transform.prepend_translation_y((height - previous.arena_height) / 2.0);
+        }
+    }
+    {
+        let mut goal_zones = world.write_resource::<GoalZones>();
+        *goal_zones = GoalZones::new(goal_zones.enabled, gameplay.arena_height);
+    }
+
+    let stale: Vec<Entity> = {
+        let entities = world.entities();
+        let cameras = world.read_storage::<Camera>();
+        let bars = world.read_storage::<LetterboxBar>();
+        let layers = world.read_storage::<Parallax>();
+        let markers = world.read_storage::<GoalZoneMarker>();
+        let mut stale: Vec<Entity> = (&entities, &cameras).join().map(|(e, _)| e).collect();
+        stale.extend((&entities, &bars).join().map(|(e, _)| e));
+        stale.extend((&entities, &layers).join().map(|(e, _)| e));
+        stale.extend((&entities, &markers).join().map(|(e, _)| e));
+        stale
+    };
+    world
+        .delete_entities(&stale)
+        .expect("arena entities should be alive");
+    if world.read_resource::<GoalZones>().enabled {
+        initialise_goal_zones(world, sprite_sheet.clone());
+    }
+    initialise_background(world);
+    initialise_camera(world);
+    initialise_letterbox(world, sprite_sheet);
+}
+
+/// Moves the transform `across` and `along` times as far from the bottom left of the arena.
+fn stretch(transform: &mut Transform, across: f32, along: f32) {
+    let x = transform.translation().x.as_f32() * across;
+    let y = transform.translation().y.as_f32() * along;
+    transform.set_translation_x(Float::from(x));
+    transform.set_translation_y(Float::from(y));
+}
+
+fn initialise_camera(world: &mut World) {
+    // Setup camera in a way that our screen covers whole arena and (0, 0) is in the bottom left.
+    let (width, height) = {
+        let gameplay = world.read_resource::<Gameplay>();
+        (gameplay.arena_width, gameplay.arena_height)
+    };
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(width * 0.5, height * 0.5, 1.0);
+
+    world
+        .create_entity()
+        .with(Camera::standard_2d(width, height))
+        .with(transform)
+        .build();
+}
+
+/// Initialises the letterbox bars, one along every edge of the arena reaching out far past it.
+/// The left and right bars run the whole height so the corners are covered too.
+fn initialise_letterbox(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
+    let (width, height) = {
+        let gameplay = world.read_resource::<Gameplay>();
+        (gameplay.arena_width, gameplay.arena_height)
+    };
+    let reach = width * LETTERBOX_REACH;
+    let tall = height + reach * 2.0;
+    let bars = [
+        (-reach * 0.5, height * 0.5, reach, tall),
+        (width + reach * 0.5, height * 0.5, reach, tall),
+        (width * 0.5, -reach * 0.5, width, reach),
+        (width * 0.5, height + reach * 0.5, width, reach),
+    ];
+    for (x, y, width, height) in &bars {
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(*x, *y, LETTERBOX_DEPTH);
+        // The sprite is 4 by 4.
+        transform.set_scale(Vector3::new(width / 4.0, height / 4.0, 1.0));
+
+        world
+            .create_entity()
+            .with(LetterboxBar)
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: SOLID_SPRITE,
+            })
+            .with(transform)
+            .build();
+    }
+}
+
+/// Lays out the layers of the background behind the arena, each one as wide as the arena and
+/// drawn once over it and once just to its right.
+fn initialise_background(world: &mut World) {
+    let sprite_sheet = world.read_resource::<BackgroundSheetHandle>().0.clone();
+    let (width, height) = {
+        let gameplay = world.read_resource::<Gameplay>();
+        (gameplay.arena_width, gameplay.arena_height)
+    };
+    let scale = Vector3::new(width / BACKGROUND_SPRITE_SIZE, height / BACKGROUND_SPRITE_SIZE, 1.0);
+
+    for (layer, speed) in BACKGROUND_SCROLL_SPEEDS.iter().enumerate() {
+        for copy in 0..2 {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(
+                width * (0.5 + copy as f32),
+                height * 0.5,
+                BACKGROUND_DEPTH + 0.1 * layer as f32,
+            );
+            transform.set_scale(scale);
+
+            world
+                .create_entity()
+                .with(SpriteRender {
+                    sprite_sheet: sprite_sheet.clone(),
+                    sprite_number: layer,
+                })
+                .with(Parallax { speed: *speed })
+                .with(Transparent)
+                .with(transform)
+                .build();
+        }
+    }
+}
+
+/// Initialises the wall of bricks, three columns in the middle of the arena with a gap around
+/// the center where new balls come in.
+fn initialise_bricks(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
+    let sprite_render = SpriteRender {
+        sprite_sheet,
+        sprite_number: BRICK_SPRITE,
+    };
+    let (width, height) = {
+        let gameplay = world.read_resource::<Gameplay>();
+        (gameplay.arena_width, gameplay.arena_height)
+    };
+    for column in -1..=1 {
+        for row in &[-3.0, -2.0, -1.0, 1.0, 2.0, 3.0] {
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(
+                width / 2.0 + column as f32 * (BRICK_WIDTH + 2.0),
+                height / 2.0 + row * (BRICK_HEIGHT + 2.0),
+                0.0,
+            );
+            // The sprite is 4 by 4.
+            transform.set_scale(Vector3::new(BRICK_WIDTH / 4.0, BRICK_HEIGHT / 4.0, 1.0));
+
+            world
+                .create_entity()
+                .with(Brick {
+                    width: BRICK_WIDTH,
+                    height: BRICK_HEIGHT,
+                    points: BRICK_POINTS,
+                })
+                .with(sprite_render.clone())
+                .with(transform)
+                .build();
+        }
+    }
+}
+
+/// Sizes and speeds up or slows down every paddle according to its side's handicap.
+fn handicap_paddles(world: &mut World, handicaps: &Handicaps, mutators: &Mutators) {
+    let size = if mutators.is_on(Mutator::TinyPaddles) {
+        TINY_PADDLE_MULTIPLIER
+    } else {
+        1.0
+    };
+    let mut paddles = world.write_storage::<Paddle>();
+    let mut transforms = world.write_storage::<Transform>();
+    for (paddle, transform) in (&mut paddles, &mut transforms).join() {
+        let handicap = handicaps.get(paddle.side);
+        paddle.speed_multiplier *= handicap.paddle_speed;
+        if paddle.side.is_horizontal() {
+            paddle.width *= handicap.paddle_size * size;
+        } else {
+            paddle.height *= handicap.paddle_size * size;
+        }
+        transform.set_scale(paddle_scale(paddle));
+    }
+}
+
+/// Writes the scores on the `ScoreBoard` to their texts.
+fn show_scores(world: &mut World) {
+    let scores = world.read_resource::<ScoreBoard>();
+    let score_text = world.read_resource::<ScoreText>();
+    let mut ui_text = world.write_storage::<UiText>();
+    for side in &[Side::Left, Side::Right, Side::Top, Side::Bottom] {
+        if let Some(text) = score_text.for_side(*side).and_then(|e| ui_text.get_mut(e)) {
+            text.text = scores.score(*side).to_string();
+        }
+    }
+}
+
+/// Initialises the markers showing the scoring windows on the left and right edges, thin
+/// lines along the edge behind the paddles.
+fn initialise_goal_zones(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
+    let (width, height) = {
+        let gameplay = world.read_resource::<Gameplay>();
+        (gameplay.arena_width, gameplay.arena_height)
+    };
+    for (side, x) in &[(Side::Left, 0.25), (Side::Right, width - 0.25)] {
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(*x, height * 0.5, -0.1);
+        transform.set_scale(Vector3::new(0.5 / PADDLE_WIDTH, GOAL_ZONE_SIZE / PADDLE_HEIGHT, 1.0));
+
+        world
+            .create_entity()
+            .with(GoalZoneMarker { side: *side })
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: 0,
+            })
+            .with(transform)
+            .build();
+    }
+}
+
+/// Gives every paddle played by a person a dash and a power shot, with their meters along the
+/// edge of the screen it guards. In doubles the lower lane's meters sit under the upper lane's.
+fn initialise_player_meters(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+    let players: Vec<(Entity, Side, Lane)> = {
+        let entities = world.entities();
+        let paddles = world.read_storage::<Paddle>();
+        let ais = world.read_storage::<AiPaddle>();
+        (&entities, &paddles, !&ais)
+            .join()
+            .map(|(entity, paddle, _)| (entity, paddle.side, paddle.lane))
+            .collect()
+    };
+
+    for (paddle, side, lane) in players {
+        let offset = if lane == Lane::Upper { 34. } else { 10. };
+        // The charge meter goes next to the dash bar, away from the edge of the screen.
+        let (anchor, x, y, charge_y) = match side {
+            Side::Left => (Anchor::BottomLeft, 40., offset, offset + 10.),
+            Side::Right => (Anchor::BottomRight, -40., offset, offset + 10.),
+            Side::Top => (Anchor::TopMiddle, 0., -75., -85.),
+            Side::Bottom => (Anchor::BottomMiddle, 0., 75., 85.),
+        };
+        let bar = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("{}_dash", side.name()), anchor.clone(), anchor.clone(),
+                x, y, 1., DASH_BAR_WIDTH, 6.,
+            ))
+            .with(UiImage::SolidColor(theme.dash_ready_color))
+            .build();
+        let meter = world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("{}_charge", side.name()), anchor.clone(), anchor,
+                x, charge_y, 1., 0., 6.,
+            ))
+            .with(UiImage::SolidColor(theme.charge_color))
+            .build();
+        world
+            .write_storage::<PowerShot>()
+            .insert(paddle, PowerShot { charge: 0.0, meter })
+            .expect("Failed to give the paddle a power shot");
+        world
+            .write_storage::<Dash>()
+            .insert(
+                paddle,
+                Dash {
+                    remaining: 0.0,
+                    cooldown: 0.0,
+                    bar,
+                },
+            )
+            .expect("Failed to give the paddle a dash");
+    }
+}
+
+/// Gives every player controlled paddle a `Mirror`, with its warning sign hidden for now.
+fn initialise_mirrors(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
+    let players: Vec<Entity> = {
+        let entities = world.entities();
+        let paddles = world.read_storage::<Paddle>();
+        let ais = world.read_storage::<AiPaddle>();
+        (&entities, &paddles, !&ais)
+            .join()
+            .map(|(entity, _, _)| entity)
+            .collect()
+    };
+
+    let mut rng = rand::thread_rng();
+    for paddle in players {
+        // The `MirrorSystem` moves the sign along with the paddle.
+        let icon = world
+            .create_entity()
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: MIRROR_SPRITE,
+            })
+            .with(Transform::default())
+            .with(Hidden)
+            .build();
+        world
+            .write_storage::<Mirror>()
+            .insert(
+                paddle,
+                Mirror {
+                    timer: rng.gen_range(MIRROR_MIN_INTERVAL, MIRROR_MAX_INTERVAL),
+                    icon,
+                },
+            )
+            .expect("Failed to give the paddle a mirror");
+    }
+}
+
+/// Initialises the ghost of the player's paddle in their best practice session, faded out
+/// behind their own.
+fn initialise_ghost_paddle(world: &mut World, sprite_sheet: Handle<SpriteSheet>) {
+    let color = world.read_resource::<Palette>().paddle(Side::Left);
+    let gameplay = world.read_resource::<Gameplay>().clone();
+    let mut transform = Transform::default();
+    let x = gameplay.paddle_width * 0.5;
+    transform.set_translation_xyz(x, gameplay.arena_height / 2.0, -0.1);
+    transform.set_scale(Vector3::new(
+        gameplay.paddle_width / PADDLE_WIDTH,
+        gameplay.paddle_height / PADDLE_HEIGHT,
+        1.0,
+    ));
+
+    world
+        .create_entity()
+        .with(GhostPaddle)
+        .with(SpriteRender {
+            sprite_sheet,
+            sprite_number: 0, // paddle is the first sprite in the sprite_sheet
+        })
+        .with(faded_tint(color, GHOST_ALPHA))
+        .with(Transparent)
+        .with(transform)
+        .build();
+}
+
+/// Initialises one paddle on the left, and one paddle on the right, plus one on the top and
+/// bottom in four player matches or a second one on the left and right in doubles.
+/// The right paddle is handed to the AI when `ai_opponent` is set, survival runs don't have
+/// one.
+fn initialise_paddles(
+    world: &mut World,
+    sprite_sheet: Handle<SpriteSheet>,
+    ai_opponent: bool,
+    mode: GameMode,
+) {
+    let gameplay = world.read_resource::<Gameplay>().clone();
+    let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+    let inset = gameplay.paddle_width * 0.5;
+    let mut left_transform = Transform::default();
+    let mut right_transform = Transform::default();
+
+    // In doubles these are the paddles of the upper lane, the lower ones are added below.
+    let lane = if mode == GameMode::Doubles {
+        Lane::Upper
+    } else {
+        Lane::Full
+    };
+    let (lane_start, lane_end) = lane.range(height);
+
+    // Correctly position the paddles.
+    let y = (lane_start + lane_end) / 2.0;
+    left_transform.set_translation_xyz(inset, y, 0.0);
+    right_transform.set_translation_xyz(width - inset, y, 0.0);
+    let left = Paddle::new(Side::Left, &gameplay).in_lane(lane);
+    let right = Paddle::new(Side::Right, &gameplay).in_lane(lane);
+    left_transform.set_scale(paddle_scale(&left));
+    right_transform.set_scale(paddle_scale(&right));
+
+    // Assign the sprites for the paddles
+    let sprite_render = SpriteRender {
+        sprite_sheet: sprite_sheet.clone(),
+        sprite_number: 0, // paddle is the first sprite in the sprite_sheet
+    };
+
+    // Create a left plank entity.
+    world
+        .create_entity()
+        .with(left)
+        .with(left_transform)
+        .with(sprite_render.clone())
+        .with(Animation::paddle())
+        .build();
+
+    // Create right plank entity, nobody plays there when practicing or in survival.
+    if !mode.is_solo() {
+        let right = world
+            .create_entity()
+            .with(right)
+            .with(right_transform)
+            .with(sprite_render.clone())
+            .with(Animation::paddle());
+
+        if ai_opponent {
+            right
+                .with(AiPaddle {
+                    target: y,
+                    reaction_timer: 0.0,
+                })
+                .build();
+        } else {
+            right.build();
+        }
+    }
+
+    if mode == GameMode::Doubles {
+        let (lane_start, lane_end) = Lane::Lower.range(height);
+        let y = (lane_start + lane_end) / 2.0;
+        for side in &[Side::Left, Side::Right] {
+            let x = match side {
+                Side::Left => inset,
+                _ => width - inset,
+            };
+            let paddle = Paddle::new(*side, &gameplay).in_lane(Lane::Lower);
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(x, y, 0.0);
+            transform.set_scale(paddle_scale(&paddle));
+
+            world
+                .create_entity()
+                .with(paddle)
+                .with(transform)
+                .with(sprite_render.clone())
+                .with(Animation::paddle())
+                .build();
+        }
+    }
+
+    if mode != GameMode::FourPlayer {
+        return;
+    }
+
+    // The top and bottom paddles use the same sprite turned on its side.
+    let x = width / 2.0;
+    let top = Paddle::new(Side::Top, &gameplay);
+    let bottom = Paddle::new(Side::Bottom, &gameplay);
+    let mut top_transform = Transform::default();
+    let mut bottom_transform = Transform::default();
+    top_transform.set_translation_xyz(x, height - inset, 0.0);
+    bottom_transform.set_translation_xyz(x, inset, 0.0);
+    top_transform.set_scale(paddle_scale(&top));
+    bottom_transform.set_scale(paddle_scale(&bottom));
+    top_transform.set_rotation_2d(std::f32::consts::FRAC_PI_2);
+    bottom_transform.set_rotation_2d(std::f32::consts::FRAC_PI_2);
+
+    world
+        .create_entity()
+        .with(top)
+        .with(top_transform)
+        .with(sprite_render.clone())
+        .with(Animation::paddle())
+        .build();
+
+    world
+        .create_entity()
+        .with(bottom)
+        .with(bottom_transform)
+        .with(sprite_render.clone())
+        .with(Animation::paddle())
+        .build();
+}
+
+/// Hands the right paddle over to the Twitch chat, joining it, and puts the tally of its votes
+/// in the top right corner.
+fn initialise_chat_paddle(world: &mut World) {
+    let channel = world.read_resource::<GameConfig>().twitch_channel.clone();
+    match channel {
+        Some(channel) => world.add_resource(TwitchChat::connect(&channel)),
+        None => log::warn!("No twitch_channel is set in the game config, the chat can't vote"),
+    }
+    let paddles: Vec<Entity> = {
+        let entities = world.entities();
+        let paddles = world.read_storage::<Paddle>();
+        (&entities, &paddles)
+            .join()
+            .filter(|(_, paddle)| paddle.side == Side::Right)
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    {
+        let mut chat_paddles = world.write_storage::<ChatPaddle>();
+        for paddle in paddles {
+            chat_paddles
+                .insert(paddle, ChatPaddle)
+                .expect("Failed to hand the paddle to the chat");
+        }
+    }
+
+    let theme = world.read_resource::<Theme>().clone();
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "chat_votes".to_string(), Anchor::TopRight, Anchor::TopRight,
+            -110., -20., 1., 200., 25.,
+        ))
+        .with(theme.text("Type up or down in the chat".to_string(), 13.))
+        .build();
+    world.add_resource(ChatVoteText(text));
+}
+
+/// Hands the paddle of the opponent in an online match over to the `OnlineSystem`, and both
+/// paddles of a match being watched to the `SpectatorSystem`.
+fn initialise_remote_paddle(world: &mut World) {
+    let local = {
+        let online = world.read_resource::<Online>();
+        match (online.local_side(), &online.watching) {
+            (_, Some(_)) => None,
+            (Some(local), _) => Some(local),
+            (None, None) => return,
+        }
+    };
+    let paddles: Vec<Entity> = {
+        let entities = world.entities();
+        let paddles = world.read_storage::<Paddle>();
+        (&entities, &paddles)
+            .join()
+            .filter(|(_, paddle)| Some(paddle.side) != local)
+            .map(|(entity, _)| entity)
+            .collect()
+    };
+    let mut remotes = world.write_storage::<RemotePaddle>();
+    for paddle in paddles {
+        remotes
+            .insert(paddle, RemotePaddle)
+            .expect("Failed to hand the paddle to the opponent");
+    }
+}
+
+/// Initialises the obstacles, they reuse the paddle sprite stretched to their size.
+fn initialise_obstacles(
+    world: &mut World,
+    sprite_sheet: Handle<SpriteSheet>,
+    obstacles: &[(Vector2<f32>, Obstacle)],
+) {
+    let sprite_render = SpriteRender {
+        sprite_sheet,
+        sprite_number: 0,
+    };
+
+    for (center, obstacle) in obstacles {
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(center.x, center.y, 0.0);
+        transform.set_scale(Vector3::new(
+            obstacle.width / PADDLE_WIDTH,
+            obstacle.height / PADDLE_HEIGHT,
+            1.0,
+        ));
+
+        world
+            .create_entity()
+            .with(obstacle.clone())
+            .with(transform)
+            .with(sprite_render.clone())
+            .build();
+    }
+}
+
+/// Initialises the pairs of portals, each one leading to the other.
+fn initialise_portals(
+    world: &mut World,
+    sprite_sheet: Handle<SpriteSheet>,
+    portals: &[(Vector2<f32>, Vector2<f32>)],
+) {
+    let sprite_render = SpriteRender {
+        sprite_sheet,
+        sprite_number: PORTAL_SPRITE,
+    };
+    let portal_transform = |center: &Vector2<f32>| {
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(center.x, center.y, 0.0);
+        // The sprite is 4 by 4.
+        let scale = PORTAL_RADIUS * 2.0 / 4.0;
+        transform.set_scale(Vector3::new(scale, scale, 1.0));
+        transform
+    };
+
+    for (a, b) in portals {
+        let entity_a = world
+            .create_entity()
+            .with(portal_transform(a))
+            .with(sprite_render.clone())
+            .build();
+        let entity_b = world
+            .create_entity()
+            .with(Portal {
+                exit: entity_a,
+                radius: PORTAL_RADIUS,
+            })
+            .with(portal_transform(b))
+            .with(sprite_render.clone())
+            .build();
+        world
+            .write_storage::<Portal>()
+            .insert(
+                entity_a,
+                Portal {
+                    exit: entity_b,
+                    radius: PORTAL_RADIUS,
+                },
+            )
+            .expect("portal entity should be alive");
+    }
+}
+
+fn initialise_scoreboard(world: &mut World, mode: GameMode) {
+    let theme = world.read_resource::<Theme>().clone();
+    // With four players every score sits next to the edge its player guards.
+    let (p1_transform, p2_transform) = match mode {
+        GameMode::Classic | GameMode::Doubles | GameMode::Survival | GameMode::Practice => (
+            UiTransform::new(
+                "P1".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                -50., -50., 1., 200., 50.,
+            ),
+            UiTransform::new(
+                "P2".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                50., -50., 1., 200., 50.,
+            ),
+        ),
+        GameMode::FourPlayer => (
+            UiTransform::new(
+                "P1".to_string(), Anchor::MiddleLeft, Anchor::MiddleLeft,
+                40., 0., 1., 200., 50.,
+            ),
+            UiTransform::new(
+                "P2".to_string(), Anchor::MiddleRight, Anchor::MiddleRight,
+                -40., 0., 1., 200., 50.,
+            ),
+        ),
+    };
+
+    let p1_score = world
+        .create_entity()
+        .with(p1_transform)
+        .with(theme.score_text("0".to_string()))
+        .build();
+
+    let p2_score = world
+        .create_entity()
+        .with(p2_transform)
+        .with(theme.score_text("0".to_string()))
+        .build();
+
+    let (p3_score, p4_score) = if mode == GameMode::FourPlayer {
+        let p3_transform = UiTransform::new(
+            "P3".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+            0., -40., 1., 200., 50.,
+        );
+        let p4_transform = UiTransform::new(
+            "P4".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+            0., 40., 1., 200., 50.,
+        );
+        let p3_score = world
+            .create_entity()
+            .with(p3_transform)
+            .with(theme.score_text("0".to_string()))
+            .build();
+        let p4_score = world
+            .create_entity()
+            .with(p4_transform)
+            .with(theme.score_text("0".to_string()))
+            .build();
+        (Some(p3_score), Some(p4_score))
+    } else {
+        (None, None)
+    };
+
+    let overtime_transform = UiTransform::new(
+        "overtime".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+        0., 10., 1., 300., 30.,
+    );
+    let overtime = world
+        .create_entity()
+        .with(overtime_transform)
+        .with(UiText::new(theme.font.clone(), String::new(), theme.warning_color, 25.))
+        .build();
+
+    let timer_transform = UiTransform::new(
+        "timer".to_string(), Anchor::TopRight, Anchor::TopRight,
+        -60., -20., 1., 200., 30.,
+    );
+    let timer = world
+        .create_entity()
+        .with(timer_transform)
+        .with(theme.text(String::new(), 25.))
+        .build();
+
+    world.add_resource(ScoreText {
+        p1_score,
+        p2_score,
+        p3_score,
+        p4_score,
+        overtime,
+        timer,
+    });
+}
+
+/// Initialises the practice overlay, stats along the top and the launcher's settings along the
+/// bottom. The `LauncherSystem` fills them in.
+fn initialise_practice_text(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    let stats = world
+        .create_entity()
+        .with(UiTransform::new(
+            "practice_stats".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+            0., -30., 1., 600., 30.,
+        ))
+        .with(theme.text(String::new(), 20.))
+        .build();
+    let launcher = world
+        .create_entity()
+        .with(UiTransform::new(
+            "practice_launcher".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+            0., 40., 1., 700., 30.,
+        ))
+        .with(theme.text(String::new(), 15.))
+        .build();
+
+    world.add_resource(PracticeText { stats, launcher });
+}
+
+/// Puts the name of each player above their score.
+fn initialise_player_names(world: &mut World, mode: GameMode, names: &[(Side, String)]) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    for (side, name) in names {
+        let (anchor, x, y) = match (mode, side) {
+            (GameMode::FourPlayer, Side::Left) => (Anchor::MiddleLeft, 40., 35.),
+            (GameMode::FourPlayer, Side::Right) => (Anchor::MiddleRight, -40., 35.),
+            (GameMode::FourPlayer, Side::Top) => (Anchor::TopMiddle, 0., -10.),
+            (GameMode::FourPlayer, Side::Bottom) => (Anchor::BottomMiddle, 0., 75.),
+            (_, Side::Right) => (Anchor::TopMiddle, 60., -15.),
+            _ => (Anchor::TopMiddle, -60., -15.),
+        };
+        world
+            .create_entity()
+            .with(UiTransform::new(
+                format!("{}_name", side.name()), anchor.clone(), anchor,
+                x, y, 1., 110., 20.,
+            ))
+            .with(theme.text(name.clone(), 15.))
+            .build();
+    }
+}
+
+/// Creates the countdown text, it stays empty until a point is scored.
+fn initialise_countdown_text(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "countdown".to_string(), Anchor::Middle, Anchor::Middle,
+            0., 0., 1., 200., 100.,
+        ))
+        .with(theme.text(String::new(), COUNTDOWN_FONT_START))
+        .build();
+
+    world.add_resource(CountdownText(text));
+}
+
+/// Puts the keys of every player next to the edge they guard, read from the bindings so they
+/// follow any keys rebound in the settings. The `ControlHintSystem` fades them out.
+fn initialise_control_hints(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+    let players: Vec<(Side, Lane, String)> = {
+        let paddles = world.read_storage::<Paddle>();
+        let ais = world.read_storage::<AiPaddle>();
+        let remotes = world.read_storage::<RemotePaddle>();
+        let chat_paddles = world.read_storage::<ChatPaddle>();
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        (&paddles, !&ais, !&remotes, !&chat_paddles)
+            .join()
+            .map(|(paddle, _, _, _)| {
+                let hint = control_hint(&input.bindings, paddle.side, paddle.lane);
+                (paddle.side, paddle.lane, hint)
+            })
+            .collect()
+    };
+
+    let mut texts: Vec<Entity> = players
+        .into_iter()
+        .map(|(side, lane, hint)| {
+            let y = if lane == Lane::Lower { -85. } else { -60. };
+            let (anchor, x, y) = match side {
+                Side::Left => (Anchor::MiddleLeft, 10., y),
+                Side::Right => (Anchor::MiddleRight, -10., y),
+                Side::Top => (Anchor::TopMiddle, 0., -110.),
+                Side::Bottom => (Anchor::BottomMiddle, 0., 110.),
+            };
+            world
+                .create_entity()
+                .with(UiTransform::new(
+                    format!("{}_{:?}_hint", side.name(), lane), anchor.clone(), anchor,
+                    x, y, 1., 230., 20.,
+                ))
+                .with(theme.text(hint, 13.))
+                .build()
+        })
+        .collect();
+    let toggle = world
+        .create_entity()
+        .with(UiTransform::new(
+            "hints_toggle".to_string(), Anchor::Middle, Anchor::Middle,
+            0., -60., 1., 300., 20.,
+        ))
+        .with(theme.text(format!("{:?} shows or hides the controls", CONTROL_HINTS_KEY), 13.))
+        .build();
+    texts.push(toggle);
+
+    world.add_resource(ControlHints {
+        texts,
+        remaining: CONTROL_HINTS_DURATION,
+        pinned: false,
+    });
+}
+
+/// Describes the keys moving, serving and dashing the paddle, as they're bound right now.
+fn control_hint(bindings: &Bindings<StringBindings>, side: Side, lane: Lane) -> String {
+    let controls = Controls::of(side, lane);
+    let movement = match axis_key_names(bindings, controls.paddle) {
+        Some((up, down)) => format!("{}/{} move", up, down),
+        None => "unbound move".to_string(),
+    };
+    // The lower lane in doubles doesn't serve, its hit action only charges power shots.
+    let hit_name = if lane == Lane::Lower { "hit" } else { "serve" };
+    format!(
+        "{}, {} {}, {} dash",
+        movement,
+        action_key_name(bindings, controls.serve),
+        hit_name,
+        action_key_name(bindings, controls.dash)
+    )
+}
+
+/// The player in the match playing with the gamepad, if anyone is. AI paddles, the online
+/// opponent's and the Twitch chat's have none.
+fn gamepad_player(world: &World, gamepad: u32) -> Option<&'static str> {
+    let input = world.read_resource::<InputHandler<StringBindings>>();
+    let paddles = world.read_storage::<Paddle>();
+    let ai_paddles = world.read_storage::<AiPaddle>();
+    let remotes = world.read_storage::<RemotePaddle>();
+    let chat_paddles = world.read_storage::<ChatPaddle>();
+    (&paddles, !&ai_paddles, !&remotes, !&chat_paddles)
+        .join()
+        .map(|(paddle, _, _, _)| Controls::of(paddle.side, paddle.lane))
+        .find(|controls| controls.gamepad(&input.bindings) == Some(gamepad))
+        .map(|controls| controls.player)
+}
+
+/// What a button is called on the screen, keys go by their name and keys bound by position by
+/// the name of the key there on a QWERTY keyboard.
+pub fn button_name(button: &Button) -> String {
+    match button {
+        Button::Key(key) => format!("{:?}", key),
+        Button::ScanCode(scan_code) => match convert_button(*button, KeyMode::Names) {
+            Button::Key(key) => format!("{:?} position", key),
+            _ => format!("key {}", scan_code),
+        },
+        button => format!("{:?}", button),
+    }
+}
+
+/// The name of the button bound to the action, its key if it has one.
+pub fn action_key_name(bindings: &Bindings<StringBindings>, action: &str) -> String {
+    action_button(bindings, action)
+        .map(|button| button_name(&button))
+        .unwrap_or_else(|| "unbound".to_string())
+}
+
+/// The names of the buttons moving an emulated axis up and down.
+pub fn axis_key_names(bindings: &Bindings<StringBindings>, axis: &str) -> Option<(String, String)> {
+    match bindings.axis(axis) {
+        Some(Axis::Emulated { pos, neg }) => Some((button_name(pos), button_name(neg))),
+        _ => None,
+    }
+}
+
+/// Creates the rally counter in the top left corner, the `RallyUiSystem` fills it in.
+fn initialise_rally_text(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "rally".to_string(), Anchor::TopLeft, Anchor::TopLeft,
+            80., -20., 1., 160., 25.,
+        ))
+        .with(theme.text(String::new(), 15.))
+        .build();
+
+    world.add_resource(RallyText(text));
+}
+
+fn initialise_replay_text(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    let text = world
+        .create_entity()
+        .with(UiTransform::new(
+            "replay".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+            0., 40., 1., 300., 25.,
+        ))
+        .with(theme.text(String::new(), 15.))
+        .build();
+    world
+        .create_entity()
+        .with(UiTransform::new(
+            "replay_controls".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+            0., 20., 1., 480., 25.,
+        ))
+        .with(theme.text(REPLAY_CONTROLS.to_string(), 12.))
+        .build();
+
+    world.add_resource(ReplayText(text));
+}
+
+/// Pauses, steps through, speeds up, slows down or jumps through the replay being played back
+/// on the keys in `REPLAY_CONTROLS`.
+fn control_playback(world: &World, event: &Event) {
+    let mut playback = world.write_resource::<Playback>();
+    if is_key_down(event, VirtualKeyCode::Space) {
+        playback.paused = !playback.paused;
+    } else if is_key_down(event, VirtualKeyCode::Period) {
+        // Stepping pauses it, so the frame stepped to stays up.
+        playback.paused = true;
+        playback.steps += 1;
+    } else if is_key_down(event, VirtualKeyCode::Minus) {
+        playback.slower();
+    } else if is_key_down(event, VirtualKeyCode::Equals) {
+        playback.faster();
+    } else if is_key_down(event, VirtualKeyCode::Left) {
+        playback.previous_point();
+    } else if is_key_down(event, VirtualKeyCode::Right) {
+        playback.next_point();
+    }
+}
+
+/// Tells spectators they're watching, along the bottom of the screen.
+fn initialise_spectator_text(world: &mut World) {
+    let theme = world.read_resource::<Theme>().clone();
+
+    world
+        .create_entity()
+        .with(UiTransform::new(
+            "spectating".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+            0., 20., 1., 300., 25.,
+        ))
+        .with(theme.text("Spectating".to_string(), 15.))
+        .build();
 }
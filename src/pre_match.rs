@@ -0,0 +1,318 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::{is_close_requested, is_key_down},
+    prelude::*,
+    ui::{Anchor, Selectable, Selected, TextEditing, UiText, UiTransform},
+    winit::VirtualKeyCode,
+};
+
+use crate::{
+    config::PlayerNames,
+    confirm::ConfirmQuit,
+    menu::{menu_input, refresh_rows, MenuInput},
+    net::{FromLobby, Online, PeerMessage, ToLobby},
+    pong::{Mutators, Pong, Side},
+    rollback::ONLINE_MUTATORS,
+    theme::Theme,
+};
+
+/// Most chat lines shown, older ones scroll off the top.
+const CHAT_LINES: usize = 8;
+/// Longest chat line that can be typed in, or is shown from the opponent.
+const MAX_CHAT_LENGTH: usize = 40;
+const READY_KEY: VirtualKeyCode = VirtualKeyCode::F1;
+const MUTATOR_KEY: VirtualKeyCode = VirtualKeyCode::F2;
+const START_KEY: VirtualKeyCode = VirtualKeyCode::F3;
+
+/// Where the two players of an online match meet once the lobby paired them, to chat and get
+/// ready. The host picks the mutators, which the guest sees as they change, and starts the
+/// match once both players are ready.
+///
+/// Enter sends the line typed in, F1 gets ready or not, up and down pick a mutator and F2 turns
+/// it on or off, F3 starts the match. Escape leaves, which the lobby tells the opponent.
+#[derive(Default)]
+pub struct PreMatch {
+    mutators: Mutators,
+    ready: bool,
+    opponent_ready: bool,
+    /// Whether the opponent left, or the connection to them was lost, so there's no match to
+    /// start anymore.
+    over: bool,
+    /// The mutator the host turns on and off, out of the `ONLINE_MUTATORS`.
+    selected: usize,
+    chat: Vec<String>,
+    player_texts: Vec<Entity>,
+    mutator_texts: Vec<Entity>,
+    chat_texts: Vec<Entity>,
+    field: Option<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl PreMatch {
+    fn say(&mut self, line: String) {
+        self.chat.push(line);
+        if self.chat.len() > CHAT_LINES {
+            self.chat.remove(0);
+        }
+    }
+
+    /// Updates the ready status, the mutators and the chat.
+    fn refresh(&self, world: &mut World) {
+        let (opponent, host) = opponent(world);
+        let name = world.read_resource::<PlayerNames>().name(Side::Left).to_string();
+        let labels = ONLINE_MUTATORS.iter().map(|mutator| {
+            let on = if self.mutators.is_on(*mutator) { "x" } else { " " };
+            format!("[{}] {}", on, mutator.name().replace('_', " "))
+        });
+        // Only the host picks the mutators, the guest sees them without a selection.
+        let selected = if host { Some(self.selected) } else { None };
+        refresh_rows(world, &self.mutator_texts, labels, selected);
+        let theme = world.read_resource::<Theme>();
+        let mut ui_text = world.write_storage::<UiText>();
+
+        let players = [(name, self.ready), (opponent, self.opponent_ready)];
+        for ((name, ready), entity) in players.iter().zip(&self.player_texts) {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                let status = if *ready { "ready" } else { "not ready" };
+                text.text = format!("{}: {}", name, status);
+                text.color = theme.item_color(*ready);
+            }
+        }
+        for (index, entity) in self.chat_texts.iter().enumerate() {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                text.text = self.chat.get(index).cloned().unwrap_or_default();
+            }
+        }
+    }
+
+    /// Starts the match with the mutators picked, on both ends, by the rules the lobby gave it.
+    fn start(&self, world: &World) -> SimpleTrans {
+        let rules = world
+            .read_resource::<Online>()
+            .peer
+            .as_ref()
+            .map(|peer| peer.rules)
+            .unwrap_or_default();
+        let mutators = self.mutators.clone();
+        Trans::Switch(Box::new(Pong::online().with_rules(rules).with_mutators(mutators)))
+    }
+}
+
+/// The opponent's name, and whether the local player hosts the match.
+fn opponent(world: &World) -> (String, bool) {
+    let online = world.read_resource::<Online>();
+    online
+        .peer
+        .as_ref()
+        .map(|peer| (peer.name.clone(), peer.host))
+        .unwrap_or_default()
+}
+
+impl SimpleState for PreMatch {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+
+        let (opponent, host) = opponent(world);
+        let hint = if host {
+            "Enter to chat, F1 to get ready, F2 to change a mutator, F3 to start"
+        } else {
+            "Enter to chat, F1 to get ready, the host starts the match"
+        };
+        let lines = [
+            ("pre_match_title", format!("Playing {}", opponent), -30., 30.),
+            ("pre_match_hint", hint.to_string(), -470., 13.),
+        ];
+        for (id, line, y, font_size) in lines.iter() {
+            let text = world
+                .create_entity()
+                .with(UiTransform::new(
+                    id.to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                    0., *y, 1., 480., 30.,
+                ))
+                .with(theme.text(line.clone(), *font_size))
+                .build();
+            self.texts.push(text);
+        }
+
+        for index in 0..2 {
+            let transform = UiTransform::new(
+                format!("pre_match_player_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                -125., -80. - 22. * index as f32, 1., 240., 22.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 18.))
+                .build();
+            self.player_texts.push(text);
+        }
+        for index in 0..ONLINE_MUTATORS.len() {
+            let transform = UiTransform::new(
+                format!("pre_match_mutator_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                125., -80. - 22. * index as f32, 1., 240., 22.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 18.))
+                .build();
+            self.mutator_texts.push(text);
+        }
+        for index in 0..CHAT_LINES {
+            let transform = UiTransform::new(
+                format!("pre_match_chat_{}", index), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -230. - 20. * index as f32, 1., 460., 20.,
+            );
+            let mut line = theme.text(String::new(), 15.);
+            line.align = Anchor::MiddleLeft;
+            let text = world.create_entity().with(transform).with(line).build();
+            self.chat_texts.push(text);
+        }
+
+        let mut field = UiText::new(theme.font.clone(), String::new(), theme.input_color, 15.);
+        field.align = Anchor::MiddleLeft;
+        let field = world
+            .create_entity()
+            .with(UiTransform::new(
+                "pre_match_field".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -400., 1., 460., 25.,
+            ))
+            .with(field)
+            .with(TextEditing::new(
+                MAX_CHAT_LENGTH,
+                theme.input_selected_color,
+                theme.input_color,
+                false,
+            ))
+            .with(Selectable::<()>::new(0))
+            .with(Selected)
+            .build();
+        self.field = Some(field);
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let entities: Vec<Entity> = self
+            .texts
+            .drain(..)
+            .chain(self.player_texts.drain(..))
+            .chain(self.mutator_texts.drain(..))
+            .chain(self.chat_texts.drain(..))
+            .chain(self.field.take())
+            .collect();
+        data.world
+            .delete_entities(&entities)
+            .expect("pre-match text should be alive");
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let (opponent, host) = opponent(data.world);
+        let mut changed = false;
+        // One at a time, so the opponent's first inputs are left for the match once it starts.
+        loop {
+            let message = data.world.read_resource::<Online>().next();
+            let message = match message {
+                Some(message) => message,
+                None => break,
+            };
+            changed = true;
+            match message {
+                Ok(FromLobby::Relay(PeerMessage::Chat(line))) => {
+                    let line: String = line.chars().take(MAX_CHAT_LENGTH).collect();
+                    self.say(format!("{}: {}", opponent, line));
+                }
+                Ok(FromLobby::Relay(PeerMessage::Ready(ready))) => self.opponent_ready = ready,
+                Ok(FromLobby::Relay(PeerMessage::Mutators(mutators))) if !host => {
+                    self.mutators = mutators;
+                }
+                Ok(FromLobby::Relay(PeerMessage::Start(mutators))) if !host => {
+                    self.mutators = mutators;
+                    return self.start(data.world);
+                }
+                Ok(FromLobby::OpponentLeft) | Ok(FromLobby::HostMigrated) => {
+                    self.over = true;
+                    self.say(format!("{} left, Escape to go back", opponent));
+                }
+                Ok(FromLobby::OpponentDropped) => {
+                    self.say(format!("{} lost their connection, waiting for them", opponent));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.over = true;
+                    self.say(format!("Lost the connection to the lobby: {}", err));
+                }
+            }
+        }
+        if changed {
+            self.refresh(data.world);
+        }
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let (_, host) = opponent(data.world);
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+            // Once the opponent is gone only leaving is left to do.
+            let message = if self.over {
+                None
+            } else if is_key_down(event, READY_KEY) {
+                self.ready = !self.ready;
+                Some(PeerMessage::Ready(self.ready))
+            } else if is_key_down(event, MUTATOR_KEY) && host {
+                self.mutators.toggle(ONLINE_MUTATORS[self.selected]);
+                Some(PeerMessage::Mutators(self.mutators.clone()))
+            } else if is_key_down(event, START_KEY) && host && self.ready && self.opponent_ready {
+                let start = PeerMessage::Start(self.mutators.clone());
+                data.world.read_resource::<Online>().send(ToLobby::Relay(start));
+                return self.start(data.world);
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                data.world.read_resource::<Online>().send(ToLobby::Relay(message));
+                self.refresh(data.world);
+                return Trans::None;
+            }
+        }
+
+        let mutators = ONLINE_MUTATORS.len();
+        match menu_input(&event) {
+            Some(MenuInput::Back) => {
+                data.world.write_resource::<Online>().disconnect();
+                return Trans::Pop;
+            }
+            Some(MenuInput::Up) if host => {
+                self.selected = (self.selected + mutators - 1) % mutators;
+            }
+            Some(MenuInput::Down) if host => self.selected = (self.selected + 1) % mutators,
+            Some(MenuInput::Select) if !self.over => {
+                let line = {
+                    let mut ui_text = data.world.write_storage::<UiText>();
+                    match self.field.and_then(|field| ui_text.get_mut(field)) {
+                        Some(text) => std::mem::take(&mut text.text),
+                        None => String::new(),
+                    }
+                };
+                let line = line.trim().to_string();
+                if line.is_empty() {
+                    return Trans::None;
+                }
+                let name = data.world.read_resource::<PlayerNames>().name(Side::Left).to_string();
+                self.say(format!("{}: {}", name, line));
+                let chat = PeerMessage::Chat(line);
+                data.world.read_resource::<Online>().send(ToLobby::Relay(chat));
+            }
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,43 @@
+use crate::net::Online;
+use crate::pong::{GameMode, RunningState, ScoreBoard};
+
+/// What the player is up to, "In menu" or "Playing 2P match, 7–5", for their Discord and Steam
+/// friends to see. `in_match` is whether a match is on the screen, `against_ai` whether the
+/// computer plays in it.
+pub fn describe_activity(
+    in_match: bool,
+    running: RunningState,
+    mode: GameMode,
+    score: &ScoreBoard,
+    online: &Online,
+    against_ai: bool,
+) -> String {
+    if !in_match {
+        return "In menu".to_string();
+    }
+    let mut line = describe_match(mode, score, online, against_ai);
+    if running == RunningState::Paused {
+        line += ", paused";
+    }
+    line
+}
+
+/// What is being played and the score, e.g. "Playing 2P match, 7–5".
+fn describe_match(mode: GameMode, score: &ScoreBoard, online: &Online, against_ai: bool) -> String {
+    let activity = match (mode, &online.peer) {
+        _ if online.watching.is_some() => "Watching an online match".to_string(),
+        (_, Some(peer)) => format!("Playing online against {}", peer.name),
+        (GameMode::Survival, _) => return "Playing survival".to_string(),
+        (GameMode::Practice, _) => return "Practicing".to_string(),
+        (GameMode::FourPlayer, _) => "Playing 4P match".to_string(),
+        (GameMode::Doubles, _) => "Playing 2v2 match".to_string(),
+        (GameMode::Classic, _) if against_ai => "Playing 1P match".to_string(),
+        (GameMode::Classic, _) => "Playing 2P match".to_string(),
+    };
+    let scores: Vec<String> = mode
+        .sides()
+        .iter()
+        .map(|side| score.score(*side).to_string())
+        .collect();
+    format!("{}, {}", activity, scores.join("–"))
+}
@@ -0,0 +1,95 @@
+use amethyst::config::Config;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{settings_dir, write_to_settings_dir, PROFILES_FILE};
+
+/// Everything a player has played under their name, kept over every match they finished.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub points_scored: u32,
+    pub points_conceded: u32,
+    /// The longest rally of any of their matches, in paddle hits.
+    pub longest_rally: u32,
+    /// Seconds played with a ball in the arena.
+    pub playtime: f32,
+}
+
+impl Profile {
+    /// The playtime as hours and minutes, or minutes and seconds under an hour.
+    pub fn format_playtime(&self) -> String {
+        let seconds = self.playtime as u32;
+        if seconds >= 3600 {
+            format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+        } else {
+            format!("{}m {:02}s", seconds / 60, seconds % 60)
+        }
+    }
+}
+
+/// How a match went for one of its players, added to their profile once it's over.
+pub struct MatchRecord {
+    pub won: bool,
+    pub points_scored: u32,
+    pub points_conceded: u32,
+    pub longest_rally: u32,
+    pub playtime: f32,
+}
+
+/// The profile of every player who's finished a match on this machine under a name, kept in
+/// `profiles.ron` next to the settings. A name typed in for the first time starts a new one
+/// once its match is over, players who don't give a name don't get one.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    /// The profiles saved the last time a match was finished, none the first time.
+    pub fn load_saved() -> Profiles {
+        let path = settings_dir().join(PROFILES_FILE);
+        if path.exists() {
+            Profiles::load(path)
+        } else {
+            Profiles::default()
+        }
+    }
+
+    /// Adds the matches to the profiles of the players named, starting one for those without,
+    /// and saves the profiles.
+    pub fn record(&mut self, records: Vec<(String, MatchRecord)>) {
+        if records.is_empty() {
+            return;
+        }
+        for (name, record) in records {
+            let index = match self.profiles.iter().position(|profile| profile.name == name) {
+                Some(index) => index,
+                None => {
+                    self.profiles.push(Profile {
+                        name,
+                        ..Profile::default()
+                    });
+                    self.profiles.len() - 1
+                }
+            };
+            let profile = &mut self.profiles[index];
+            if record.won {
+                profile.wins += 1;
+            } else {
+                profile.losses += 1;
+            }
+            profile.points_scored += record.points_scored;
+            profile.points_conceded += record.points_conceded;
+            profile.longest_rally = profile.longest_rally.max(record.longest_rally);
+            profile.playtime += record.playtime;
+        }
+
+        if let Err(err) = write_to_settings_dir(self, PROFILES_FILE) {
+            log::error!("Failed to save the profiles: {}", err);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::{
+    config::{PlayerNames, UserSettings},
+    confirm::ConfirmQuit,
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::Side,
+    profiles::Profiles,
+    theme::Theme,
+};
+
+/// How many profiles fit in the list, it scrolls to show the rest.
+const VISIBLE_ROWS: usize = 12;
+/// How many lines the picked profile's statistics take up.
+const STAT_LINES: usize = 5;
+
+/// Lists every player's profile, picked on the main menu, with the lifetime statistics of the
+/// one picked next to the list.
+///
+/// Up and down pick a profile, Enter plays as it on the left side from the next match on and
+/// Escape goes back to the main menu.
+#[derive(Default)]
+pub struct ProfilesMenu {
+    selected: usize,
+    scroll: usize,
+    row_texts: Vec<Entity>,
+    stat_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl ProfilesMenu {
+    /// Updates the rows shown, marking the picked profile, and its statistics.
+    fn refresh(&self, world: &mut World) {
+        let profiles = world.read_resource::<Profiles>();
+        let playing = world.read_resource::<PlayerNames>().name(Side::Left).to_string();
+        let labels = profiles.profiles.iter().skip(self.scroll).map(|profile| {
+            let current = if profile.name == playing { " *" } else { "" };
+            format!("{}{}", profile.name, current)
+        });
+        let selected = self.selected.checked_sub(self.scroll);
+        refresh_rows(world, &self.row_texts, labels, selected);
+        let mut ui_text = world.write_storage::<UiText>();
+
+        let lines = match profiles.profiles.get(self.selected) {
+            Some(profile) => vec![
+                format!("Wins {}  Losses {}", profile.wins, profile.losses),
+                format!("Points scored {}", profile.points_scored),
+                format!("Points conceded {}", profile.points_conceded),
+                format!("Longest rally {} hits", profile.longest_rally),
+                format!("Played {}", profile.format_playtime()),
+            ],
+            None => vec![
+                "Finish a match under a name".to_string(),
+                "to start a profile".to_string(),
+            ],
+        };
+        for (line, entity) in self.stat_texts.iter().enumerate() {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                text.text = lines.get(line).cloned().unwrap_or_default();
+            }
+        }
+    }
+
+    fn rows(world: &World) -> usize {
+        world.read_resource::<Profiles>().profiles.len()
+    }
+}
+
+impl SimpleState for ProfilesMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+
+        let lines = [
+            ("profiles_title", Anchor::TopMiddle, -20., "Profiles", 30.),
+            (
+                "profiles_hint",
+                Anchor::BottomMiddle,
+                30.,
+                "Enter to play as the profile, Escape to go back",
+                15.,
+            ),
+        ];
+        for (id, anchor, y, line, font_size) in lines.iter() {
+            let text = world
+                .create_entity()
+                .with(UiTransform::new(
+                    id.to_string(), anchor.clone(), anchor.clone(),
+                    0., *y, 1., 500., 40.,
+                ))
+                .with(theme.text(line.to_string(), *font_size))
+                .build();
+            self.texts.push(text);
+        }
+
+        for row in 0..VISIBLE_ROWS {
+            let transform = UiTransform::new(
+                format!("profiles_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                -120., -70. - 22. * row as f32, 1., 200., 22.,
+            );
+            let mut line = theme.text(String::new(), 17.);
+            line.align = Anchor::MiddleLeft;
+            let text = world.create_entity().with(transform).with(line).build();
+            self.row_texts.push(text);
+        }
+        for row in 0..STAT_LINES {
+            let transform = UiTransform::new(
+                format!("profiles_stat_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                110., -70. - 22. * row as f32, 1., 220., 22.,
+            );
+            let mut line = theme.text(String::new(), 15.);
+            line.align = Anchor::MiddleLeft;
+            let text = world.create_entity().with(transform).with(line).build();
+            self.stat_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let entities: Vec<Entity> = self
+            .texts
+            .drain(..)
+            .chain(self.row_texts.drain(..))
+            .chain(self.stat_texts.drain(..))
+            .collect();
+        data.world
+            .delete_entities(&entities)
+            .expect("profile text should be alive");
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+
+        let rows = Self::rows(data.world);
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(MenuInput::Down) => {
+                self.selected = (self.selected + 1).min(rows.saturating_sub(1));
+            }
+            Some(MenuInput::Select) => {
+                let name = {
+                    let profiles = data.world.read_resource::<Profiles>();
+                    profiles.profiles.get(self.selected).map(|profile| profile.name.clone())
+                };
+                if let Some(name) = name {
+                    *data.world.write_resource::<PlayerNames>().name_mut(Side::Left) = name;
+                    UserSettings::of(data.world).save();
+                }
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        // Keep the picked profile in the list.
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,117 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::{
+    confirm::ConfirmQuit,
+    net::{Online, RECONNECT_TIME},
+    pong::RunningState,
+    theme::Theme,
+};
+
+/// Pushed on top of the `Pong` state while an online match waits for a player who lost their
+/// connection to rejoin it, the local player or their opponent. It pauses the match like the
+/// `Paused` state, and goes back to it once they're back.
+///
+/// The local player gives up on rejoining after `RECONNECT_TIME`, the lobby tells the opponent
+/// when they do.
+#[derive(Default)]
+pub struct Reconnect {
+    /// Seconds waited so far.
+    waited: f32,
+    countdown: Option<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl SimpleState for Reconnect {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        *world.write_resource::<RunningState>() = RunningState::Paused;
+        let theme = world.read_resource::<Theme>().clone();
+
+        let message = {
+            let online = world.read_resource::<Online>();
+            match &online.peer {
+                Some(peer) if !online.reconnecting => {
+                    format!("{} lost their connection", peer.name)
+                }
+                _ => "Lost the connection, rejoining the match".to_string(),
+            }
+        };
+        let backdrop = world
+            .create_entity()
+            .with(UiTransform::new(
+                "reconnect_backdrop".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 0., 0.5, 460., 110.,
+            ))
+            .with(theme.backdrop.clone())
+            .build();
+        let text = world
+            .create_entity()
+            .with(UiTransform::new(
+                "reconnect_message".to_string(), Anchor::Middle, Anchor::Middle,
+                0., 20., 1., 450., 30.,
+            ))
+            .with(theme.text(message, 20.))
+            .build();
+        let countdown = world
+            .create_entity()
+            .with(UiTransform::new(
+                "reconnect_countdown".to_string(), Anchor::Middle, Anchor::Middle,
+                0., -20., 1., 450., 30.,
+            ))
+            .with(theme.text(String::new(), 16.))
+            .build();
+        self.texts = vec![backdrop, text, countdown];
+        self.countdown = Some(countdown);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        *data.world.write_resource::<RunningState>() = RunningState::Running;
+        data.world
+            .delete_entities(&self.texts)
+            .expect("reconnect text should be alive");
+        self.texts.clear();
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.waited += data.world.read_resource::<Time>().delta_seconds();
+        {
+            let mut online = data.world.write_resource::<Online>();
+            if !online.reconnecting && !online.opponent_dropped {
+                return Trans::Pop;
+            }
+            // The match stands still from here, like when the opponent leaves it.
+            if online.reconnecting && self.waited >= RECONNECT_TIME {
+                online.lobby = None;
+                online.reconnecting = false;
+                online.lost = Some("Couldn't rejoin the match".to_string());
+                return Trans::Pop;
+            }
+        }
+
+        let left = (RECONNECT_TIME - self.waited).max(0.0).ceil();
+        let mut ui_text = data.world.write_storage::<UiText>();
+        if let Some(text) = self.countdown.and_then(|countdown| ui_text.get_mut(countdown)) {
+            text.text = format!("Waiting {} more seconds", left);
+        }
+        Trans::None
+    }
+
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+        Trans::None
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter},
+    mem,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::settings_dir;
+use crate::high_scores::today;
+use crate::net::into_io_error;
+use crate::pong::{MatchRules, Mutators};
+use crate::rollback::{MatchEvent, MatchState, PlayerInput};
+
+/// The directory in the `settings_dir()` the replays are kept in.
+pub const REPLAYS_DIR: &str = "replays";
+/// What replay files end in.
+const REPLAY_EXTENSION: &str = "replay";
+/// How many replays are kept, the oldest ones are deleted after that.
+pub const MAX_REPLAYS: usize = 20;
+
+/// Both players' inputs for every frame of an online match, as far as it's been played, kept
+/// so the match can be saved as a `Replay` once it's over. Frames played again after the match
+/// was rejoined replace the ones recorded before.
+#[derive(Default)]
+pub struct Recording {
+    inputs: Vec<[PlayerInput; 2]>,
+    /// Set when a frame's inputs never came in, the match can't be played back then.
+    broken: bool,
+}
+
+impl Recording {
+    /// Records the left and right players' inputs for the frame, once they're both known.
+    pub fn record(&mut self, frame: u32, inputs: [PlayerInput; 2]) {
+        let frame = frame as usize;
+        if frame > self.inputs.len() {
+            self.broken = true;
+            return;
+        }
+        self.inputs.truncate(frame);
+        self.inputs.push(inputs);
+    }
+
+    /// The recorded match as a replay, `None` if some of it is missing.
+    pub fn replay(
+        &self,
+        left: String,
+        right: String,
+        rules: MatchRules,
+        mutators: Mutators,
+    ) -> Option<Replay> {
+        if self.broken || self.inputs.is_empty() {
+            return None;
+        }
+        let mut frames: Vec<(u32, [PlayerInput; 2])> = Vec::new();
+        for inputs in &self.inputs {
+            match frames.last_mut() {
+                Some((count, last)) if last == inputs => *count += 1,
+                _ => frames.push((1, *inputs)),
+            }
+        }
+        Some(Replay {
+            left,
+            right,
+            date: today(),
+            win_score: rules.win_score,
+            best_of: rules.best_of,
+            mutators,
+            frames,
+        })
+    }
+}
+
+/// An online match saved to be watched again, in the `replays` directory next to the settings.
+/// The `MatchState` plays a match the same from the same inputs, and nothing in it is left to
+/// chance, so the inputs are all there is to keep. They're kept as runs of frames both
+/// players' inputs stayed the same for, which most frames are.
+///
+/// Replays play back the same in the build of the game they were recorded with.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Replay {
+    /// The names of the left and right players.
+    pub left: String,
+    pub right: String,
+    /// The day the match was played, as year-month-day.
+    pub date: String,
+    pub win_score: i32,
+    pub best_of: u32,
+    pub mutators: Mutators,
+    /// How many frames in a row were played with the left and right players' inputs.
+    frames: Vec<(u32, [PlayerInput; 2])>,
+}
+
+impl Replay {
+    /// The rules the match was played by.
+    pub fn rules(&self) -> MatchRules {
+        MatchRules {
+            win_score: self.win_score,
+            best_of: self.best_of,
+            time_limit: None,
+        }
+    }
+
+    /// Writes the replay to a new file in the replays directory, and deletes the oldest ones
+    /// past `MAX_REPLAYS`.
+    pub fn save(&self) -> io::Result<()> {
+        let dir = replays_dir();
+        fs::create_dir_all(&dir)?;
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("{}.{}", seconds, REPLAY_EXTENSION));
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self).map_err(|err| into_io_error(*err))?;
+
+        for old in saved_replays().iter().skip(MAX_REPLAYS) {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let reader = BufReader::new(File::open(path)?);
+        bincode::deserialize_from(reader).map_err(|err| into_io_error(*err))
+    }
+}
+
+/// How fast a replay can be played back, from a quarter of the speed it was played at to four
+/// times as fast.
+pub const PLAYBACK_SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+/// Frames between the snapshots of the match kept to jump back to.
+const SNAPSHOT_INTERVAL: u32 = 60;
+/// Jumping back within this many frames of the start of a point goes to the point before it.
+const REWIND_GRACE: u32 = 60;
+
+/// Where a `Playback` is, a snapshot of it can be gone back to.
+#[derive(Clone)]
+struct Position {
+    state: MatchState,
+    /// The frame to be played next.
+    frame: u32,
+    /// The run of frames being played, and how many of its frames are left.
+    run: usize,
+    left_in_run: u32,
+}
+
+/// Plays a `Replay` back a frame at a time, the way it was played. How fast it plays is up to
+/// the player watching, not the clock: the `ReplaySystem` plays as many frames as the time
+/// passed makes at the `speed`, none while it's paused, and single frames when stepped. It can
+/// also jump to the start of the points of the game being played.
+pub struct Playback {
+    replay: Replay,
+    position: Position,
+    /// Where the match was at every `SNAPSHOT_INTERVAL` frames played, to jump back to.
+    snapshots: Vec<Position>,
+    /// The frames every point played so far started on, and the one the game started on.
+    point_starts: Vec<u32>,
+    game_start: u32,
+    /// Which of the `PLAYBACK_SPEEDS` it plays at.
+    speed: usize,
+    pub paused: bool,
+    /// Frames to play next however it's paused, from stepping through it.
+    pub steps: u32,
+    /// What happened in the last frame jumped to, for the `ReplaySystem` to show.
+    events: Vec<MatchEvent>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        let state = MatchState::new(replay.win_score, replay.mutators.clone());
+        let left_in_run = replay.frames.first().map_or(0, |(count, _)| *count);
+        let position = Position {
+            state,
+            frame: 0,
+            run: 0,
+            left_in_run,
+        };
+        Playback {
+            replay,
+            snapshots: vec![position.clone()],
+            position,
+            point_starts: vec![0],
+            game_start: 0,
+            speed: 2,
+            paused: false,
+            steps: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn state(&self) -> &MatchState {
+        &self.position.state
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.position.frame
+    }
+
+    /// How many times as fast as it was played it plays back.
+    pub fn speed(&self) -> f32 {
+        PLAYBACK_SPEEDS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(PLAYBACK_SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    /// Whether every frame of the match has been played.
+    pub fn is_over(&self) -> bool {
+        self.position.run >= self.replay.frames.len()
+    }
+
+    /// Plays the next frame, telling what happened in it. Does nothing once it's over.
+    pub fn step(&mut self, events: &mut Vec<MatchEvent>) {
+        let position = &mut self.position;
+        let inputs = match self.replay.frames.get(position.run) {
+            Some((_, inputs)) => *inputs,
+            None => return,
+        };
+        let first = events.len();
+        position.state.step(inputs, events);
+        position.frame += 1;
+        position.left_in_run -= 1;
+        if position.left_in_run == 0 {
+            position.run += 1;
+            position.left_in_run = self
+                .replay
+                .frames
+                .get(position.run)
+                .map_or(0, |(count, _)| *count);
+        }
+
+        let frame = position.frame;
+        let snapshot = frame / SNAPSHOT_INTERVAL;
+        if frame.is_multiple_of(SNAPSHOT_INTERVAL) && snapshot as usize >= self.snapshots.len() {
+            self.snapshots.push(position.clone());
+        }
+        for event in &events[first..] {
+            match event {
+                MatchEvent::Goal { .. } if self.point_starts.last() < Some(&frame) => {
+                    self.point_starts.push(frame);
+                }
+                MatchEvent::GameWon { .. } => self.game_start = frame,
+                _ => {}
+            }
+        }
+    }
+
+    /// Plays on up to the start of the next point, or the end of the match. What happened in
+    /// the frame the point being played was won in is shown, the frames before it are skipped.
+    pub fn next_point(&mut self) {
+        let mut events = Vec::new();
+        while !self.is_over() {
+            events.clear();
+            self.step(&mut events);
+            if events.iter().any(|event| matches!(event, MatchEvent::Goal { .. })) {
+                break;
+            }
+        }
+        self.events = events;
+    }
+
+    /// What happened in the last frame jumped to since the last call.
+    pub fn take_events(&mut self) -> Vec<MatchEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Goes back to the start of the point being played, or of the one before it right after
+    /// a point started. It doesn't go back past the start of the game.
+    pub fn previous_point(&mut self) {
+        let now = self.position.frame;
+        let target = self
+            .point_starts
+            .iter()
+            .rev()
+            .find(|start| **start + REWIND_GRACE <= now)
+            .map_or(0, |start| *start)
+            .max(self.game_start);
+        self.seek(target);
+    }
+
+    /// Goes back to `frame`, played from the last snapshot before it.
+    fn seek(&mut self, frame: u32) {
+        let snapshot = (frame / SNAPSHOT_INTERVAL) as usize;
+        if let Some(position) = self.snapshots.get(snapshot.min(self.snapshots.len() - 1)) {
+            self.position = position.clone();
+        }
+        let mut events = Vec::new();
+        while self.position.frame < frame && !self.is_over() {
+            self.step(&mut events);
+        }
+    }
+}
+
+fn replays_dir() -> PathBuf {
+    settings_dir().join(REPLAYS_DIR)
+}
+
+/// The files of the replays saved, the newest first.
+pub fn saved_replays() -> Vec<PathBuf> {
+    let mut replays: Vec<PathBuf> = fs::read_dir(replays_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+                .collect()
+        })
+        .unwrap_or_default();
+    // They're named after the second they were saved.
+    replays.sort_by_key(|path| {
+        let stem = path.file_stem().and_then(|stem| stem.to_str());
+        std::cmp::Reverse(stem.and_then(|stem| stem.parse::<u64>().ok()).unwrap_or(0))
+    });
+    replays
+}
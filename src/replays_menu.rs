@@ -0,0 +1,124 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::{
+    confirm::ConfirmQuit,
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::Pong,
+    replay::{saved_replays, Replay, MAX_REPLAYS},
+    theme::Theme,
+};
+
+/// Lists the online matches saved as replays, the newest first, picked on the main menu.
+///
+/// Up and down pick a replay, Enter plays it back and Escape goes back to the main menu.
+#[derive(Default)]
+pub struct ReplaysMenu {
+    /// Every replay that could be read.
+    replays: Vec<Replay>,
+    selected: usize,
+    row_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl ReplaysMenu {
+    /// Updates the text of every row, marking the selected one.
+    fn refresh(&self, world: &mut World) {
+        let labels = self
+            .replays
+            .iter()
+            .map(|replay| format!("{}  {} vs {}", replay.date, replay.left, replay.right));
+        refresh_rows(world, &self.row_texts, labels, Some(self.selected));
+    }
+}
+
+impl SimpleState for ReplaysMenu {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let theme = world.read_resource::<Theme>().clone();
+        self.replays = saved_replays()
+            .iter()
+            .take(MAX_REPLAYS)
+            .filter_map(|path| match Replay::load(path) {
+                Ok(replay) => Some(replay),
+                Err(err) => {
+                    log::warn!("Failed to read the replay {}: {}", path.display(), err);
+                    None
+                }
+            })
+            .collect();
+        self.selected = 0;
+
+        let hint = if self.replays.is_empty() {
+            "Online matches are saved here once they're over"
+        } else {
+            "Enter to watch, Escape to go back"
+        };
+        let lines = [
+            ("replays_title", Anchor::TopMiddle, -20., "Replays", 30.),
+            ("replays_hint", Anchor::BottomMiddle, 30., hint, 15.),
+        ];
+        for (id, anchor, y, line, font_size) in lines.iter() {
+            let text = world
+                .create_entity()
+                .with(UiTransform::new(
+                    id.to_string(), anchor.clone(), anchor.clone(),
+                    0., *y, 1., 500., 40.,
+                ))
+                .with(theme.text(line.to_string(), *font_size))
+                .build();
+            self.texts.push(text);
+        }
+
+        for row in 0..self.replays.len() {
+            let transform = UiTransform::new(
+                format!("replays_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -65. - 18. * row as f32, 1., 420., 18.,
+            );
+            let mut line = theme.text(String::new(), 15.);
+            line.align = Anchor::MiddleLeft;
+            let text = world.create_entity().with(transform).with(line).build();
+            self.row_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let entities: Vec<Entity> = self.texts.drain(..).chain(self.row_texts.drain(..)).collect();
+        data.world
+            .delete_entities(&entities)
+            .expect("replay text should be alive");
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Push(Box::new(ConfirmQuit::default()));
+            }
+        }
+
+        let rows = self.replays.len().max(1);
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.selected = (self.selected + rows - 1) % rows,
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % rows,
+            Some(MenuInput::Select) => {
+                return match self.replays.get(self.selected) {
+                    Some(replay) => Trans::Switch(Box::new(Pong::replay(replay.clone()))),
+                    None => Trans::None,
+                };
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,240 @@
+use amethyst::{
+    ecs::prelude::Entity,
+    input::is_close_requested,
+    prelude::*,
+    ui::{Anchor, UiTransform},
+};
+
+use crate::{
+    menu::{menu_input, refresh_rows, MenuInput},
+    pong::{MatchStats, Pong, Rally},
+    theme::Theme,
+};
+
+/// Shown once a match has been won with a summary of the match, offers a rematch or going
+/// back to the main menu.
+pub struct Results {
+    /// Who won and the final score in games.
+    message: String,
+    stats: MatchStats,
+    rally: Rally,
+    /// The match to play on a rematch, set up like the one that just ended.
+    rematch: Option<Pong>,
+    texts: Vec<Entity>,
+    choices: Choices,
+}
+
+impl Results {
+    pub fn new(message: String, stats: MatchStats, rally: Rally, rematch: Pong) -> Results {
+        Results {
+            message,
+            stats,
+            rally,
+            rematch: Some(rematch),
+            texts: Vec::new(),
+            choices: Choices::new("Rematch"),
+        }
+    }
+}
+
+impl SimpleState for Results {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let seconds = self.stats.duration as u32;
+        let lines = [
+            (self.message.clone(), 70., 30.),
+            (format!("Longest rally {} hits", self.rally.best), 20., 20.),
+            (format!("Match time {}:{:02}", seconds / 60, seconds % 60), -10., 20.),
+        ];
+        self.texts = show_lines(data.world, &lines);
+        self.choices.show(data.world, -60.);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("results text should be alive");
+        self.texts.clear();
+        self.choices.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+        match self.choices.pick(data.world, &event) {
+            Some(Choice::Again) => match self.rematch.take() {
+                Some(rematch) => Trans::Switch(Box::new(rematch)),
+                None => Trans::None,
+            },
+            // The match was pushed on top of the main menu.
+            Some(Choice::Menu) => Trans::Pop,
+            None => Trans::None,
+        }
+    }
+}
+
+/// Shown once a survival run is over, offers another run or going back to the main menu.
+pub struct SurvivalResults {
+    /// How long the run lasted, and the longest one this session.
+    time: f32,
+    best: f32,
+    retry: Option<Pong>,
+    texts: Vec<Entity>,
+    choices: Choices,
+}
+
+impl SurvivalResults {
+    pub fn new(time: f32, best: f32, retry: Pong) -> SurvivalResults {
+        SurvivalResults {
+            time,
+            best,
+            retry: Some(retry),
+            texts: Vec::new(),
+            choices: Choices::new("Try again"),
+        }
+    }
+}
+
+impl SimpleState for SurvivalResults {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let lines = [
+            (format!("You survived {:.1} seconds", self.time), 40., 30.),
+            (format!("Best {:.1} seconds", self.best), 0., 20.),
+        ];
+        self.texts = show_lines(data.world, &lines);
+        self.choices.show(data.world, -40.);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        data.world
+            .delete_entities(&self.texts)
+            .expect("results text should be alive");
+        self.texts.clear();
+        self.choices.hide(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+        match self.choices.pick(data.world, &event) {
+            Some(Choice::Again) => match self.retry.take() {
+                Some(retry) => Trans::Switch(Box::new(retry)),
+                None => Trans::None,
+            },
+            Some(Choice::Menu) => Trans::Pop,
+            None => Trans::None,
+        }
+    }
+}
+
+/// What the results screens offer, playing again or going back to the main menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Again,
+    Menu,
+}
+
+impl Choice {
+    const ALL: [Choice; 2] = [Choice::Again, Choice::Menu];
+}
+
+/// The entries under the results, marking the one that's selected. Back always goes to the
+/// main menu.
+struct Choices {
+    /// What playing again is called on the screen.
+    again: &'static str,
+    selected: usize,
+    texts: Vec<Entity>,
+}
+
+impl Choices {
+    fn new(again: &'static str) -> Choices {
+        Choices {
+            again,
+            selected: 0,
+            texts: Vec::new(),
+        }
+    }
+
+    /// Puts the entries on the screen, from `y` above the middle down.
+    fn show(&mut self, world: &mut World, y: f32) {
+        let theme = world.read_resource::<Theme>().clone();
+        for index in 0..Choice::ALL.len() {
+            let transform = UiTransform::new(
+                format!("results_choice_{}", index), Anchor::Middle, Anchor::Middle,
+                0., y - 30. * index as f32, 1., 250., 30.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 22.))
+                .build();
+            self.texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.texts)
+            .expect("results text should be alive");
+        self.texts.clear();
+    }
+
+    fn refresh(&self, world: &mut World) {
+        let labels = Choice::ALL.iter().map(|choice| match choice {
+            Choice::Again => self.again,
+            Choice::Menu => "Main menu",
+        });
+        refresh_rows(world, &self.texts, labels, Some(self.selected));
+    }
+
+    /// Moves the selection for a menu input, and returns the choice picked by it if any.
+    fn pick(&mut self, world: &mut World, event: &StateEvent) -> Option<Choice> {
+        let count = Choice::ALL.len();
+        match menu_input(event)? {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Select => return Some(Choice::ALL[self.selected]),
+            MenuInput::Back => return Some(Choice::Menu),
+            _ => return None,
+        }
+        self.refresh(world);
+        None
+    }
+}
+
+/// Puts lines of text in the middle of the screen, given as the text, its height above the
+/// middle and its font size.
+fn show_lines(world: &mut World, lines: &[(String, f32, f32)]) -> Vec<Entity> {
+    let theme = world.read_resource::<Theme>().clone();
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, (line, y, font_size))| {
+            let transform = UiTransform::new(
+                format!("results_{}", index), Anchor::Middle, Anchor::Middle,
+                0., *y, 1., 500., 40.,
+            );
+            world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(line.clone(), *font_size))
+                .build()
+        })
+        .collect()
+}
@@ -0,0 +1,692 @@
+use std::{collections::VecDeque, mem};
+
+use amethyst::core::math::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::pong::{
+    Mutator, Mutators, Side, ARENA_HEIGHT, ARENA_WIDTH, BALL_RADIUS, BALL_VELOCITY_X,
+    BALL_VELOCITY_Y, DOUBLE_SPEED_MULTIPLIER, GRAVITY_ACCELERATION, GRAVITY_RESTITUTION,
+    PADDLE_HEIGHT, PADDLE_SPEED, PADDLE_WIDTH, SERVE_COUNTDOWN, TINY_PADDLE_MULTIPLIER,
+};
+use crate::replay::Recording;
+use crate::systems::{bounce::paddle_return, move_balls::spin_ball, serve::serve_velocity};
+
+/// How long a frame of an online match is, in seconds. Both players play it at this rate
+/// however fast they draw.
+pub const ROLLBACK_FRAME: f32 = 1.0 / 60.0;
+/// How many frames after it's read the local player's input is played, which gives it time to
+/// reach the opponent before they need it.
+pub const INPUT_DELAY: u32 = 2;
+/// How many frames the match gets ahead of the opponent's input at most, it waits for them
+/// after that.
+pub const MAX_ROLLBACK: usize = 8;
+/// Frames the ball waits in the middle of the arena before the first game starts, like the
+/// `Pong` state waits to put it there, and before the games after it, which start after the
+/// intermission.
+const FIRST_GAME_WAIT: u32 = 60;
+const NEXT_GAME_WAIT: u32 = 240;
+/// Most frames played ahead of an authoritative lobby, the oldest inputs it hasn't played are
+/// given up on after that.
+const MAX_PREDICTION: usize = 60;
+/// How much of the difference between the match predicted and the one the lobby played is
+/// still shown after a frame, so paddles and the ball slide into place rather than jump.
+const CORRECTION_KEPT: f32 = 0.8;
+/// Differences farther than this in arena units are shown right away, like the ball being put
+/// back in the middle after a goal.
+const MAX_CORRECTION: f32 = 20.0;
+/// The mutators online matches can be played with, the ones that only change how the ball and
+/// the paddles move or how they look.
+pub const ONLINE_MUTATORS: [Mutator; 6] = [
+    Mutator::BigBall,
+    Mutator::TinyPaddles,
+    Mutator::DoubleSpeed,
+    Mutator::NoWalls,
+    Mutator::Gravity,
+    Mutator::Blink,
+];
+
+/// What a player does in a frame of an online match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PlayerInput {
+    /// How far the paddle is pushed up, from -127 for all the way down to 127.
+    pub movement: i8,
+    pub serve: bool,
+}
+
+impl PlayerInput {
+    /// The input of a player moving their paddle `movement`, from -1.0 to 1.0.
+    pub fn new(movement: f64, serve: bool) -> PlayerInput {
+        PlayerInput {
+            movement: (movement.clamp(-1.0, 1.0) * 127.0).round() as i8,
+            serve,
+        }
+    }
+}
+
+/// What happened in a frame of an online match.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum MatchEvent {
+    /// The ball bounced at `position`, off the paddle on the side if it hit one.
+    Bounce {
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        paddle: Option<Side>,
+    },
+    /// The ball went out of play on the side at `speed`, the other side scored.
+    Goal {
+        conceded: Side,
+        speed: f32,
+    },
+    GameWon {
+        winner: Side,
+    },
+}
+
+/// Everything played in an online match, saved every frame so the match can be rolled back to
+/// any of them. It's played the same from the same inputs on both ends, as long as they run
+/// the same build of the game.
+///
+/// Online matches are classic matches with one ball and without dashes, power shots or
+/// power-ups, played to the rules' win score with any of the `ONLINE_MUTATORS`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MatchState {
+    /// Where the left and right paddles are along their edges, and how fast they moved in the
+    /// last frame in arena units per second.
+    paddles: [(f32, f32); 2],
+    pub ball_position: Vector2<f32>,
+    pub ball_velocity: Vector2<f32>,
+    pub ball_spin: f32,
+    /// The side holding the ball on their paddle for their serve.
+    serving: Option<Side>,
+    /// Frames the ball waits for, in the middle before a game or on the server's paddle before
+    /// they can serve.
+    wait: u32,
+    scores: [i32; 2],
+    /// Whether a game was just won, the scores are kept until the next game starts.
+    game_over: bool,
+    pub rally: u32,
+    win_score: i32,
+    mutators: Mutators,
+}
+
+impl MatchState {
+    pub fn new(win_score: i32, mutators: Mutators) -> MatchState {
+        let mut state = MatchState {
+            paddles: [(ARENA_HEIGHT / 2.0, 0.0); 2],
+            ball_position: Vector2::zeros(),
+            ball_velocity: Vector2::zeros(),
+            ball_spin: 0.0,
+            serving: None,
+            wait: 0,
+            scores: [0; 2],
+            game_over: false,
+            rally: 0,
+            win_score,
+            mutators,
+        };
+        state.start_game(FIRST_GAME_WAIT);
+        state
+    }
+
+    /// Where the paddle on the side is along its edge, and how fast it's moving.
+    pub fn paddle(&self, side: Side) -> (f32, f32) {
+        self.paddles[index(side)]
+    }
+
+    pub fn score(&self, side: Side) -> i32 {
+        self.scores[index(side)]
+    }
+
+    /// Whether the match is between games, from the winning point until the next game starts.
+    pub fn between_games(&self) -> bool {
+        self.game_over
+    }
+
+    /// What the AI pushes in place of a player who left the match for good, it follows the
+    /// ball and serves right away.
+    pub fn stand_in_input(&self, side: Side) -> PlayerInput {
+        let (position, _) = self.paddle(side);
+        let off = (self.ball_position.y - position) / (PADDLE_HEIGHT * 0.5);
+        PlayerInput::new(f64::from(off), true)
+    }
+
+    /// Seconds left until the player holding the ball can serve it.
+    pub fn serve_countdown(&self) -> f32 {
+        match self.serving {
+            Some(_) => self.wait as f32 * ROLLBACK_FRAME,
+            None => 0.0,
+        }
+    }
+
+    /// Puts the ball in the middle for `wait` frames, it goes off like the `Pong` state's does.
+    fn start_game(&mut self, wait: u32) {
+        self.ball_position = Vector2::new(ARENA_WIDTH / 2.0, ARENA_HEIGHT / 2.0);
+        self.ball_velocity = Vector2::new(BALL_VELOCITY_X, BALL_VELOCITY_Y);
+        self.ball_spin = 0.0;
+        self.serving = None;
+        self.wait = wait;
+        self.rally = 0;
+    }
+
+    /// Plays a frame with the inputs of the left and right players.
+    pub fn step(&mut self, inputs: [PlayerInput; 2], events: &mut Vec<MatchEvent>) {
+        let half_length = if self.mutators.is_on(Mutator::TinyPaddles) {
+            PADDLE_HEIGHT * TINY_PADDLE_MULTIPLIER * 0.5
+        } else {
+            PADDLE_HEIGHT * 0.5
+        };
+        let radius = self.mutators.ball_radius(BALL_RADIUS);
+        for ((position, velocity), input) in self.paddles.iter_mut().zip(&inputs) {
+            let moved = (*position + PADDLE_SPEED * f32::from(input.movement) / 127.0)
+                .clamp(half_length, ARENA_HEIGHT - half_length);
+            *velocity = (moved - *position) / ROLLBACK_FRAME;
+            *position = moved;
+        }
+
+        // The ball rests in front of the server's paddle until they serve, like the
+        // `ServeSystem` holds it.
+        if let Some(side) = self.serving {
+            let (position, velocity) = self.paddle(side);
+            self.ball_position = Vector2::new(paddle_x(side), position)
+                + side.normal() * (PADDLE_WIDTH * 0.5 + radius + 1.0);
+            if self.wait > 0 {
+                self.wait -= 1;
+            } else if inputs[index(side)].serve {
+                let speed = self.ball_velocity.norm();
+                self.ball_velocity = serve_velocity(side, velocity, speed);
+                self.serving = None;
+            }
+            return;
+        }
+        if self.wait > 0 {
+            self.wait -= 1;
+            if self.wait == 0 && self.game_over {
+                self.scores = [0; 2];
+                self.game_over = false;
+            }
+            return;
+        }
+
+        let (velocity, spin) = spin_ball(
+            self.ball_velocity,
+            self.ball_spin,
+            Some(1.0),
+            ROLLBACK_FRAME,
+        );
+        self.ball_velocity = velocity;
+        self.ball_spin = spin;
+        // The same as the `MoveBallsSystem` does with the mutators.
+        if self.mutators.is_on(Mutator::Gravity) {
+            self.ball_velocity.y -= GRAVITY_ACCELERATION * ROLLBACK_FRAME;
+        }
+        let speed = if self.mutators.is_on(Mutator::DoubleSpeed) {
+            DOUBLE_SPEED_MULTIPLIER
+        } else {
+            1.0
+        };
+        self.ball_position += self.ball_velocity * speed * ROLLBACK_FRAME;
+        if self.mutators.is_on(Mutator::NoWalls) {
+            if self.ball_position.y < 0.0 {
+                self.ball_position.y += ARENA_HEIGHT;
+            } else if self.ball_position.y > ARENA_HEIGHT {
+                self.ball_position.y -= ARENA_HEIGHT;
+            }
+        }
+        let position = self.ball_position;
+
+        let hit_floor = position.y <= radius && self.ball_velocity.y < 0.0;
+        let hit_ceiling = position.y >= ARENA_HEIGHT - radius && self.ball_velocity.y > 0.0;
+        if (hit_floor || hit_ceiling) && !self.mutators.is_on(Mutator::NoWalls) {
+            self.ball_velocity.y = -self.ball_velocity.y;
+            self.ball_spin = -self.ball_spin;
+            if hit_floor && self.mutators.is_on(Mutator::Gravity) {
+                self.ball_velocity.y *= GRAVITY_RESTITUTION;
+            }
+            events.push(MatchEvent::Bounce {
+                position,
+                velocity: self.ball_velocity,
+                paddle: None,
+            });
+        }
+
+        for side in &SIDES {
+            let (paddle_y, paddle_velocity) = self.paddle(*side);
+            let offset = position - Vector2::new(paddle_x(*side), paddle_y);
+            let inside = offset.x.abs() <= PADDLE_WIDTH * 0.5 + radius
+                && offset.y.abs() <= half_length + radius;
+            if !inside || self.ball_velocity.dot(&side.normal()) >= 0.0 {
+                continue;
+            }
+            let offset = (offset.y / (half_length + radius)).clamp(-1.0, 1.0);
+            let speed = self.ball_velocity.norm();
+            let (velocity, spin) = paddle_return(*side, offset, speed, paddle_velocity);
+            self.ball_velocity = velocity;
+            self.ball_spin = spin;
+            self.rally += 1;
+            events.push(MatchEvent::Bounce {
+                position,
+                velocity,
+                paddle: Some(*side),
+            });
+        }
+
+        let conceded = if position.x <= radius {
+            Side::Left
+        } else if position.x >= ARENA_WIDTH - radius {
+            Side::Right
+        } else {
+            return;
+        };
+        let scorer = conceded.opponent();
+        let score = self.scores[index(scorer)] + 1;
+        self.scores[index(scorer)] = score;
+        events.push(MatchEvent::Goal {
+            conceded,
+            speed: self.ball_velocity.norm(),
+        });
+        if score >= self.win_score {
+            events.push(MatchEvent::GameWon { winner: scorer });
+            self.game_over = true;
+            self.start_game(NEXT_GAME_WAIT);
+        } else {
+            // The player who conceded serves next, after the countdown.
+            self.serving = Some(conceded);
+            self.wait = (SERVE_COUNTDOWN / ROLLBACK_FRAME) as u32;
+            self.ball_spin = 0.0;
+            self.rally = 0;
+        }
+    }
+}
+
+const SIDES: [Side; 2] = [Side::Left, Side::Right];
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::Left => 0,
+        _ => 1,
+    }
+}
+
+fn paddle_x(side: Side) -> f32 {
+    match side {
+        Side::Left => PADDLE_WIDTH * 0.5,
+        _ => ARENA_WIDTH - PADDLE_WIDTH * 0.5,
+    }
+}
+
+/// Plays an online match without waiting for the opponent's input. Their input is guessed to
+/// be the same as the last one that came in, and when it turns out different the match is
+/// rolled back to the snapshot of the frame it was for and played again from there. The local
+/// player's input is played `INPUT_DELAY` frames late, so it's usually there in time on the
+/// other end.
+///
+/// Frames every input is known for are never played again, only their snapshots are dropped.
+pub struct Rollback {
+    local: usize,
+    /// The frame of the oldest snapshot, every input before it is known.
+    base: u32,
+    /// The match at the start of every frame from `base` on, the last one is the match now.
+    snapshots: VecDeque<MatchState>,
+    /// Both players' inputs from `base` on, as far as they've come in. The local player's go up
+    /// to `INPUT_DELAY` frames past now.
+    inputs: VecDeque<[Option<PlayerInput>; 2]>,
+    /// The inputs every frame from `base` on was played with, guesses and all, and what
+    /// happened in it.
+    played: VecDeque<([PlayerInput; 2], Vec<MatchEvent>)>,
+    /// The opponent's latest input and its frame, the inputs still to come are guessed from it.
+    last_remote: (u32, PlayerInput),
+    /// The first frame the opponent's input was guessed wrong for.
+    mispredicted: Option<u32>,
+    /// How many frames have been played again after a wrong guess.
+    pub rolled_back: u64,
+    /// The inputs of every frame they're all known for, for the replay.
+    pub recording: Recording,
+}
+
+impl Rollback {
+    pub fn new(local: Side, win_score: i32, mutators: Mutators) -> Rollback {
+        Rollback::resume(local, 0, MatchState::new(win_score, mutators))
+    }
+
+    /// Plays on from the match at the start of `frame`, after a player rejoined it.
+    pub fn resume(local: Side, frame: u32, state: MatchState) -> Rollback {
+        // Nobody pushes anything in the frames before the first inputs are played.
+        let inputs = (0..INPUT_DELAY)
+            .map(|_| [Some(PlayerInput::default()); 2])
+            .collect();
+        Rollback {
+            local: index(local),
+            base: frame,
+            snapshots: vec![state].into(),
+            inputs,
+            played: VecDeque::new(),
+            last_remote: (frame, PlayerInput::default()),
+            mispredicted: None,
+            rolled_back: 0,
+            recording: Recording::default(),
+        }
+    }
+
+    /// Plays on from the match at the start of `frame` both players agreed on, after one of
+    /// them rejoined it. What was recorded up to there is kept.
+    pub fn resync(&mut self, frame: u32, state: MatchState) {
+        let recording = mem::take(&mut self.recording);
+        *self = Rollback {
+            recording,
+            ..Rollback::resume(SIDES[self.local], frame, state)
+        };
+    }
+
+    /// The frame to be played next.
+    pub fn frame(&self) -> u32 {
+        self.base + self.snapshots.len() as u32 - 1
+    }
+
+    /// The first frame not every input is known for yet, and the match at its start, which
+    /// both ends played the same.
+    pub fn confirmed(&self) -> (u32, &MatchState) {
+        let state = self.snapshots.front().expect("there's always a snapshot");
+        (self.base, state)
+    }
+
+    /// The match as it is now, with the opponent's latest inputs guessed.
+    pub fn state(&self) -> &MatchState {
+        self.snapshots.back().expect("there's always a snapshot")
+    }
+
+    /// Whether the next frame can be played, the match waits for the opponent once it's
+    /// `MAX_ROLLBACK` frames ahead of their input.
+    pub fn can_advance(&self) -> bool {
+        self.snapshots.len() <= MAX_ROLLBACK
+    }
+
+    /// The inputs of the frame, `None` for frames that are never played again, and for frames
+    /// further ahead than the opponent can get, which only a broken or hostile one sends.
+    fn inputs_mut(&mut self, frame: u32) -> Option<&mut [Option<PlayerInput>; 2]> {
+        if frame > self.frame() + MAX_ROLLBACK as u32 + INPUT_DELAY {
+            return None;
+        }
+        let index = frame.checked_sub(self.base)? as usize;
+        while self.inputs.len() <= index {
+            self.inputs.push_back([None; 2]);
+        }
+        self.inputs.get_mut(index)
+    }
+
+    /// Keeps the local player's input for the frame `INPUT_DELAY` after the next one, and
+    /// returns that frame to send it to the opponent with.
+    pub fn add_local_input(&mut self, input: PlayerInput) -> u32 {
+        let frame = self.frame() + INPUT_DELAY;
+        let local = self.local;
+        if let Some(inputs) = self.inputs_mut(frame) {
+            inputs[local] = Some(input);
+        }
+        frame
+    }
+
+    pub fn add_remote_input(&mut self, frame: u32, input: PlayerInput) {
+        let remote = 1 - self.local;
+        match self.inputs_mut(frame) {
+            Some(inputs) => inputs[remote] = Some(input),
+            None => return,
+        }
+        if frame >= self.last_remote.0 {
+            self.last_remote = (frame, input);
+        }
+        // A frame played with another guess is played again from its snapshot.
+        let guessed = self
+            .played
+            .get((frame - self.base) as usize)
+            .map(|(inputs, _)| inputs[remote]);
+        if guessed.is_some_and(|guessed| guessed != input) {
+            let first = self.mispredicted.map_or(frame, |first| first.min(frame));
+            self.mispredicted = Some(first);
+        }
+    }
+
+    /// Has the opponent's input be `input` for every frame up to the next one that it isn't
+    /// known for yet, once nobody plays the other side anymore.
+    pub fn add_stand_in_input(&mut self, input: PlayerInput) {
+        let first = self.base.max(self.last_remote.0 + 1);
+        for frame in first..=self.frame() {
+            self.add_remote_input(frame, input);
+        }
+    }
+
+    /// Plays the next frame, after rolling back to the first frame the opponent's input was
+    /// guessed wrong for and playing again from there. Returns what happened in the frames
+    /// every input is known for by now, which can't change anymore, and in the new frame.
+    pub fn advance(&mut self) -> (Vec<MatchEvent>, Vec<MatchEvent>) {
+        if let Some(first) = self.mispredicted.take() {
+            let now = self.frame();
+            let index = (first - self.base) as usize;
+            self.snapshots.truncate(index + 1);
+            self.played.truncate(index);
+            for frame in first..now {
+                self.play(frame);
+                self.rolled_back += 1;
+            }
+        }
+        let now = self.frame();
+        self.play(now);
+        let new = self
+            .played
+            .back()
+            .map(|(_, events)| events.clone())
+            .unwrap_or_default();
+
+        let mut confirmed = Vec::new();
+        while !self.played.is_empty()
+            && self
+                .inputs
+                .front()
+                .is_some_and(|inputs| inputs.iter().all(Option::is_some))
+        {
+            if let Some((inputs, events)) = self.played.pop_front() {
+                self.recording.record(self.base, inputs);
+                confirmed.extend(events);
+            }
+            self.snapshots.pop_front();
+            self.inputs.pop_front();
+            self.base += 1;
+        }
+        (confirmed, new)
+    }
+
+    fn play(&mut self, frame: u32) {
+        let known = self
+            .inputs
+            .get((frame - self.base) as usize)
+            .cloned()
+            .unwrap_or([None; 2]);
+        let guess = self.last_remote.1;
+        let inputs = [known[0].unwrap_or(guess), known[1].unwrap_or(guess)];
+        let mut state = self.state().clone();
+        let mut events = Vec::new();
+        state.step(inputs, &mut events);
+        self.snapshots.push_back(state);
+        self.played.push_back((inputs, events));
+    }
+}
+
+/// Plays an online match ahead of the authoritative lobby playing it, with the local player's
+/// inputs it hasn't played yet, so their paddle moves as soon as they push it however far away
+/// the lobby is. Every frame that comes in from the lobby replaces the match, and the inputs
+/// still to be played there are played again on top of it. The opponent's input is guessed to
+/// stay the last one the lobby played.
+pub struct Prediction {
+    local: usize,
+    next_frame: u32,
+    /// The local player's inputs the lobby hasn't played yet, and their frames.
+    pending: VecDeque<(u32, PlayerInput)>,
+    /// The opponent's input in the latest frame the lobby played.
+    remote: PlayerInput,
+    /// The match as predicted, once the first frame came in from the lobby.
+    predicted: Option<MatchState>,
+    /// How far the left and right paddles and the ball are still shown from where they're
+    /// predicted, after the lobby played something else.
+    paddle_errors: [f32; 2],
+    ball_error: Vector2<f32>,
+    /// The inputs of every frame the lobby played, for the replay.
+    pub recording: Recording,
+}
+
+impl Prediction {
+    pub fn new(local: Side) -> Prediction {
+        Prediction {
+            local: index(local),
+            next_frame: 0,
+            pending: VecDeque::new(),
+            remote: PlayerInput::default(),
+            predicted: None,
+            paddle_errors: [0.0; 2],
+            ball_error: Vector2::zeros(),
+            recording: Recording::default(),
+        }
+    }
+
+    fn inputs(&self, local: PlayerInput) -> [PlayerInput; 2] {
+        let mut inputs = [self.remote; 2];
+        inputs[self.local] = local;
+        inputs
+    }
+
+    /// Plays the local player's input for the next frame, and returns the frame to send it to
+    /// the lobby with.
+    pub fn add_local_input(&mut self, input: PlayerInput) -> u32 {
+        let frame = self.next_frame;
+        self.next_frame += 1;
+        self.pending.push_back((frame, input));
+        if self.pending.len() > MAX_PREDICTION {
+            self.pending.pop_front();
+        }
+        let inputs = self.inputs(input);
+        if let Some(predicted) = &mut self.predicted {
+            predicted.step(inputs, &mut Vec::new());
+        }
+        for error in &mut self.paddle_errors {
+            *error *= CORRECTION_KEPT;
+        }
+        self.ball_error *= CORRECTION_KEPT;
+        frame
+    }
+
+    /// Replaces the match with a frame the lobby played with `inputs`, the local player's
+    /// being the one of frame `played` if it played any yet.
+    pub fn reconcile(&mut self, state: MatchState, inputs: [PlayerInput; 2], played: Option<u32>) {
+        if let Some(played) = played {
+            self.pending.retain(|(frame, _)| *frame > played);
+        }
+        self.remote = inputs[1 - self.local];
+        let mut predicted = state;
+        for (_, input) in &self.pending {
+            predicted.step(self.inputs(*input), &mut Vec::new());
+        }
+
+        // What's shown now slides over to the new prediction.
+        if let Some(shown) = self.state() {
+            for (side, error) in self.paddle_errors.iter_mut().enumerate() {
+                let off = shown.paddles[side].0 - predicted.paddles[side].0;
+                *error = if off.abs() > MAX_CORRECTION { 0.0 } else { off };
+            }
+            let off = shown.ball_position - predicted.ball_position;
+            self.ball_error = if off.norm() > MAX_CORRECTION {
+                Vector2::zeros()
+            } else {
+                off
+            };
+        }
+        self.predicted = Some(predicted);
+    }
+
+    /// The match as it's shown, the prediction with what's still sliding into place, once the
+    /// first frame came in from the lobby.
+    pub fn state(&self) -> Option<MatchState> {
+        let mut state = self.predicted.clone()?;
+        for (paddle, error) in state.paddles.iter_mut().zip(&self.paddle_errors) {
+            paddle.0 += error;
+        }
+        state.ball_position += self.ball_error;
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames the opponent's inputs come in late, within `MAX_ROLLBACK` of the match.
+    const LATENESS: u32 = 6;
+
+    /// Inputs that keep changing, so the opponent's are guessed wrong every few frames.
+    fn left_input(frame: u32) -> PlayerInput {
+        PlayerInput::new(f64::from(frame % 11) / 5.0 - 1.0, true)
+    }
+
+    fn right_input(frame: u32) -> PlayerInput {
+        PlayerInput::new(f64::from(frame / 7 % 3) - 1.0, frame.is_multiple_of(50))
+    }
+
+    /// The inputs the frame is played with, nobody pushes anything before `INPUT_DELAY`.
+    fn inputs(frame: u32) -> [PlayerInput; 2] {
+        if frame < INPUT_DELAY {
+            [PlayerInput::default(); 2]
+        } else {
+            [left_input(frame), right_input(frame)]
+        }
+    }
+
+    /// The state compared bit for bit, floats and all.
+    fn bytes(state: &MatchState) -> Vec<u8> {
+        bincode::serialize(state).unwrap()
+    }
+
+    #[test]
+    fn plays_the_same_again_from_a_snapshot() {
+        let mut state = MatchState::new(5, Mutators::default());
+        for frame in 0..100 {
+            state.step(inputs(frame), &mut Vec::new());
+        }
+        let mut again = state.clone();
+        for frame in 100..400 {
+            state.step(inputs(frame), &mut Vec::new());
+            again.step(inputs(frame), &mut Vec::new());
+        }
+        assert_eq!(bytes(&state), bytes(&again));
+    }
+
+    #[test]
+    fn ignores_inputs_too_far_ahead() {
+        let mut rollback = Rollback::new(Side::Left, 5, Mutators::default());
+        rollback.add_remote_input(u32::MAX, right_input(0));
+        assert!(rollback.inputs.len() <= MAX_ROLLBACK + INPUT_DELAY as usize + 1);
+        assert_eq!(rollback.last_remote, (0, PlayerInput::default()));
+    }
+
+    #[test]
+    fn rolls_back_late_inputs_to_the_match_played_on_time() {
+        const FRAMES: u32 = 400;
+        let mut on_time = MatchState::new(5, Mutators::default());
+        for frame in 0..=FRAMES {
+            on_time.step(inputs(frame), &mut Vec::new());
+        }
+
+        let mut rollback = Rollback::new(Side::Left, 5, Mutators::default());
+        for now in 0..FRAMES {
+            let frame = rollback.add_local_input(left_input(now + INPUT_DELAY));
+            if let Some(late) = frame.checked_sub(LATENESS).filter(|late| *late >= INPUT_DELAY) {
+                rollback.add_remote_input(late, right_input(late));
+            }
+            assert!(rollback.can_advance());
+            rollback.advance();
+        }
+        for late in FRAMES + INPUT_DELAY - LATENESS..=FRAMES {
+            rollback.add_remote_input(late, right_input(late));
+        }
+        rollback.advance();
+
+        assert!(rollback.rolled_back > 0);
+        assert_eq!(rollback.frame(), FRAMES + 1);
+        assert_eq!(bytes(rollback.state()), bytes(&on_time));
+    }
+}
@@ -0,0 +1,545 @@
+use amethyst::{
+    assets::ProgressCounter,
+    ecs::prelude::Entity,
+    input::{is_close_requested, InputHandler, StringBindings},
+    prelude::*,
+    ui::{Anchor, UiText, UiTransform},
+    winit::{dpi::LogicalSize, Window},
+};
+
+use crate::{
+    config::{AudioSettings, GameConfig, KeyMode, Msaa, PresentMode, UserSettings},
+    controls::convert_keys,
+    controls_menu::ControlsMenu,
+    menu::{menu_input, refresh_rows, MenuInput},
+    palette::{Palette, PaletteName},
+    pong::Side,
+    skin::{apply_skin, skin_names},
+    theme::{theme_names, Theme},
+};
+
+/// Steps the volumes, screen shake and curve strength are changed by.
+const VOLUME_STEP: f32 = 0.1;
+const CURVE_STEP: f32 = 0.25;
+const MAX_CURVE_STRENGTH: f32 = 3.0;
+/// Step and range of the mouse sensitivity.
+const SENSITIVITY_STEP: f32 = 0.25;
+const MIN_SENSITIVITY: f32 = 0.5;
+const MAX_SENSITIVITY: f32 = 3.0;
+/// Steps and limits of how the paddles answer the gamepad sticks.
+const DEAD_ZONE_STEP: f32 = 0.05;
+const MAX_DEAD_ZONE: f32 = 0.5;
+const STICK_CURVE_STEP: f32 = 0.25;
+const MIN_STICK_CURVE: f32 = 0.5;
+const MAX_STICK_CURVE: f32 = 3.0;
+const SMOOTHING_STEP: f32 = 0.1;
+const MAX_SMOOTHING: f32 = 0.9;
+/// The players the mouse can be given to, or nobody.
+const MOUSE_PADDLES: [Option<Side>; 5] = [
+    None,
+    Some(Side::Left),
+    Some(Side::Right),
+    Some(Side::Top),
+    Some(Side::Bottom),
+];
+/// How many notches the sliders have, one for every `VOLUME_STEP`.
+const SLIDER_NOTCHES: usize = 10;
+/// How many options fit on the screen, the list scrolls to show the rest.
+const VISIBLE_ROWS: usize = 22;
+/// Window sizes offered in the settings, the ones bigger than the monitor are left out.
+const WINDOW_SIZES: [(u32, u32); 7] = [
+    (500, 500),
+    (640, 640),
+    (800, 800),
+    (1024, 768),
+    (1280, 720),
+    (1280, 1024),
+    (1920, 1080),
+];
+
+const HINT: &str = "Up and down to pick, left and right to change, Enter opens the controls";
+
+/// One of the options on the settings screen.
+#[derive(Clone, Copy)]
+enum Setting {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    /// The size of the window, out of the ones that fit on the monitor.
+    Resolution,
+    /// Vsync, or presenting frames sooner at the cost of some tearing.
+    PresentMode,
+    /// How many samples the sprites are drawn with, off or 2x or 4x.
+    Msaa,
+    /// The UI theme, one of the files in `assets/themes/`.
+    Theme,
+    /// The colors of the arena and the UI, one of the `PaletteName`s.
+    Palette,
+    /// The sprite sheet of the arena, the default one or one of `assets/skins/`.
+    Skin,
+    Announcer,
+    /// How hard the screen shakes on goals and power shots, it can be turned off.
+    ScreenShake,
+    /// The CRT look, scanlines and all.
+    Crt,
+    /// The glow around the ball and everything else bright.
+    Bloom,
+    MagnusEffect,
+    CurveStrength,
+    /// The player whose paddle follows the mouse.
+    MousePaddle,
+    MouseSensitivity,
+    /// How far the gamepad sticks have to be pushed before the paddles move.
+    StickDeadZone,
+    /// How the paddles' speed follows the push of the sticks past the dead zone.
+    StickCurve,
+    StickSmoothing,
+    /// Whether the gamepads rumble on hits and goals.
+    Rumble,
+    /// Whether what's being played shows on Discord.
+    DiscordPresence,
+    /// Whether keys are bound by their name or where they are on the keyboard.
+    KeyMode,
+    /// Opens the `ControlsMenu`, where the keys and gamepads are changed.
+    Controls,
+}
+
+impl Setting {
+    const ALL: [Setting; 25] = [
+        Setting::MasterVolume,
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::Fullscreen,
+        Setting::Resolution,
+        Setting::PresentMode,
+        Setting::Msaa,
+        Setting::Theme,
+        Setting::Palette,
+        Setting::Skin,
+        Setting::Announcer,
+        Setting::ScreenShake,
+        Setting::Crt,
+        Setting::Bloom,
+        Setting::MagnusEffect,
+        Setting::CurveStrength,
+        Setting::MousePaddle,
+        Setting::MouseSensitivity,
+        Setting::StickDeadZone,
+        Setting::StickCurve,
+        Setting::StickSmoothing,
+        Setting::Rumble,
+        Setting::DiscordPresence,
+        Setting::KeyMode,
+        Setting::Controls,
+    ];
+
+    fn describe(self, config: &GameConfig, audio: &AudioSettings) -> String {
+        match self {
+            Setting::MasterVolume => format!("master volume {}", slider(audio.master)),
+            Setting::MusicVolume => format!("music volume {}", slider(audio.music)),
+            Setting::SfxVolume => format!("effects volume {}", slider(audio.sfx)),
+            Setting::Fullscreen => {
+                let on = if config.fullscreen { "on" } else { "off" };
+                format!("fullscreen {}", on)
+            }
+            Setting::Resolution => {
+                let (width, height) = config.resolution;
+                format!("window size {}x{}", width, height)
+            }
+            Setting::PresentMode => format!("present mode {}", config.present_mode.name()),
+            Setting::Msaa => format!("antialiasing {}", config.msaa.name()),
+            Setting::Theme => format!("theme {}", config.theme),
+            Setting::Palette => format!("palette {}", config.palette.name()),
+            Setting::Skin => format!("skin {}", config.skin),
+            Setting::Announcer => {
+                let on = if config.announcer { "on" } else { "off" };
+                format!("announcer {}", on)
+            }
+            Setting::ScreenShake => format!("screen shake {}", slider(config.screen_shake)),
+            Setting::Crt => {
+                let on = if config.crt { "on" } else { "off" };
+                format!("CRT filter {}", on)
+            }
+            Setting::Bloom => {
+                let on = if config.bloom { "on" } else { "off" };
+                format!("bloom {}", on)
+            }
+            Setting::MagnusEffect => {
+                let on = if config.magnus_effect { "on" } else { "off" };
+                format!("spin curves the ball {}", on)
+            }
+            Setting::CurveStrength => format!("curve strength {:.2}", config.curve_strength),
+            Setting::MousePaddle => match config.mouse_paddle {
+                Some(side) => format!("mouse moves the {} paddle", side.name().to_lowercase()),
+                None => "mouse moves no paddle".to_string(),
+            },
+            Setting::MouseSensitivity => {
+                format!("mouse sensitivity {:.2}", config.mouse_sensitivity)
+            }
+            Setting::StickDeadZone => format!("stick dead zone {:.2}", config.stick.dead_zone),
+            Setting::StickCurve => format!("stick curve {:.2}", config.stick.curve),
+            Setting::StickSmoothing => format!("stick smoothing {:.1}", config.stick.smoothing),
+            Setting::Rumble => {
+                let on = if config.rumble { "on" } else { "off" };
+                format!("gamepad rumble {}", on)
+            }
+            Setting::DiscordPresence => {
+                let on = if config.discord_presence { "on" } else { "off" };
+                format!("Discord status {}", on)
+            }
+            Setting::KeyMode => format!("keys bound by {}", config.key_mode.name()),
+            Setting::Controls => "controls...".to_string(),
+        }
+    }
+
+    /// Changes the setting one step, the window size stepping through `sizes`.
+    fn change(
+        self,
+        config: &mut GameConfig,
+        audio: &mut AudioSettings,
+        sizes: &[(u32, u32)],
+        up: bool,
+    ) {
+        let volume_step = if up { VOLUME_STEP } else { -VOLUME_STEP };
+        match self {
+            Setting::MasterVolume => audio.master = (audio.master + volume_step).clamp(0.0, 1.0),
+            Setting::MusicVolume => audio.music = (audio.music + volume_step).clamp(0.0, 1.0),
+            Setting::SfxVolume => audio.sfx = (audio.sfx + volume_step).clamp(0.0, 1.0),
+            Setting::Fullscreen => config.fullscreen = !config.fullscreen,
+            Setting::Resolution => {
+                if let Some(size) = step(sizes, &config.resolution, up) {
+                    config.resolution = size;
+                }
+            }
+            Setting::PresentMode => {
+                if let Some(mode) = step(&PresentMode::ALL, &config.present_mode, up) {
+                    config.present_mode = mode;
+                }
+            }
+            Setting::Msaa => {
+                if let Some(msaa) = step(&Msaa::ALL, &config.msaa, up) {
+                    config.msaa = msaa;
+                }
+            }
+            Setting::Theme => {
+                if let Some(name) = step(&theme_names(), &config.theme, up) {
+                    config.theme = name;
+                }
+            }
+            Setting::Palette => {
+                if let Some(palette) = step(&PaletteName::ALL, &config.palette, up) {
+                    config.palette = palette;
+                }
+            }
+            Setting::Skin => {
+                if let Some(name) = step(&skin_names(), &config.skin, up) {
+                    config.skin = name;
+                }
+            }
+            Setting::Announcer => config.announcer = !config.announcer,
+            Setting::Crt => config.crt = !config.crt,
+            Setting::Bloom => config.bloom = !config.bloom,
+            Setting::ScreenShake => {
+                config.screen_shake = (config.screen_shake + volume_step).clamp(0.0, 1.0)
+            }
+            Setting::MagnusEffect => config.magnus_effect = !config.magnus_effect,
+            Setting::CurveStrength => {
+                let step = if up { CURVE_STEP } else { -CURVE_STEP };
+                config.curve_strength =
+                    (config.curve_strength + step).clamp(0.0, MAX_CURVE_STRENGTH);
+            }
+            Setting::MousePaddle => {
+                if let Some(side) = step(&MOUSE_PADDLES, &config.mouse_paddle, up) {
+                    config.mouse_paddle = side;
+                }
+            }
+            Setting::MouseSensitivity => {
+                let step = if up { SENSITIVITY_STEP } else { -SENSITIVITY_STEP };
+                config.mouse_sensitivity = (config.mouse_sensitivity + step)
+                    .clamp(MIN_SENSITIVITY, MAX_SENSITIVITY);
+            }
+            Setting::StickDeadZone => {
+                let step = if up { DEAD_ZONE_STEP } else { -DEAD_ZONE_STEP };
+                config.stick.dead_zone = (config.stick.dead_zone + step).clamp(0.0, MAX_DEAD_ZONE);
+            }
+            Setting::StickCurve => {
+                let step = if up { STICK_CURVE_STEP } else { -STICK_CURVE_STEP };
+                config.stick.curve =
+                    (config.stick.curve + step).clamp(MIN_STICK_CURVE, MAX_STICK_CURVE);
+            }
+            Setting::StickSmoothing => {
+                let step = if up { SMOOTHING_STEP } else { -SMOOTHING_STEP };
+                config.stick.smoothing = (config.stick.smoothing + step).clamp(0.0, MAX_SMOOTHING);
+            }
+            Setting::Rumble => config.rumble = !config.rumble,
+            Setting::DiscordPresence => config.discord_presence = !config.discord_presence,
+            Setting::KeyMode => {
+                if let Some(mode) = step(&KeyMode::ALL, &config.key_mode, up) {
+                    config.key_mode = mode;
+                }
+            }
+            Setting::Controls => {}
+        }
+    }
+}
+
+/// The option after `current` in `options`, or the one before it, going around at the ends. An
+/// option that isn't in the list goes to the first one.
+fn step<T: Clone + PartialEq>(options: &[T], current: &T, up: bool) -> Option<T> {
+    let current = options.iter().position(|option| option == current);
+    let next = match (current, up) {
+        _ if options.is_empty() => return None,
+        (Some(index), true) => (index + 1) % options.len(),
+        (Some(index), false) => (index + options.len() - 1) % options.len(),
+        (None, _) => 0,
+    };
+    Some(options[next].clone())
+}
+
+/// Draws a volume as a slider, like `[######----] 60%`.
+fn slider(volume: f32) -> String {
+    let filled = ((volume * SLIDER_NOTCHES as f32).round() as usize).min(SLIDER_NOTCHES);
+    format!(
+        "[{}{}] {:.0}%",
+        "#".repeat(filled),
+        "-".repeat(SLIDER_NOTCHES - filled),
+        volume * 100.0
+    )
+}
+
+/// Puts the window in or out of fullscreen to match the `GameConfig`.
+pub fn apply_window_mode(world: &World) {
+    let fullscreen = world.read_resource::<GameConfig>().fullscreen;
+    set_fullscreen(&world.read_resource::<Window>(), fullscreen);
+}
+
+/// The window sizes that fit on the monitor the window is on, out of `WINDOW_SIZES`, followed by
+/// the size of the monitor itself.
+fn window_sizes(window: &Window) -> Vec<(u32, u32)> {
+    let monitor = window.get_current_monitor();
+    let size = monitor.get_dimensions().to_logical(monitor.get_hidpi_factor());
+    let monitor_size = (size.width.round() as u32, size.height.round() as u32);
+
+    let mut sizes: Vec<(u32, u32)> = WINDOW_SIZES
+        .iter()
+        .cloned()
+        .filter(|(width, height)| *width <= monitor_size.0 && *height <= monitor_size.1)
+        .collect();
+    if !sizes.contains(&monitor_size) {
+        sizes.push(monitor_size);
+    }
+    sizes
+}
+
+/// Resizes the window to the `resolution` in the `GameConfig`. The renderer builds its graph
+/// again for the new `ScreenDimensions`, and the UI and camera stretch to fit them.
+pub fn apply_window_size(world: &World) {
+    let (width, height) = world.read_resource::<GameConfig>().resolution;
+    world
+        .read_resource::<Window>()
+        .set_inner_size(LogicalSize::new(width.into(), height.into()));
+}
+
+/// Puts the window in fullscreen on the monitor it's on, or back in a window.
+pub fn set_fullscreen(window: &Window, fullscreen: bool) {
+    let monitor = if fullscreen {
+        Some(window.get_current_monitor())
+    } else {
+        None
+    };
+    window.set_fullscreen(monitor);
+}
+
+/// Converts the key bindings to the `KeyMode` picked in the `GameConfig` and saves them. The
+/// mode goes back to the one the bindings are in if they can't be converted.
+fn apply_key_mode(world: &World) {
+    let mode = world.read_resource::<GameConfig>().key_mode;
+    let bindings = &world.read_resource::<InputHandler<StringBindings>>().bindings;
+    match convert_keys(bindings, mode) {
+        Ok(converted) => {
+            world.write_resource::<InputHandler<StringBindings>>().bindings = converted;
+            UserSettings::of(world).save();
+        }
+        Err(err) => {
+            log::warn!("Failed to convert the key bindings: {}", err);
+            world.write_resource::<GameConfig>().key_mode = match mode {
+                KeyMode::Names => KeyMode::Positions,
+                KeyMode::Positions => KeyMode::Names,
+            };
+        }
+    }
+}
+
+/// Swaps the `Theme` for the one picked in the `GameConfig`. Its font and textures show up once
+/// they're loaded.
+fn load_theme(world: &mut World) {
+    let name = world.read_resource::<GameConfig>().theme.clone();
+    let theme = Theme::load(world, &name, &mut ProgressCounter::new());
+    world.add_resource(theme);
+}
+
+/// Changes the `GameConfig` and the `AudioSettings`, pushed on top of the screen it was opened
+/// from. Changes apply right away, and are saved to their RON files when leaving the screen.
+///
+/// Up and down pick an option, left and right change it, Enter opens the `ControlsMenu` from
+/// the controls row and escape goes back.
+#[derive(Default)]
+pub struct Settings {
+    selected: usize,
+    /// The option shown in the first row, the list scrolls to keep the selected one in view.
+    scroll: usize,
+    hint: Option<Entity>,
+    row_texts: Vec<Entity>,
+    texts: Vec<Entity>,
+}
+
+impl Settings {
+    fn show(&mut self, world: &mut World) {
+        let theme = world.read_resource::<Theme>().clone();
+
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "settings_title".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -30., 1., 500., 40.,
+            ))
+            .with(theme.text("Settings".to_string(), 30.))
+            .build();
+        let hint = world
+            .create_entity()
+            .with(UiTransform::new(
+                "settings_hint".to_string(), Anchor::BottomMiddle, Anchor::BottomMiddle,
+                0., 30., 1., 600., 30.,
+            ))
+            .with(theme.text(String::new(), 15.))
+            .build();
+        self.texts.push(title);
+        self.texts.push(hint);
+        self.hint = Some(hint);
+
+        for row in 0..Setting::ALL.len().min(VISIBLE_ROWS) {
+            let transform = UiTransform::new(
+                format!("settings_{}", row), Anchor::TopMiddle, Anchor::TopMiddle,
+                0., -55. - 16. * row as f32, 1., 400., 16.,
+            );
+            let text = world
+                .create_entity()
+                .with(transform)
+                .with(theme.text(String::new(), 15.))
+                .build();
+            self.row_texts.push(text);
+        }
+        self.refresh(world);
+    }
+
+    fn hide(&mut self, world: &mut World) {
+        world
+            .delete_entities(&self.texts)
+            .expect("settings text should be alive");
+        world
+            .delete_entities(&self.row_texts)
+            .expect("settings text should be alive");
+        self.texts.clear();
+        self.row_texts.clear();
+        self.hint = None;
+    }
+
+    /// Updates the text of every row, marking the selected one.
+    fn refresh(&mut self, world: &mut World) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + VISIBLE_ROWS {
+            self.scroll = self.selected + 1 - VISIBLE_ROWS;
+        }
+
+        let config = world.read_resource::<GameConfig>();
+        let audio = world.read_resource::<AudioSettings>();
+        let shown = Setting::ALL.iter().skip(self.scroll);
+        let labels = shown.map(|setting| setting.describe(&config, &audio));
+        refresh_rows(world, &self.row_texts, labels, Some(self.selected - self.scroll));
+        let mut ui_text = world.write_storage::<UiText>();
+        if let Some(text) = self.hint.and_then(|hint| ui_text.get_mut(hint)) {
+            text.text = HINT.to_string();
+        }
+    }
+}
+
+
+impl SimpleState for Settings {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.show(data.world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        UserSettings::of(data.world).save();
+        self.hide(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.hide(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.show(data.world);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Window(event) = &event {
+            if is_close_requested(event) {
+                return Trans::Quit;
+            }
+        }
+
+        let rows = Setting::ALL.len();
+        match menu_input(&event) {
+            Some(MenuInput::Up) => self.selected = (self.selected + rows - 1) % rows,
+            Some(MenuInput::Down) => self.selected = (self.selected + 1) % rows,
+            Some(input @ MenuInput::Left) | Some(input @ MenuInput::Right) => {
+                let setting = Setting::ALL[self.selected];
+                let sizes = window_sizes(&data.world.read_resource::<Window>());
+                setting.change(
+                    &mut data.world.write_resource::<GameConfig>(),
+                    &mut data.world.write_resource::<AudioSettings>(),
+                    &sizes,
+                    input == MenuInput::Right,
+                );
+                match setting {
+                    Setting::Fullscreen => apply_window_mode(data.world),
+                    Setting::Resolution => apply_window_size(data.world),
+                    Setting::Skin => apply_skin(data.world),
+                    Setting::KeyMode => apply_key_mode(data.world),
+                    // Build the screen again in the new theme, or the theme in the new palette.
+                    Setting::Palette => {
+                        let name = data.world.read_resource::<GameConfig>().palette;
+                        data.world.add_resource(Palette::new(name));
+                        load_theme(data.world);
+                        self.hide(data.world);
+                        self.show(data.world);
+                        return Trans::None;
+                    }
+                    Setting::Theme => {
+                        load_theme(data.world);
+                        self.hide(data.world);
+                        self.show(data.world);
+                        return Trans::None;
+                    }
+                    _ => {}
+                }
+            }
+            Some(MenuInput::Select) => {
+                if let Setting::Controls = Setting::ALL[self.selected] {
+                    return Trans::Push(Box::new(ControlsMenu::default()));
+                }
+            }
+            Some(MenuInput::Back) => return Trans::Pop,
+            _ => return Trans::None,
+        }
+        self.refresh(data.world);
+        Trans::None
+    }
+}
@@ -0,0 +1,66 @@
+use amethyst::{
+    assets::{Handle, ProgressCounter},
+    ecs::prelude::Join,
+    prelude::*,
+    renderer::{sprite::SpriteSheet, SpriteRender},
+};
+
+use crate::{config::GameConfig, loading::load_sprite_sheet, pong::SpriteSheetHandle};
+
+/// Where the skins are kept, a directory per skin named after it with a `spritesheet.png` and
+/// `spritesheet.ron` in it. The sprites have to be in the same order as in the default sheet.
+pub const SKINS_DIR: &str = "assets/skins";
+/// The skin the game starts out with, the sprite sheet in `assets/texture/`.
+pub const DEFAULT_SKIN: &str = "default";
+
+/// Names of the skins that can be picked, the default one first and the ones in `assets/skins/`
+/// after it in alphabetical order. Directories missing either file of the sprite sheet are
+/// left out.
+pub fn skin_names() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(SKINS_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.join("spritesheet.png").is_file() && path.join("spritesheet.ron").is_file()
+                })
+                .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+                .filter(|name| name != DEFAULT_SKIN)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_SKIN.to_string());
+    names
+}
+
+/// Loads the sprite sheet of the skin called `name`.
+pub fn load_skin(
+    world: &mut World,
+    name: &str,
+    progress: &mut ProgressCounter,
+) -> Handle<SpriteSheet> {
+    if name == DEFAULT_SKIN {
+        load_sprite_sheet(world, "texture/pong_spritesheet", progress)
+    } else {
+        load_sprite_sheet(world, &format!("skins/{}/spritesheet", name), progress)
+    }
+}
+
+/// Loads the skin picked in the `GameConfig` and swaps it in for the sprite sheet in use, on
+/// everything already in the arena as well as what's spawned from now on. The sprites show up
+/// again once the new sheet is loaded.
+pub fn apply_skin(world: &mut World) {
+    let name = world.read_resource::<GameConfig>().skin.clone();
+    let sheet = load_skin(world, &name, &mut ProgressCounter::new());
+    let old = std::mem::replace(
+        &mut world.write_resource::<SpriteSheetHandle>().0,
+        sheet.clone(),
+    );
+    for sprite in (&mut world.write_storage::<SpriteRender>()).join() {
+        if sprite.sprite_sheet == old {
+            sprite.sprite_sheet = sheet.clone();
+        }
+    }
+}
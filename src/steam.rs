@@ -0,0 +1,265 @@
+use std::{ffi::CString, fs, io, os::raw::c_void, time::UNIX_EPOCH};
+
+use crate::config::{
+    settings_dir, ACHIEVEMENT_PROGRESS_FILE, HIGH_SCORES_FILE, HISTORY_FILE, PROFILES_FILE,
+    SETTINGS_FILE,
+};
+use crate::ghost::GHOST_FILE;
+
+/// The files in the `settings_dir()` kept in Steam Cloud, under the same names.
+pub const CLOUD_FILES: [&str; 6] = [
+    SETTINGS_FILE,
+    PROFILES_FILE,
+    HIGH_SCORES_FILE,
+    HISTORY_FILE,
+    ACHIEVEMENT_PROGRESS_FILE,
+    GHOST_FILE,
+];
+
+/// The few calls made into the flat C API of the Steamworks SDK, with the interface versions of
+/// SDK 1.57. They're declared here in the place of generated bindings, which can't be built
+/// alongside the older bindgen the audio of amethyst generates its own with.
+mod sys {
+    use std::os::raw::{c_char, c_void};
+
+    #[cfg_attr(all(windows, target_pointer_width = "64"), link(name = "steam_api64"))]
+    #[cfg_attr(not(all(windows, target_pointer_width = "64")), link(name = "steam_api"))]
+    extern "C" {
+        pub fn SteamAPI_Init() -> bool;
+        pub fn SteamAPI_Shutdown();
+        pub fn SteamAPI_RunCallbacks();
+
+        pub fn SteamAPI_SteamUserStats_v012() -> *mut c_void;
+        pub fn SteamAPI_ISteamUserStats_RequestCurrentStats(stats: *mut c_void) -> bool;
+        pub fn SteamAPI_ISteamUserStats_SetAchievement(
+            stats: *mut c_void,
+            name: *const c_char,
+        ) -> bool;
+        pub fn SteamAPI_ISteamUserStats_SetStatInt32(
+            stats: *mut c_void,
+            name: *const c_char,
+            data: i32,
+        ) -> bool;
+        pub fn SteamAPI_ISteamUserStats_StoreStats(stats: *mut c_void) -> bool;
+
+        pub fn SteamAPI_SteamFriends_v017() -> *mut c_void;
+        pub fn SteamAPI_ISteamFriends_SetRichPresence(
+            friends: *mut c_void,
+            key: *const c_char,
+            value: *const c_char,
+        ) -> bool;
+
+        pub fn SteamAPI_SteamRemoteStorage_v016() -> *mut c_void;
+        pub fn SteamAPI_ISteamRemoteStorage_IsCloudEnabledForAccount(
+            storage: *mut c_void,
+        ) -> bool;
+        pub fn SteamAPI_ISteamRemoteStorage_IsCloudEnabledForApp(storage: *mut c_void) -> bool;
+        pub fn SteamAPI_ISteamRemoteStorage_FileExists(
+            storage: *mut c_void,
+            file: *const c_char,
+        ) -> bool;
+        pub fn SteamAPI_ISteamRemoteStorage_GetFileSize(
+            storage: *mut c_void,
+            file: *const c_char,
+        ) -> i32;
+        pub fn SteamAPI_ISteamRemoteStorage_GetFileTimestamp(
+            storage: *mut c_void,
+            file: *const c_char,
+        ) -> i64;
+        pub fn SteamAPI_ISteamRemoteStorage_FileRead(
+            storage: *mut c_void,
+            file: *const c_char,
+            data: *mut c_void,
+            data_to_read: i32,
+        ) -> i32;
+        pub fn SteamAPI_ISteamRemoteStorage_FileWrite(
+            storage: *mut c_void,
+            file: *const c_char,
+            data: *const c_void,
+            length: i32,
+        ) -> bool;
+    }
+}
+
+/// The connection to Steam, in builds with the `steam` feature when the game is started
+/// through Steam, or with a `steam_appid.txt` next to it. Shut down when it's dropped.
+///
+/// Steam is only talked to on the thread that connected to it.
+pub struct Steam {
+    user_stats: *mut c_void,
+    friends: *mut c_void,
+    remote_storage: *mut c_void,
+}
+
+impl Steam {
+    /// Connects to Steam, `None` when it isn't running.
+    pub fn init() -> Option<Steam> {
+        unsafe {
+            if !sys::SteamAPI_Init() {
+                log::warn!("Failed to connect to Steam");
+                return None;
+            }
+            Some(Steam {
+                user_stats: sys::SteamAPI_SteamUserStats_v012(),
+                friends: sys::SteamAPI_SteamFriends_v017(),
+                remote_storage: sys::SteamAPI_SteamRemoteStorage_v016(),
+            })
+        }
+    }
+
+    /// Runs the callbacks Steam answered with since the last time.
+    pub fn run_callbacks(&self) {
+        unsafe { sys::SteamAPI_RunCallbacks() }
+    }
+
+    /// Asks Steam for the player's achievements and stats, which have to have come in before
+    /// they can be set.
+    pub fn request_current_stats(&self) -> bool {
+        unsafe { sys::SteamAPI_ISteamUserStats_RequestCurrentStats(self.user_stats) }
+    }
+
+    /// Unlocks the achievement with the `id` set up in the Steamworks partner site.
+    pub fn set_achievement(&self, id: &str) -> bool {
+        c_string(id).is_some_and(|id| unsafe {
+            sys::SteamAPI_ISteamUserStats_SetAchievement(self.user_stats, id.as_ptr())
+        })
+    }
+
+    /// Sets the stat `name` to `value`, kept by Steam once `store_stats` is called.
+    pub fn set_stat(&self, name: &str, value: i32) -> bool {
+        c_string(name).is_some_and(|name| unsafe {
+            sys::SteamAPI_ISteamUserStats_SetStatInt32(self.user_stats, name.as_ptr(), value)
+        })
+    }
+
+    /// Sends the achievements unlocked and the stats set to Steam.
+    pub fn store_stats(&self) -> bool {
+        unsafe { sys::SteamAPI_ISteamUserStats_StoreStats(self.user_stats) }
+    }
+
+    /// Sets the rich presence `key`, the one named `status` is what friends see being played.
+    pub fn set_rich_presence(&self, key: &str, value: &str) -> bool {
+        match (c_string(key), c_string(value)) {
+            (Some(key), Some(value)) => unsafe {
+                sys::SteamAPI_ISteamFriends_SetRichPresence(
+                    self.friends,
+                    key.as_ptr(),
+                    value.as_ptr(),
+                )
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the player has Steam Cloud on for the game.
+    fn cloud_enabled(&self) -> bool {
+        unsafe {
+            sys::SteamAPI_ISteamRemoteStorage_IsCloudEnabledForAccount(self.remote_storage)
+                && sys::SteamAPI_ISteamRemoteStorage_IsCloudEnabledForApp(self.remote_storage)
+        }
+    }
+
+    /// When the file in Steam Cloud was last saved, in seconds since the Unix epoch. `None` if
+    /// there's no such file.
+    fn cloud_timestamp(&self, name: &CString) -> Option<i64> {
+        unsafe {
+            if !sys::SteamAPI_ISteamRemoteStorage_FileExists(self.remote_storage, name.as_ptr()) {
+                return None;
+            }
+            Some(sys::SteamAPI_ISteamRemoteStorage_GetFileTimestamp(
+                self.remote_storage,
+                name.as_ptr(),
+            ))
+        }
+    }
+
+    /// Reads the whole file from Steam Cloud.
+    fn read_cloud_file(&self, name: &CString) -> io::Result<Vec<u8>> {
+        let size = unsafe {
+            sys::SteamAPI_ISteamRemoteStorage_GetFileSize(self.remote_storage, name.as_ptr())
+        };
+        let mut contents = vec![0u8; size.max(0) as usize];
+        let read = unsafe {
+            sys::SteamAPI_ISteamRemoteStorage_FileRead(
+                self.remote_storage,
+                name.as_ptr(),
+                contents.as_mut_ptr() as *mut c_void,
+                size,
+            )
+        };
+        if read != size {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the file was cut short"));
+        }
+        Ok(contents)
+    }
+
+    /// Replaces the `CLOUD_FILES` here with the ones in Steam Cloud that were saved after them,
+    /// on another machine. Done before the files are read as the game starts.
+    pub fn download_saves(&self) {
+        if !self.cloud_enabled() {
+            return;
+        }
+        for name in CLOUD_FILES.iter() {
+            let file = match c_string(name) {
+                Some(file) => file,
+                None => continue,
+            };
+            match self.cloud_timestamp(&file) {
+                Some(saved) if saved > modified(name).unwrap_or(0) => {}
+                _ => continue,
+            }
+            let written = self.read_cloud_file(&file).and_then(|contents| {
+                fs::create_dir_all(settings_dir())?;
+                fs::write(settings_dir().join(name), &contents)
+            });
+            if let Err(err) = written {
+                log::warn!("Failed to download {} from Steam Cloud: {}", name, err);
+            }
+        }
+    }
+
+    /// Puts the file from the `settings_dir()` in Steam Cloud.
+    pub fn upload_save(&self, name: &str) {
+        if !self.cloud_enabled() {
+            return;
+        }
+        let uploaded = fs::read(settings_dir().join(name)).and_then(|contents| {
+            let written = c_string(name).is_some_and(|file| unsafe {
+                sys::SteamAPI_ISteamRemoteStorage_FileWrite(
+                    self.remote_storage,
+                    file.as_ptr(),
+                    contents.as_ptr() as *const c_void,
+                    contents.len() as i32,
+                )
+            });
+            if written {
+                Ok(())
+            } else {
+                Err(io::Error::other("Steam didn't take the file"))
+            }
+        });
+        if let Err(err) = uploaded {
+            log::warn!("Failed to upload {} to Steam Cloud: {}", name, err);
+        }
+    }
+}
+
+impl Drop for Steam {
+    fn drop(&mut self) {
+        unsafe { sys::SteamAPI_Shutdown() }
+    }
+}
+
+/// The `text` as the C string Steam takes, `None` if it has a nul in it.
+fn c_string(text: &str) -> Option<CString> {
+    CString::new(text).ok()
+}
+
+/// When the file in the `settings_dir()` was last saved, in seconds since the Unix epoch.
+pub fn modified(name: &str) -> io::Result<i64> {
+    let modified = fs::metadata(settings_dir().join(name))?.modified()?;
+    let since = modified
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?;
+    Ok(since.as_secs() as i64)
+}
@@ -0,0 +1,146 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{
+        Entities, Entity, Read, ReadExpect, Resources, System, SystemData, Write, WriteStorage,
+    },
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::achievements::Achievements;
+use crate::pong::{
+    GameMode, GameWon, GoalConceded, MatchRules, MatchScore, Rally, ScoreEvent, Survival,
+};
+use crate::theme::Theme;
+
+/// Seconds an unlocked achievement is shown for.
+const TOAST_DURATION: f32 = 3.0;
+
+/// Counts the points, games, rallies and survival runs of the match towards the
+/// `Achievements`, as they're played.
+#[derive(Default)]
+pub struct AchievementSystem {
+    score_reader: Option<ReaderId<ScoreEvent>>,
+    goal_reader: Option<ReaderId<GoalConceded>>,
+    game_won_reader: Option<ReaderId<GameWon>>,
+    /// The rally as it was last counted.
+    rally: u32,
+}
+
+impl<'s> System<'s> for AchievementSystem {
+    type SystemData = (
+        Write<'s, Achievements>,
+        Read<'s, EventChannel<ScoreEvent>>,
+        Read<'s, EventChannel<GoalConceded>>,
+        Read<'s, EventChannel<GameWon>>,
+        Read<'s, Rally>,
+        Read<'s, Survival>,
+        Read<'s, GameMode>,
+        Read<'s, MatchScore>,
+        Read<'s, MatchRules>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut achievements,
+            score_events,
+            goals,
+            game_won,
+            rally,
+            survival,
+            mode,
+            match_score,
+            rules,
+        ): Self::SystemData,
+    ) {
+        for event in score_events.read(self.score_reader.as_mut().unwrap()) {
+            achievements.scored(event.side);
+        }
+        for goal in goals.read(self.goal_reader.as_mut().unwrap()) {
+            achievements.conceded(goal.side);
+        }
+        for won in game_won.read(self.game_won_reader.as_mut().unwrap()) {
+            // The game isn't added to the match score until the match has seen it.
+            let match_won = match_score.games(won.winner) + 1 >= rules.games_to_win();
+            achievements.game_won(won.winner, match_won);
+        }
+        if rally.current != self.rally {
+            self.rally = rally.current;
+            achievements.rally(rally.current);
+        }
+        if *mode == GameMode::Survival && !survival.over {
+            achievements.survived(survival.elapsed);
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.score_reader = Some(res.fetch_mut::<EventChannel<ScoreEvent>>().register_reader());
+        self.goal_reader = Some(res.fetch_mut::<EventChannel<GoalConceded>>().register_reader());
+        self.game_won_reader = Some(res.fetch_mut::<EventChannel<GameWon>>().register_reader());
+    }
+}
+
+/// Shows the achievements unlocked one after the other at the top of the screen, on every
+/// screen, for `TOAST_DURATION` each.
+#[derive(Default)]
+pub struct AchievementToastSystem {
+    /// The line being shown, the text showing it and how much longer it's shown for.
+    shown: Option<(String, Option<Entity>, f32)>,
+}
+
+impl<'s> System<'s> for AchievementToastSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, Achievements>,
+        Read<'s, Time>,
+        Option<ReadExpect<'s, Theme>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut achievements, time, theme, mut transforms, mut texts): Self::SystemData,
+    ) {
+        // Counted in real time, so it goes away while the game is paused too.
+        if let Some((_, entity, remaining)) = &mut self.shown {
+            *remaining -= time.delta_real_seconds();
+            if *remaining <= 0.0 {
+                if let Some(entity) = entity.filter(|entity| entities.is_alive(*entity)) {
+                    entities.delete(entity).expect("achievement toast should be alive");
+                }
+                self.shown = None;
+            }
+        }
+        if self.shown.is_none() {
+            self.shown = achievements.unlocked.pop_front().map(|achievement| {
+                let line = format!("Achievement unlocked: {}", achievement.name);
+                (line, None, TOAST_DURATION)
+            });
+        }
+        let theme = match theme {
+            Some(theme) => theme,
+            None => return,
+        };
+
+        // A match deletes every entity as it ends, the text is made again whenever it's gone.
+        if let Some((line, entity, _)) = &mut self.shown {
+            if entity.is_none_or(|entity| !entities.is_alive(entity)) {
+                let text = entities
+                    .build_entity()
+                    .with(
+                        UiTransform::new(
+                            "achievement_toast".to_string(), Anchor::TopMiddle, Anchor::TopMiddle,
+                            0., -70., 2., 400., 25.,
+                        ),
+                        &mut transforms,
+                    )
+                    .with(theme.text(line.clone(), 16.), &mut texts)
+                    .build();
+                *entity = Some(text);
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Float, Transform},
+    ecs::prelude::{Join, Read, ReadStorage, System, WriteStorage},
+};
+
+use crate::config::Gameplay;
+use crate::pong::{AiPaddle, Ball, Difficulty, Overtime, Paddle, Side};
+
+/// Moves paddles marked with `AiPaddle` towards the ball.
+///
+/// The AI only looks at the ball every `Difficulty::reaction_delay` seconds and
+/// can't move faster than `Difficulty::max_speed`, which is what makes it beatable.
+pub struct AiPaddleSystem;
+
+impl<'s> System<'s> for AiPaddleSystem {
+    type SystemData = (
+        WriteStorage<'s, AiPaddle>,
+        WriteStorage<'s, Paddle>,
+        ReadStorage<'s, Ball>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Difficulty>,
+        Read<'s, Time>,
+        Read<'s, Overtime>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut ais,
+            mut paddles,
+            balls,
+            mut transforms,
+            difficulty,
+            time,
+            overtime,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        // Gather the balls first since we need mutable access to the paddle transforms below.
+        let ball_states: Vec<(Vector2<f32>, Vector2<f32>)> = (&balls, &transforms)
+            .join()
+            .map(|(ball, transform)| {
+                (
+                    Vector2::new(
+                        transform.translation().x.as_f32(),
+                        transform.translation().y.as_f32(),
+                    ),
+                    ball.velocity,
+                )
+            })
+            .collect();
+
+        for (ai, paddle, transform) in (&mut ais, &mut paddles, &mut transforms).join() {
+            let paddle_position = Vector2::new(
+                transform.translation().x.as_f32(),
+                transform.translation().y.as_f32(),
+            );
+            // Left and right paddles move along y, top and bottom ones along x.
+            let (axis, half_length, arena_length) = if paddle.side.is_horizontal() {
+                (0, paddle.width * 0.5, gameplay.arena_width)
+            } else {
+                (1, paddle.height * 0.5, gameplay.arena_height)
+            };
+            let across = 1 - axis;
+
+            ai.reaction_timer -= time.delta_seconds();
+            if ai.reaction_timer <= 0.0 {
+                ai.reaction_timer = difficulty.reaction_delay();
+
+                // Follow the closest ball heading our way, or drift back to the middle.
+                let incoming = ball_states
+                    .iter()
+                    .filter(|(_, velocity)| match paddle.side {
+                        Side::Left => velocity.x < 0.0,
+                        Side::Right => velocity.x > 0.0,
+                        Side::Top => velocity.y > 0.0,
+                        Side::Bottom => velocity.y < 0.0,
+                    })
+                    .min_by(|(a, _), (b, _)| {
+                        (a[across] - paddle_position[across])
+                            .abs()
+                            .total_cmp(&(b[across] - paddle_position[across]).abs())
+                    });
+                ai.target = match incoming {
+                    Some((ball_position, _)) => ball_position[axis],
+                    None => arena_length / 2.0,
+                };
+            }
+
+            let position = paddle_position[axis];
+            let max_step = difficulty.max_speed() * paddle.speed_multiplier * time.delta_seconds();
+            let step = (ai.target - position).clamp(-max_step, max_step);
+            let (lane_start, lane_end) = paddle.lane.range(arena_length);
+            // Stay inside the walls during overtime.
+            let (lane_start, lane_end) = if paddle.side.is_horizontal() {
+                (lane_start, lane_end)
+            } else {
+                (
+                    lane_start.max(overtime.wall_inset),
+                    lane_end.min(arena_length - overtime.wall_inset),
+                )
+            };
+            let new_position =
+                (position + step).clamp(lane_start + half_length, lane_end - half_length);
+            if paddle.side.is_horizontal() {
+                transform.set_translation_x(Float::from(new_position));
+            } else {
+                transform.set_translation_y(Float::from(new_position));
+            }
+
+            if time.delta_seconds() > 0.0 {
+                paddle.velocity = (new_position - position) / time.delta_seconds();
+            }
+        }
+    }
+}
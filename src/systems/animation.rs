@@ -0,0 +1,47 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Join, Read, Resources, System, SystemData, WriteStorage},
+    renderer::SpriteRender,
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::pong::{Animation, BallHit, RunningState};
+
+/// Lights up the paddles balls come off and moves every `Animation` on to the frame it shows
+/// now. The animations hold still while the match is paused.
+#[derive(Default)]
+pub struct AnimationSystem {
+    hit_reader: Option<ReaderId<BallHit>>,
+}
+
+impl<'s> System<'s> for AnimationSystem {
+    type SystemData = (
+        WriteStorage<'s, Animation>,
+        WriteStorage<'s, SpriteRender>,
+        Read<'s, EventChannel<BallHit>>,
+        Read<'s, RunningState>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut animations, mut sprites, ball_hits, running, time): Self::SystemData) {
+        for hit in ball_hits.read(self.hit_reader.as_mut().unwrap()) {
+            if let Some(animation) = hit.paddle.and_then(|paddle| animations.get_mut(paddle)) {
+                animation.play(Animation::paddle_hit());
+            }
+        }
+        if *running != RunningState::Running {
+            return;
+        }
+
+        for (animation, sprite) in (&mut animations, &mut sprites).join() {
+            if let Some(sprite_number) = animation.advance(time.delta_seconds()) {
+                sprite.sprite_number = sprite_number;
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.hit_reader = Some(res.fetch_mut::<EventChannel<BallHit>>().register_reader());
+    }
+}
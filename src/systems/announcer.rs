@@ -0,0 +1,108 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::Source,
+    ecs::prelude::{Read, Resources, System, SystemData, WriteExpect},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::announcer::{Announcer, Line, RALLY_MILESTONES};
+use crate::audio::Music;
+use crate::config::{AudioSettings, GameConfig};
+use crate::pong::{MatchRules, MatchScore, Rally, ScoreEvent};
+
+/// How long the music stays turned down after each check while the announcer is talking.
+const ANNOUNCER_DUCK: f32 = 0.2;
+
+/// Has the announcer call out every point, a player getting to match point and the rally
+/// reaching one of the `RALLY_MILESTONES`, unless it's turned off in the settings. The lines are
+/// said one at a time with the music turned down under them.
+#[derive(Default)]
+pub struct AnnouncerSystem {
+    score_reader: Option<ReaderId<ScoreEvent>>,
+    /// The rally length the last time the system ran, a milestone is called out as the rally
+    /// gets past it.
+    last_rally: u32,
+}
+
+impl<'s> System<'s> for AnnouncerSystem {
+    type SystemData = (
+        Option<WriteExpect<'s, Announcer>>,
+        Option<WriteExpect<'s, Music>>,
+        Read<'s, EventChannel<ScoreEvent>>,
+        Read<'s, Rally>,
+        Read<'s, MatchRules>,
+        Read<'s, MatchScore>,
+        Read<'s, GameConfig>,
+        Read<'s, AudioSettings>,
+        Read<'s, AssetStorage<Source>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            announcer,
+            mut music,
+            score_events,
+            rally,
+            rules,
+            match_score,
+            config,
+            audio_settings,
+            storage,
+        ): Self::SystemData,
+    ) {
+        // Read the events even without an announcer, so they don't pile up.
+        let events = score_events.read(self.score_reader.as_mut().unwrap());
+        let mut announcer = match announcer {
+            Some(announcer) => announcer,
+            None => return,
+        };
+
+        if config.announcer {
+            for event in events {
+                announcer.announce(Line::Scores(event.side));
+                // One point away from winning the game that wins the match, time attack games
+                // have no match point.
+                if rules.time_limit.is_none()
+                    && event.score == rules.win_score - 1
+                    && match_score.games(event.side) + 1 >= rules.games_to_win()
+                {
+                    announcer.announce(Line::MatchPoint);
+                }
+            }
+            if let Some(milestone) = RALLY_MILESTONES
+                .iter()
+                .rev()
+                .find(|milestone| rally.current >= **milestone && self.last_rally < **milestone)
+            {
+                announcer.announce(Line::Rally(*milestone));
+            }
+        }
+        self.last_rally = rally.current;
+
+        // The next line waits until the one before it is over.
+        let speaking = match &announcer.sink {
+            Some(sink) => !sink.empty(),
+            None => return,
+        };
+        let clip = if speaking { None } else { announcer.next_clip() };
+        if let Some(sink) = &mut announcer.sink {
+            sink.set_volume(audio_settings.sfx_volume());
+            if let Some(source) = clip.as_ref().and_then(|clip| storage.get(clip)) {
+                if let Err(err) = sink.append(source) {
+                    log::warn!("Failed to play the announcer: {}", err);
+                }
+            }
+            if !sink.empty() {
+                if let Some(music) = &mut music {
+                    music.duck(ANNOUNCER_DUCK);
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.score_reader = Some(res.fetch_mut::<EventChannel<ScoreEvent>>().register_reader());
+    }
+}
@@ -0,0 +1,61 @@
+use amethyst::{
+    core::Transform,
+    ecs::prelude::{Entities, Join, Read, ReadStorage, System, WriteStorage},
+    renderer::{resources::Tint, Transparent},
+};
+
+use crate::config::Gameplay;
+use crate::palette::{faded_tint, Palette};
+use crate::pong::{Ball, Mutator, Mutators, BLINK_FADE_END, BLINK_FADE_START};
+
+/// Fades the balls in and out depending on how far they are from the left and right edges,
+/// when the blink mutator is on.
+pub struct BlinkSystem;
+
+impl<'s> System<'s> for BlinkSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Transform>,
+        WriteStorage<'s, Tint>,
+        WriteStorage<'s, Transparent>,
+        Read<'s, Mutators>,
+        Read<'s, Palette>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            balls,
+            transforms,
+            mut tints,
+            mut transparent,
+            mutators,
+            palette,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        if !mutators.is_on(Mutator::Blink) {
+            return;
+        }
+
+        for (entity, _, transform) in (&entities, &balls, &transforms).join() {
+            let ball_x = transform.translation().x.as_f32();
+            let width = gameplay.arena_width;
+            let from_edge = ball_x.min(width - ball_x) / width;
+            let alpha = ((BLINK_FADE_END - from_edge) / (BLINK_FADE_END - BLINK_FADE_START))
+                .clamp(0.0, 1.0);
+
+            tints
+                .insert(entity, faded_tint(palette.ball, alpha))
+                .expect("ball entity should be alive");
+            if !transparent.contains(entity) {
+                transparent
+                    .insert(entity, Transparent)
+                    .expect("ball entity should be alive");
+            }
+        }
+    }
+}
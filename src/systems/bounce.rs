@@ -1,37 +1,202 @@
 use amethyst::{
-    core::{Float, Transform},
-    ecs::prelude::{Join, ReadStorage, System, WriteStorage},
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::{math::Vector2, Float, Transform},
+    ecs::prelude::{
+        Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write, WriteStorage,
+    },
+    shrev::EventChannel,
 };
 
-use crate::pong::{Ball, Side, Paddle, ARENA_HEIGHT};
+use crate::audio::{play_bounce_sound, play_wall_sound, Sounds};
+use crate::config::{AudioSettings, Gameplay};
+use crate::net::Online;
+use crate::pong::{
+    ActivePowerUp, Ball, BallHit, Brick, BrickBroken, GameMode, GoalZones, Mutator, Mutators,
+    Obstacle, Overtime, Paddle, PowerShot, PowerUpKind, Rally, ScreenShake, Serving, Side,
+    BALL_MAX_BOUNCE_ANGLE, BALL_MAX_SPIN, BALL_SPIN_TRANSFER, GRAVITY_RESTITUTION,
+    POWER_SHOT_SHAKE_AMOUNT, POWER_SHOT_SHAKE_DURATION, POWER_SHOT_SPEED_MULTIPLIER,
+    SERVE_AI_DELAY,
+};
 
 pub struct BounceSystem;
 
 impl<'s> System<'s> for BounceSystem {
     type SystemData = (
+        Entities<'s>,
         WriteStorage<'s, Ball>,
         ReadStorage<'s, Paddle>,
+        WriteStorage<'s, PowerShot>,
+        ReadStorage<'s, Obstacle>,
+        ReadStorage<'s, Brick>,
+        ReadStorage<'s, ActivePowerUp>,
+        WriteStorage<'s, Serving>,
         ReadStorage<'s, Transform>,
+        Read<'s, GameMode>,
+        Read<'s, Overtime>,
+        Write<'s, ScreenShake>,
+        Read<'s, Mutators>,
+        Read<'s, GoalZones>,
+        Write<'s, EventChannel<BrickBroken>>,
+        Write<'s, EventChannel<BallHit>>,
+        Write<'s, Rally>,
+        Read<'s, AssetStorage<Source>>,
+        Option<ReadExpect<'s, Sounds>>,
+        Option<Read<'s, Output>>,
+        Read<'s, AudioSettings>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Online>,
+        Read<'s, Gameplay>,
     );
 
-    fn run(&mut self, (mut balls, paddles, transforms): Self::SystemData) {
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut balls,
+            paddles,
+            mut shots,
+            obstacles,
+            bricks,
+            active_power_ups,
+            mut serving,
+            transforms,
+            mode,
+            overtime,
+            mut shake,
+            mutators,
+            goal_zones,
+            mut brick_broken,
+            mut ball_hits,
+            mut rally,
+            audio_storage,
+            sounds,
+            audio_output,
+            audio_settings,
+            lazy,
+            online,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        // Online matches bounce the ball in the `OnlineSystem`.
+        if online.in_match() {
+            return;
+        }
+
+        // The walls close in during overtime.
+        let bottom_wall = overtime.wall_inset;
+        let top_wall = gameplay.arena_height - overtime.wall_inset;
+
+        // Paddles with a sticky power-up catch the ball instead of returning it.
+        let sticky: Vec<_> = active_power_ups
+            .join()
+            .filter(|power_up| power_up.kind == PowerUpKind::Sticky && power_up.remaining > 0.0)
+            .map(|power_up| power_up.target)
+            .collect();
+        let mut caught = Vec::new();
+        // Which sounds to play once every ball has been moved, each at most once a frame, and
+        // how far across the arena the ball was when it bounced.
+        let mut hit_wall = None;
+        let mut hit_paddle = None;
+
         // Check whether a ball collided, and bounce off accordingly.
         //
         // We also check for the velocity of the ball every time, to prevent multiple collisions
         // from occurring.
-        for (ball, transform) in (&mut balls, &transforms).join() {
+        for (ball_entity, ball, transform) in (&entities, &mut balls, &transforms).join() {
             let ball_x = transform.translation().x;
             let ball_y = transform.translation().y;
+            // Whatever the ball bounced off, it comes out with another velocity.
+            let incoming = ball.velocity;
+            let mut struck = None;
 
-            // Bounce at the top or the bottom of the arena.
-            if (ball_y.as_f32() <= ball.radius && ball.velocity[1] < 0.0)
-                || (ball_y.as_f32() >= ARENA_HEIGHT - ball.radius && ball.velocity[1] > 0.0)
+            // Bounce at the top or the bottom of the arena, unless there are paddles guarding
+            // them or the walls are gone.
+            if *mode != GameMode::FourPlayer && !mutators.is_on(Mutator::NoWalls) {
+                let hit_floor =
+                    ball_y.as_f32() <= bottom_wall + ball.radius && ball.velocity.y < 0.0;
+                let hit_ceiling =
+                    ball_y.as_f32() >= top_wall - ball.radius && ball.velocity.y > 0.0;
+                if hit_floor || hit_ceiling {
+                    ball.velocity.y = -ball.velocity.y;
+                    // Mirror the curve along with the path.
+                    ball.spin = -ball.spin;
+                    hit_wall = Some(ball_x.as_f32() / gameplay.arena_width);
+                }
+                // With gravity on the floor takes a bit out of every bounce.
+                if hit_floor && mutators.is_on(Mutator::Gravity) {
+                    ball.velocity.y *= GRAVITY_RESTITUTION;
+                }
+            }
+
+            // In survival the right edge is a wall too.
+            if *mode == GameMode::Survival
+                && ball_x.as_f32() >= gameplay.arena_width - ball.radius
+                && ball.velocity.x > 0.0
             {
-                ball.velocity[1] = -ball.velocity[1];
+                ball.velocity.x = -ball.velocity.x;
+                ball.spin = -ball.spin;
+                hit_wall = Some(ball_x.as_f32() / gameplay.arena_width);
+            }
+
+            // With moving goal zones the left and right edges only let the ball through inside
+            // their scoring window.
+            if (ball_x.as_f32() <= ball.radius
+                && ball.velocity.x < 0.0
+                && !goal_zones.contains(Side::Left, ball_y.as_f32()))
+                || (ball_x.as_f32() >= gameplay.arena_width - ball.radius
+                    && ball.velocity.x > 0.0
+                    && !goal_zones.contains(Side::Right, ball_y.as_f32()))
+            {
+                ball.velocity.x = -ball.velocity.x;
+                ball.spin = -ball.spin;
+                hit_wall = Some(ball_x.as_f32() / gameplay.arena_width);
+            }
+
+            // Bounce off obstacles.
+            for (obstacle, obstacle_transform) in (&obstacles, &transforms).join() {
+                let hit = reflect_off_rect(
+                    ball,
+                    ball_x.as_f32(),
+                    ball_y.as_f32(),
+                    obstacle_transform.translation().x.as_f32(),
+                    obstacle_transform.translation().y.as_f32(),
+                    obstacle.width * 0.5,
+                    obstacle.height * 0.5,
+                );
+                if hit {
+                    hit_wall = Some(ball_x.as_f32() / gameplay.arena_width);
+                }
+            }
+
+            // Bounce off bricks, which break.
+            for (entity, brick, brick_transform) in (&entities, &bricks, &transforms).join() {
+                if !entities.is_alive(entity) {
+                    continue;
+                }
+                let hit = reflect_off_rect(
+                    ball,
+                    ball_x.as_f32(),
+                    ball_y.as_f32(),
+                    brick_transform.translation().x.as_f32(),
+                    brick_transform.translation().y.as_f32(),
+                    brick.width * 0.5,
+                    brick.height * 0.5,
+                );
+                if hit {
+                    hit_wall = Some(ball_x.as_f32() / gameplay.arena_width);
+                    entities.delete(entity).expect("brick entity should be alive");
+                    brick_broken.single_write(BrickBroken {
+                        side: ball.last_hit,
+                        points: brick.points,
+                    });
+                }
             }
 
             // Bounce at the paddles.
-            for (paddle, paddle_transform) in (&paddles, &transforms).join() {
+            for (paddle_entity, paddle, paddle_transform, shot) in
+                (&entities, &paddles, &transforms, (&mut shots).maybe()).join()
+            {
                 let paddle_x = paddle_transform.translation().x - Float::from(paddle.width * 0.5);
                 let paddle_y = paddle_transform.translation().y - Float::from(paddle.height * 0.5);
 
@@ -48,15 +213,160 @@ impl<'s> System<'s> for BounceSystem {
                     paddle_x + (paddle.width + ball.radius).into(),
                     paddle_y + (paddle.height + ball.radius).into(),
                 ) {
-                    if (paddle.side == Side::Left && ball.velocity[0] < 0.0)
-                        || (paddle.side == Side::Right && ball.velocity[0] > 0.0)
-                    {
-                        ball.velocity[0] = -ball.velocity[0];
+                    // `normal` points from the paddle into the arena, `tangent` along the
+                    // paddle in the direction its `velocity` is measured in.
+                    let normal = paddle.side.normal();
+                    let tangent = paddle.side.tangent();
+                    // Only bounce when the ball is moving into the paddle.
+                    if ball.velocity.dot(&normal) >= 0.0 {
+                        continue;
+                    }
+
+                    // Where the ball struck the paddle, from -1.0 at one end to 1.0 at the
+                    // other. A hit in the center returns the ball flat, hits closer to the
+                    // ends send it off at a steeper angle.
+                    let ball_position = Vector2::new(ball_x.as_f32(), ball_y.as_f32());
+                    let paddle_center = Vector2::new(
+                        paddle_transform.translation().x.as_f32(),
+                        paddle_transform.translation().y.as_f32(),
+                    );
+                    let half_length = if paddle.side.is_horizontal() {
+                        paddle.width * 0.5
+                    } else {
+                        paddle.height * 0.5
+                    };
+                    let offset = ((ball_position - paddle_center).dot(&tangent)
+                        / (half_length + ball.radius))
+                        .clamp(-1.0, 1.0);
+
+                    // Keep the speed, only the direction changes. Unless the ball comes off a
+                    // power shot, which only lasts until the next paddle, or this paddle fires
+                    // one.
+                    let mut speed = ball.velocity.norm();
+                    if ball.power_shot {
+                        speed /= POWER_SHOT_SPEED_MULTIPLIER;
+                        ball.power_shot = false;
+                    }
+                    if let Some(shot) = shot {
+                        if shot.charge >= 1.0 {
+                            speed *= POWER_SHOT_SPEED_MULTIPLIER;
+                            ball.power_shot = true;
+                            shake.start(POWER_SHOT_SHAKE_DURATION, POWER_SHOT_SHAKE_AMOUNT);
+                        }
+                        shot.charge = 0.0;
+                    }
+                    let (velocity, spin) =
+                        paddle_return(paddle.side, offset, speed, paddle.velocity);
+                    ball.velocity = velocity;
+                    ball.spin = spin;
+                    ball.last_hit = Some(paddle.side);
+                    rally.current += 1;
+                    rally.best = rally.best.max(rally.current);
+                    hit_paddle = Some(ball_x.as_f32() / gameplay.arena_width);
+                    struck = Some(paddle_entity);
+
+                    // A caught ball goes out flat, the release is aimed like a serve.
+                    if sticky.contains(&paddle_entity) {
+                        ball.spin = 0.0;
+                        caught.push((ball_entity, paddle.side, paddle_entity));
                     }
                 }
             }
+
+            if ball.velocity != incoming {
+                ball_hits.single_write(BallHit {
+                    position: Vector2::new(ball_x.as_f32(), ball_y.as_f32()),
+                    velocity: ball.velocity,
+                    paddle: struck,
+                });
+            }
+        }
+
+        if let Some(sounds) = &sounds {
+            let output = audio_output.as_deref();
+            let volume = audio_settings.sfx_volume();
+            if let Some(x) = hit_paddle {
+                play_bounce_sound(sounds, &audio_storage, output, volume, x, &entities, &lazy);
+            }
+            if let Some(x) = hit_wall {
+                play_wall_sound(sounds, &audio_storage, output, volume, x, &entities, &lazy);
+            }
+        }
+
+        // Caught balls are held by the `ServeSystem` until the player serves them again, the
+        // speed they came in with is kept for the release.
+        for (ball_entity, side, paddle_entity) in caught {
+            serving
+                .insert(
+                    ball_entity,
+                    Serving {
+                        side,
+                        paddle: Some(paddle_entity),
+                        timer: SERVE_AI_DELAY,
+                    },
+                )
+                .expect("ball entity should be alive");
+        }
+    }
+}
+
+/// The velocity and spin a ball going at `speed` comes off the paddle on `side` with, having
+/// struck it `offset` from its center, from -1.0 at one end to 1.0 at the other.
+///
+/// A moving paddle puts spin on the ball that curves it the way the paddle was moving. The sign
+/// of the cross product turns that into the right direction of rotation for the side the paddle
+/// is on.
+pub fn paddle_return(
+    side: Side,
+    offset: f32,
+    speed: f32,
+    paddle_velocity: f32,
+) -> (Vector2<f32>, f32) {
+    let normal = side.normal();
+    let tangent = side.tangent();
+    let angle = offset * BALL_MAX_BOUNCE_ANGLE;
+    let velocity = (normal * angle.cos() + tangent * angle.sin()) * speed;
+    let handedness = normal.x * tangent.y - normal.y * tangent.x;
+    let spin =
+        (handedness * paddle_velocity * BALL_SPIN_TRANSFER).clamp(-BALL_MAX_SPIN, BALL_MAX_SPIN);
+    (velocity, spin)
+}
+
+// Reflects the ball off an axis aligned rectangle with the given center and half extents, and
+// returns whether it did.
+//
+// Like with the paddles the rectangle is grown by the ball radius. When the ball is inside it
+// we bounce off whichever side the ball overlaps the least, since that is the side it most
+// likely came through.
+fn reflect_off_rect(
+    ball: &mut Ball,
+    ball_x: f32,
+    ball_y: f32,
+    center_x: f32,
+    center_y: f32,
+    half_width: f32,
+    half_height: f32,
+) -> bool {
+    let dx = ball_x - center_x;
+    let dy = ball_y - center_y;
+    let overlap_x = half_width + ball.radius - dx.abs();
+    let overlap_y = half_height + ball.radius - dy.abs();
+    if overlap_x < 0.0 || overlap_y < 0.0 {
+        return false;
+    }
+
+    if overlap_x < overlap_y {
+        // Hit the left or right side, only bounce when moving into the rectangle.
+        if dx * ball.velocity.x < 0.0 {
+            ball.velocity.x = -ball.velocity.x;
+            return true;
         }
+    } else if dy * ball.velocity.y < 0.0 {
+        ball.velocity.y = -ball.velocity.y;
+        ball.spin = -ball.spin;
+        return true;
     }
+    false
 }
 
 // A point is in a box when its coordinates are smaller or equal than the top
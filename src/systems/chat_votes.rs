@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Read, ReadExpect, System, Write, WriteStorage},
+    ui::UiText,
+};
+
+use crate::pong::{ChatVoteText, ChatVotes};
+use crate::twitch::TwitchChat;
+
+/// Seconds the chat's votes are counted over before the chat paddle follows them.
+const VOTE_WINDOW: f32 = 1.0;
+
+/// Turns the votes cast in the `TwitchChat` into the `ChatVotes` the chat paddle of a chat plays
+/// match moves by. Votes are counted over a `VOTE_WINDOW`, every viewer once by the last one
+/// they cast, and the paddle follows the count for the whole of the next window: up at full
+/// speed when everyone voted up, still when the votes are even. The tally of every count is
+/// shown in the `ChatVoteText`.
+#[derive(Default)]
+pub struct ChatVoteSystem {
+    /// Whether every viewer who voted in this window voted up, by their name.
+    ballot: HashMap<String, bool>,
+    counted: f32,
+}
+
+impl<'s> System<'s> for ChatVoteSystem {
+    type SystemData = (
+        Read<'s, TwitchChat>,
+        Write<'s, ChatVotes>,
+        Option<ReadExpect<'s, ChatVoteText>>,
+        WriteStorage<'s, UiText>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (chat, mut votes, vote_text, mut ui_text, time): Self::SystemData) {
+        for vote in chat.votes() {
+            self.ballot.insert(vote.viewer, vote.up);
+        }
+        self.counted += time.delta_seconds();
+        if self.counted < VOTE_WINDOW {
+            return;
+        }
+        self.counted = 0.0;
+
+        let up = self.ballot.values().filter(|up| **up).count();
+        let down = self.ballot.len() - up;
+        votes.movement = if self.ballot.is_empty() {
+            0.0
+        } else {
+            (up as f32 - down as f32) / self.ballot.len() as f32
+        };
+        if let Some(text) = vote_text.and_then(|text| ui_text.get_mut(text.0)) {
+            text.text = format!("chat: {} up, {} down", up, down);
+        }
+        self.ballot.clear();
+    }
+}
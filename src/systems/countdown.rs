@@ -0,0 +1,44 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Read, ReadExpect, System, Write, WriteStorage},
+    ui::UiText,
+};
+
+use crate::pong::{CountdownText, ServeCountdown, COUNTDOWN_FONT_END, COUNTDOWN_FONT_START};
+
+/// Runs the `ServeCountdown` after a point and shows it as 3, 2, 1 in the middle of the
+/// arena. Each number starts big and shrinks over its second on the screen.
+pub struct CountdownSystem;
+
+impl<'s> System<'s> for CountdownSystem {
+    type SystemData = (
+        Write<'s, ServeCountdown>,
+        Option<ReadExpect<'s, CountdownText>>,
+        WriteStorage<'s, UiText>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut countdown, countdown_text, mut ui_text, time): Self::SystemData) {
+        let text = match countdown_text.and_then(|text| ui_text.get_mut(text.0)) {
+            Some(text) => text,
+            None => return,
+        };
+
+        if countdown.remaining <= 0.0 {
+            if !text.text.is_empty() {
+                text.text.clear();
+            }
+            return;
+        }
+
+        countdown.remaining -= time.delta_seconds();
+        if countdown.remaining <= 0.0 {
+            text.text.clear();
+            return;
+        }
+        // How much of the current number's second is left, from 1 down to 0.
+        let left = countdown.remaining - countdown.remaining.ceil() + 1.0;
+        text.text = countdown.remaining.ceil().to_string();
+        text.font_size = COUNTDOWN_FONT_END + (COUNTDOWN_FONT_START - COUNTDOWN_FONT_END) * left;
+    }
+}
@@ -0,0 +1,105 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::timing::Time,
+    ecs::prelude::{Read, ReadExpect, Resources, System, SystemData, WriteExpect},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::audio::{
+    play_cheer, silence, Crowd, Music, Track, CROWD_CALM, CROWD_CALM_DOWN, CROWD_RALLY_PEAK,
+    MUSIC_CROSSFADE,
+};
+use crate::config::AudioSettings;
+use crate::pong::{Rally, ScoreEvent};
+
+/// Plays the crowd during a match, fading them in and out with the match music. They get more
+/// excited as the rally goes on, and erupt when someone scores before calming down again.
+#[derive(Default)]
+pub struct CrowdSystem {
+    score_reader: Option<ReaderId<ScoreEvent>>,
+}
+
+impl<'s> System<'s> for CrowdSystem {
+    type SystemData = (
+        Option<WriteExpect<'s, Crowd>>,
+        Option<ReadExpect<'s, Music>>,
+        Read<'s, EventChannel<ScoreEvent>>,
+        Read<'s, Rally>,
+        Read<'s, Time>,
+        Read<'s, AudioSettings>,
+        Read<'s, AssetStorage<Source>>,
+        Option<Read<'s, Output>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            crowd,
+            music,
+            score_events,
+            rally,
+            time,
+            audio_settings,
+            storage,
+            output,
+        ): Self::SystemData,
+    ) {
+        // Read the events even without a crowd, so they don't pile up.
+        let scored = score_events.read(self.score_reader.as_mut().unwrap()).count() > 0;
+        let (mut crowd, output) = match (crowd, output) {
+            (Some(crowd), Some(output)) => (crowd, output),
+            _ => return,
+        };
+        let in_match = music.map(|music| music.playing) == Some(Some(Track::Game));
+
+        let step = time.delta_seconds() / MUSIC_CROSSFADE;
+        crowd.fade = if in_match {
+            (crowd.fade + step).min(1.0)
+        } else {
+            (crowd.fade - step).max(0.0)
+        };
+
+        // A score gets the crowd on their feet, from there they calm down towards what the rally
+        // asks for.
+        let rally_excitement = (rally.current as f32 / CROWD_RALLY_PEAK as f32).min(1.0);
+        if scored && in_match {
+            crowd.excitement = 1.0;
+            play_cheer(&crowd, &storage, Some(&output), audio_settings.sfx_volume());
+        } else if crowd.excitement > rally_excitement {
+            crowd.excitement =
+                (crowd.excitement - time.delta_seconds() / CROWD_CALM_DOWN).max(rally_excitement);
+        } else {
+            crowd.excitement = rally_excitement;
+        }
+
+        let volume = (CROWD_CALM + (1.0 - CROWD_CALM) * crowd.excitement)
+            * crowd.fade
+            * audio_settings.sfx_volume();
+        let murmur = crowd.murmur.clone();
+        let fade = crowd.fade;
+        let sink = match &mut crowd.sink {
+            Some(sink) => sink,
+            None => return,
+        };
+        if fade <= 0.0 {
+            silence(sink, &output);
+            return;
+        }
+        sink.set_volume(volume);
+        // Queue the murmur again as it runs out, so it loops.
+        if sink.empty() {
+            if let Some(source) = storage.get(&murmur) {
+                if let Err(err) = sink.append(source) {
+                    log::warn!("Failed to play the crowd: {}", err);
+                    crowd.sink = None;
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.score_reader = Some(res.fetch_mut::<EventChannel<ScoreEvent>>().register_reader());
+    }
+}
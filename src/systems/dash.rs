@@ -0,0 +1,64 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, WriteStorage},
+    input::{InputHandler, StringBindings},
+    ui::{UiImage, UiTransform},
+};
+
+use crate::{
+    pong::{Dash, Lane, Paddle, Side, DASH_BAR_WIDTH, DASH_COOLDOWN, DASH_DURATION},
+    theme::Theme,
+};
+
+/// Starts a dash when a player presses their dash action and the previous one has cooled
+/// down, and keeps each paddle's cooldown bar in the UI up to date.
+///
+/// The `PaddleSystem` speeds up paddles while their dash is running.
+pub struct DashSystem;
+
+impl<'s> System<'s> for DashSystem {
+    type SystemData = (
+        WriteStorage<'s, Dash>,
+        ReadStorage<'s, Paddle>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiImage>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+        ReadExpect<'s, Theme>,
+    );
+
+    fn run(
+        &mut self,
+        (mut dashes, paddles, mut ui_transforms, mut images, input, time, theme): Self::SystemData,
+    ) {
+        for (dash, paddle) in (&mut dashes, &paddles).join() {
+            dash.remaining = (dash.remaining - time.delta_seconds()).max(0.0);
+            dash.cooldown = (dash.cooldown - time.delta_seconds()).max(0.0);
+
+            let action = match (paddle.side, paddle.lane) {
+                (Side::Left, Lane::Lower) => "left_lower_dash",
+                (Side::Right, Lane::Lower) => "right_lower_dash",
+                (Side::Left, _) => "left_dash",
+                (Side::Right, _) => "right_dash",
+                (Side::Top, _) => "top_dash",
+                (Side::Bottom, _) => "bottom_dash",
+            };
+            if dash.cooldown <= 0.0 && input.action_is_down(action).unwrap_or(false) {
+                dash.remaining = DASH_DURATION;
+                dash.cooldown = DASH_COOLDOWN;
+            }
+
+            // The bar fills back up while the dash cools down, and lights up once it's ready.
+            if let Some(bar) = ui_transforms.get_mut(dash.bar) {
+                bar.width = DASH_BAR_WIDTH * (1.0 - dash.cooldown / DASH_COOLDOWN);
+            }
+            if let Some(image) = images.get_mut(dash.bar) {
+                *image = if dash.cooldown <= 0.0 {
+                    UiImage::SolidColor(theme.dash_ready_color)
+                } else {
+                    UiImage::SolidColor(theme.dash_cooldown_color)
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use amethyst::ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System};
+use discord_rpc_client::Client;
+
+use crate::audio::{Music, Track};
+use crate::config::GameConfig;
+use crate::net::Online;
+use crate::pong::{AiPaddle, GameMode, RunningState, ScoreBoard};
+use crate::presence::describe_activity;
+
+/// Shows what the player is up to on their Discord profile through Rich Presence, "In menu" or
+/// "Playing 2P match, 7–5". It tells the menus from a match by the music playing, like the
+/// crowd does, and only talks to Discord when the line changes.
+///
+/// Discord is only talked to while `GameConfig::discord_presence` is on and an application is
+/// set in `discord_application_id`, turning it off clears the status.
+#[derive(Default)]
+pub struct DiscordPresenceSystem {
+    /// Started the first time there's something to show.
+    client: Option<Client>,
+    /// The line on the profile, `None` while it's clear.
+    shown: Option<String>,
+}
+
+impl<'s> System<'s> for DiscordPresenceSystem {
+    type SystemData = (
+        Read<'s, GameConfig>,
+        Option<ReadExpect<'s, Music>>,
+        Read<'s, RunningState>,
+        Read<'s, GameMode>,
+        Read<'s, ScoreBoard>,
+        Read<'s, Online>,
+        ReadStorage<'s, AiPaddle>,
+    );
+
+    fn run(
+        &mut self,
+        (config, music, running, mode, score, online, ai_paddles): Self::SystemData,
+    ) {
+        let application = match config.discord_application_id {
+            Some(application) if config.discord_presence => application,
+            _ => {
+                if let (Some(client), Some(_)) = (self.client.as_mut(), self.shown.take()) {
+                    if let Err(err) = client.clear_activity() {
+                        log::warn!("Failed to clear the Discord status: {}", err);
+                    }
+                }
+                return;
+            }
+        };
+
+        let in_match = music.map(|music| music.playing) == Some(Some(Track::Game));
+        let against_ai = (&ai_paddles).join().next().is_some();
+        let line = describe_activity(in_match, *running, *mode, &score, &online, against_ai);
+        if self.shown.as_ref() == Some(&line) {
+            return;
+        }
+
+        let client = self.client.get_or_insert_with(|| {
+            let mut client = Client::new(application);
+            client.start();
+            client
+        });
+        // Discord may not be running, the status is tried again the next time it changes.
+        if let Err(err) = client.set_activity(|activity| activity.details(line.clone())) {
+            log::warn!("Failed to set the Discord status: {}", err);
+        }
+        self.shown = Some(line);
+    }
+}
@@ -0,0 +1,89 @@
+use amethyst::{
+    ecs::prelude::{
+        Entities, Entity, Read, ReadExpect, Resources, System, SystemData, WriteStorage,
+    },
+    input::InputEvent,
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, UiText, UiTransform},
+    utils::fps_counter::FPSCounter,
+};
+
+use crate::theme::Theme;
+
+/// Shows how many frames a second are drawn, right now and on average, in the top right corner
+/// of every screen. The `toggle_fps` action shows and hides it.
+#[derive(Default)]
+pub struct FpsSystem {
+    input_reader: Option<ReaderId<InputEvent<String>>>,
+    shown: bool,
+    text: Option<Entity>,
+}
+
+impl<'s> System<'s> for FpsSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, EventChannel<InputEvent<String>>>,
+        Read<'s, FPSCounter>,
+        Option<ReadExpect<'s, Theme>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, input_events, counter, theme, mut transforms, mut texts): Self::SystemData,
+    ) {
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            if let InputEvent::ActionPressed(action) = event {
+                if action == "toggle_fps" {
+                    self.shown = !self.shown;
+                }
+            }
+        }
+
+        // A match deletes every entity as it ends, the counter included, so it's made again
+        // whenever it's gone.
+        let text = self.text.filter(|entity| entities.is_alive(*entity));
+        if !self.shown {
+            if let Some(entity) = text {
+                entities.delete(entity).expect("fps counter should be alive");
+            }
+            self.text = None;
+            return;
+        }
+        let theme = match theme {
+            Some(theme) => theme,
+            None => return,
+        };
+
+        let entity = text.unwrap_or_else(|| {
+            entities
+                .build_entity()
+                .with(
+                    UiTransform::new(
+                        "fps_counter".to_string(), Anchor::TopRight, Anchor::TopRight,
+                        -8., -8., 2., 220., 20.,
+                    ),
+                    &mut transforms,
+                )
+                .with(theme.text(String::new(), 14.), &mut texts)
+                .build()
+        });
+        self.text = Some(entity);
+        if let Some(text) = texts.get_mut(entity) {
+            text.text = format!(
+                "{:.0} fps, {:.0} on average",
+                counter.frame_fps(),
+                counter.sampled_fps()
+            );
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
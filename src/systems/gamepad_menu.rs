@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Read, Resources, System, SystemData, Write},
+    input::{ControllerAxis, ControllerButton, InputEvent, InputHandler, StringBindings},
+    shrev::{EventChannel, ReaderId},
+};
+
+/// How far a stick has to be pushed to move through a menu.
+const STICK_THRESHOLD: f64 = 0.5;
+/// Seconds a direction is held before it starts repeating, and between the repeats after that.
+const REPEAT_DELAY: f32 = 0.4;
+const REPEAT_INTERVAL: f32 = 0.12;
+
+/// The `menu_*` actions `menu_input` reads, sent for the buttons of every gamepad and the
+/// directions of their d-pads.
+const BUTTON_ACTIONS: [(ControllerButton, &str); 4] = [
+    (ControllerButton::A, "menu_select"),
+    (ControllerButton::B, "menu_back"),
+    (ControllerButton::Back, "menu_back"),
+    (ControllerButton::Start, "menu_start"),
+];
+const DPAD_ACTIONS: [(ControllerButton, &str); 4] = [
+    (ControllerButton::DPadUp, "menu_up"),
+    (ControllerButton::DPadDown, "menu_down"),
+    (ControllerButton::DPadLeft, "menu_left"),
+    (ControllerButton::DPadRight, "menu_right"),
+];
+
+/// Lets any gamepad move through the menus, sending the `menu_*` actions for its buttons and
+/// for its d-pad or left stick being pushed, repeated while they're held. The sticks aren't
+/// buttons the bindings could turn into actions, so every gamepad is read here instead.
+#[derive(Default)]
+pub struct GamepadMenuSystem {
+    event_reader: Option<ReaderId<InputEvent<String>>>,
+    /// Where the left stick of every gamepad is, across and up.
+    sticks: HashMap<u32, (f64, f64)>,
+    /// The direction every gamepad is held in, and the time left until it repeats.
+    held: HashMap<u32, (&'static str, f32)>,
+}
+
+impl<'s> System<'s> for GamepadMenuSystem {
+    type SystemData = (
+        Write<'s, EventChannel<InputEvent<String>>>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut events, input, time): Self::SystemData) {
+        let mut actions = Vec::new();
+        for event in events.read(self.event_reader.as_mut().unwrap()) {
+            match event {
+                InputEvent::ControllerAxisMoved { which, axis, value } => {
+                    let stick = self.sticks.entry(*which).or_insert((0.0, 0.0));
+                    match axis {
+                        ControllerAxis::LeftX => stick.0 = *value,
+                        // SDL has the stick's y going down.
+                        ControllerAxis::LeftY => stick.1 = -*value,
+                        _ => {}
+                    }
+                }
+                InputEvent::ControllerButtonPressed { button, .. } => {
+                    let action = BUTTON_ACTIONS.iter().find(|(pressed, _)| pressed == button);
+                    actions.extend(action.map(|(_, action)| *action));
+                }
+                InputEvent::ControllerDisconnected { which } => {
+                    self.sticks.remove(which);
+                    self.held.remove(which);
+                }
+                _ => {}
+            }
+        }
+
+        for gamepad in input.connected_controllers() {
+            let direction = match self.direction(&input, gamepad) {
+                Some(direction) => direction,
+                None => {
+                    self.held.remove(&gamepad);
+                    continue;
+                }
+            };
+            match self.held.get_mut(&gamepad) {
+                Some((held, repeat)) if *held == direction => {
+                    *repeat -= time.delta_seconds();
+                    if *repeat <= 0.0 {
+                        *repeat = REPEAT_INTERVAL;
+                        actions.push(direction);
+                    }
+                }
+                _ => {
+                    self.held.insert(gamepad, (direction, REPEAT_DELAY));
+                    actions.push(direction);
+                }
+            }
+        }
+
+        for action in actions {
+            events.single_write(InputEvent::ActionPressed(action.to_string()));
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.event_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
+
+impl GamepadMenuSystem {
+    /// The direction action the gamepad's d-pad is held in, or its stick pushed in the furthest.
+    fn direction(
+        &self,
+        input: &InputHandler<StringBindings>,
+        gamepad: u32,
+    ) -> Option<&'static str> {
+        let dpad = DPAD_ACTIONS
+            .iter()
+            .find(|(button, _)| input.controller_button_is_down(gamepad, *button))
+            .map(|(_, action)| *action);
+        let (x, y) = self.sticks.get(&gamepad).cloned().unwrap_or((0.0, 0.0));
+        let stick = if x.abs().max(y.abs()) < STICK_THRESHOLD {
+            None
+        } else if y.abs() >= x.abs() {
+            Some(if y > 0.0 { "menu_up" } else { "menu_down" })
+        } else {
+            Some(if x > 0.0 { "menu_right" } else { "menu_left" })
+        };
+        dpad.or(stick)
+    }
+}
@@ -0,0 +1,58 @@
+use amethyst::{
+    core::{timing::Time, Float, Hidden, Transform},
+    ecs::prelude::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage},
+};
+
+use crate::ghost::{Ghost, GHOST_INTERVAL};
+use crate::pong::{GhostPaddle, Paddle};
+
+/// Plays the best practice session back on the `GhostPaddle`, as far into it as the session
+/// being played is, and keeps where the player's paddle is for the next time. The ghost is
+/// hidden once the best session is played out.
+pub struct GhostSystem;
+
+impl<'s> System<'s> for GhostSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, GhostPaddle>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Hidden>,
+        Write<'s, Ghost>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, paddles, ghosts, mut transforms, mut hidden, mut ghost, time): Self::SystemData,
+    ) {
+        if !ghost.active {
+            return;
+        }
+        ghost.elapsed += time.delta_seconds();
+        let player = (&paddles, &transforms)
+            .join()
+            .next()
+            .map(|(_, transform)| transform.translation().y.as_f32());
+        if let Some(y) = player {
+            let due = (ghost.elapsed / GHOST_INTERVAL) as usize + 1;
+            while ghost.current.positions.len() < due {
+                ghost.current.positions.push(y);
+            }
+        }
+
+        let position = ghost.best.as_ref().and_then(|best| best.position(ghost.elapsed));
+        for (entity, _, transform) in (&entities, &ghosts, &mut transforms).join() {
+            match position {
+                Some(y) => {
+                    transform.set_translation_y(Float::from(y));
+                    hidden.remove(entity);
+                }
+                None if !hidden.contains(entity) => {
+                    hidden.insert(entity, Hidden).expect("ghost paddle should be alive");
+                }
+                None => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use amethyst::{
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{Join, Read, ReadStorage, System, Write, WriteStorage},
+};
+
+use crate::config::Gameplay;
+use crate::pong::{GoalZoneMarker, GoalZones, GOAL_ZONE_SIZE, GOAL_ZONE_SPEED};
+
+/// Slides the scoring windows up and down the left and right edges, in opposite directions,
+/// and moves their markers along with them.
+pub struct GoalZoneSystem;
+
+impl<'s> System<'s> for GoalZoneSystem {
+    type SystemData = (
+        Write<'s, GoalZones>,
+        ReadStorage<'s, GoalZoneMarker>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(&mut self, (mut zones, markers, mut transforms, time, gameplay): Self::SystemData) {
+        if !zones.enabled {
+            return;
+        }
+
+        zones.phase += GOAL_ZONE_SPEED * time.delta_seconds();
+        let height = gameplay.arena_height;
+        let amplitude = (height - GOAL_ZONE_SIZE) * 0.5;
+        let offset = amplitude * zones.phase.sin();
+        zones.left_center = height * 0.5 + offset;
+        zones.right_center = height * 0.5 - offset;
+
+        for (marker, transform) in (&markers, &mut transforms).join() {
+            if let Some(center) = zones.center(marker.side) {
+                transform.set_translation_y(Float::from(center));
+            }
+        }
+    }
+}
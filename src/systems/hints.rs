@@ -0,0 +1,36 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Read, System, Write, WriteStorage},
+    ui::UiText,
+};
+
+use crate::pong::{ControlHints, CONTROL_HINTS_FADE};
+
+/// Counts down the `ControlHints` and fades them out over their last second, unless they were
+/// brought back to stay.
+pub struct ControlHintSystem;
+
+impl<'s> System<'s> for ControlHintSystem {
+    type SystemData = (
+        Write<'s, ControlHints>,
+        WriteStorage<'s, UiText>,
+        Read<'s, Time>,
+    );
+
+    fn run(&mut self, (mut hints, mut ui_text, time): Self::SystemData) {
+        hints.remaining = (hints.remaining - time.delta_seconds()).max(0.0);
+
+        let alpha = if hints.pinned {
+            1.0
+        } else if hints.visible() {
+            (hints.remaining / CONTROL_HINTS_FADE).min(1.0)
+        } else {
+            0.0
+        };
+        for entity in &hints.texts {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                text.color[3] = alpha;
+            }
+        }
+    }
+}
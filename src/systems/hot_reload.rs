@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, mem,
+    time::SystemTime,
+};
+
+use amethyst::{
+    assets::{AssetStorage, Loader, ProgressCounter},
+    config::Config,
+    core::timing::Time,
+    ecs::prelude::{Join, Read, ReadExpect, System, Write, WriteExpect, WriteStorage},
+    input::{BindingError, Bindings, Button, InputHandler, StringBindings},
+    renderer::Texture,
+    ui::{FontAsset, UiImage, UiText},
+};
+
+use crate::config::{
+    GameConfig, Gameplay, KeyMode, ShippedConfig, BINDINGS_CONFIG_PATH, GAME_CONFIG_PATH,
+};
+use crate::controls::convert_keys;
+use crate::net::Online;
+use crate::palette::Palette;
+use crate::pong::{GameplayReload, MatchRules};
+use crate::theme::{theme_path, Theme, ThemeConfig};
+
+/// Seconds between looks at whether the files changed.
+const CHECK_INTERVAL: f32 = 0.5;
+
+/// Reads `resources/game_config.ron`, the file of the theme in use and
+/// `resources/bindings_config.ron` again as they're changed, while the `hot_reload` of the
+/// `GameConfig` is on.
+///
+/// Only the `Gameplay` and `hot_reload` of the game config are taken from it, the rest are the
+/// player's settings. A match played on this machine is fitted to the new `Gameplay` by the
+/// `Pong` state, while an online match is played the game config is looked at once it's over.
+/// The UI on the screen is put in the new theme. The controls changed in the bindings file are
+/// bound like it says, the others keep the player's rebinds. A file that can't be read is left
+/// as it was until it's changed again.
+#[derive(Default)]
+pub struct HotReloadSystem {
+    /// When every file looked at was last changed, as of the last look.
+    modified: HashMap<String, SystemTime>,
+    /// The theme looked at last, a new one picked is read in like a changed one.
+    theme: Option<String>,
+    /// The bindings file as it was last read, to tell which controls were changed in it.
+    bindings: Option<Bindings<StringBindings>>,
+    /// Seconds until the next look.
+    timer: f32,
+}
+
+impl HotReloadSystem {
+    /// Whether the file at `path` changed since the last look at it. The first look only notes
+    /// when it was last changed.
+    fn changed(&mut self, path: &str) -> bool {
+        let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return false,
+        };
+        self.modified
+            .insert(path.to_string(), modified)
+            .is_some_and(|before| before != modified)
+    }
+}
+
+impl<'s> System<'s> for HotReloadSystem {
+    type SystemData = (
+        Write<'s, GameConfig>,
+        Write<'s, Gameplay>,
+        Write<'s, GameplayReload>,
+        Write<'s, MatchRules>,
+        Read<'s, Online>,
+        Write<'s, InputHandler<StringBindings>>,
+        Option<WriteExpect<'s, Theme>>,
+        ReadExpect<'s, Loader>,
+        Read<'s, AssetStorage<FontAsset>>,
+        Read<'s, AssetStorage<Texture>>,
+        Read<'s, Palette>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, UiImage>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut config,
+            mut gameplay,
+            mut reload,
+            mut rules,
+            online,
+            mut input,
+            theme,
+            loader,
+            fonts,
+            textures,
+            palette,
+            mut texts,
+            mut images,
+            time,
+        ): Self::SystemData,
+    ) {
+        if !config.hot_reload {
+            return;
+        }
+        self.timer -= time.delta_real_seconds();
+        if self.timer > 0.0 {
+            return;
+        }
+        self.timer = CHECK_INTERVAL;
+
+        if !online.in_match() && self.changed(GAME_CONFIG_PATH) {
+            match ShippedConfig::load_no_fallback(GAME_CONFIG_PATH) {
+                Ok(loaded) => {
+                    if loaded.gameplay != *gameplay {
+                        let previous = mem::replace(&mut *gameplay, loaded.gameplay);
+                        if previous.win_score != gameplay.win_score {
+                            rules.win_score = gameplay.win_score;
+                        }
+                        // Fitted to from the first one changed, if it hasn't been yet.
+                        reload.previous.get_or_insert(previous);
+                    }
+                    config.hot_reload = loaded.hot_reload;
+                }
+                Err(err) => log::warn!("Failed to reload the game config: {}", err),
+            }
+        }
+
+        let path = theme_path(&config.theme);
+        let changed = self.changed(&path);
+        let picked = self
+            .theme
+            .replace(config.theme.clone())
+            .is_some_and(|name| name != config.theme);
+        if let (true, Some(mut theme)) = (changed || picked, theme) {
+            match ThemeConfig::load_no_fallback(&path) {
+                Ok(loaded) => {
+                    let mut progress = ProgressCounter::new();
+                    let loaded = Theme::from_config(
+                        loaded,
+                        &loader,
+                        &fonts,
+                        &textures,
+                        &palette,
+                        &mut progress,
+                    );
+                    for text in (&mut texts).join() {
+                        loaded.restyle_text(&theme, text);
+                    }
+                    for image in (&mut images).join() {
+                        loaded.restyle_image(&theme, image);
+                    }
+                    *theme = loaded;
+                }
+                Err(err) => log::warn!("Failed to reload the theme {}: {}", config.theme, err),
+            }
+        }
+
+        // The file is read at the first look too, for the controls changed in it later.
+        let changed = self.changed(BINDINGS_CONFIG_PATH);
+        if changed || self.bindings.is_none() {
+            let loaded = Bindings::<StringBindings>::load_no_fallback(BINDINGS_CONFIG_PATH)
+                .map_err(|err| err.to_string());
+            self.bindings = match (self.bindings.take(), loaded) {
+                (Some(before), Ok(loaded)) => {
+                    match merge_changes(&input.bindings, &before, &loaded, config.key_mode) {
+                        Ok(merged) => {
+                            input.bindings = merged;
+                            Some(loaded)
+                        }
+                        Err(err) => {
+                            log::warn!("Failed to reload the key bindings: {}", err);
+                            Some(before)
+                        }
+                    }
+                }
+                (None, Ok(loaded)) => Some(loaded),
+                (before, Err(err)) => {
+                    if changed {
+                        log::warn!("Failed to reload the key bindings: {}", err);
+                    }
+                    before
+                }
+            };
+        }
+    }
+}
+
+/// The player's `bindings`, with the controls that were changed in the bindings file from
+/// `before` to `after` bound like it says now. The file is converted to the keys of the
+/// `KeyMode` in use, like the saved ones.
+fn merge_changes(
+    bindings: &Bindings<StringBindings>,
+    before: &Bindings<StringBindings>,
+    after: &Bindings<StringBindings>,
+    mode: KeyMode,
+) -> Result<Bindings<StringBindings>, BindingError<StringBindings>> {
+    let converted = convert_keys(after, mode)?;
+    let axes: HashSet<String> = before
+        .axes()
+        .chain(after.axes())
+        .filter(|id| before.axis(*id) != after.axis(*id))
+        .cloned()
+        .collect();
+    let actions: HashSet<String> = before
+        .actions()
+        .chain(after.actions())
+        .filter(|id| !before.action_bindings(*id).eq(after.action_bindings(*id)))
+        .cloned()
+        .collect();
+
+    // Everything changed is unbound first, so it doesn't clash with what it's bound to next.
+    let mut merged = bindings.clone();
+    for id in &axes {
+        merged.remove_axis(id);
+    }
+    for id in &actions {
+        let combos: Vec<Vec<Button>> = merged.action_bindings(id).map(<[_]>::to_vec).collect();
+        for combo in combos {
+            merged.remove_action_binding(id, &combo).ok();
+        }
+    }
+    for id in axes {
+        if let Some(axis) = converted.axis(&id) {
+            merged.insert_axis(id.clone(), axis.clone())?;
+        }
+    }
+    for id in actions {
+        for combo in converted.action_bindings(&id) {
+            merged.insert_action_binding(id.clone(), combo.iter().cloned())?;
+        }
+    }
+    Ok(merged)
+}
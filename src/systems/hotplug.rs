@@ -0,0 +1,61 @@
+use amethyst::{
+    ecs::prelude::{Read, Resources, System, SystemData, Write},
+    input::{InputEvent, InputHandler, StringBindings},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::controls::CONTROLS;
+
+/// Hands a gamepad plugged in while the game runs to the first player without one, so
+/// plugging a gamepad back in after it came loose gives it back to its player. SDL numbers the
+/// gamepad anew when it comes back, so the player's bindings no longer have it.
+///
+/// The `Pong` state pauses the match when a player's gamepad is unplugged.
+#[derive(Default)]
+pub struct GamepadHotplugSystem {
+    event_reader: Option<ReaderId<InputEvent<String>>>,
+}
+
+impl<'s> System<'s> for GamepadHotplugSystem {
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<String>>>,
+        Write<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(&mut self, (events, mut input): Self::SystemData) {
+        for event in events.read(self.event_reader.as_mut().unwrap()) {
+            let gamepad = match event {
+                InputEvent::ControllerConnected { which } => *which,
+                _ => continue,
+            };
+            let bindings = &input.bindings;
+            if CONTROLS
+                .iter()
+                .any(|controls| controls.gamepad(bindings) == Some(gamepad))
+            {
+                continue;
+            }
+            let unassigned = CONTROLS.iter().find(|controls| {
+                controls
+                    .gamepad(bindings)
+                    .is_some_and(|assigned| !input.is_controller_connected(assigned))
+            });
+            if let Some(controls) = unassigned {
+                if let Err(err) = controls.assign_gamepad(&mut input.bindings, gamepad) {
+                    log::warn!(
+                        "Failed to give the {} player a gamepad: {}",
+                        controls.player, err
+                    );
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.event_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use amethyst::{
+    ecs::prelude::{Join, Read, ReadExpect, System, WriteStorage},
+    renderer::camera::{Camera, Projection},
+    window::ScreenDimensions,
+};
+
+use crate::config::Gameplay;
+use crate::net::Online;
+use crate::pong::SPECTATOR_ZOOM;
+
+/// Keeps the arena in proportion whatever the shape of the window. The camera shows the whole
+/// arena as big as it fits and the space left over on either side of it, where the
+/// `LetterboxBar`s are, so a wide window gets bars on the left and right and a tall one above
+/// and below. Spectators see a bit less of it, from closer up.
+pub struct LetterboxSystem;
+
+impl<'s> System<'s> for LetterboxSystem {
+    type SystemData = (
+        WriteStorage<'s, Camera>,
+        Option<ReadExpect<'s, ScreenDimensions>>,
+        Read<'s, Online>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(&mut self, (mut cameras, dimensions, online, gameplay): Self::SystemData) {
+        let dimensions = match dimensions {
+            Some(dimensions) if dimensions.height() > 0.0 => dimensions,
+            _ => return,
+        };
+
+        let (width, height) = view_size(&dimensions, &gameplay, online.watching.is_some());
+        // The same projection as `Camera::standard_2d`, only sized to the window.
+        for camera in (&mut cameras).join() {
+            camera.set_projection(Projection::orthographic(
+                -width / 2.0,
+                width / 2.0,
+                -height / 2.0,
+                height / 2.0,
+                0.1,
+                2000.0,
+            ));
+        }
+    }
+}
+
+/// How much of the arena and the space around it the camera shows in a window of the given
+/// size, closer up for spectators.
+pub fn view_size(
+    dimensions: &ScreenDimensions,
+    gameplay: &Gameplay,
+    spectating: bool,
+) -> (f32, f32) {
+    let aspect = dimensions.aspect_ratio();
+    let (arena_width, arena_height) = (gameplay.arena_width, gameplay.arena_height);
+    let (width, height) = if aspect > arena_width / arena_height {
+        (arena_height * aspect, arena_height)
+    } else {
+        (arena_width, arena_width / aspect)
+    };
+    if spectating {
+        (width / SPECTATOR_ZOOM, height / SPECTATOR_ZOOM)
+    } else {
+        (width, height)
+    }
+}
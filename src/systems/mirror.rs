@@ -0,0 +1,70 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Float, Hidden, Transform},
+    ecs::prelude::{Join, Read, ReadStorage, System, WriteStorage},
+};
+use rand::Rng;
+
+use crate::pong::{
+    Ball, Mirror, Paddle, MIRROR_DURATION, MIRROR_MAX_INTERVAL, MIRROR_MIN_INTERVAL,
+    MIRROR_WARNING,
+};
+
+/// Flips the controls of paddles with a `Mirror` for `MIRROR_DURATION` seconds at random
+/// intervals, for the `PaddleSystem` to pick up. The warning sign next to the paddle shows
+/// up a moment before the flip and stays until it's over.
+pub struct MirrorSystem;
+
+impl<'s> System<'s> for MirrorSystem {
+    type SystemData = (
+        WriteStorage<'s, Mirror>,
+        WriteStorage<'s, Paddle>,
+        ReadStorage<'s, Ball>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Hidden>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (mut mirrors, mut paddles, balls, mut transforms, mut hidden, time): Self::SystemData,
+    ) {
+        // Only count down while a ball is in play.
+        if balls.join().next().is_none() {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut icons = Vec::new();
+        for (mirror, paddle, transform) in (&mut mirrors, &mut paddles, &transforms).join() {
+            mirror.timer -= time.delta_seconds();
+            if mirror.timer <= 0.0 {
+                paddle.inverted = !paddle.inverted;
+                mirror.timer = if paddle.inverted {
+                    MIRROR_DURATION
+                } else {
+                    rng.gen_range(MIRROR_MIN_INTERVAL, MIRROR_MAX_INTERVAL)
+                };
+            }
+
+            // Put the sign just in front of the paddle, so it's hard to miss.
+            let position = Vector2::new(
+                transform.translation().x.as_f32(),
+                transform.translation().y.as_f32(),
+            ) + paddle.side.normal() * 6.0;
+            let warning = paddle.inverted || mirror.timer <= MIRROR_WARNING;
+            icons.push((mirror.icon, position, warning));
+        }
+
+        for (icon, position, warning) in icons {
+            if let Some(transform) = transforms.get_mut(icon) {
+                transform.set_translation_x(Float::from(position.x));
+                transform.set_translation_y(Float::from(position.y));
+            }
+            if warning {
+                hidden.remove(icon);
+            } else if !hidden.contains(icon) {
+                hidden.insert(icon, Hidden).expect("mirror icon should be alive");
+            }
+        }
+    }
+}
@@ -1,4 +1,52 @@
 pub mod paddle;
 pub mod move_balls;
 pub mod bounce;
-pub mod winner;
\ No newline at end of file
+pub mod winner;
+pub mod ai;
+pub mod multi_ball;
+pub mod powerups;
+pub mod overtime;
+pub mod serve;
+pub mod dash;
+pub mod power_shot;
+pub mod goal_zones;
+pub mod survival;
+pub mod time_attack;
+pub mod practice;
+pub mod portals;
+pub mod blink;
+pub mod mirror;
+pub mod stats;
+pub mod countdown;
+pub mod score_ui;
+pub mod rally;
+pub mod hints;
+pub mod music;
+pub mod sound;
+pub mod mute;
+pub mod announcer;
+pub mod crowd;
+pub mod particles;
+pub mod parallax;
+pub mod palette;
+pub mod fps;
+pub mod window_mode;
+pub mod animation;
+pub mod letterbox;
+pub mod touch;
+#[cfg(feature = "gamepad")]
+pub mod rumble;
+pub mod gamepad_menu;
+pub mod hotplug;
+pub mod online;
+pub mod spectator;
+pub mod net_stats;
+#[cfg(feature = "discord")]
+pub mod discord;
+#[cfg(feature = "steam")]
+pub mod steam;
+pub mod chat_votes;
+pub mod replay;
+pub mod ghost;
+pub mod achievements;
+pub mod hot_reload;
\ No newline at end of file
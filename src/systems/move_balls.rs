@@ -1,25 +1,111 @@
 use amethyst::{
+    core::math::{Rotation2, Vector2},
     core::timing::Time,
     core::transform::Transform,
+    core::Float,
     ecs::prelude::{Join, Read, ReadStorage, System, WriteStorage},
 };
 
-use crate::pong::Ball;
+use crate::config::{GameConfig, Gameplay};
+use crate::net::Online;
+use crate::pong::{
+    Ball, GameMode, Mutator, Mutators, Serving, BALL_SPIN_DECAY, DOUBLE_SPEED_MULTIPLIER,
+    GRAVITY_ACCELERATION,
+};
 
 pub struct MoveBallsSystem;
 
+/// The velocity and spin of a ball after `delta` seconds. With the Magnus effect on, at
+/// `curve_strength`, spin turns the velocity a little every frame. Either way it wears off over
+/// time.
+pub fn spin_ball(
+    velocity: Vector2<f32>,
+    spin: f32,
+    curve_strength: Option<f32>,
+    delta: f32,
+) -> (Vector2<f32>, f32) {
+    if spin == 0.0 {
+        return (velocity, spin);
+    }
+    let velocity = match curve_strength {
+        Some(strength) => Rotation2::new(spin * strength * delta) * velocity,
+        None => velocity,
+    };
+    (velocity, spin * (1.0 - BALL_SPIN_DECAY * delta).max(0.0))
+}
+
 impl<'s> System<'s> for MoveBallsSystem {
     type SystemData = (
-        ReadStorage<'s, Ball>,
+        WriteStorage<'s, Ball>,
         WriteStorage<'s, Transform>,
+        ReadStorage<'s, Serving>,
         Read<'s, Time>,
+        Read<'s, GameConfig>,
+        Read<'s, Mutators>,
+        Read<'s, GameMode>,
+        Read<'s, Online>,
+        Read<'s, Gameplay>,
     );
 
-    fn run(&mut self, (balls, mut locals, time): Self::SystemData) {
-        // Move every ball according to its speed, and the time passed.
-        for (ball, local) in (&balls, &mut locals).join() {
-            local.prepend_translation_x(ball.velocity[0] * time.delta_seconds());
-            local.prepend_translation_y(ball.velocity[1] * time.delta_seconds());
+    fn run(
+        &mut self,
+        (
+            mut balls,
+            mut locals,
+            serving,
+            time,
+            config,
+            mutators,
+            mode,
+            online,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        // Online matches move the ball in the `OnlineSystem`.
+        if online.in_match() {
+            return;
+        }
+        let gravity = if mutators.is_on(Mutator::Gravity) {
+            GRAVITY_ACCELERATION
+        } else {
+            0.0
+        };
+        let speed = if mutators.is_on(Mutator::DoubleSpeed) {
+            DOUBLE_SPEED_MULTIPLIER
+        } else {
+            1.0
+        };
+        // With four players the top and bottom edges are goals, so they always stay closed.
+        let wrap = mutators.is_on(Mutator::NoWalls) && *mode != GameMode::FourPlayer;
+
+        // Move every ball according to its speed, and the time passed. Balls waiting to be
+        // served are carried along by the `ServeSystem` instead.
+        for (ball, local, _) in (&mut balls, &mut locals, !&serving).join() {
+            let curve_strength = if config.magnus_effect {
+                Some(config.curve_strength)
+            } else {
+                None
+            };
+            let (velocity, spin) =
+                spin_ball(ball.velocity, ball.spin, curve_strength, time.delta_seconds());
+            ball.velocity = velocity;
+            ball.spin = spin;
+
+            ball.velocity.y -= gravity * time.delta_seconds();
+
+            local.prepend_translation_x(ball.velocity.x * speed * time.delta_seconds());
+            local.prepend_translation_y(ball.velocity.y * speed * time.delta_seconds());
+
+            // Without walls the ball comes back in on the other side.
+            if wrap {
+                let y = local.translation().y.as_f32();
+                let height = gameplay.arena_height;
+                if y < 0.0 {
+                    local.set_translation_y(Float::from(y + height));
+                } else if y > height {
+                    local.set_translation_y(Float::from(y - height));
+                }
+            }
         }
     }
 }
@@ -0,0 +1,105 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Transform},
+    ecs::prelude::{
+        Builder, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write,
+    },
+    renderer::SpriteRender,
+};
+
+use crate::config::Gameplay;
+use crate::net::Online;
+use crate::pong::{
+    ball_scale, Animation, Ball, GameMode, MultiBall, Mutators, SpriteSheetHandle, MAX_BALLS,
+    MULTI_BALL_INTERVAL,
+};
+
+/// Throws another ball into the arena whenever `MultiBall::timer` runs out, up to `MAX_BALLS`.
+/// Survival runs and practice bring in their own balls instead, and online matches keep to one.
+#[derive(Default)]
+pub struct MultiBallSystem {
+    /// Used to alternate the direction new balls are served in.
+    serve_right: bool,
+}
+
+impl<'s> System<'s> for MultiBallSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Ball>,
+        Write<'s, MultiBall>,
+        Read<'s, GameMode>,
+        Read<'s, Mutators>,
+        Read<'s, Online>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            balls,
+            mut multi_ball,
+            mode,
+            mutators,
+            online,
+            sprite_sheet,
+            lazy,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        if mode.is_solo() || online.in_match() {
+            return;
+        }
+
+        // Nothing to do until the first ball has been served.
+        let balls_in_play = balls.join().count();
+        if balls_in_play == 0 {
+            return;
+        }
+        let sprite_sheet = match sprite_sheet {
+            Some(sprite_sheet) => sprite_sheet,
+            None => return,
+        };
+
+        multi_ball.timer -= time.delta_seconds();
+        if multi_ball.timer > 0.0 {
+            return;
+        }
+        multi_ball.timer = MULTI_BALL_INTERVAL;
+
+        if balls_in_play >= MAX_BALLS {
+            return;
+        }
+
+        let radius = mutators.ball_radius(gameplay.ball_radius);
+        let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(width / 2.0, height / 2.0, 0.0);
+        transform.set_scale(ball_scale(radius));
+
+        self.serve_right = !self.serve_right;
+        let direction = if self.serve_right { 1.0 } else { -1.0 };
+
+        lazy.create_entity(&entities)
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.0.clone(),
+                sprite_number: 1, // ball is the second sprite on the sprite sheet
+            })
+            .with(Animation::ball())
+            .with(Ball {
+                radius,
+                velocity: Vector2::new(
+                    gameplay.ball_velocity_x * direction,
+                    -gameplay.ball_velocity_y,
+                ),
+                spin: 0.0,
+                last_hit: None,
+                power_shot: false,
+            })
+            .with(transform)
+            .build();
+    }
+}
@@ -0,0 +1,128 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::timing::Time,
+    ecs::prelude::{Read, System, WriteExpect},
+};
+
+use crate::audio::{
+    silence, Music, Track, MUSIC_CROSSFADE, MUSIC_DUCK_FADE, MUSIC_DUCK_LEVEL, MUSIC_STEM_FADE,
+};
+use crate::config::AudioSettings;
+use crate::pong::Rally;
+
+/// Loops the music track that's playing and crossfades between tracks when a screen picks
+/// another one. During a match the stems of the match music come in one by one as the rally
+/// gets longer, and drop out again once it's over. The volume follows the `AudioSettings` as
+/// they change, and dips while a loud sound plays. It keeps running while the game is paused,
+/// the music plays on.
+pub struct MusicSystem;
+
+impl<'s> System<'s> for MusicSystem {
+    type SystemData = (
+        Option<WriteExpect<'s, Music>>,
+        Read<'s, AssetStorage<Source>>,
+        Read<'s, Time>,
+        Read<'s, AudioSettings>,
+        Read<'s, Rally>,
+        Option<Read<'s, Output>>,
+    );
+
+    fn run(&mut self, (music, storage, time, audio_settings, rally, output): Self::SystemData) {
+        // The music is loaded along with the sprites, there is nothing to play before that, and
+        // nothing to play it on without an audio output.
+        let (mut music, output) = match (music, output) {
+            (Some(music), Some(output)) => (music, output),
+            _ => return,
+        };
+        let Music {
+            playing,
+            tracks,
+            stems,
+            duck,
+            duck_level,
+        } = &mut *music;
+
+        // Turned down quickly for a loud sound, and back up once it's over.
+        let duck_step = time.delta_seconds() * (1.0 - MUSIC_DUCK_LEVEL) / MUSIC_DUCK_FADE;
+        *duck_level = if *duck > 0.0 {
+            (*duck_level - duck_step).max(MUSIC_DUCK_LEVEL)
+        } else {
+            (*duck_level + duck_step).min(1.0)
+        };
+        *duck = (*duck - time.delta_seconds()).max(0.0);
+        let volume = audio_settings.music_volume() * *duck_level;
+
+        let step = time.delta_seconds() / MUSIC_CROSSFADE;
+        // How loud the match music is playing, and whether it started over this frame.
+        let mut game_fade = 0.0;
+        let mut game_queued = false;
+
+        for track in tracks.iter_mut() {
+            let fading_in = *playing == Some(track.track);
+            track.fade = if fading_in {
+                (track.fade + step).min(1.0)
+            } else {
+                (track.fade - step).max(0.0)
+            };
+
+            let sink = match &mut track.sink {
+                Some(sink) => sink,
+                None => continue,
+            };
+            if track.fade <= 0.0 && !fading_in {
+                // Faded out, it starts over from the top the next time it's picked.
+                silence(sink, &output);
+                continue;
+            }
+            sink.set_volume(track.fade * volume);
+            // Queue the track again as it runs out, so it loops.
+            if sink.empty() {
+                if let Some(source) = storage.get(&track.source) {
+                    match sink.append(source) {
+                        Ok(()) => game_queued |= track.track == Track::Game,
+                        Err(err) => {
+                            // Leave the track quiet rather than failing again every frame.
+                            log::warn!("Failed to play the music: {}", err);
+                            track.sink = None;
+                        }
+                    }
+                }
+            }
+            if track.track == Track::Game {
+                game_fade = track.fade;
+            }
+        }
+
+        let stem_step = time.delta_seconds() / MUSIC_STEM_FADE;
+        for stem in stems.iter_mut() {
+            let coming_in = *playing == Some(Track::Game) && rally.current >= stem.rally;
+            stem.level = if coming_in {
+                (stem.level + stem_step).min(1.0)
+            } else {
+                (stem.level - stem_step).max(0.0)
+            };
+
+            let sink = match &mut stem.sink {
+                Some(sink) => sink,
+                None => continue,
+            };
+            if game_fade <= 0.0 {
+                silence(sink, &output);
+                continue;
+            }
+            sink.set_volume(game_fade * stem.level * volume);
+            // The stems are queued along with the match music so they stay in time with it,
+            // playing quietly until the rally brings them in.
+            if game_queued {
+                silence(sink, &output);
+                if let Some(source) = storage.get(&stem.source) {
+                    if let Err(err) = sink.append(source) {
+                        log::warn!("Failed to play the music: {}", err);
+                        stem.sink = None;
+                    }
+                }
+            }
+        }
+    }
+}
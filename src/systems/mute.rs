@@ -0,0 +1,38 @@
+use amethyst::{
+    ecs::prelude::{Read, Resources, System, SystemData, Write},
+    input::InputEvent,
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::config::AudioSettings;
+
+/// Turns all of the sound off and back on with the `mute` action, on every screen.
+#[derive(Default)]
+pub struct MuteSystem {
+    input_reader: Option<ReaderId<InputEvent<String>>>,
+}
+
+impl<'s> System<'s> for MuteSystem {
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<String>>>,
+        Write<'s, AudioSettings>,
+    );
+
+    fn run(&mut self, (input_events, mut audio_settings): Self::SystemData) {
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            if let InputEvent::ActionPressed(action) = event {
+                if action == "mute" {
+                    audio_settings.muted = !audio_settings.muted;
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{
+        Entities, Entity, Read, ReadExpect, Resources, System, SystemData, WriteStorage,
+    },
+    input::InputEvent,
+    shrev::{EventChannel, ReaderId},
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::net::Online;
+use crate::rollback::Rollback;
+use crate::theme::Theme;
+
+/// How often the rollback frames a second are counted again, in seconds.
+const ROLLBACK_SAMPLE: f32 = 1.0;
+
+/// Shows how the connection to the lobby is doing under the fps counter while connected: the
+/// ping, how much it varies, how many pings are lost and how many frames a second the
+/// `Rollback` plays again. The `toggle_net_stats` action shows and hides it.
+#[derive(Default)]
+pub struct NetStatsSystem {
+    input_reader: Option<ReaderId<InputEvent<String>>>,
+    shown: bool,
+    text: Option<Entity>,
+    /// Frames played again by the time of the last sample, and the time since.
+    rolled_back: u64,
+    sampled: f32,
+    /// Frames played again a second, as of the last sample.
+    rollback_rate: f32,
+}
+
+impl<'s> System<'s> for NetStatsSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, EventChannel<InputEvent<String>>>,
+        Read<'s, Online>,
+        Option<ReadExpect<'s, Rollback>>,
+        Read<'s, Time>,
+        Option<ReadExpect<'s, Theme>>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            input_events,
+            online,
+            rollback,
+            time,
+            theme,
+            mut transforms,
+            mut texts,
+        ): Self::SystemData,
+    ) {
+        for event in input_events.read(self.input_reader.as_mut().unwrap()) {
+            if let InputEvent::ActionPressed(action) = event {
+                if action == "toggle_net_stats" {
+                    self.shown = !self.shown;
+                }
+            }
+        }
+
+        // A new `Rollback` for every match counts from nothing again.
+        let rolled_back = rollback.as_ref().map_or(0, |rollback| rollback.rolled_back);
+        self.sampled += time.delta_seconds();
+        if self.sampled >= ROLLBACK_SAMPLE || rolled_back < self.rolled_back {
+            let played_again = rolled_back.saturating_sub(self.rolled_back);
+            self.rollback_rate = played_again as f32 / self.sampled.max(ROLLBACK_SAMPLE);
+            self.rolled_back = rolled_back;
+            self.sampled = 0.0;
+        }
+
+        let text = self.text.filter(|entity| entities.is_alive(*entity));
+        let (stats, theme) = match (online.stats(), theme) {
+            (Some(stats), Some(theme)) if self.shown => (stats, theme),
+            _ => {
+                if let Some(entity) = text {
+                    entities.delete(entity).expect("net stats should be alive");
+                }
+                self.text = None;
+                return;
+            }
+        };
+
+        let entity = text.unwrap_or_else(|| {
+            entities
+                .build_entity()
+                .with(
+                    UiTransform::new(
+                        "net_stats".to_string(), Anchor::TopRight, Anchor::TopRight,
+                        -8., -30., 2., 420., 20.,
+                    ),
+                    &mut transforms,
+                )
+                .with(theme.text(String::new(), 14.), &mut texts)
+                .build()
+        });
+        self.text = Some(entity);
+        if let Some(text) = texts.get_mut(entity) {
+            let ping = match stats.ping {
+                Some(ping) => format!("{:.0} ms ping", ping),
+                None => "no ping yet".to_string(),
+            };
+            let mut line = format!(
+                "{}, {:.0} ms jitter, {:.0}% loss",
+                ping,
+                stats.jitter,
+                stats.loss * 100.0
+            );
+            // Matches the lobby plays aren't rolled back.
+            if online.peer.as_ref().is_some_and(|peer| !peer.authoritative) {
+                line += &format!(", {:.0} rollback frames/s", self.rollback_rate);
+            }
+            text.text = line;
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
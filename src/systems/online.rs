@@ -0,0 +1,372 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{
+        Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write, WriteExpect,
+        WriteStorage,
+    },
+    input::{InputHandler, StringBindings},
+    shrev::EventChannel,
+};
+
+use crate::audio::{
+    play_bounce_sound, play_score_sound, play_wall_sound, Music, Sounds, SCORE_DUCK,
+};
+use crate::config::{AudioSettings, GameConfig};
+use crate::controls::Controls;
+use crate::net::{FromLobby, Online, PeerMessage, SpectatorMessage, ToLobby};
+use crate::pong::{
+    Ball, BallHit, GameWon, GoalConceded, MatchScore, Paddle, Rally, RemotePaddle, ScoreBoard,
+    ScoreEvent, ScreenShake, ServeCountdown, Side, ARENA_WIDTH, GOAL_SHAKE_AMOUNT,
+    GOAL_SHAKE_DURATION,
+};
+use crate::rollback::{
+    MatchEvent, PlayerInput, Prediction, Rollback, MAX_ROLLBACK, ROLLBACK_FRAME,
+};
+
+/// Seconds before reconnecting to the lobby is tried again, after a try failed.
+const RECONNECT_DELAY: f32 = 2.0;
+
+/// Plays an online match in the `Rollback`, in place of the systems moving the paddles and the
+/// ball and keeping the score offline. Every frame the local player's input goes to the
+/// opponent, and theirs comes in to be played, then the arena is put where the match is.
+///
+/// Bounces and goals are shown as soon as they're played, a game is only won once both
+/// players' inputs are known up to the winning point. The host broadcasts the match to
+/// everyone watching it as it plays it.
+///
+/// When the lobby is authoritative it plays the match instead, the local player's input goes
+/// to it and the arena is put where the `Prediction` has the match.
+///
+/// A player who loses their connection reconnects to rejoin the match, which waits for them
+/// meanwhile. The opponent has them play on from where both of them had played the match.
+///
+/// It isn't paused, the match goes on for the opponent while the local player has it paused.
+#[derive(Default)]
+pub struct OnlineSystem {
+    /// Time passed that no frame has been played for yet, in seconds.
+    unplayed: f32,
+    /// Seconds until reconnecting is tried again, while the connection is lost.
+    retry: Option<f32>,
+    /// Whether the match was rejoined and waits for the opponent to say where it's at.
+    resyncing: bool,
+}
+
+impl<'s> System<'s> for OnlineSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Paddle>,
+        ReadStorage<'s, RemotePaddle>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Transform>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+        Read<'s, GameConfig>,
+        Write<'s, Online>,
+        Option<WriteExpect<'s, Rollback>>,
+        Option<WriteExpect<'s, Prediction>>,
+        Write<'s, ScoreBoard>,
+        Read<'s, MatchScore>,
+        Write<'s, ServeCountdown>,
+        Write<'s, Rally>,
+        Write<'s, ScreenShake>,
+        Write<'s, EventChannel<ScoreEvent>>,
+        Write<'s, EventChannel<GoalConceded>>,
+        Write<'s, EventChannel<GameWon>>,
+        Write<'s, EventChannel<BallHit>>,
+        Read<'s, AssetStorage<Source>>,
+        Option<ReadExpect<'s, Sounds>>,
+        Option<Read<'s, Output>>,
+        Read<'s, AudioSettings>,
+        Option<WriteExpect<'s, Music>>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut paddles,
+            remotes,
+            mut balls,
+            mut transforms,
+            input,
+            time,
+            config,
+            mut online,
+            rollback,
+            prediction,
+            mut scores,
+            match_score,
+            mut countdown,
+            mut rally,
+            mut shake,
+            mut score_events,
+            mut goals_conceded,
+            mut game_won,
+            mut ball_hits,
+            audio_storage,
+            sounds,
+            audio_output,
+            audio_settings,
+            mut music,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        let (opponent, host, authoritative, migrated) = match &online.peer {
+            Some(peer) => (peer.name.clone(), peer.host, peer.authoritative, peer.migrated),
+            None => return,
+        };
+        let remote = if host { Side::Right } else { Side::Left };
+        let mut rollback = rollback.filter(|_| !authoritative);
+        let mut prediction = prediction.filter(|_| authoritative);
+        if rollback.is_none() && prediction.is_none() {
+            return;
+        }
+        // Between matches, on the results screen, nothing is played. The opponent's inputs
+        // wait for the next one.
+        if (&remotes).join().next().is_none() {
+            return;
+        }
+
+        let mut played = Vec::new();
+        let mut confirmed = Vec::new();
+        for message in online.receive() {
+            match message {
+                Ok(FromLobby::Relay(PeerMessage::Input { frame, input })) => {
+                    if let Some(rollback) = &mut rollback {
+                        rollback.add_remote_input(frame, input);
+                    }
+                }
+                Ok(FromLobby::Frame {
+                    frame,
+                    state,
+                    events,
+                    inputs,
+                    played: played_frame,
+                }) => {
+                    if let Some(prediction) = &mut prediction {
+                        prediction.recording.record(frame, inputs);
+                        played.extend(events.iter().copied());
+                        confirmed.extend(events);
+                        prediction.reconcile(state, inputs, played_frame);
+                    }
+                }
+                Ok(FromLobby::OpponentLeft) => {
+                    online.opponent_dropped = false;
+                    online.lost = Some(format!("{} left the match", opponent));
+                }
+                Ok(FromLobby::OpponentDropped) => online.opponent_dropped = true,
+                Ok(FromLobby::OpponentRejoined) => {
+                    online.opponent_dropped = false;
+                    // Both play on from where both had played the match, whatever was played
+                    // past that only the local player has seen.
+                    if let Some(rollback) = &mut rollback {
+                        let (frame, state) = rollback.confirmed();
+                        let state = state.clone();
+                        let resync = PeerMessage::Resync {
+                            frame,
+                            state: state.clone(),
+                        };
+                        online.send(ToLobby::Relay(resync));
+                        rollback.resync(frame, state);
+                    }
+                }
+                Ok(FromLobby::Rejoined) => {
+                    online.reconnecting = false;
+                    self.resyncing = rollback.is_some();
+                }
+                Ok(FromLobby::Relay(PeerMessage::Resync { frame, state })) => {
+                    if let Some(rollback) = &mut rollback {
+                        rollback.resync(frame, state);
+                    }
+                    self.resyncing = false;
+                }
+                Ok(FromLobby::HostMigrated) => {
+                    online.opponent_dropped = false;
+                    if let Some(peer) = &mut online.peer {
+                        peer.migrated = true;
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    // The first try is right away, a failed one is tried again after a while.
+                    let delay = if online.reconnecting { RECONNECT_DELAY } else { 0.0 };
+                    online.reconnecting = true;
+                    self.retry = Some(delay);
+                }
+            }
+        }
+        if online.reconnecting {
+            if let Some(left) = self.retry.take() {
+                let left = left - time.delta_seconds();
+                if left <= 0.0 {
+                    online.reconnect();
+                } else {
+                    self.retry = Some(left);
+                }
+            }
+            return;
+        }
+        // Nothing's played until the opponent says where to play on from, what's played
+        // before would never reach them.
+        if self.resyncing {
+            return;
+        }
+
+        let local_input = (&paddles, !&remotes)
+            .join()
+            .next()
+            .map(|(paddle, _)| {
+                let controls = Controls::of(paddle.side, paddle.lane);
+                let stick = input
+                    .axis_value(controls.stick)
+                    .map(|push| config.stick.shape(push as f32));
+                let movement = controls.movement(&input, stick).unwrap_or(0.0);
+                let serve = input.action_is_down(controls.serve).unwrap_or(false);
+                PlayerInput::new(movement, serve)
+            })
+            .unwrap_or_default();
+
+        // Frames are played at the same rate on both ends, as many as the time passed makes
+        // up for. Waiting on the opponent holds the match up.
+        self.unplayed =
+            (self.unplayed + time.delta_seconds()).min(ROLLBACK_FRAME * MAX_ROLLBACK as f32);
+        let mut advanced = false;
+        let state = match (&mut rollback, &mut prediction) {
+            (Some(rollback), _) => {
+                while self.unplayed >= ROLLBACK_FRAME && rollback.can_advance() {
+                    self.unplayed -= ROLLBACK_FRAME;
+                    if migrated {
+                        let input = rollback.state().stand_in_input(remote);
+                        rollback.add_stand_in_input(input);
+                    }
+                    let frame = rollback.add_local_input(local_input);
+                    online.send(ToLobby::Relay(PeerMessage::Input {
+                        frame,
+                        input: local_input,
+                    }));
+                    let (known, new) = rollback.advance();
+                    confirmed.extend(known);
+                    played.extend(new);
+                    advanced = true;
+                }
+                rollback.state().clone()
+            }
+            (_, Some(prediction)) => {
+                while self.unplayed >= ROLLBACK_FRAME {
+                    self.unplayed -= ROLLBACK_FRAME;
+                    let frame = prediction.add_local_input(local_input);
+                    online.send(ToLobby::Input {
+                        frame,
+                        input: local_input,
+                    });
+                }
+                match prediction.state() {
+                    Some(state) => state,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        if (host || migrated) && advanced {
+            let games = [match_score.games_left, match_score.games_right];
+            let state = state.clone();
+            online.send(ToLobby::Broadcast(SpectatorMessage::State { state, games }));
+        }
+        for (paddle, transform) in (&mut paddles, &mut transforms).join() {
+            let (position, velocity) = state.paddle(paddle.side);
+            transform.set_translation_y(Float::from(position));
+            paddle.velocity = velocity;
+        }
+        for (ball, transform) in (&mut balls, &mut transforms).join() {
+            transform.set_translation_x(Float::from(state.ball_position.x));
+            transform.set_translation_y(Float::from(state.ball_position.y));
+            ball.velocity = state.ball_velocity;
+            ball.spin = state.ball_spin;
+        }
+        countdown.remaining = state.serve_countdown();
+        rally.current = state.rally;
+        rally.best = rally.best.max(rally.current);
+        // The `Pong` state clears the scores once a game is won, the match catches up when the
+        // next one starts.
+        if !state.between_games() {
+            for side in &[Side::Left, Side::Right] {
+                let score = state.score(*side);
+                if scores.score(*side) != score {
+                    *scores.score_mut(*side) = score;
+                    score_events.single_write(ScoreEvent { side: *side, score });
+                }
+            }
+        }
+
+        let volume = audio_settings.sfx_volume();
+        let output = audio_output.as_deref();
+        for event in played {
+            match event {
+                MatchEvent::Bounce {
+                    position,
+                    velocity,
+                    paddle,
+                } => {
+                    let struck = paddle.and_then(|side| {
+                        (&entities, &paddles)
+                            .join()
+                            .find(|(_, paddle)| paddle.side == side)
+                            .map(|(entity, _)| entity)
+                    });
+                    ball_hits.single_write(BallHit {
+                        position,
+                        velocity,
+                        paddle: struck,
+                    });
+                    if let Some(sounds) = &sounds {
+                        let x = position.x / ARENA_WIDTH;
+                        if paddle.is_some() {
+                            play_bounce_sound(
+                                sounds,
+                                &audio_storage,
+                                output,
+                                volume,
+                                x,
+                                &entities,
+                                &lazy,
+                            );
+                        } else {
+                            play_wall_sound(
+                                sounds,
+                                &audio_storage,
+                                output,
+                                volume,
+                                x,
+                                &entities,
+                                &lazy,
+                            );
+                        }
+                    }
+                }
+                MatchEvent::Goal { conceded, speed } => {
+                    goals_conceded.single_write(GoalConceded {
+                        side: conceded,
+                        speed,
+                    });
+                    if let Some(sounds) = &sounds {
+                        play_score_sound(sounds, &audio_storage, output, volume);
+                    }
+                    if let Some(music) = &mut music {
+                        music.duck(SCORE_DUCK);
+                    }
+                    shake.start(GOAL_SHAKE_DURATION, GOAL_SHAKE_AMOUNT);
+                }
+                MatchEvent::GameWon { .. } => {}
+            }
+        }
+        // The `Pong` state moves on to the next game, which both players must agree on.
+        for event in confirmed {
+            if let MatchEvent::GameWon { winner } = event {
+                game_won.single_write(GameWon { winner });
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use amethyst::{
+    core::{math::Vector3, timing::Time, Transform},
+    ecs::prelude::{
+        Builder, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    renderer::SpriteRender,
+    ui::UiText,
+};
+
+use crate::config::Gameplay;
+use crate::pong::{
+    Ball, GameMode, MatchRules, Overtime, OvertimeWall, ScoreBoard, ScoreText, SpriteSheetHandle,
+    OVERTIME_MAX_INSET, OVERTIME_RAMP_INTERVAL, OVERTIME_SHRINK,
+    OVERTIME_SPEED_RAMP, PADDLE_HEIGHT, PADDLE_WIDTH,
+};
+
+/// Starts sudden-death overtime once every player is a point away from winning the game.
+///
+/// Every `OVERTIME_RAMP_INTERVAL` seconds the balls speed up and, unless there are paddles
+/// guarding them, the top and bottom walls close in. It ends as soon as the scores are reset
+/// for the next game.
+pub struct OvertimeSystem;
+
+impl<'s> System<'s> for OvertimeSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, Overtime>,
+        Read<'s, ScoreBoard>,
+        Read<'s, MatchRules>,
+        Read<'s, GameMode>,
+        WriteStorage<'s, Ball>,
+        ReadStorage<'s, OvertimeWall>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, UiText>,
+        Option<ReadExpect<'s, ScoreText>>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut overtime,
+            scores,
+            rules,
+            mode,
+            mut balls,
+            walls,
+            mut transforms,
+            mut ui_text,
+            score_text,
+            sprite_sheet,
+            lazy,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        // Time attack games have their own tiebreaker.
+        let match_point = rules.win_score - 1;
+        let sudden_death = rules.time_limit.is_none() && match *mode {
+            GameMode::FourPlayer => [
+                scores.score_left,
+                scores.score_right,
+                scores.score_top,
+                scores.score_bottom,
+            ]
+            .iter()
+            .all(|score| *score >= match_point),
+            _ => scores.score_left >= match_point && scores.score_right >= match_point,
+        };
+        // In four player matches the top and bottom are goals, so only the speed ramps up.
+        let walls_close_in = *mode != GameMode::FourPlayer;
+
+        if sudden_death && !overtime.active {
+            overtime.active = true;
+            overtime.timer = OVERTIME_RAMP_INTERVAL;
+            overtime.wall_inset = 0.0;
+            set_banner(&score_text, &mut ui_text, "OVERTIME");
+
+            if let (true, Some(sprite_sheet)) = (walls_close_in, sprite_sheet) {
+                // The walls are the paddle sprite turned on its side and stretched across
+                // the arena.
+                for _ in 0..2 {
+                    let mut transform = Transform::default();
+                    transform.set_rotation_2d(std::f32::consts::FRAC_PI_2);
+                    let width = gameplay.arena_width;
+                    transform.set_scale(Vector3::new(1.0, width / PADDLE_HEIGHT, 1.0));
+                    transform.set_translation_xyz(width / 2.0, -PADDLE_WIDTH, 0.0);
+                    lazy.create_entity(&entities)
+                        .with(OvertimeWall)
+                        .with(SpriteRender {
+                            sprite_sheet: sprite_sheet.0.clone(),
+                            sprite_number: 0,
+                        })
+                        .with(transform)
+                        .build();
+                }
+            }
+        } else if !sudden_death && overtime.active {
+            *overtime = Overtime::default();
+            set_banner(&score_text, &mut ui_text, "");
+            for (entity, _) in (&entities, &walls).join() {
+                entities
+                    .delete(entity)
+                    .expect("overtime wall should be alive");
+            }
+        }
+
+        if !overtime.active {
+            return;
+        }
+
+        overtime.timer -= time.delta_seconds();
+        if overtime.timer <= 0.0 {
+            overtime.timer = OVERTIME_RAMP_INTERVAL;
+            for ball in (&mut balls).join() {
+                ball.velocity *= OVERTIME_SPEED_RAMP;
+            }
+            if walls_close_in {
+                overtime.wall_inset =
+                    (overtime.wall_inset + OVERTIME_SHRINK).min(OVERTIME_MAX_INSET);
+            }
+        }
+
+        // Keep the walls lined up with the inset, the first one at the bottom.
+        for (index, (_, transform)) in (&walls, &mut transforms).join().enumerate() {
+            let y = if index == 0 {
+                overtime.wall_inset - PADDLE_WIDTH * 0.5
+            } else {
+                gameplay.arena_height - overtime.wall_inset + PADDLE_WIDTH * 0.5
+            };
+            transform.set_translation_y(y);
+        }
+    }
+}
+
+fn set_banner(
+    score_text: &Option<ReadExpect<'_, ScoreText>>,
+    ui_text: &mut WriteStorage<'_, UiText>,
+    banner: &str,
+) {
+    if let Some(text) = score_text
+        .as_ref()
+        .and_then(|score_text| ui_text.get_mut(score_text.overtime))
+    {
+        text.text = banner.to_string();
+    }
+}
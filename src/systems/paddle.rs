@@ -1,36 +1,196 @@
-use amethyst::core::{math::RealField, Float, Transform};
-use amethyst::ecs::{Join, Read, ReadStorage, System, WriteStorage};
+use std::collections::HashMap;
+
+use amethyst::core::{math::RealField, timing::Time, Float, Transform};
+use amethyst::ecs::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage};
 use amethyst::input::{InputHandler, StringBindings};
+use amethyst::window::ScreenDimensions;
 
 // You'll have to mark PADDLE_HEIGHT as public in pong.rs
-use crate::pong::{Paddle, Side, ARENA_HEIGHT, PADDLE_HEIGHT};
+use crate::config::{GameConfig, Gameplay};
+use crate::controls::Controls;
+use crate::net::Online;
+use crate::pong::{
+    AiPaddle, ChatPaddle, ChatVotes, Dash, Lane, Overtime, Paddle, Side, TouchDrag,
+    DASH_SPEED_MULTIPLIER,
+};
 
-pub struct PaddleSystem;
+/// Moves the players' paddles with their keys and gamepads, the one picked in the settings with
+/// the mouse and the left and right ones with fingers dragged on a touch screen. The Twitch
+/// chat's paddle follows the `ChatVotes`.
+#[derive(Default)]
+pub struct PaddleSystem {
+    /// Where the smoothing has got every player's stick to, by the name of its axis.
+    sticks: HashMap<&'static str, f32>,
+}
 
 impl<'s> System<'s> for PaddleSystem {
     type SystemData = (
         WriteStorage<'s, Transform>,
-        ReadStorage<'s, Paddle>,
+        WriteStorage<'s, Paddle>,
+        ReadStorage<'s, AiPaddle>,
+        ReadStorage<'s, Dash>,
+        ReadStorage<'s, ChatPaddle>,
+        Read<'s, ChatVotes>,
         Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+        Read<'s, Overtime>,
+        Read<'s, GameConfig>,
+        Read<'s, Gameplay>,
+        Read<'s, Online>,
+        Option<ReadExpect<'s, ScreenDimensions>>,
+        Write<'s, TouchDrag>,
     );
 
-    fn run(&mut self, (mut transforms, paddles, input): Self::SystemData) {
-        for (paddle, transform) in (&paddles, &mut transforms).join() {
-            let movement = match paddle.side {
-                Side::Left => input.axis_value("left_paddle"),
-                Side::Right => input.axis_value("right_paddle"),
+    fn run(
+        &mut self,
+        (
+            mut transforms,
+            mut paddles,
+            ais,
+            dashes,
+            chat_paddles,
+            chat_votes,
+            input,
+            time,
+            overtime,
+            config,
+            gameplay,
+            online,
+            dimensions,
+            mut drag,
+        ): Self::SystemData,
+    ) {
+        // Online matches move both paddles in the `OnlineSystem`.
+        if online.in_match() {
+            *drag = TouchDrag::default();
+            return;
+        }
+
+        // Where the mouse points in the arena, if it moves one of the paddles.
+        let pointer = match (config.mouse_paddle, dimensions) {
+            (Some(_), Some(dimensions)) => arena_position(&input, &dimensions, &gameplay),
+            _ => None,
+        };
+
+        // Paddles played by the AI are moved by the `AiPaddleSystem` instead.
+        for (paddle, transform, _, dash, chat) in (
+            &mut paddles,
+            &mut transforms,
+            !&ais,
+            dashes.maybe(),
+            chat_paddles.maybe(),
+        )
+            .join()
+        {
+            paddle.velocity = 0.0;
+            let horizontal = paddle.side.is_horizontal();
+            // Top and bottom paddles slide along the x axis, the others along y.
+            let (position, half_length, arena_length) = if horizontal {
+                (transform.translation().x, paddle.width * 0.5, gameplay.arena_width)
+            } else {
+                (transform.translation().y, paddle.height * 0.5, gameplay.arena_height)
             };
-            if let Some(mv_amount) = movement {
-                if mv_amount != 0.0 {
-                  let scaled_amount = 1.2 * mv_amount as f32;
-                  let paddle_y = transform.translation().y;
-                  transform.set_translation_y(
-                    (paddle_y + Float::from(scaled_amount))
-                      .min(Float::from(ARENA_HEIGHT - PADDLE_HEIGHT * 0.5))
-                      .max(Float::from(PADDLE_HEIGHT * 0.5)),
-                  );
+
+            // The mouse moves the paddle in the upper lane in doubles, the chat's is left to it.
+            let mouse = match pointer {
+                Some((x, y))
+                    if config.mouse_paddle == Some(paddle.side)
+                        && paddle.lane != Lane::Lower
+                        && chat.is_none() =>
+                {
+                    Some(if horizontal { x } else { y })
                 }
+                _ => None,
+            };
+            // Fingers drag the paddles on the left and right, in the upper lane in doubles.
+            let touch = match (paddle.side, paddle.lane) {
+                _ if chat.is_some() => 0.0,
+                (_, Lane::Lower) => 0.0,
+                (Side::Left, _) => drag.left,
+                (Side::Right, _) => drag.right,
+                _ => 0.0,
+            };
+            let target = if let Some(pointed) = mouse {
+                // The sensitivity spreads the paddle's reach out from the middle of the arena,
+                // so it gets to the walls before the mouse gets to the edges of the window.
+                let middle = arena_length * 0.5;
+                Float::from(middle + (pointed - middle) * config.mouse_sensitivity)
+            } else if touch != 0.0 {
+                position + Float::from(touch)
+            } else {
+                let movement = if chat.is_some() {
+                    Some(f64::from(chat_votes.movement))
+                } else {
+                    // In doubles the lower lane teammates get their own controls.
+                    let controls = Controls::of(paddle.side, paddle.lane);
+                    let stick = input.axis_value(controls.stick).map(|push| {
+                        let shaped = config.stick.shape(push as f32);
+                        let smoothed = self.sticks.entry(controls.stick).or_insert(0.0);
+                        *smoothed = config.stick.smooth(*smoothed, shaped, time.delta_seconds());
+                        *smoothed
+                    });
+                    controls.movement(&input, stick)
+                };
+                // The mirror mutator swaps up and down every now and then.
+                let movement = movement.map(|mv| if paddle.inverted { -mv } else { mv });
+                let mv_amount = match movement {
+                    Some(mv_amount) if mv_amount != 0.0 => mv_amount,
+                    _ => continue,
+                };
+                let dash_multiplier = match dash {
+                    Some(dash) if dash.remaining > 0.0 => DASH_SPEED_MULTIPLIER,
+                    _ => 1.0,
+                };
+                let scaled_amount = gameplay.paddle_speed
+                    * mv_amount as f32
+                    * paddle.speed_multiplier
+                    * dash_multiplier;
+                position + Float::from(scaled_amount)
+            };
+
+            // Keep the paddle within its lane, and inside the walls during overtime.
+            let (lane_start, lane_end) = paddle.lane.range(arena_length);
+            let (lane_start, lane_end) = if horizontal {
+                (lane_start, lane_end)
+            } else {
+                (
+                    lane_start.max(overtime.wall_inset),
+                    lane_end.min(arena_length - overtime.wall_inset),
+                )
+            };
+            let new_position = target
+                .min(Float::from(lane_end - half_length))
+                .max(Float::from(lane_start + half_length));
+            if horizontal {
+                transform.set_translation_x(new_position);
+            } else {
+                transform.set_translation_y(new_position);
+            }
+
+            // Remember how fast we moved so the bounce can put spin on the ball.
+            if time.delta_seconds() > 0.0 {
+                paddle.velocity = (new_position - position).as_f32() / time.delta_seconds();
             }
         }
+        *drag = TouchDrag::default();
+    }
+}
+
+/// Where the mouse points in the arena, going by how the `LetterboxSystem` fits the arena in the
+/// window. It's off the arena when the mouse is over the bars.
+fn arena_position(
+    input: &InputHandler<StringBindings>,
+    dimensions: &ScreenDimensions,
+    gameplay: &Gameplay,
+) -> Option<(f32, f32)> {
+    let (x, y) = input.mouse_position()?;
+    if dimensions.width() <= 0.0 || dimensions.height() <= 0.0 {
+        return None;
     }
+    let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+    let scale = (dimensions.width() / width).min(dimensions.height() / height);
+    // The window's y goes down from its top, the arena's goes up from its bottom.
+    let x = (x as f32 - dimensions.width() * 0.5) / scale + width * 0.5;
+    let y = (dimensions.height() * 0.5 - y as f32) / scale + height * 0.5;
+    Some((x, y))
 }
@@ -0,0 +1,40 @@
+use amethyst::{
+    ecs::prelude::{Entities, Join, Read, ReadStorage, System, WriteStorage},
+    renderer::resources::Tint,
+};
+
+use crate::palette::{faded_tint, Palette};
+use crate::pong::{Ball, LetterboxBar, Paddle};
+
+/// Tints the paddles, balls and letterbox bars in the colors of the `Palette`, so the ones
+/// spawned during the match and a palette picked in the settings color them all the same.
+pub struct PaletteSystem;
+
+impl<'s> System<'s> for PaletteSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, LetterboxBar>,
+        WriteStorage<'s, Tint>,
+        Read<'s, Palette>,
+    );
+
+    fn run(&mut self, (entities, paddles, balls, bars, mut tints, palette): Self::SystemData) {
+        for (entity, paddle) in (&entities, &paddles).join() {
+            tints
+                .insert(entity, faded_tint(palette.paddle(paddle.side), 1.0))
+                .expect("paddle entity should be alive");
+        }
+        for (entity, _) in (&entities, &balls).join() {
+            tints
+                .insert(entity, faded_tint(palette.ball, 1.0))
+                .expect("ball entity should be alive");
+        }
+        for (entity, _) in (&entities, &bars).join() {
+            tints
+                .insert(entity, faded_tint(palette.bars, 1.0))
+                .expect("letterbox bar entity should be alive");
+        }
+    }
+}
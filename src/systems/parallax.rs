@@ -0,0 +1,29 @@
+use amethyst::{
+    core::{timing::Time, Transform},
+    ecs::prelude::{Join, Read, ReadStorage, System, WriteStorage},
+};
+
+use crate::config::Gameplay;
+use crate::pong::Parallax;
+
+/// Scrolls the layers of the background to the left, each at its own speed. A layer that has
+/// scrolled all the way out of the arena goes back in on the right, behind the other copy of it.
+pub struct ParallaxSystem;
+
+impl<'s> System<'s> for ParallaxSystem {
+    type SystemData = (
+        ReadStorage<'s, Parallax>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(&mut self, (layers, mut transforms, time, gameplay): Self::SystemData) {
+        for (layer, transform) in (&layers, &mut transforms).join() {
+            transform.prepend_translation_x(-layer.speed * time.delta_seconds());
+            if transform.translation().x.as_f32() < -gameplay.arena_width * 0.5 {
+                transform.prepend_translation_x(gameplay.arena_width * 2.0);
+            }
+        }
+    }
+}
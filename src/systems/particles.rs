@@ -0,0 +1,169 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Transform},
+    ecs::prelude::{
+        Builder, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, Resources, System,
+        SystemData, WriteStorage,
+    },
+    renderer::{resources::Tint, SpriteRender, Transparent},
+    shrev::{EventChannel, ReaderId},
+};
+use rand::Rng;
+
+use crate::config::GameConfig;
+use crate::palette::{faded_tint, Palette};
+use crate::pong::{
+    ball_scale, Ball, BallHit, Particle, Serving, SpriteSheetHandle, PARTICLES_PER_HIT,
+    PARTICLE_LIFETIME, PARTICLE_RADIUS, PARTICLE_SPREAD, TRAIL_DEPTH,
+};
+
+/// Bursts particles out of every hit the `BounceSystem` sends, spraying along the path the ball
+/// bounced off at.
+#[derive(Default)]
+pub struct ParticleEmitterSystem {
+    hit_reader: Option<ReaderId<BallHit>>,
+}
+
+impl<'s> System<'s> for ParticleEmitterSystem {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, EventChannel<BallHit>>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, Palette>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(&mut self, (entities, ball_hits, sprite_sheet, palette, lazy): Self::SystemData) {
+        // Read the events even before the sprites are loaded, so they don't pile up.
+        let hits = ball_hits.read(self.hit_reader.as_mut().unwrap());
+        let sprite_sheet = match sprite_sheet {
+            Some(sprite_sheet) => sprite_sheet,
+            None => return,
+        };
+
+        let mut rng = rand::thread_rng();
+        for hit in hits {
+            let speed = hit.velocity.norm();
+            let heading = hit.velocity.y.atan2(hit.velocity.x);
+            for _ in 0..PARTICLES_PER_HIT {
+                let angle = heading + rng.gen_range(-PARTICLE_SPREAD, PARTICLE_SPREAD);
+                let velocity =
+                    Vector2::new(angle.cos(), angle.sin()) * speed * rng.gen_range(0.3, 0.8);
+
+                let mut transform = Transform::default();
+                transform.set_translation_xyz(hit.position.x, hit.position.y, 0.0);
+                transform.set_scale(ball_scale(PARTICLE_RADIUS));
+
+                lazy.create_entity(&entities)
+                    .with(SpriteRender {
+                        sprite_sheet: sprite_sheet.0.clone(),
+                        sprite_number: 1, // ball is the second sprite on the sprite sheet
+                    })
+                    .with(Particle {
+                        velocity,
+                        lifetime: PARTICLE_LIFETIME,
+                        remaining: PARTICLE_LIFETIME,
+                    })
+                    .with(faded_tint(palette.ball, 1.0))
+                    .with(Transparent)
+                    .with(transform)
+                    .build();
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.hit_reader = Some(res.fetch_mut::<EventChannel<BallHit>>().register_reader());
+    }
+}
+
+/// Leaves a ghost of every ball in play behind each frame, which fades out over the
+/// `trail_length` from the `GameConfig` and makes for a trail following the ball.
+pub struct TrailSystem;
+
+impl<'s> System<'s> for TrailSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Serving>,
+        ReadStorage<'s, Transform>,
+        Read<'s, GameConfig>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, Palette>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            balls,
+            serving,
+            transforms,
+            config,
+            sprite_sheet,
+            palette,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        let sprite_sheet = match sprite_sheet {
+            Some(sprite_sheet) if config.trail_length > 0.0 => sprite_sheet,
+            _ => return,
+        };
+
+        // Balls sitting on a paddle for a serve don't leave a trail.
+        for (_, transform, _) in (&balls, &transforms, !&serving).join() {
+            let mut ghost = transform.clone();
+            ghost.set_translation_z(TRAIL_DEPTH);
+
+            lazy.create_entity(&entities)
+                .with(SpriteRender {
+                    sprite_sheet: sprite_sheet.0.clone(),
+                    sprite_number: 1, // ball is the second sprite on the sprite sheet
+                })
+                .with(Particle {
+                    velocity: Vector2::zeros(),
+                    lifetime: config.trail_length,
+                    remaining: config.trail_length,
+                })
+                .with(faded_tint(palette.ball, 1.0))
+                .with(Transparent)
+                .with(ghost)
+                .build();
+        }
+    }
+}
+
+/// Moves the particles along, fades them out and deletes them once their lifetime is over.
+pub struct ParticleSystem;
+
+impl<'s> System<'s> for ParticleSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Particle>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, Tint>,
+        Read<'s, Palette>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut particles, mut transforms, mut tints, palette, time): Self::SystemData,
+    ) {
+        let delta = time.delta_seconds();
+        for (entity, particle, transform, tint) in
+            (&entities, &mut particles, &mut transforms, &mut tints).join()
+        {
+            particle.remaining -= delta;
+            if particle.remaining <= 0.0 {
+                entities.delete(entity).expect("particle entity should be alive");
+                continue;
+            }
+            transform.prepend_translation_x(particle.velocity.x * delta);
+            transform.prepend_translation_y(particle.velocity.y * delta);
+
+            *tint = faded_tint(palette.ball, particle.remaining / particle.lifetime);
+        }
+    }
+}
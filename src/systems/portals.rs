@@ -0,0 +1,54 @@
+use amethyst::{
+    core::{Float, Transform},
+    ecs::prelude::{Join, ReadStorage, System, WriteStorage},
+};
+
+use crate::pong::{Ball, Portal, Serving};
+
+/// Sends balls that went into a portal out of the other end of the pair. The ball keeps its
+/// velocity and comes out just past the edge of the exit, so it doesn't go straight back in.
+pub struct PortalSystem;
+
+impl<'s> System<'s> for PortalSystem {
+    type SystemData = (
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Serving>,
+        ReadStorage<'s, Portal>,
+        WriteStorage<'s, Transform>,
+    );
+
+    fn run(&mut self, (balls, serving, portals, mut transforms): Self::SystemData) {
+        // The portal positions, taken up front since the balls need the transforms mutably.
+        let entrances: Vec<(f32, f32, f32, f32, f32)> = (&portals, &transforms)
+            .join()
+            .filter_map(|(portal, transform)| {
+                let exit = transforms.get(portal.exit)?;
+                Some((
+                    transform.translation().x.as_f32(),
+                    transform.translation().y.as_f32(),
+                    portal.radius,
+                    exit.translation().x.as_f32(),
+                    exit.translation().y.as_f32(),
+                ))
+            })
+            .collect();
+
+        for (ball, transform, _) in (&balls, &mut transforms, !&serving).join() {
+            let ball_x = transform.translation().x.as_f32();
+            let ball_y = transform.translation().y.as_f32();
+
+            let entered = entrances.iter().find(|(x, y, radius, _, _)| {
+                (ball_x - x).powi(2) + (ball_y - y).powi(2) <= radius.powi(2)
+            });
+            if let Some((_, _, radius, exit_x, exit_y)) = entered {
+                let speed = ball.velocity.norm();
+                if speed <= 0.0 {
+                    continue;
+                }
+                let offset = ball.velocity * ((radius + ball.radius) / speed);
+                transform.set_translation_x(Float::from(exit_x + offset.x));
+                transform.set_translation_y(Float::from(exit_y + offset.y));
+            }
+        }
+    }
+}
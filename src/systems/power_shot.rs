@@ -0,0 +1,125 @@
+use amethyst::{
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    input::{InputHandler, StringBindings},
+    renderer::camera::Camera,
+    ui::{UiImage, UiTransform},
+};
+use rand::Rng;
+
+use crate::{
+    config::{GameConfig, Gameplay},
+    pong::{
+        Ball, Lane, Paddle, PowerShot, ScreenShake, Serving, Side, CHARGE_METER_WIDTH,
+        POWER_SHOT_CHARGE_TIME,
+    },
+    theme::Theme,
+};
+
+/// Charges a paddle's power shot while its player holds their hit action and a ball is
+/// coming towards them, and keeps the charge meters in the UI up to date. Letting go loses the
+/// charge.
+///
+/// The `BounceSystem` fires the shot when the ball hits a fully charged paddle.
+pub struct PowerShotSystem;
+
+impl<'s> System<'s> for PowerShotSystem {
+    type SystemData = (
+        WriteStorage<'s, PowerShot>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, Ball>,
+        ReadStorage<'s, Serving>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiImage>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, Time>,
+        ReadExpect<'s, Theme>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut shots,
+            paddles,
+            balls,
+            serving,
+            mut ui_transforms,
+            mut images,
+            input,
+            time,
+            theme,
+        ): Self::SystemData,
+    ) {
+        for (shot, paddle) in (&mut shots, &paddles).join() {
+            // The hit action is the same as serving, except for the lower lane in doubles
+            // which doesn't serve.
+            let action = match (paddle.side, paddle.lane) {
+                (Side::Left, Lane::Lower) => "left_lower_hit",
+                (Side::Right, Lane::Lower) => "right_lower_hit",
+                (Side::Left, _) => "left_serve",
+                (Side::Right, _) => "right_serve",
+                (Side::Top, _) => "top_serve",
+                (Side::Bottom, _) => "bottom_serve",
+            };
+            let normal = paddle.side.normal();
+            let incoming = (&balls, !&serving)
+                .join()
+                .any(|(ball, _)| ball.velocity.dot(&normal) < 0.0);
+
+            if !input.action_is_down(action).unwrap_or(false) {
+                shot.charge = 0.0;
+            } else if incoming {
+                shot.charge =
+                    (shot.charge + time.delta_seconds() / POWER_SHOT_CHARGE_TIME).min(1.0);
+            }
+
+            if let Some(meter) = ui_transforms.get_mut(shot.meter) {
+                meter.width = CHARGE_METER_WIDTH * shot.charge;
+            }
+            if let Some(image) = images.get_mut(shot.meter) {
+                *image = if shot.charge >= 1.0 {
+                    UiImage::SolidColor(theme.charged_color)
+                } else {
+                    UiImage::SolidColor(theme.charge_color)
+                };
+            }
+        }
+    }
+}
+
+/// Jiggles the camera around the center of the arena while a `ScreenShake` is running, less
+/// and less as it dies down. The `screen_shake` in the `GameConfig` scales how far it goes.
+pub struct ScreenShakeSystem;
+
+impl<'s> System<'s> for ScreenShakeSystem {
+    type SystemData = (
+        ReadStorage<'s, Camera>,
+        WriteStorage<'s, Transform>,
+        Write<'s, ScreenShake>,
+        Read<'s, GameConfig>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (cameras, mut transforms, mut shake, config, time, gameplay): Self::SystemData,
+    ) {
+        if shake.remaining <= 0.0 {
+            return;
+        }
+        shake.remaining = (shake.remaining - time.delta_seconds()).max(0.0);
+
+        let amount = shake.amount * shake.decay() * config.screen_shake;
+        let mut rng = rand::thread_rng();
+        for (_, transform) in (&cameras, &mut transforms).join() {
+            let (x, y) = if amount > 0.0 {
+                (rng.gen_range(-amount, amount), rng.gen_range(-amount, amount))
+            } else {
+                (0.0, 0.0)
+            };
+            transform.set_translation_x(Float::from(gameplay.arena_width * 0.5 + x));
+            transform.set_translation_y(Float::from(gameplay.arena_height * 0.5 + y));
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use amethyst::{
+    core::{math::Vector3, timing::Time, Transform},
+    ecs::prelude::{
+        Builder, Entities, Entity, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    renderer::SpriteRender,
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::config::Gameplay;
+use crate::net::Online;
+use crate::pong::{
+    paddle_scale, ActivePowerUp, Ball, Paddle, PowerUp, PowerUpKind, PowerUpTimer, Shield, Side,
+    SpriteSheetHandle, POWER_UP_DURATION, POWER_UP_INTERVAL, POWER_UP_RADIUS, SHIELD_SPRITE,
+};
+
+/// Spawns a random power-up somewhere in the middle of the arena every `POWER_UP_INTERVAL`
+/// seconds, as long as there isn't one waiting to be collected already.
+pub struct PowerUpSpawnSystem;
+
+impl<'s> System<'s> for PowerUpSpawnSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, Ball>,
+        Write<'s, PowerUpTimer>,
+        Read<'s, Online>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            power_ups,
+            balls,
+            mut timer,
+            online,
+            sprite_sheet,
+            lazy,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        // Only count down while a ball is in play. Online matches are played without them.
+        if balls.join().next().is_none() || online.in_match() {
+            return;
+        }
+        let sprite_sheet = match sprite_sheet {
+            Some(sprite_sheet) => sprite_sheet,
+            None => return,
+        };
+
+        timer.timer -= time.delta_seconds();
+        if timer.timer > 0.0 {
+            return;
+        }
+        timer.timer = POWER_UP_INTERVAL;
+
+        if power_ups.join().next().is_some() {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        let kind = *PowerUpKind::ALL.choose(&mut rng).unwrap();
+        let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(
+            rng.gen_range(width * 0.3, width * 0.7),
+            rng.gen_range(height * 0.1, height * 0.9),
+            0.0,
+        );
+
+        lazy.create_entity(&entities)
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.0.clone(),
+                sprite_number: kind.sprite_number(),
+            })
+            .with(PowerUp {
+                kind,
+                radius: POWER_UP_RADIUS,
+            })
+            .with(transform)
+            .build();
+    }
+}
+
+/// Activates power-ups that a ball passes through.
+///
+/// The power-up goes to the player who last hit the ball. Before anyone touched it that's
+/// taken to be the player the ball is moving away from. Shields go up right away instead of
+/// being timed, a player only gets one at a time.
+pub struct PowerUpCollectSystem;
+
+impl<'s> System<'s> for PowerUpCollectSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, PowerUp>,
+        ReadStorage<'s, Shield>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Paddle>,
+        WriteStorage<'s, Transform>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            power_ups,
+            shields,
+            mut balls,
+            mut paddles,
+            mut transforms,
+            sprite_sheet,
+            lazy,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        let sprite_sheet = match sprite_sheet {
+            Some(sprite_sheet) => sprite_sheet,
+            None => return,
+        };
+
+        let mut collected: Vec<(Entity, PowerUpKind, Side, Entity)> = Vec::new();
+        for (power_up_entity, power_up, power_up_transform) in
+            (&entities, &power_ups, &transforms).join()
+        {
+            let power_up_x = power_up_transform.translation().x.as_f32();
+            let power_up_y = power_up_transform.translation().y.as_f32();
+
+            for (ball_entity, ball, ball_transform) in (&entities, &balls, &transforms).join() {
+                let dx = ball_transform.translation().x.as_f32() - power_up_x;
+                let dy = ball_transform.translation().y.as_f32() - power_up_y;
+                let reach = ball.radius + power_up.radius;
+                if dx * dx + dy * dy <= reach * reach {
+                    let side = ball.last_hit.unwrap_or(if ball.velocity.x > 0.0 {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    });
+                    collected.push((power_up_entity, power_up.kind, side, ball_entity));
+                    break;
+                }
+            }
+        }
+
+        for (power_up_entity, kind, side, ball_entity) in collected {
+            entities
+                .delete(power_up_entity)
+                .expect("power-up entity should be alive");
+
+            if kind == PowerUpKind::Shield {
+                if !shields.join().any(|shield| shield.side == side) {
+                    lazy.create_entity(&entities)
+                        .with(Shield { side })
+                        .with(SpriteRender {
+                            sprite_sheet: sprite_sheet.0.clone(),
+                            sprite_number: SHIELD_SPRITE,
+                        })
+                        .with(shield_transform(side, &gameplay))
+                        .build();
+                }
+                continue;
+            }
+
+            let target = match kind {
+                PowerUpKind::FasterBall => Some(ball_entity),
+                PowerUpKind::BiggerPaddle => paddle_on(side, &entities, &paddles),
+                PowerUpKind::SlowOpponent => paddle_on(side.opponent(), &entities, &paddles),
+                // The `BounceSystem` looks for the active power-up to see if a paddle is sticky.
+                PowerUpKind::Sticky => paddle_on(side, &entities, &paddles),
+                PowerUpKind::Shield => None,
+            };
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+            scale_effect(
+                kind,
+                target,
+                kind.multiplier(),
+                &mut balls,
+                &mut paddles,
+                &mut transforms,
+            );
+
+            // The active effect is shown as an icon in the HUD, the `PowerUpEffectSystem`
+            // positions it.
+            lazy.create_entity(&entities)
+                .with(SpriteRender {
+                    sprite_sheet: sprite_sheet.0.clone(),
+                    sprite_number: kind.sprite_number(),
+                })
+                .with(ActivePowerUp {
+                    kind,
+                    side,
+                    target,
+                    remaining: POWER_UP_DURATION,
+                })
+                .with(Transform::default())
+                .build();
+        }
+    }
+}
+
+/// Counts down active power-ups, undoes their effect when they run out and lines up
+/// their icons along the top of the arena.
+pub struct PowerUpEffectSystem;
+
+impl<'s> System<'s> for PowerUpEffectSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, ActivePowerUp>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Paddle>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut active,
+            mut balls,
+            mut paddles,
+            mut transforms,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        let mut expired = Vec::new();
+        for (entity, power_up) in (&entities, &mut active).join() {
+            if power_up.remaining <= 0.0 {
+                continue;
+            }
+            power_up.remaining -= time.delta_seconds();
+            if power_up.remaining <= 0.0 {
+                expired.push((entity, power_up.kind, power_up.target));
+            }
+        }
+
+        for (entity, kind, target) in expired {
+            scale_effect(
+                kind,
+                target,
+                1.0 / kind.multiplier(),
+                &mut balls,
+                &mut paddles,
+                &mut transforms,
+            );
+            entities
+                .delete(entity)
+                .expect("power-up entity should be alive");
+        }
+
+        // Stack the icons of the left and right players inwards from their corner along the
+        // top, and those of the top and bottom players outwards from the middle of their edge.
+        let (mut left, mut right, mut top, mut bottom) = (0.0, 0.0, 0.0, 0.0);
+        let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+        for (power_up, transform) in (&active, &mut transforms).join() {
+            if power_up.remaining <= 0.0 {
+                continue;
+            }
+            let (x, y) = match power_up.side {
+                Side::Left => {
+                    left += 1.0;
+                    (left * 6.0, height - 4.0)
+                }
+                Side::Right => {
+                    right += 1.0;
+                    (width - right * 6.0, height - 4.0)
+                }
+                Side::Top => {
+                    top += 1.0;
+                    (width / 2.0 + top * 6.0, height - 10.0)
+                }
+                Side::Bottom => {
+                    bottom += 1.0;
+                    (width / 2.0 + bottom * 6.0, 10.0)
+                }
+            };
+            transform.set_translation_xyz(x, y, 0.0);
+        }
+    }
+}
+
+/// Places a shield as a thin line covering the whole `side` edge, behind the paddles.
+fn shield_transform(side: Side, gameplay: &Gameplay) -> Transform {
+    // The sprite is 4 by 12, it's stretched along its own y axis and turned on its side for
+    // the top and bottom edges.
+    let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+    let mut transform = Transform::default();
+    let length = if side.is_horizontal() {
+        transform.set_rotation_2d(std::f32::consts::FRAC_PI_2);
+        width
+    } else {
+        height
+    };
+    transform.set_scale(Vector3::new(0.25, length / 12.0, 1.0));
+    let (x, y) = match side {
+        Side::Left => (0.5, height / 2.0),
+        Side::Right => (width - 0.5, height / 2.0),
+        Side::Top => (width / 2.0, height - 0.5),
+        Side::Bottom => (width / 2.0, 0.5),
+    };
+    transform.set_translation_xyz(x, y, -0.1);
+    transform
+}
+
+fn paddle_on(
+    side: Side,
+    entities: &Entities<'_>,
+    paddles: &WriteStorage<'_, Paddle>,
+) -> Option<Entity> {
+    (entities, paddles)
+        .join()
+        .find(|(_, paddle)| paddle.side == side)
+        .map(|(entity, _)| entity)
+}
+
+/// Applies a power-up's effect to `target` by `multiplier`, and undoes it with the inverse.
+fn scale_effect(
+    kind: PowerUpKind,
+    target: Entity,
+    multiplier: f32,
+    balls: &mut WriteStorage<'_, Ball>,
+    paddles: &mut WriteStorage<'_, Paddle>,
+    transforms: &mut WriteStorage<'_, Transform>,
+) {
+    match kind {
+        PowerUpKind::FasterBall => {
+            if let Some(ball) = balls.get_mut(target) {
+                ball.velocity *= multiplier;
+            }
+        }
+        PowerUpKind::BiggerPaddle => {
+            if let Some(paddle) = paddles.get_mut(target) {
+                if paddle.side.is_horizontal() {
+                    paddle.width *= multiplier;
+                } else {
+                    paddle.height *= multiplier;
+                }
+                if let Some(transform) = transforms.get_mut(target) {
+                    transform.set_scale(paddle_scale(paddle));
+                }
+            }
+        }
+        PowerUpKind::SlowOpponent => {
+            if let Some(paddle) = paddles.get_mut(target) {
+                paddle.speed_multiplier *= multiplier;
+            }
+        }
+        PowerUpKind::Shield | PowerUpKind::Sticky => {}
+    }
+}
@@ -0,0 +1,146 @@
+use amethyst::{
+    core::{
+        math::{Rotation2, Vector2},
+        timing::Time,
+        Transform,
+    },
+    ecs::prelude::{Builder, Entities, LazyUpdate, Read, ReadExpect, System, Write, WriteStorage},
+    input::{InputHandler, StringBindings},
+    renderer::SpriteRender,
+    ui::UiText,
+};
+
+use crate::config::Gameplay;
+use crate::pong::{
+    ball_scale, Animation, Ball, GameMode, Mutators, Practice, PracticeText, ScoreText,
+    SpriteSheetHandle, BALL_MAX_BOUNCE_ANGLE, LAUNCHER_MAX_INTERVAL, LAUNCHER_MAX_SPEED,
+    LAUNCHER_MIN_INTERVAL, LAUNCHER_MIN_SPEED,
+};
+
+/// How fast holding the launcher controls changes its angle, in radians per second.
+const ANGLE_RATE: f32 = 0.5;
+/// How fast holding the launcher controls changes its speed, in arena units per second.
+const SPEED_RATE: f32 = 40.0;
+/// How fast holding the launcher controls changes the seconds between shots.
+const INTERVAL_RATE: f32 = 1.0;
+
+/// Runs the ball launcher in practice mode. It fires a ball from the middle of the right edge
+/// every `Practice::interval` seconds, or whenever it's told to when it doesn't fire on its
+/// own, and its angle, speed and interval can be changed while playing.
+///
+/// Also keeps the practice overlay up to date, the `WinnerSystem` counts the returns and
+/// misses.
+pub struct LauncherSystem;
+
+impl<'s> System<'s> for LauncherSystem {
+    type SystemData = (
+        Entities<'s>,
+        Write<'s, Practice>,
+        Read<'s, GameMode>,
+        Read<'s, Mutators>,
+        WriteStorage<'s, UiText>,
+        Option<ReadExpect<'s, PracticeText>>,
+        Option<ReadExpect<'s, ScoreText>>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut practice,
+            mode,
+            mutators,
+            mut ui_text,
+            practice_text,
+            score_text,
+            sprite_sheet,
+            input,
+            lazy,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        if *mode != GameMode::Practice {
+            return;
+        }
+        let (practice_text, score_text, sprite_sheet) =
+            match (practice_text, score_text, sprite_sheet) {
+                (Some(practice_text), Some(score_text), Some(sprite_sheet)) => {
+                    (practice_text, score_text, sprite_sheet)
+                }
+                _ => return,
+            };
+        let dt = time.delta_seconds();
+
+        let change = |axis: &str| input.axis_value(axis).unwrap_or(0.0) as f32 * dt;
+        practice.angle = (practice.angle + change("launcher_angle") * ANGLE_RATE)
+            .clamp(-BALL_MAX_BOUNCE_ANGLE, BALL_MAX_BOUNCE_ANGLE);
+        practice.speed = (practice.speed + change("launcher_speed") * SPEED_RATE)
+            .clamp(LAUNCHER_MIN_SPEED, LAUNCHER_MAX_SPEED);
+        practice.interval = (practice.interval + change("launcher_interval") * INTERVAL_RATE)
+            .clamp(LAUNCHER_MIN_INTERVAL, LAUNCHER_MAX_INTERVAL);
+
+        if practice.auto_fire {
+            practice.timer -= dt;
+        }
+        if practice.timer <= 0.0 {
+            practice.timer = practice.interval;
+
+            let radius = mutators.ball_radius(gameplay.ball_radius);
+            let (width, height) = (gameplay.arena_width, gameplay.arena_height);
+            let mut transform = Transform::default();
+            transform.set_translation_xyz(width - radius * 2.0, height / 2.0, 0.0);
+            transform.set_scale(ball_scale(radius));
+            // Positive angles point upwards, which is clockwise when firing to the left.
+            let velocity = Rotation2::new(-practice.angle) * Vector2::new(-practice.speed, 0.0);
+
+            lazy.create_entity(&entities)
+                .with(SpriteRender {
+                    sprite_sheet: sprite_sheet.0.clone(),
+                    sprite_number: 1, // ball is the second sprite on the sprite sheet
+                })
+                .with(Animation::ball())
+                .with(Ball {
+                    radius,
+                    velocity,
+                    spin: 0.0,
+                    last_hit: None,
+                    power_shot: false,
+                })
+                .with(transform)
+                .build();
+        }
+
+        // Practice doesn't keep score, the overlay takes its place.
+        for entity in &[score_text.p1_score, score_text.p2_score] {
+            if let Some(text) = ui_text.get_mut(*entity) {
+                text.text.clear();
+            }
+        }
+        let shots = practice.returned + practice.missed;
+        let accuracy = if shots > 0 {
+            format!("{:.0}%", practice.returned as f32 * 100. / shots as f32)
+        } else {
+            "-".to_string()
+        };
+        if let Some(text) = ui_text.get_mut(practice_text.stats) {
+            text.text = format!(
+                "Returned {}  Missed {}  Accuracy {}",
+                practice.returned, practice.missed, accuracy
+            );
+        }
+        if let Some(text) = ui_text.get_mut(practice_text.launcher) {
+            text.text = format!(
+                "Angle {:.0} (1/2)  Speed {:.0} (3/4)  Every {:.1}s (5/6)",
+                practice.angle.to_degrees(),
+                practice.speed,
+                practice.interval
+            );
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use amethyst::{
+    ecs::prelude::{Read, ReadExpect, System, WriteStorage},
+    ui::UiText,
+};
+
+use crate::pong::{Rally, RallyText};
+
+/// Shows the current and best `Rally` on the HUD.
+pub struct RallyUiSystem;
+
+impl<'s> System<'s> for RallyUiSystem {
+    type SystemData = (
+        Read<'s, Rally>,
+        Option<ReadExpect<'s, RallyText>>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(&mut self, (rally, rally_text, mut ui_text): Self::SystemData) {
+        if let Some(text) = rally_text.and_then(|text| ui_text.get_mut(text.0)) {
+            let line = format!("Rally {}  Best {}", rally.current, rally.best);
+            // Only touch the text when it changed, setting it makes the UI lay it out again.
+            if text.text != line {
+                text.text = line;
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use amethyst::{
+    assets::AssetStorage,
+    audio::{output::Output, Source},
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{
+        Entities, Join, LazyUpdate, Read, ReadExpect, System, Write, WriteExpect, WriteStorage,
+    },
+    shrev::EventChannel,
+    ui::UiText,
+};
+
+use crate::audio::{
+    play_bounce_sound, play_score_sound, play_wall_sound, Music, Sounds, SCORE_DUCK,
+};
+use crate::config::AudioSettings;
+use crate::net::Online;
+use crate::pong::{
+    Ball, BallHit, GameWon, GoalConceded, Paddle, Rally, ReplayText, ScoreBoard, ScoreEvent,
+    ScreenShake, ServeCountdown, Side, ARENA_WIDTH, GOAL_SHAKE_AMOUNT, GOAL_SHAKE_DURATION,
+};
+use crate::replay::Playback;
+use crate::rollback::{MatchEvent, ROLLBACK_FRAME};
+
+/// Plays a saved online match back out of the `Playback`, in place of the systems moving the
+/// paddles and the ball and keeping the score. A frame is played every `ROLLBACK_FRAME` at the
+/// playback's speed, like the match was at normal speed, and the arena is put where the match
+/// is, the same as the `OnlineSystem` does. The arena stays where the match ended once every
+/// frame has been played. The `ReplayText` shows how it's being played.
+#[derive(Default)]
+pub struct ReplaySystem {
+    /// Time passed that no frame has been played for yet, in seconds.
+    unplayed: f32,
+}
+
+impl<'s> System<'s> for ReplaySystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Paddle>,
+        WriteStorage<'s, Ball>,
+        WriteStorage<'s, Transform>,
+        Read<'s, Time>,
+        Read<'s, Online>,
+        Option<WriteExpect<'s, Playback>>,
+        Option<ReadExpect<'s, ReplayText>>,
+        WriteStorage<'s, UiText>,
+        Write<'s, ScoreBoard>,
+        Write<'s, ServeCountdown>,
+        Write<'s, Rally>,
+        Write<'s, ScreenShake>,
+        Write<'s, EventChannel<ScoreEvent>>,
+        Write<'s, EventChannel<GoalConceded>>,
+        Write<'s, EventChannel<GameWon>>,
+        Write<'s, EventChannel<BallHit>>,
+        Read<'s, AssetStorage<Source>>,
+        Option<ReadExpect<'s, Sounds>>,
+        Option<Read<'s, Output>>,
+        Read<'s, AudioSettings>,
+        Option<WriteExpect<'s, Music>>,
+        Read<'s, LazyUpdate>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut paddles,
+            mut balls,
+            mut transforms,
+            time,
+            online,
+            playback,
+            replay_text,
+            mut ui_text,
+            mut scores,
+            mut countdown,
+            mut rally,
+            mut shake,
+            mut score_events,
+            mut goals_conceded,
+            mut game_won,
+            mut ball_hits,
+            audio_storage,
+            sounds,
+            audio_output,
+            audio_settings,
+            mut music,
+            lazy,
+        ): Self::SystemData,
+    ) {
+        let mut playback = match playback {
+            Some(playback) if online.replaying => playback,
+            _ => {
+                self.unplayed = 0.0;
+                return;
+            }
+        };
+        let mut events = playback.take_events();
+        if !playback.paused {
+            self.unplayed += time.delta_seconds() * playback.speed();
+        }
+        while self.unplayed >= ROLLBACK_FRAME && !playback.is_over() {
+            self.unplayed -= ROLLBACK_FRAME;
+            playback.step(&mut events);
+        }
+        while playback.steps > 0 {
+            playback.steps -= 1;
+            playback.step(&mut events);
+        }
+        if playback.is_over() {
+            self.unplayed = 0.0;
+        }
+        if let Some(text) = replay_text.and_then(|text| ui_text.get_mut(text.0)) {
+            let paused = if playback.paused { ", paused" } else { "" };
+            text.text = format!("Replay {}x{}", playback.speed(), paused);
+        }
+
+        let state = playback.state();
+        for (paddle, transform) in (&mut paddles, &mut transforms).join() {
+            let (position, velocity) = state.paddle(paddle.side);
+            transform.set_translation_y(Float::from(position));
+            paddle.velocity = velocity;
+        }
+        for (ball, transform) in (&mut balls, &mut transforms).join() {
+            transform.set_translation_x(Float::from(state.ball_position.x));
+            transform.set_translation_y(Float::from(state.ball_position.y));
+            ball.velocity = state.ball_velocity;
+            ball.spin = state.ball_spin;
+        }
+        countdown.remaining = state.serve_countdown();
+        rally.current = state.rally;
+        rally.best = rally.best.max(rally.current);
+        if !state.between_games() {
+            for side in &[Side::Left, Side::Right] {
+                let score = state.score(*side);
+                if scores.score(*side) != score {
+                    *scores.score_mut(*side) = score;
+                    score_events.single_write(ScoreEvent { side: *side, score });
+                }
+            }
+        }
+
+        let volume = audio_settings.sfx_volume();
+        let output = audio_output.as_deref();
+        for event in events {
+            match event {
+                MatchEvent::Bounce {
+                    position,
+                    velocity,
+                    paddle,
+                } => {
+                    let struck = paddle.and_then(|side| {
+                        (&entities, &paddles)
+                            .join()
+                            .find(|(_, paddle)| paddle.side == side)
+                            .map(|(entity, _)| entity)
+                    });
+                    ball_hits.single_write(BallHit {
+                        position,
+                        velocity,
+                        paddle: struck,
+                    });
+                    if let Some(sounds) = &sounds {
+                        let play = if paddle.is_some() {
+                            play_bounce_sound
+                        } else {
+                            play_wall_sound
+                        };
+                        let across = position.x / ARENA_WIDTH;
+                        play(sounds, &audio_storage, output, volume, across, &entities, &lazy);
+                    }
+                }
+                MatchEvent::Goal { conceded, speed } => {
+                    goals_conceded.single_write(GoalConceded {
+                        side: conceded,
+                        speed,
+                    });
+                    if let Some(sounds) = &sounds {
+                        play_score_sound(sounds, &audio_storage, output, volume);
+                    }
+                    if let Some(music) = &mut music {
+                        music.duck(SCORE_DUCK);
+                    }
+                    shake.start(GOAL_SHAKE_DURATION, GOAL_SHAKE_AMOUNT);
+                }
+                MatchEvent::GameWon { winner } => game_won.single_write(GameWon { winner }),
+            }
+        }
+    }
+}
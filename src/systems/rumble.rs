@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use amethyst::{
+    ecs::prelude::{Join, Read, ReadStorage, Resources, System, SystemData},
+    input::{InputHandler, StringBindings},
+    shrev::{EventChannel, ReaderId},
+};
+use sdl2::sys;
+
+use crate::config::GameConfig;
+use crate::controls::Controls;
+use crate::pong::{BallHit, GoalConceded, Paddle};
+
+/// Ball speed the rumble is at its strongest from, in arena units per second.
+const RUMBLE_FULL_SPEED: f32 = 150.0;
+/// How strong the rumble of the slowest ball is, from 0.0 to 1.0.
+const RUMBLE_MIN_STRENGTH: f32 = 0.2;
+/// How long the pulses of a hit and a goal last, in milliseconds.
+const HIT_RUMBLE_LENGTH: u32 = 80;
+const GOAL_RUMBLE_LENGTH: u32 = 400;
+
+/// Rumbles the gamepad of a player when their paddle hits the ball, and for longer when they
+/// concede a goal, harder the faster the ball was going. It runs on the main thread next to the
+/// `SdlEventsSystem`, since SDL's haptics aren't shared between threads.
+#[derive(Default)]
+pub struct RumbleSystem {
+    hit_reader: Option<ReaderId<BallHit>>,
+    goal_reader: Option<ReaderId<GoalConceded>>,
+    /// The rumble of every gamepad that has been pulsed, by its id, `None` when it can't rumble.
+    haptics: HashMap<u32, Option<Haptic>>,
+}
+
+impl<'s> System<'s> for RumbleSystem {
+    type SystemData = (
+        Read<'s, EventChannel<BallHit>>,
+        Read<'s, EventChannel<GoalConceded>>,
+        ReadStorage<'s, Paddle>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, GameConfig>,
+    );
+
+    fn run(&mut self, (hits, goals, paddles, input, config): Self::SystemData) {
+        let mut pulses = Vec::new();
+        for hit in hits.read(self.hit_reader.as_mut().unwrap()) {
+            if let Some(paddle) = hit.paddle.and_then(|paddle| paddles.get(paddle)) {
+                let strength = rumble_strength(hit.velocity.norm());
+                pulses.push((paddle.side, paddle.lane, strength, HIT_RUMBLE_LENGTH));
+            }
+        }
+        for goal in goals.read(self.goal_reader.as_mut().unwrap()) {
+            // Everyone guarding the goal feels it, both lanes in doubles.
+            for paddle in (&paddles).join().filter(|paddle| paddle.side == goal.side) {
+                let strength = rumble_strength(goal.speed);
+                pulses.push((paddle.side, paddle.lane, strength, GOAL_RUMBLE_LENGTH));
+            }
+        }
+        if !config.rumble {
+            return;
+        }
+
+        for (side, lane, strength, length) in pulses {
+            let gamepad = match Controls::of(side, lane).gamepad(&input.bindings) {
+                Some(gamepad) => gamepad,
+                None => continue,
+            };
+            let haptic = self
+                .haptics
+                .entry(gamepad)
+                .or_insert_with(|| Haptic::open(gamepad));
+            // A gamepad that was unplugged is opened again the next time it's pulsed.
+            if let Some(false) = haptic.as_ref().map(|haptic| haptic.play(strength, length)) {
+                self.haptics.remove(&gamepad);
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.hit_reader = Some(res.fetch_mut::<EventChannel<BallHit>>().register_reader());
+        self.goal_reader = Some(
+            res.fetch_mut::<EventChannel<GoalConceded>>()
+                .register_reader(),
+        );
+        // The `SdlEventsSystem` started SDL without its haptics.
+        unsafe {
+            if sys::SDL_InitSubSystem(sys::SDL_INIT_HAPTIC) != 0 {
+                log::warn!("Failed to start the gamepad rumble");
+            }
+        }
+    }
+}
+
+/// How strong the rumble of a ball going at `speed` is.
+fn rumble_strength(speed: f32) -> f32 {
+    let fraction = (speed / RUMBLE_FULL_SPEED).min(1.0);
+    RUMBLE_MIN_STRENGTH + (1.0 - RUMBLE_MIN_STRENGTH) * fraction
+}
+
+/// The rumble of a gamepad opened through SDL, closed when it's dropped.
+struct Haptic(*mut sys::SDL_Haptic);
+
+impl Haptic {
+    /// Opens the rumble of the gamepad with the id `InputHandler` knows it by, which is its
+    /// joystick's instance id. `None` if it's gone or can't rumble.
+    fn open(gamepad: u32) -> Option<Haptic> {
+        unsafe {
+            let joystick = sys::SDL_JoystickFromInstanceID(gamepad as sys::SDL_JoystickID);
+            if joystick.is_null() {
+                return None;
+            }
+            let haptic = sys::SDL_HapticOpenFromJoystick(joystick);
+            if haptic.is_null() {
+                return None;
+            }
+            let haptic = Haptic(haptic);
+            if sys::SDL_HapticRumbleInit(haptic.0) != 0 {
+                return None;
+            }
+            Some(haptic)
+        }
+    }
+
+    /// Rumbles at `strength` for `length` milliseconds, false if the gamepad can't anymore.
+    fn play(&self, strength: f32, length: u32) -> bool {
+        unsafe { sys::SDL_HapticRumblePlay(self.0, strength, length) == 0 }
+    }
+}
+
+impl Drop for Haptic {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_HapticClose(self.0) }
+    }
+}
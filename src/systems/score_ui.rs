@@ -0,0 +1,41 @@
+use amethyst::{
+    ecs::prelude::{Read, ReadExpect, Resources, System, SystemData, WriteStorage},
+    shrev::{EventChannel, ReaderId},
+    ui::UiText,
+};
+
+use crate::pong::{ScoreEvent, ScoreText};
+
+/// Puts the scores sent by the `WinnerSystem` on the scoreboard.
+#[derive(Default)]
+pub struct ScoreUiSystem {
+    score_reader: Option<ReaderId<ScoreEvent>>,
+}
+
+impl<'s> System<'s> for ScoreUiSystem {
+    type SystemData = (
+        Read<'s, EventChannel<ScoreEvent>>,
+        Option<ReadExpect<'s, ScoreText>>,
+        WriteStorage<'s, UiText>,
+    );
+
+    fn run(&mut self, (score_events, score_text, mut ui_text): Self::SystemData) {
+        // Read the events even without a scoreboard, so they don't pile up.
+        let events = score_events.read(self.score_reader.as_mut().unwrap());
+        let score_text = match score_text {
+            Some(score_text) => score_text,
+            None => return,
+        };
+
+        for event in events {
+            if let Some(text) = score_text.for_side(event.side).and_then(|e| ui_text.get_mut(e)) {
+                text.text = event.score.to_string();
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.score_reader = Some(res.fetch_mut::<EventChannel<ScoreEvent>>().register_reader());
+    }
+}
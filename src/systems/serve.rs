@@ -0,0 +1,156 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Float, Transform},
+    ecs::prelude::{Entities, Entity, Join, Read, ReadStorage, System, WriteStorage},
+    input::{InputHandler, StringBindings},
+};
+
+use crate::net::Online;
+use crate::pong::{
+    AiPaddle, Ball, Lane, Paddle, ServeCountdown, Serving, Side, BALL_MAX_BOUNCE_ANGLE,
+    SERVE_ANGLE_PER_SPEED,
+};
+
+/// Keeps balls that are being served on the serving player's paddle, and launches them when
+/// that player presses their serve action. AI players serve on their own after a delay. Online
+/// matches are served in the `OnlineSystem`.
+///
+/// Moving the paddle while serving angles the serve the same way. Balls caught by a sticky
+/// paddle are released the same way, from the paddle that caught them. Nobody can serve while
+/// the `ServeCountdown` after a point is running, the ball just moves along with the paddle.
+pub struct ServeSystem;
+
+impl<'s> System<'s> for ServeSystem {
+    type SystemData = (
+        Entities<'s>,
+        WriteStorage<'s, Serving>,
+        WriteStorage<'s, Ball>,
+        ReadStorage<'s, Paddle>,
+        ReadStorage<'s, AiPaddle>,
+        WriteStorage<'s, Transform>,
+        Read<'s, InputHandler<StringBindings>>,
+        Read<'s, ServeCountdown>,
+        Read<'s, Time>,
+        Read<'s, Online>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            mut serving,
+            mut balls,
+            paddles,
+            ais,
+            mut transforms,
+            input,
+            countdown,
+            time,
+            online,
+        ): Self::SystemData,
+    ) {
+        if online.in_match() {
+            return;
+        }
+
+        // Where each paddle is. In doubles the first paddle of a team serves.
+        let mut servers: Vec<Server> = Vec::new();
+        for (entity, paddle, transform) in (&entities, &paddles, &transforms).join() {
+            let thickness = if paddle.side.is_horizontal() {
+                paddle.height
+            } else {
+                paddle.width
+            };
+            servers.push(Server {
+                paddle: entity,
+                side: paddle.side,
+                lane: paddle.lane,
+                position: Vector2::new(
+                    transform.translation().x.as_f32(),
+                    transform.translation().y.as_f32(),
+                ),
+                half_thickness: thickness * 0.5,
+                velocity: paddle.velocity,
+                is_ai: ais.contains(entity),
+            });
+        }
+
+        let mut served = Vec::new();
+        for (entity, serve, ball, transform) in
+            (&entities, &mut serving, &mut balls, &mut transforms).join()
+        {
+            let server = servers.iter().find(|server| match serve.paddle {
+                Some(holder) => server.paddle == holder,
+                None => server.side == serve.side,
+            });
+            let server = match server {
+                Some(server) => server,
+                // Nobody to serve, just let the ball go.
+                None => {
+                    served.push(entity);
+                    continue;
+                }
+            };
+
+            // Rest the ball just in front of the paddle.
+            let normal = serve.side.normal();
+            let position = server.position + normal * (server.half_thickness + ball.radius + 1.0);
+            transform.set_translation_x(Float::from(position.x));
+            transform.set_translation_y(Float::from(position.y));
+
+            if countdown.remaining > 0.0 {
+                continue;
+            }
+            let serve_pressed = if server.is_ai {
+                serve.timer -= time.delta_seconds();
+                serve.timer <= 0.0
+            } else {
+                input
+                    .action_is_down(serve_action(serve.side, server.lane))
+                    .unwrap_or(false)
+            };
+            if !serve_pressed {
+                continue;
+            }
+
+            let speed = ball.velocity.norm();
+            ball.velocity = serve_velocity(serve.side, server.velocity, speed);
+            ball.last_hit = Some(serve.side);
+            served.push(entity);
+        }
+
+        for entity in served {
+            serving.remove(entity);
+        }
+    }
+}
+
+/// The velocity a ball going at `speed` is served with from the paddle on `side`, angled the
+/// way the paddle is moving at `paddle_velocity`.
+pub fn serve_velocity(side: Side, paddle_velocity: f32, speed: f32) -> Vector2<f32> {
+    let angle = (paddle_velocity * SERVE_ANGLE_PER_SPEED)
+        .clamp(-BALL_MAX_BOUNCE_ANGLE, BALL_MAX_BOUNCE_ANGLE);
+    (side.normal() * angle.cos() + side.tangent() * angle.sin()) * speed
+}
+
+struct Server {
+    paddle: Entity,
+    side: Side,
+    lane: Lane,
+    position: Vector2<f32>,
+    half_thickness: f32,
+    velocity: f32,
+    is_ai: bool,
+}
+
+// Only the upper paddle of a team ever serves, the lower one uses its hit action to release
+// the balls it caught.
+fn serve_action(side: Side, lane: Lane) -> &'static str {
+    match (side, lane) {
+        (Side::Left, Lane::Lower) => "left_lower_hit",
+        (Side::Right, Lane::Lower) => "right_lower_hit",
+        (Side::Left, _) => "left_serve",
+        (Side::Right, _) => "right_serve",
+        (Side::Top, _) => "top_serve",
+        (Side::Bottom, _) => "bottom_serve",
+    }
+}
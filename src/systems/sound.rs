@@ -0,0 +1,22 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Entities, Join, Read, System, WriteStorage},
+};
+
+use crate::audio::SoundEmitter;
+
+/// Deletes the emitters of the sounds played from the arena once they've had the time to play.
+pub struct SoundEmitterSystem;
+
+impl<'s> System<'s> for SoundEmitterSystem {
+    type SystemData = (Entities<'s>, WriteStorage<'s, SoundEmitter>, Read<'s, Time>);
+
+    fn run(&mut self, (entities, mut emitters, time): Self::SystemData) {
+        for (entity, emitter) in (&entities, &mut emitters).join() {
+            emitter.remaining -= time.delta_seconds();
+            if emitter.remaining <= 0.0 {
+                entities.delete(entity).expect("sound emitter should be alive");
+            }
+        }
+    }
+}
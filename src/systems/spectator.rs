@@ -0,0 +1,133 @@
+use amethyst::{
+    core::{timing::Time, Float, Transform},
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    renderer::camera::Camera,
+    shrev::EventChannel,
+    window::ScreenDimensions,
+};
+
+use crate::config::Gameplay;
+use crate::net::{FromLobby, Online, SpectatorMessage};
+use crate::pong::{
+    Ball, MatchScore, Paddle, Rally, ScoreBoard, ScoreEvent, ServeCountdown, Side, ARENA_HEIGHT,
+    ARENA_WIDTH, SPECTATOR_CAMERA_LAG,
+};
+use crate::rollback::MatchState;
+use crate::systems::letterbox::view_size;
+
+/// Shows the online match being watched as its host broadcasts it, nobody's input moves
+/// anything. The camera follows the ball, as far as it can without looking past the arena.
+///
+/// It isn't paused, the match goes on while the spectator has it paused.
+#[derive(Default)]
+pub struct SpectatorSystem {
+    /// The latest broadcast, the arena stays there until the next one comes in.
+    state: Option<MatchState>,
+}
+
+impl<'s> System<'s> for SpectatorSystem {
+    type SystemData = (
+        WriteStorage<'s, Paddle>,
+        WriteStorage<'s, Ball>,
+        ReadStorage<'s, Camera>,
+        WriteStorage<'s, Transform>,
+        Write<'s, Online>,
+        Write<'s, ScoreBoard>,
+        Write<'s, MatchScore>,
+        Write<'s, ServeCountdown>,
+        Write<'s, Rally>,
+        Write<'s, EventChannel<ScoreEvent>>,
+        Read<'s, Time>,
+        Option<ReadExpect<'s, ScreenDimensions>>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut paddles,
+            mut balls,
+            cameras,
+            mut transforms,
+            mut online,
+            mut scores,
+            mut match_score,
+            mut countdown,
+            mut rally,
+            mut score_events,
+            time,
+            dimensions,
+        ): Self::SystemData,
+    ) {
+        if online.watching.is_none() {
+            self.state = None;
+            return;
+        }
+
+        for message in online.receive() {
+            match message {
+                Ok(FromLobby::Broadcast(SpectatorMessage::State { state, games })) => {
+                    match_score.games_left = games[0];
+                    match_score.games_right = games[1];
+                    self.state = Some(state);
+                }
+                Ok(FromLobby::MatchEnded) => {
+                    online.lost = Some("The match is over".to_string());
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    online.lost = Some(format!("Lost the connection to the lobby: {}", err));
+                }
+            }
+        }
+        let state = match &self.state {
+            Some(state) => state,
+            None => return,
+        };
+
+        for (paddle, transform) in (&mut paddles, &mut transforms).join() {
+            let (position, velocity) = state.paddle(paddle.side);
+            transform.set_translation_y(Float::from(position));
+            paddle.velocity = velocity;
+        }
+        for (ball, transform) in (&mut balls, &mut transforms).join() {
+            transform.set_translation_x(Float::from(state.ball_position.x));
+            transform.set_translation_y(Float::from(state.ball_position.y));
+            ball.velocity = state.ball_velocity;
+            ball.spin = state.ball_spin;
+        }
+        countdown.remaining = state.serve_countdown();
+        rally.current = state.rally;
+        rally.best = rally.best.max(rally.current);
+        for side in &[Side::Left, Side::Right] {
+            let score = state.score(*side);
+            if scores.score(*side) != score {
+                *scores.score_mut(*side) = score;
+                score_events.single_write(ScoreEvent { side: *side, score });
+            }
+        }
+
+        // Keep the view inside the arena, a window wider than the arena just keeps it centered
+        // that way. Online matches are played in the standard arena.
+        let arena = Gameplay::default();
+        let (width, height) = match dimensions {
+            Some(dimensions) if dimensions.height() > 0.0 => view_size(&dimensions, &arena, true),
+            _ => return,
+        };
+        let follow = |ball: f32, view: f32, arena: f32| {
+            if view >= arena {
+                arena * 0.5
+            } else {
+                ball.clamp(view * 0.5, arena - view * 0.5)
+            }
+        };
+        let target_x = follow(state.ball_position.x, width, ARENA_WIDTH);
+        let target_y = follow(state.ball_position.y, height, ARENA_HEIGHT);
+        let kept = SPECTATOR_CAMERA_LAG.powf(time.delta_seconds());
+        for (_, transform) in (&cameras, &mut transforms).join() {
+            let x = transform.translation().x.as_f32() * kept + target_x * (1.0 - kept);
+            let y = transform.translation().y.as_f32() * kept + target_y * (1.0 - kept);
+            transform.set_translation_x(Float::from(x));
+            transform.set_translation_y(Float::from(y));
+        }
+    }
+}
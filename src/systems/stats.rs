@@ -0,0 +1,43 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Join, Read, ReadStorage, Resources, System, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+};
+
+use crate::pong::{Ball, GoalConceded, MatchStats, ScoreEvent};
+
+/// Keeps the match clock for the results screen, it only runs while a ball is in play. Also
+/// counts the points every side scores and concedes, for the players' profiles.
+#[derive(Default)]
+pub struct MatchStatsSystem {
+    score_reader: Option<ReaderId<ScoreEvent>>,
+    goal_reader: Option<ReaderId<GoalConceded>>,
+}
+
+impl<'s> System<'s> for MatchStatsSystem {
+    type SystemData = (
+        Write<'s, MatchStats>,
+        ReadStorage<'s, Ball>,
+        Read<'s, Time>,
+        Read<'s, EventChannel<ScoreEvent>>,
+        Read<'s, EventChannel<GoalConceded>>,
+    );
+
+    fn run(&mut self, (mut stats, balls, time, score_events, goals): Self::SystemData) {
+        if balls.join().next().is_some() {
+            stats.duration += time.delta_seconds();
+        }
+        for event in score_events.read(self.score_reader.as_mut().unwrap()) {
+            *stats.points_scored.score_mut(event.side) += 1;
+        }
+        for goal in goals.read(self.goal_reader.as_mut().unwrap()) {
+            *stats.points_conceded.score_mut(goal.side) += 1;
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.score_reader = Some(res.fetch_mut::<EventChannel<ScoreEvent>>().register_reader());
+        self.goal_reader = Some(res.fetch_mut::<EventChannel<GoalConceded>>().register_reader());
+    }
+}
@@ -0,0 +1,118 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System},
+};
+
+use crate::achievements::Achievements;
+use crate::audio::{Music, Track};
+use crate::net::Online;
+use crate::pong::{AiPaddle, GameMode, RunningState, ScoreBoard};
+use crate::presence::describe_activity;
+use crate::steam::{modified, Steam, CLOUD_FILES};
+
+/// Seconds between passing the achievements and saves on to Steam.
+const SYNC_INTERVAL: f32 = 5.0;
+
+/// Mirrors the game onto Steam while it's connected: the `Achievements` unlocked are unlocked
+/// on Steam under the same ids, with the `match_wins` and `points` counted towards them as
+/// Steam stats, what's being played is the rich presence status, like the Discord one, and the
+/// `CLOUD_FILES` are uploaded to Steam Cloud whenever they're saved.
+///
+/// The achievements and stats have to be set up in the Steamworks partner site under the same
+/// names for Steam to show them. It runs on the main thread, which Steam was connected to on.
+pub struct SteamSystem {
+    steam: Steam,
+    /// How many of the unlocked achievements and which totals Steam has.
+    unlocked_sent: usize,
+    stats_sent: (u32, u32),
+    /// The rich presence status, `None` until it's first set.
+    shown: Option<String>,
+    /// Time until the next sync with Steam, and when each of the `CLOUD_FILES` uploaded was
+    /// saved.
+    sync_timer: f32,
+    uploaded: Vec<i64>,
+}
+
+impl SteamSystem {
+    pub fn new(steam: Steam) -> SteamSystem {
+        steam.request_current_stats();
+        // The files read as the game started are the ones in the cloud, or newer.
+        let uploaded = CLOUD_FILES.iter().map(|name| modified(name).unwrap_or(0)).collect();
+        SteamSystem {
+            steam,
+            unlocked_sent: 0,
+            stats_sent: (0, 0),
+            shown: None,
+            sync_timer: 0.0,
+            uploaded,
+        }
+    }
+
+    /// Unlocks the achievements Steam doesn't have yet and sets the stats, again the next
+    /// time if Steam hasn't sent the player's stats yet.
+    fn sync_achievements(&mut self, achievements: &Achievements) {
+        let progress = &achievements.progress;
+        let stats = (progress.match_wins, progress.points);
+        if self.unlocked_sent == progress.unlocked.len() && self.stats_sent == stats {
+            return;
+        }
+        let steam = &self.steam;
+        let unlocked = progress.unlocked[self.unlocked_sent..]
+            .iter()
+            .all(|id| steam.set_achievement(id));
+        let set = steam.set_stat("match_wins", stats.0 as i32)
+            && steam.set_stat("points", stats.1 as i32);
+        if unlocked && set && steam.store_stats() {
+            self.unlocked_sent = progress.unlocked.len();
+            self.stats_sent = stats;
+        }
+    }
+
+    /// Uploads the `CLOUD_FILES` saved since they were last uploaded.
+    fn sync_saves(&mut self) {
+        for (name, uploaded) in CLOUD_FILES.iter().zip(self.uploaded.iter_mut()) {
+            match modified(name) {
+                Ok(saved) if saved > *uploaded => {
+                    self.steam.upload_save(name);
+                    *uploaded = saved;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'s> System<'s> for SteamSystem {
+    type SystemData = (
+        Read<'s, Achievements>,
+        Option<ReadExpect<'s, Music>>,
+        Read<'s, RunningState>,
+        Read<'s, GameMode>,
+        Read<'s, ScoreBoard>,
+        Read<'s, Online>,
+        ReadStorage<'s, AiPaddle>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (achievements, music, running, mode, score, online, ai_paddles, time): Self::SystemData,
+    ) {
+        self.steam.run_callbacks();
+
+        let in_match = music.map(|music| music.playing) == Some(Some(Track::Game));
+        let against_ai = (&ai_paddles).join().next().is_some();
+        let line = describe_activity(in_match, *running, *mode, &score, &online, against_ai);
+        if self.shown.as_ref() != Some(&line) {
+            self.steam.set_rich_presence("status", &line);
+            self.shown = Some(line);
+        }
+
+        self.sync_timer -= time.delta_real_seconds();
+        if self.sync_timer <= 0.0 {
+            self.sync_timer = SYNC_INTERVAL;
+            self.sync_achievements(&achievements);
+            self.sync_saves();
+        }
+    }
+}
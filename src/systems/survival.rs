@@ -0,0 +1,120 @@
+use amethyst::{
+    core::{
+        math::{Rotation2, Vector2},
+        timing::Time,
+        Transform,
+    },
+    ecs::prelude::{
+        Builder, Entities, Join, LazyUpdate, Read, ReadExpect, ReadStorage, System, Write,
+        WriteStorage,
+    },
+    renderer::SpriteRender,
+    ui::UiText,
+};
+use rand::Rng;
+
+use crate::config::Gameplay;
+use crate::pong::{
+    ball_scale, Animation, Ball, GameMode, Mutators, ScoreText, SpriteSheetHandle, Survival,
+    BALL_MAX_BOUNCE_ANGLE, SURVIVAL_INTERVAL_DECAY, SURVIVAL_MAX_BALLS, SURVIVAL_MIN_INTERVAL,
+};
+
+/// Keeps the clock of a survival run, shown where the left player's score usually is, and
+/// throws in balls from the right wall more and more often.
+pub struct SurvivalSystem;
+
+impl<'s> System<'s> for SurvivalSystem {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, Ball>,
+        Write<'s, Survival>,
+        Read<'s, GameMode>,
+        Read<'s, Mutators>,
+        WriteStorage<'s, UiText>,
+        Option<ReadExpect<'s, ScoreText>>,
+        Option<ReadExpect<'s, SpriteSheetHandle>>,
+        Read<'s, LazyUpdate>,
+        Read<'s, Time>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            entities,
+            balls,
+            mut survival,
+            mode,
+            mutators,
+            mut ui_text,
+            score_text,
+            sprite_sheet,
+            lazy,
+            time,
+            gameplay,
+        ): Self::SystemData,
+    ) {
+        if *mode != GameMode::Survival || survival.over {
+            return;
+        }
+        // The clock starts with the first ball.
+        let balls_in_play = balls.join().count();
+        if balls_in_play == 0 {
+            return;
+        }
+        let (score_text, sprite_sheet) = match (score_text, sprite_sheet) {
+            (Some(score_text), Some(sprite_sheet)) => (score_text, sprite_sheet),
+            _ => return,
+        };
+
+        survival.elapsed += time.delta_seconds();
+        if let Some(text) = ui_text.get_mut(score_text.p1_score) {
+            text.text = format!("{:.1}", survival.elapsed);
+        }
+        if let Some(text) = ui_text.get_mut(score_text.p2_score) {
+            text.text.clear();
+        }
+
+        survival.spawn_timer -= time.delta_seconds();
+        if survival.spawn_timer > 0.0 {
+            return;
+        }
+        survival.spawn_interval =
+            (survival.spawn_interval * SURVIVAL_INTERVAL_DECAY).max(SURVIVAL_MIN_INTERVAL);
+        survival.spawn_timer = survival.spawn_interval;
+
+        if balls_in_play >= SURVIVAL_MAX_BALLS {
+            return;
+        }
+
+        // New balls come off the right wall towards the player at a random angle.
+        let mut rng = rand::thread_rng();
+        let radius = mutators.ball_radius(gameplay.ball_radius);
+        let height = gameplay.arena_height;
+        let mut transform = Transform::default();
+        transform.set_translation_xyz(
+            gameplay.arena_width - radius * 2.0,
+            rng.gen_range(height * 0.2, height * 0.8),
+            0.0,
+        );
+        transform.set_scale(ball_scale(radius));
+        let angle = rng.gen_range(-BALL_MAX_BOUNCE_ANGLE, BALL_MAX_BOUNCE_ANGLE) * 0.5;
+        let velocity = Rotation2::new(angle) * Vector2::new(-gameplay.ball_velocity_x, 0.0);
+
+        lazy.create_entity(&entities)
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.0.clone(),
+                sprite_number: 1, // ball is the second sprite on the sprite sheet
+            })
+            .with(Animation::ball())
+            .with(Ball {
+                radius,
+                velocity,
+                spin: 0.0,
+                last_hit: None,
+                power_shot: false,
+            })
+            .with(transform)
+            .build();
+    }
+}
@@ -0,0 +1,85 @@
+use amethyst::{
+    core::timing::Time,
+    ecs::prelude::{Join, Read, ReadExpect, ReadStorage, System, Write, WriteStorage},
+    shrev::EventChannel,
+    ui::UiText,
+};
+
+use crate::pong::{Ball, GameMode, GameWon, MatchRules, MatchTimer, ScoreBoard, ScoreText};
+
+/// Runs the clock in time attack games and shows it in the HUD. When it runs out whoever has
+/// the most points wins the game.
+///
+/// If the lead is shared at that point the game goes to a golden goal, the `WinnerSystem`
+/// ends it with the next point.
+pub struct TimeAttackSystem;
+
+impl<'s> System<'s> for TimeAttackSystem {
+    type SystemData = (
+        Write<'s, MatchTimer>,
+        ReadStorage<'s, Ball>,
+        Read<'s, MatchRules>,
+        Read<'s, GameMode>,
+        Read<'s, ScoreBoard>,
+        WriteStorage<'s, UiText>,
+        Option<ReadExpect<'s, ScoreText>>,
+        Write<'s, EventChannel<GameWon>>,
+        Read<'s, Time>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut timer,
+            balls,
+            rules,
+            mode,
+            scores,
+            mut ui_text,
+            score_text,
+            mut game_won,
+            time,
+        ): Self::SystemData,
+    ) {
+        if rules.time_limit.is_none() || timer.finished {
+            return;
+        }
+        let score_text = match score_text {
+            Some(score_text) => score_text,
+            None => return,
+        };
+
+        // The clock only runs while a ball is in play.
+        if !timer.golden_goal && balls.join().next().is_some() {
+            timer.remaining = (timer.remaining - time.delta_seconds()).max(0.0);
+        }
+        if timer.remaining <= 0.0 && !timer.golden_goal {
+            let best = mode
+                .sides()
+                .iter()
+                .map(|side| scores.score(*side))
+                .max()
+                .unwrap_or(0);
+            let leaders: Vec<_> = mode
+                .sides()
+                .iter()
+                .filter(|side| scores.score(**side) == best)
+                .collect();
+            if let [winner] = leaders.as_slice() {
+                timer.finished = true;
+                game_won.single_write(GameWon { winner: **winner });
+            } else {
+                timer.golden_goal = true;
+            }
+        }
+
+        if let Some(text) = ui_text.get_mut(score_text.timer) {
+            text.text = if timer.golden_goal {
+                "GOLDEN GOAL".to_string()
+            } else {
+                let seconds = timer.remaining.ceil() as u32;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            };
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use amethyst::{
+    ecs::prelude::{Read, ReadExpect, Resources, System, SystemData, Write},
+    shrev::{EventChannel, ReaderId},
+    window::ScreenDimensions,
+    winit::{Event, TouchPhase, WindowEvent},
+};
+
+use crate::config::Gameplay;
+use crate::pong::{RunningState, Side, TouchDrag};
+
+/// Lets the players drag their paddles up and down with their fingers on a touch screen, each
+/// on their own half of it. A finger stays with the half it touched down on however far it's
+/// dragged, so two players can play at once.
+#[derive(Default)]
+pub struct TouchSystem {
+    event_reader: Option<ReaderId<Event>>,
+    /// The side every finger on the screen drags, and how far down the window it was last.
+    fingers: HashMap<u64, (Side, f64)>,
+}
+
+impl<'s> System<'s> for TouchSystem {
+    type SystemData = (
+        Read<'s, EventChannel<Event>>,
+        Write<'s, TouchDrag>,
+        Option<ReadExpect<'s, ScreenDimensions>>,
+        Read<'s, RunningState>,
+        Read<'s, Gameplay>,
+    );
+
+    fn run(&mut self, (events, mut drag, dimensions, running, gameplay): Self::SystemData) {
+        let touches = events
+            .read(self.event_reader.as_mut().unwrap())
+            .filter_map(|event| match event {
+                Event::WindowEvent {
+                    event: WindowEvent::Touch(touch),
+                    ..
+                } => Some(*touch),
+                _ => None,
+            });
+        let dimensions = match dimensions {
+            Some(dimensions) if dimensions.width() > 0.0 && dimensions.height() > 0.0 => dimensions,
+            _ => return,
+        };
+        // Touches are in logical pixels, the screen dimensions in physical ones. The arena is
+        // scaled to fit the window like the `LetterboxSystem` does.
+        let hidpi = dimensions.hidpi_factor();
+        let scale = (dimensions.width() / gameplay.arena_width)
+            .min(dimensions.height() / gameplay.arena_height);
+
+        for touch in touches {
+            let x = touch.location.x * hidpi;
+            let y = touch.location.y * hidpi;
+            match touch.phase {
+                TouchPhase::Started => {
+                    let side = if x < f64::from(dimensions.width()) * 0.5 {
+                        Side::Left
+                    } else {
+                        Side::Right
+                    };
+                    self.fingers.insert(touch.id, (side, y));
+                }
+                TouchPhase::Moved => {
+                    if let Some((side, last_y)) = self.fingers.get_mut(&touch.id) {
+                        // The window's y goes down, the arena's goes up.
+                        let moved = (*last_y - y) as f32 / scale;
+                        *last_y = y;
+                        // Dragging while the match is paused doesn't move the paddles later.
+                        if *running != RunningState::Running {
+                            continue;
+                        }
+                        match side {
+                            Side::Left => drag.left += moved,
+                            _ => drag.right += moved,
+                        }
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.fingers.remove(&touch.id);
+                }
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.event_reader = Some(res.fetch_mut::<EventChannel<Event>>().register_reader());
+    }
+}
@@ -0,0 +1,63 @@
+use amethyst::{
+    ecs::prelude::{Read, ReadExpect, Resources, System, SystemData, Write},
+    input::{InputEvent, InputHandler, StringBindings},
+    shrev::{EventChannel, ReaderId},
+    winit::{VirtualKeyCode, Window},
+};
+
+use crate::config::{AudioSettings, GameConfig, PlayerNames, UserSettings};
+use crate::settings::set_fullscreen;
+
+/// Switches between windowed and fullscreen when Alt+Enter is pressed, on every screen, and
+/// saves the choice in the settings. The renderer picks the new window size up on its own.
+#[derive(Default)]
+pub struct WindowModeSystem {
+    input_reader: Option<ReaderId<InputEvent<String>>>,
+}
+
+impl<'s> System<'s> for WindowModeSystem {
+    type SystemData = (
+        Read<'s, EventChannel<InputEvent<String>>>,
+        Read<'s, InputHandler<StringBindings>>,
+        Write<'s, GameConfig>,
+        Read<'s, AudioSettings>,
+        Read<'s, PlayerNames>,
+        ReadExpect<'s, Window>,
+    );
+
+    fn run(
+        &mut self,
+        (input_events, input, mut config, audio, names, window): Self::SystemData,
+    ) {
+        let alt =
+            input.key_is_down(VirtualKeyCode::LAlt) || input.key_is_down(VirtualKeyCode::RAlt);
+        let toggled = input_events
+            .read(self.input_reader.as_mut().unwrap())
+            .filter(|event| match event {
+                InputEvent::KeyPressed { key_code, .. } => *key_code == VirtualKeyCode::Return,
+                _ => false,
+            })
+            .count();
+        if !alt || toggled == 0 {
+            return;
+        }
+
+        config.fullscreen = !config.fullscreen;
+        set_fullscreen(&window, config.fullscreen);
+        let settings = UserSettings {
+            game: config.clone(),
+            audio: audio.clone(),
+            controls: input.bindings.clone(),
+            names: names.clone(),
+        };
+        settings.save();
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+        self.input_reader = Some(
+            res.fetch_mut::<EventChannel<InputEvent<String>>>()
+                .register_reader(),
+        );
+    }
+}
@@ -1,60 +1,294 @@
 use amethyst::{
+  assets::AssetStorage,
+  audio::{output::Output, Source},
   core::transform::Transform,
-  ecs::prelude::{Join, ReadExpect, System, Write, WriteStorage},
-  ui::UiText,
+  ecs::prelude::{
+    Entities, Join, Read, ReadExpect, ReadStorage, Resources, System, SystemData, Write,
+    WriteExpect, WriteStorage,
+  },
+  shrev::{EventChannel, ReaderId},
 };
 
-use crate::pong::{Ball, ScoreBoard, ScoreText, ARENA_WIDTH};
+use crate::audio::{play_score_sound, play_shield_sound, Music, Sounds, SCORE_DUCK};
+use crate::config::{AudioSettings, Gameplay};
+use crate::net::Online;
+use crate::pong::{
+  Ball, BrickBroken, GameMode, GameWon, GoalConceded, GoalZones, MatchRules, MatchTimer,
+  MultiBall, Practice, Rally, ScoreBoard, ScoreEvent, ScreenShake, ServeCountdown, Serving,
+  Shield, Side, Survival, GOAL_SHAKE_AMOUNT, GOAL_SHAKE_DURATION, MULTI_BALL_INTERVAL,
+  POWER_SHOT_SPEED_MULTIPLIER, SERVE_AI_DELAY, SERVE_COUNTDOWN,
+};
 
-pub struct WinnerSystem;
+#[derive(Default)]
+pub struct WinnerSystem {
+  /// Bricks broken by the `BounceSystem`, which are worth points to whoever broke them.
+  brick_reader: Option<ReaderId<BrickBroken>>,
+}
 
 impl<'s> System<'s> for WinnerSystem {
   type SystemData = (
+    Entities<'s>,
     WriteStorage<'s, Ball>,
+    WriteStorage<'s, Serving>,
+    ReadStorage<'s, Shield>,
     WriteStorage<'s, Transform>,
-    WriteStorage<'s, UiText>,
     Write<'s, ScoreBoard>,
-    ReadExpect<'s, ScoreText>,
+    Write<'s, MultiBall>,
+    Read<'s, GameMode>,
+    Read<'s, MatchRules>,
+    Read<'s, GoalZones>,
+    Write<'s, Survival>,
+    Write<'s, Practice>,
+    Write<'s, MatchTimer>,
+    Write<'s, Rally>,
+    Write<'s, ServeCountdown>,
+    Write<'s, EventChannel<ScoreEvent>>,
+    Write<'s, EventChannel<GameWon>>,
+    Write<'s, EventChannel<GoalConceded>>,
+    Read<'s, EventChannel<BrickBroken>>,
+    Read<'s, AssetStorage<Source>>,
+    Option<ReadExpect<'s, Sounds>>,
+    Option<Read<'s, Output>>,
+    Read<'s, AudioSettings>,
+    Option<WriteExpect<'s, Music>>,
+    Write<'s, ScreenShake>,
+    // Nested to stay within the 26 resources a system can take.
+    (Read<'s, Online>, Read<'s, Gameplay>),
   );
 
   fn run(
     &mut self,
-    (mut balls, mut locals, mut ui_text, mut scores, score_text): Self::SystemData,
+    (
+      entities,
+      mut balls,
+      mut serving,
+      shields,
+      mut locals,
+      mut scores,
+      mut multi_ball,
+      mode,
+      rules,
+      goal_zones,
+      mut survival,
+      mut practice,
+      mut match_timer,
+      mut rally,
+      mut countdown,
+      mut score_events,
+      mut game_won,
+      mut goals_conceded,
+      brick_broken,
+      audio_storage,
+      sounds,
+      audio_output,
+      audio_settings,
+      mut music,
+      mut shake,
+      (online, gameplay),
+    ): Self::SystemData,
   ) {
-    for (ball, transform) in (&mut balls, &mut locals).join() {
-      let ball_x = transform.translation().x;
+    let four_player = *mode == GameMode::FourPlayer;
+    let volume = audio_settings.sfx_volume();
 
-      let did_hit = if ball_x.as_f32() <= ball.radius {
-        // Right player scored on the left side.
-        // We top the score at 999 to avoid text overlap.
-        scores.score_right = (scores.score_right + 1).min(999);
+    for brick in brick_broken.read(self.brick_reader.as_mut().unwrap()) {
+      if let Some(scorer) = brick.side {
+        add_points(
+          scorer,
+          brick.points,
+          &mut scores,
+          &rules,
+          &mut match_timer,
+          &mut score_events,
+          &mut game_won,
+        );
+      }
+    }
 
-        if let Some(text) = ui_text.get_mut(score_text.p2_score) {
-          text.text = scores.score_right.to_string();
+    // Online matches keep the score in the `OnlineSystem`.
+    if online.in_match() {
+      return;
+    }
+
+    let mut balls_in_play = (&balls).join().count();
+
+    let mut conceded_serves = Vec::new();
+
+    // Balls that are being served are sitting on a paddle and can't go out of play.
+    for (entity, ball, transform, _) in (&entities, &mut balls, &mut locals, !&serving).join() {
+      let ball_x = transform.translation().x.as_f32();
+      let ball_y = transform.translation().y.as_f32();
+
+      // The edge the ball went out of play through, top and bottom are only goals with four
+      // players.
+      let conceded = if ball_x <= ball.radius {
+        Some(Side::Left)
+      } else if ball_x >= gameplay.arena_width - ball.radius {
+        Some(Side::Right)
+      } else if four_player && ball_y <= ball.radius {
+        Some(Side::Bottom)
+      } else if four_player && ball_y >= gameplay.arena_height - ball.radius {
+        Some(Side::Top)
+      } else {
+        None
+      };
+      // Outside of a goal zone the `BounceSystem` sends the ball back instead.
+      let conceded = match conceded {
+        Some(side) if goal_zones.contains(side, ball_y) => side,
+        _ => continue,
+      };
+
+      // A shield behind the conceding player sends the ball back and is used up.
+      if let Some((shield, _)) = (&entities, &shields).join().find(|(_, s)| s.side == conceded) {
+        entities.delete(shield).expect("shield entity should be alive");
+        let normal = conceded.normal();
+        ball.velocity -= normal * (2.0 * ball.velocity.dot(&normal));
+        // Put the ball back in the arena so it doesn't go out again before moving away.
+        match conceded {
+          Side::Left => transform.set_translation_x(ball.radius * 2.0),
+          Side::Right => transform.set_translation_x(gameplay.arena_width - ball.radius * 2.0),
+          Side::Bottom => transform.set_translation_y(ball.radius * 2.0),
+          Side::Top => transform.set_translation_y(gameplay.arena_height - ball.radius * 2.0),
+        };
+        if let Some(sounds) = &sounds {
+          play_shield_sound(sounds, &audio_storage, audio_output.as_deref(), volume);
+        }
+        continue;
+      }
+
+      // Practice doesn't keep score, it only counts the shots the player returned to the
+      // launcher and the ones they let through.
+      if *mode == GameMode::Practice {
+        if conceded == Side::Left {
+          practice.missed += 1;
+        } else {
+          practice.returned += 1;
         }
-        true
-      } else if ball_x.as_f32() >= ARENA_WIDTH - ball.radius {
-        // Left player scored on the right side.
-        // We top the score at 999 to avoid text overlap.
-        scores.score_left = (scores.score_left + 1).min(999);
-        if let Some(text) = ui_text.get_mut(score_text.p1_score) {
-          text.text = scores.score_left.to_string();
+        entities.delete(entity).expect("ball entity should be alive");
+        balls_in_play -= 1;
+        continue;
+      }
+
+      // In survival the right edge is a wall, and any ball getting past the player ends the
+      // run. The `Pong` state picks that up.
+      if *mode == GameMode::Survival {
+        if conceded == Side::Left {
+          survival.over = true;
         }
-        true
+        continue;
+      }
+
+      // While other balls are still in play the rally goes on, only the last ball scores.
+      if balls_in_play > 1 {
+        entities.delete(entity).expect("ball entity should be alive");
+        balls_in_play -= 1;
+        continue;
+      }
+
+      goals_conceded.single_write(GoalConceded {
+        side: conceded,
+        speed: ball.velocity.norm(),
+      });
+
+      // With two players, or two teams in doubles, the side across the arena scores. With four
+      // players whoever touched the ball last scores, unless they put it into their own goal.
+      let scorer = if four_player {
+        ball.last_hit.filter(|side| *side != conceded)
       } else {
-        false
+        Some(conceded.opponent())
       };
 
-      if did_hit {
-        ball.velocity[0] = -ball.velocity[0]; // Reverse Direction
-        transform.set_translation_x(ARENA_WIDTH / 2.0); // Reset Position
+      if let Some(scorer) = scorer {
+        add_points(
+          scorer,
+          1,
+          &mut scores,
+          &rules,
+          &mut match_timer,
+          &mut score_events,
+          &mut game_won,
+        );
+        if let Some(sounds) = &sounds {
+          play_score_sound(sounds, &audio_storage, audio_output.as_deref(), volume);
+        }
+        if let Some(music) = &mut music {
+          music.duck(SCORE_DUCK);
+        }
+        shake.start(GOAL_SHAKE_DURATION, GOAL_SHAKE_AMOUNT);
+      }
+
+      // The player who conceded serves next, the `ServeSystem` puts the ball on their paddle and
+      // holds it there until the countdown is over.
+      conceded_serves.push((entity, conceded));
+      countdown.remaining = SERVE_COUNTDOWN;
+      ball.spin = 0.0;
+      ball.last_hit = None;
+      // Serves go out at the normal speed again after a power shot scored.
+      if ball.power_shot {
+        ball.velocity /= POWER_SHOT_SPEED_MULTIPLIER;
+        ball.power_shot = false;
+      }
+      multi_ball.timer = MULTI_BALL_INTERVAL;
+      rally.current = 0;
 
-        // Print the scoreboard.
+      // Print the scoreboard.
+      if four_player {
+        println!(
+          "Score: | {:^3} | {:^3} | {:^3} | {:^3} |",
+          scores.score_left, scores.score_right, scores.score_top, scores.score_bottom
+        );
+      } else {
         println!(
           "Score: | {:^3} | {:^3} |",
           scores.score_left, scores.score_right
         );
       }
     }
+
+    for (entity, side) in conceded_serves {
+      serving
+        .insert(
+          entity,
+          Serving {
+            side,
+            paddle: None,
+            timer: SERVE_AI_DELAY,
+          },
+        )
+        .expect("ball entity should be alive");
+    }
+  }
+
+  fn setup(&mut self, res: &mut Resources) {
+    Self::SystemData::setup(res);
+    self.brick_reader = Some(res.fetch_mut::<EventChannel<BrickBroken>>().register_reader());
+  }
+}
+
+// Gives the scorer points and sends a `ScoreEvent` with their new score, and `GameWon` if that
+// won them the game.
+fn add_points(
+  scorer: Side,
+  points: i32,
+  scores: &mut ScoreBoard,
+  rules: &MatchRules,
+  match_timer: &mut MatchTimer,
+  score_events: &mut EventChannel<ScoreEvent>,
+  game_won: &mut EventChannel<GameWon>,
+) {
+  // We top the score at 999 to avoid text overlap.
+  let score = scores.score_mut(scorer);
+  *score = (*score + points).min(999);
+  let score = *score;
+  score_events.single_write(ScoreEvent { side: scorer, score });
+
+  // The `Pong` state picks this up and moves on to the next game. Time attack games are decided
+  // by the clock instead, unless they went to a golden goal.
+  let won = match rules.time_limit {
+    Some(_) => match_timer.golden_goal && !match_timer.finished,
+    None => score >= rules.win_score,
+  };
+  if won && !match_timer.finished {
+    match_timer.finished = true;
+    game_won.single_write(GameWon { winner: scorer });
   }
 }
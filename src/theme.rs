@@ -0,0 +1,251 @@
+use amethyst::{
+    assets::{AssetStorage, Loader, ProgressCounter},
+    config::Config,
+    prelude::*,
+    renderer::{formats::texture::ImageFormat, Texture},
+    ui::{FontAsset, FontHandle, TtfFormat, UiImage, UiText},
+};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+
+use crate::palette::Palette;
+
+/// Where the themes are kept, one RON file per theme named after it.
+pub const THEMES_DIR: &str = "assets/themes";
+/// The theme the game starts out with, until another one is picked in the settings.
+pub const DEFAULT_THEME: &str = "classic";
+
+/// Something drawn behind part of the UI, a flat color or a texture from the assets directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ThemeImage {
+    Color([f32; 4]),
+    Texture(String),
+}
+
+/// How the UI looks, as written in a theme file under `assets/themes/`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// The font every screen writes its text in, relative to the assets directory.
+    pub font: String,
+    pub text_color: [f32; 4],
+    /// Color of the picked entry in a menu.
+    pub highlight_color: [f32; 4],
+    /// Color of the text being typed in, like the player names, and of the selection in it.
+    pub input_color: [f32; 4],
+    /// Color of the selected text being typed in, on top of the `input_color`.
+    pub input_selected_color: [f32; 4],
+    /// Color of warnings on the HUD, like overtime.
+    pub warning_color: [f32; 4],
+    /// Drawn behind every entry of a menu.
+    pub button: ThemeImage,
+    /// Drawn behind the pause menu and dialogs, over the screen underneath.
+    pub backdrop: ThemeImage,
+    pub score_color: [f32; 4],
+    pub score_font_size: f32,
+    /// Color the winner's score flashes in once they win the match.
+    pub score_flash_color: [f32; 4],
+    /// Colors of the loading bar and its frame.
+    pub bar_color: [f32; 4],
+    pub bar_frame_color: [f32; 4],
+    /// Colors of the dash bar when the dash is ready and while it cools down.
+    pub dash_ready_color: [f32; 4],
+    pub dash_cooldown_color: [f32; 4],
+    /// Colors of the power shot meter while it charges and once it's full.
+    pub charge_color: [f32; 4],
+    pub charged_color: [f32; 4],
+    /// What the screen is cleared to behind the arena and the menus, in place of the background
+    /// of the `Palette`. The palette's own is kept when it's left out.
+    pub background: Option<[f32; 4]>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            font: "font/square.ttf".to_string(),
+            text_color: [1., 1., 1., 1.],
+            highlight_color: [1., 1., 1., 1.],
+            input_color: [1., 1., 0.5, 1.],
+            input_selected_color: [0., 0., 0., 1.],
+            warning_color: [1., 0.3, 0.3, 1.],
+            button: ThemeImage::Color([0., 0., 0., 0.]),
+            backdrop: ThemeImage::Color([0., 0., 0., 0.8]),
+            score_color: [1., 1., 1., 1.],
+            score_font_size: 50.,
+            score_flash_color: [1., 0.85, 0.2, 1.],
+            bar_color: [1., 1., 1., 1.],
+            bar_frame_color: [0.3, 0.3, 0.3, 1.],
+            dash_ready_color: [0.3, 1., 0.3, 1.],
+            dash_cooldown_color: [0.5, 0.5, 0.5, 1.],
+            charge_color: [1., 0.8, 0.2, 1.],
+            charged_color: [1., 0.3, 0.2, 1.],
+            background: None,
+        }
+    }
+}
+
+/// The theme the UI is built with, a `ThemeConfig` with its font and textures loaded. Every
+/// screen reads it when it's shown, so a new theme picked in the settings shows up on the
+/// screens opened after it.
+#[derive(Clone)]
+pub struct Theme {
+    pub font: FontHandle,
+    pub text_color: [f32; 4],
+    pub highlight_color: [f32; 4],
+    pub input_color: [f32; 4],
+    pub input_selected_color: [f32; 4],
+    pub warning_color: [f32; 4],
+    pub button: UiImage,
+    pub backdrop: UiImage,
+    pub score_color: [f32; 4],
+    pub score_font_size: f32,
+    pub score_flash_color: [f32; 4],
+    pub bar_color: [f32; 4],
+    pub bar_frame_color: [f32; 4],
+    pub dash_ready_color: [f32; 4],
+    pub dash_cooldown_color: [f32; 4],
+    pub charge_color: [f32; 4],
+    pub charged_color: [f32; 4],
+    pub background: Option<[f32; 4]>,
+}
+
+impl Theme {
+    /// Loads the theme called `name` from `assets/themes/`, counting its font and textures on
+    /// `progress`, in the colors of the `Palette` if it has its own for the UI. A theme file
+    /// that can't be read falls back to the default look.
+    pub fn load(world: &World, name: &str, progress: &mut ProgressCounter) -> Theme {
+        Theme::from_config(
+            ThemeConfig::load(theme_path(name)),
+            &world.read_resource(),
+            &world.read_resource(),
+            &world.read_resource(),
+            &world.read_resource(),
+            progress,
+        )
+    }
+
+    /// The theme written in `config`, with its font and textures loaded like `load` does.
+    pub fn from_config(
+        config: ThemeConfig,
+        loader: &Loader,
+        fonts: &AssetStorage<FontAsset>,
+        textures: &AssetStorage<Texture>,
+        palette: &Palette,
+        progress: &mut ProgressCounter,
+    ) -> Theme {
+        let font = loader.load(config.font, TtfFormat, &mut *progress, fonts);
+        let mut image = |image: ThemeImage| match image {
+            ThemeImage::Color(color) => UiImage::SolidColor(color),
+            ThemeImage::Texture(path) => UiImage::Texture(loader.load(
+                path,
+                ImageFormat::default(),
+                &mut *progress,
+                textures,
+            )),
+        };
+        let button = image(config.button);
+        let backdrop = image(config.backdrop);
+
+        let mut theme = Theme {
+            font,
+            text_color: config.text_color,
+            highlight_color: config.highlight_color,
+            input_color: config.input_color,
+            input_selected_color: config.input_selected_color,
+            warning_color: config.warning_color,
+            button,
+            backdrop,
+            score_color: config.score_color,
+            score_font_size: config.score_font_size,
+            score_flash_color: config.score_flash_color,
+            bar_color: config.bar_color,
+            bar_frame_color: config.bar_frame_color,
+            dash_ready_color: config.dash_ready_color,
+            dash_cooldown_color: config.dash_cooldown_color,
+            charge_color: config.charge_color,
+            charged_color: config.charged_color,
+            background: config.background,
+        };
+        palette.recolor(&mut theme);
+        theme
+    }
+
+    /// Every color of the theme, in the same order for every theme.
+    fn colors(&self) -> [[f32; 4]; 13] {
+        [
+            self.text_color,
+            self.highlight_color,
+            self.input_color,
+            self.input_selected_color,
+            self.warning_color,
+            self.score_color,
+            self.score_flash_color,
+            self.bar_color,
+            self.bar_frame_color,
+            self.dash_ready_color,
+            self.dash_cooldown_color,
+            self.charge_color,
+            self.charged_color,
+        ]
+    }
+
+    /// Puts text written in the `old` theme in this one: its font, and the color of this theme
+    /// in the place of the one of the old theme it's in.
+    pub fn restyle_text(&self, old: &Theme, text: &mut UiText) {
+        if text.font == old.font {
+            text.font = self.font.clone();
+        }
+        if let Some(index) = old.colors().iter().position(|color| *color == text.color) {
+            text.color = self.colors()[index];
+        }
+    }
+
+    /// Puts a button or backdrop of the `old` theme in this one.
+    pub fn restyle_image(&self, old: &Theme, image: &mut UiImage) {
+        if *image == old.button {
+            *image = self.button.clone();
+        } else if *image == old.backdrop {
+            *image = self.backdrop.clone();
+        }
+    }
+
+    /// Text in the theme's font and text color.
+    pub fn text(&self, text: String, font_size: f32) -> UiText {
+        UiText::new(self.font.clone(), text, self.text_color, font_size)
+    }
+
+    /// A score on the HUD.
+    pub fn score_text(&self, text: String) -> UiText {
+        UiText::new(self.font.clone(), text, self.score_color, self.score_font_size)
+    }
+
+    /// Color of a menu entry, depending on whether it's the picked one.
+    pub fn item_color(&self, selected: bool) -> [f32; 4] {
+        if selected {
+            self.highlight_color
+        } else {
+            self.text_color
+        }
+    }
+}
+
+/// The file of the theme called `name`.
+pub fn theme_path(name: &str) -> String {
+    format!("{}/{}.ron", THEMES_DIR, name)
+}
+
+/// Names of the themes in `assets/themes/`, in alphabetical order.
+pub fn theme_names() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(THEMES_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension() == Some(OsStr::new("ron")))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
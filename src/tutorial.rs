@@ -0,0 +1,315 @@
+use amethyst::{
+    core::{math::Vector2, timing::Time, Hidden, Transform},
+    ecs::prelude::{Entity, Join},
+    input::{is_close_requested, InputHandler, StringBindings},
+    prelude::*,
+    renderer::SpriteRender,
+    ui::{Anchor, UiText, UiTransform},
+};
+
+use crate::{
+    config::Gameplay,
+    menu::{menu_input, MenuInput},
+    pong::{
+        action_key_name, axis_key_names, ball_scale, AiPaddle, Animation, Ball, Mutators, Paddle,
+        Pong, Practice, PracticeText, Serving, Side, SpriteSheetHandle,
+    },
+    theme::Theme,
+};
+
+/// How far the paddle has to be moved to finish the first step.
+const MOVE_DISTANCE: f32 = 20.0;
+/// Seconds the prompt of a finished step stays up before the next one starts.
+const STEP_PAUSE: f32 = 1.5;
+
+/// What happens as a step of the tutorial starts.
+enum Cue {
+    Nothing,
+    /// The launcher fires a ball at the angle, in radians, and speed.
+    Launch(f32, f32),
+    /// A ball is put on the player's paddle for them to serve.
+    HoldBall,
+}
+
+/// What a step of the tutorial waits for before the next one starts.
+enum Goal {
+    /// The player moves their paddle this far.
+    Move(f32),
+    /// The player sends the launched ball back, it's launched again if they miss.
+    Return,
+    /// The player serves the ball on their paddle.
+    Serve,
+    /// The last step, it waits for the player to leave.
+    Done,
+}
+
+struct Step {
+    prompt: String,
+    cue: Cue,
+    goal: Goal,
+}
+
+/// Where things stood as a step started, its goal is measured from there.
+#[derive(Default)]
+struct StepStart {
+    paddle: f32,
+    returned: u32,
+    missed: u32,
+}
+
+/// Walks a new player through a practice match: moving their paddle, returning a couple of
+/// launched balls and serving. Each step shows a prompt, cues the launcher or a serve and waits
+/// for the player to do what it asks, the `Pong` state underneath runs the match as usual.
+///
+/// Pushed from the main menu, Enter or Escape goes back to it once the last step is reached.
+pub struct Tutorial {
+    pong: Pong,
+    steps: Vec<Step>,
+    current: usize,
+    start: StepStart,
+    /// Time left before the next step starts, once the current one is done.
+    pause: Option<f32>,
+    prompt: Option<Entity>,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        Tutorial {
+            pong: Pong::practice().without_ghost(),
+            steps: Vec::new(),
+            current: 0,
+            start: StepStart::default(),
+            pause: None,
+            prompt: None,
+        }
+    }
+}
+
+impl Tutorial {
+    /// The steps of the tutorial, naming the keys as they're bound right now.
+    fn script(world: &World) -> Vec<Step> {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let (up, down) = axis_key_names(&input.bindings, "left_paddle")
+            .unwrap_or_else(|| ("up".to_string(), "down".to_string()));
+        let serve = action_key_name(&input.bindings, "left_serve");
+        let speed = world.read_resource::<Gameplay>().ball_velocity_x;
+
+        vec![
+            Step {
+                prompt: format!("Move your paddle up and down with {} and {}", up, down),
+                cue: Cue::Nothing,
+                goal: Goal::Move(MOVE_DISTANCE),
+            },
+            Step {
+                prompt: "Here comes a ball, get in its way to send it back".to_string(),
+                cue: Cue::Launch(0.0, speed * 0.6),
+                goal: Goal::Return,
+            },
+            Step {
+                prompt: "This one comes in at an angle".to_string(),
+                cue: Cue::Launch(0.4, speed),
+                goal: Goal::Return,
+            },
+            Step {
+                prompt: format!("The ball is yours to serve, press {} to send it off", serve),
+                cue: Cue::HoldBall,
+                goal: Goal::Serve,
+            },
+            Step {
+                prompt: "That's it! Enter or Escape goes back to the menu".to_string(),
+                cue: Cue::Nothing,
+                goal: Goal::Done,
+            },
+        ]
+    }
+
+    fn set_prompt(&self, world: &World, prompt: &str) {
+        if let Some(entity) = self.prompt {
+            if let Some(text) = world.write_storage::<UiText>().get_mut(entity) {
+                text.text = prompt.to_string();
+            }
+        }
+    }
+
+    /// Shows the prompt of the current step and cues it.
+    fn begin_step(&mut self, world: &mut World) {
+        let step = &self.steps[self.current];
+        let prompt = step.prompt.clone();
+        self.set_prompt(world, &prompt);
+
+        self.start = StepStart {
+            paddle: player_paddle(world).unwrap_or(0.0),
+            returned: world.read_resource::<Practice>().returned,
+            missed: world.read_resource::<Practice>().missed,
+        };
+        match self.steps[self.current].cue {
+            Cue::Nothing => {}
+            Cue::Launch(angle, speed) => launch(world, angle, speed),
+            Cue::HoldBall => hold_ball(world),
+        }
+    }
+
+    /// Whether the player has done what the current step asks, launching the ball again if
+    /// they missed it.
+    fn goal_reached(&mut self, world: &mut World) -> bool {
+        match self.steps[self.current].goal {
+            Goal::Move(distance) => match player_paddle(world) {
+                Some(paddle) => (paddle - self.start.paddle).abs() >= distance,
+                None => false,
+            },
+            Goal::Return => {
+                let (returned, missed) = {
+                    let practice = world.read_resource::<Practice>();
+                    (practice.returned, practice.missed)
+                };
+                if missed > self.start.missed {
+                    self.start.missed = missed;
+                    self.set_prompt(world, "Missed it, here it comes again");
+                    if let Cue::Launch(angle, speed) = self.steps[self.current].cue {
+                        launch(world, angle, speed);
+                    }
+                }
+                returned > self.start.returned
+            }
+            Goal::Serve => world.read_storage::<Serving>().join().next().is_none(),
+            Goal::Done => false,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        match self.steps.get(self.current) {
+            Some(step) => matches!(step.goal, Goal::Done),
+            None => true,
+        }
+    }
+}
+
+impl SimpleState for Tutorial {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        SimpleState::on_start(&mut self.pong, StateData::new(data.world, data.data));
+        let world = data.world;
+
+        // The tutorial fires the launcher itself, and its prompts take the place of the
+        // practice overlay.
+        world.write_resource::<Practice>().auto_fire = false;
+        let overlay = {
+            let overlay = world.read_resource::<PracticeText>();
+            [overlay.stats, overlay.launcher]
+        };
+        for entity in &overlay {
+            world
+                .write_storage::<Hidden>()
+                .insert(*entity, Hidden)
+                .expect("practice overlay should be alive");
+        }
+
+        let theme = world.read_resource::<Theme>().clone();
+        self.prompt = Some(
+            world
+                .create_entity()
+                .with(UiTransform::new(
+                    "tutorial_prompt".to_string(), Anchor::Middle, Anchor::Middle,
+                    0., 30., 1., 600., 30.,
+                ))
+                .with(theme.text(String::new(), 18.))
+                .build(),
+        );
+
+        self.steps = Tutorial::script(world);
+        self.current = 0;
+        self.begin_step(world);
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        // The match cleans up everything, the prompt included.
+        self.prompt = None;
+        SimpleState::on_stop(&mut self.pong, data);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if self.finished() {
+            match &event {
+                StateEvent::Window(event) if is_close_requested(event) => {}
+                _ => {
+                    if let Some(MenuInput::Select) | Some(MenuInput::Back) = menu_input(&event) {
+                        return Trans::Pop;
+                    }
+                    return Trans::None;
+                }
+            }
+        }
+        SimpleState::handle_event(&mut self.pong, data, event)
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        let trans = SimpleState::update(&mut self.pong, data);
+
+        if let Some(pause) = self.pause.take() {
+            let pause = pause - data.world.read_resource::<Time>().delta_seconds();
+            if pause > 0.0 {
+                self.pause = Some(pause);
+            } else {
+                self.current += 1;
+                self.begin_step(data.world);
+            }
+        } else if !self.finished() && self.goal_reached(data.world) {
+            self.set_prompt(data.world, "Nice!");
+            self.pause = Some(STEP_PAUSE);
+        }
+        trans
+    }
+}
+
+/// Where the player's paddle is along the left edge.
+fn player_paddle(world: &World) -> Option<f32> {
+    let paddles = world.read_storage::<Paddle>();
+    let ais = world.read_storage::<AiPaddle>();
+    let transforms = world.read_storage::<Transform>();
+    (&paddles, !&ais, &transforms)
+        .join()
+        .find(|(paddle, _, _)| paddle.side == Side::Left)
+        .map(|(_, _, transform)| transform.translation().y.as_f32())
+}
+
+/// Has the practice launcher fire a ball at the angle and speed.
+fn launch(world: &World, angle: f32, speed: f32) {
+    let mut practice = world.write_resource::<Practice>();
+    practice.angle = angle;
+    practice.speed = speed;
+    practice.fire();
+}
+
+/// Puts a ball on the player's paddle, the `ServeSystem` lets it go when they serve.
+fn hold_ball(world: &mut World) {
+    let gameplay = world.read_resource::<Gameplay>().clone();
+    let radius = world.read_resource::<Mutators>().ball_radius(gameplay.ball_radius);
+    let sprite_sheet = world.read_resource::<SpriteSheetHandle>().0.clone();
+    let mut transform = Transform::default();
+    transform.set_scale(ball_scale(radius));
+
+    world
+        .create_entity()
+        .with(SpriteRender {
+            sprite_sheet,
+            sprite_number: 1, // ball is the second sprite on the sprite sheet
+        })
+        .with(Animation::ball())
+        .with(Ball {
+            radius,
+            velocity: Vector2::new(gameplay.ball_velocity_x, 0.0),
+            spin: 0.0,
+            last_hit: None,
+            power_shot: false,
+        })
+        .with(Serving {
+            side: Side::Left,
+            paddle: None,
+            timer: 0.0,
+        })
+        .with(transform)
+        .build();
+}
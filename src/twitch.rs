@@ -0,0 +1,104 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Twitch's chat, which speaks IRC.
+const TWITCH_IRC_ADDRESS: &str = "irc.chat.twitch.tv:6667";
+/// How long to wait before joining the chat again once the connection to it is lost.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// A viewer typing "up" or "down" in the chat.
+pub struct Vote {
+    pub viewer: String,
+    pub up: bool,
+}
+
+/// Reads the chat of a Twitch channel on a thread of its own, for the votes of a chat plays
+/// match. It joins anonymously, so it only ever reads the chat, and joins again whenever the
+/// connection is lost until it's dropped. The default one reads no chat.
+pub struct TwitchChat {
+    votes: Mutex<Receiver<Vote>>,
+}
+
+impl Default for TwitchChat {
+    fn default() -> Self {
+        let (_, votes) = mpsc::channel();
+        TwitchChat {
+            votes: Mutex::new(votes),
+        }
+    }
+}
+
+impl TwitchChat {
+    pub fn connect(channel: &str) -> TwitchChat {
+        let channel = channel.trim_start_matches('#').to_lowercase();
+        let (sender, votes) = mpsc::channel();
+        thread::spawn(move || run(&channel, &sender));
+        TwitchChat {
+            votes: Mutex::new(votes),
+        }
+    }
+
+    /// The votes cast since the last call, oldest first.
+    pub fn votes(&self) -> Vec<Vote> {
+        self.votes.lock().unwrap().try_iter().collect()
+    }
+}
+
+/// Reads the chat until the `TwitchChat` is dropped.
+fn run(channel: &str, votes: &Sender<Vote>) {
+    loop {
+        match read_chat(channel, votes) {
+            Ok(()) => return,
+            Err(err) => log::warn!("Lost the Twitch chat of {}: {}", channel, err),
+        }
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Joins the chat and sends on every vote cast in it. Returns once the votes aren't wanted
+/// anymore, or the connection is lost.
+fn read_chat(channel: &str, votes: &Sender<Vote>) -> io::Result<()> {
+    let stream = TcpStream::connect(TWITCH_IRC_ADDRESS)?;
+    let mut writer = stream.try_clone()?;
+    // Twitch lets anyone read a chat under a justinfan nickname, without a password.
+    let nick = rand::random::<u32>() % 100_000;
+    write!(writer, "NICK justinfan{}\r\nJOIN #{}\r\n", nick, channel)?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        // Twitch closes the connection of those that don't answer its pings.
+        if let Some(server) = line.strip_prefix("PING") {
+            write!(writer, "PONG{}\r\n", server)?;
+        } else if let Some(vote) = parse_vote(&line) {
+            if votes.send(vote).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the chat closed the connection"))
+}
+
+/// The vote in a chat message, which looks like
+/// `:viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :up`.
+fn parse_vote(line: &str) -> Option<Vote> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let viewer = prefix.split('!').next()?;
+    let (_, message) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let up = match message.trim().to_lowercase().as_str() {
+        "up" => true,
+        "down" => false,
+        _ => return None,
+    };
+    Some(Vote {
+        viewer: viewer.to_string(),
+        up,
+    })
+}